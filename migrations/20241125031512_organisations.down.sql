@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE vehicles DROP COLUMN organisation_id;
+DROP TABLE organisation_members;
+DROP TABLE organisations;
@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+CREATE TABLE organisations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE organisation_members (
+    organisation_id UUID REFERENCES organisations(id) ON DELETE CASCADE NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (organisation_id, user_id)
+);
+
+ALTER TABLE vehicles
+    ADD COLUMN organisation_id UUID REFERENCES organisations(id) ON DELETE SET NULL;
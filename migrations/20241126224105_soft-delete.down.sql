@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE log_records DROP COLUMN deleted_at;
+ALTER TABLE vehicles DROP COLUMN deleted_at;
+ALTER TABLE users DROP COLUMN deleted_at;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE vehicles ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE log_records ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
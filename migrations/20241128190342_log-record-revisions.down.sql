@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP TRIGGER log_record_revisions_append_only ON log_record_revisions;
+DROP FUNCTION reject_log_record_revision_update;
+DROP TABLE log_record_revisions;
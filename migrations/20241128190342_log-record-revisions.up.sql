@@ -0,0 +1,24 @@
+-- Add up migration script here
+
+CREATE TABLE log_record_revisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    log_record_id UUID REFERENCES log_records(id) ON DELETE CASCADE NOT NULL,
+    action TEXT NOT NULL,
+    changed_by UUID,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    previous JSONB NOT NULL
+);
+
+CREATE INDEX log_record_revisions_log_record_id_idx
+    ON log_record_revisions (log_record_id, changed_at);
+
+-- Revisions are append-only
+CREATE FUNCTION reject_log_record_revision_update() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'log record revisions are append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER log_record_revisions_append_only
+    BEFORE UPDATE ON log_record_revisions
+    FOR EACH ROW EXECUTE FUNCTION reject_log_record_revision_update();
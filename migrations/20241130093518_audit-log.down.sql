@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE audit_log;
+ALTER TABLE users DROP COLUMN is_admin;
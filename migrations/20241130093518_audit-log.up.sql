@@ -0,0 +1,17 @@
+-- Add up migration script here
+
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE audit_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    actor_id UUID,
+    resource_type TEXT NOT NULL,
+    resource_id UUID,
+    action TEXT NOT NULL,
+    request_id TEXT,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX audit_log_occurred_at_idx ON audit_log (occurred_at);
+CREATE INDEX audit_log_resource_idx ON audit_log (resource_type, resource_id);
+CREATE INDEX audit_log_actor_id_idx ON audit_log (actor_id);
@@ -0,0 +1,10 @@
+-- Add down migration script here
+
+DROP TRIGGER log_records_increment_version ON log_records;
+DROP TRIGGER vehicles_increment_version ON vehicles;
+DROP TRIGGER users_increment_version ON users;
+DROP FUNCTION increment_row_version;
+
+ALTER TABLE log_records DROP COLUMN version;
+ALTER TABLE vehicles DROP COLUMN version;
+ALTER TABLE users DROP COLUMN version;
@@ -0,0 +1,25 @@
+-- Add up migration script here
+
+ALTER TABLE users ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE vehicles ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE log_records ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+
+-- Every write to a row moves it to a new version
+CREATE FUNCTION increment_row_version() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_increment_version
+    BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION increment_row_version();
+
+CREATE TRIGGER vehicles_increment_version
+    BEFORE UPDATE ON vehicles
+    FOR EACH ROW EXECUTE FUNCTION increment_row_version();
+
+CREATE TRIGGER log_records_increment_version
+    BEFORE UPDATE ON log_records
+    FOR EACH ROW EXECUTE FUNCTION increment_row_version();
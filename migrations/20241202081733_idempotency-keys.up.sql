@@ -0,0 +1,11 @@
+-- Add up migration script here
+
+CREATE TABLE idempotency_keys (
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    request JSONB NOT NULL,
+    -- NULL until the request that claimed the key has completed
+    resource_id UUID,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (scope, key)
+);
@@ -0,0 +1,16 @@
+-- Add down migration script here
+
+DROP TRIGGER log_records_record_purge ON log_records;
+DROP TRIGGER vehicles_record_purge ON vehicles;
+DROP TRIGGER users_record_purge ON users;
+DROP FUNCTION record_row_purge;
+DROP TABLE purged_rows;
+
+DROP TRIGGER log_records_record_change ON log_records;
+DROP TRIGGER vehicles_record_change ON vehicles;
+DROP TRIGGER users_record_change ON users;
+DROP FUNCTION record_row_change;
+
+ALTER TABLE log_records DROP COLUMN updated_at, DROP COLUMN change_xid;
+ALTER TABLE vehicles DROP COLUMN updated_at, DROP COLUMN change_xid;
+ALTER TABLE users DROP COLUMN updated_at, DROP COLUMN change_xid;
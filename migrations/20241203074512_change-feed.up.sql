@@ -0,0 +1,68 @@
+-- Add up migration script here
+
+-- Rows remember when and by which transaction they were last written. Sync
+-- tokens are snapshots, so a row has changed since a token when its writing
+-- transaction was not yet visible in that snapshot. Unlike a sequence, this
+-- cannot skip over rows written by transactions that commit out of order.
+ALTER TABLE users
+    ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    ADD COLUMN change_xid XID8 NOT NULL DEFAULT pg_current_xact_id();
+ALTER TABLE vehicles
+    ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    ADD COLUMN change_xid XID8 NOT NULL DEFAULT pg_current_xact_id();
+ALTER TABLE log_records
+    ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    ADD COLUMN change_xid XID8 NOT NULL DEFAULT pg_current_xact_id();
+
+CREATE INDEX users_change_xid_idx ON users(change_xid);
+CREATE INDEX vehicles_change_xid_idx ON vehicles(change_xid);
+CREATE INDEX log_records_change_xid_idx ON log_records(change_xid);
+
+CREATE FUNCTION record_row_change() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = now();
+    NEW.change_xid = pg_current_xact_id();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_record_change
+    BEFORE INSERT OR UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION record_row_change();
+
+CREATE TRIGGER vehicles_record_change
+    BEFORE INSERT OR UPDATE ON vehicles
+    FOR EACH ROW EXECUTE FUNCTION record_row_change();
+
+CREATE TRIGGER log_records_record_change
+    BEFORE INSERT OR UPDATE ON log_records
+    FOR EACH ROW EXECUTE FUNCTION record_row_change();
+
+-- Purged rows leave a tombstone behind so replicas can drop them too
+CREATE TABLE purged_rows (
+    resource_type TEXT NOT NULL,
+    resource_id UUID NOT NULL,
+    purged_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    change_xid XID8 NOT NULL DEFAULT pg_current_xact_id()
+);
+
+CREATE INDEX purged_rows_change_xid_idx ON purged_rows(change_xid);
+
+CREATE FUNCTION record_row_purge() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO purged_rows(resource_type, resource_id) VALUES (TG_ARGV[0], OLD.id);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_record_purge
+    AFTER DELETE ON users
+    FOR EACH ROW EXECUTE FUNCTION record_row_purge('user');
+
+CREATE TRIGGER vehicles_record_purge
+    AFTER DELETE ON vehicles
+    FOR EACH ROW EXECUTE FUNCTION record_row_purge('vehicle');
+
+CREATE TRIGGER log_records_record_purge
+    AFTER DELETE ON log_records
+    FOR EACH ROW EXECUTE FUNCTION record_row_purge('log_record');
@@ -0,0 +1,23 @@
+-- Add down migration script here
+
+ALTER TABLE log_records
+    DROP CONSTRAINT log_records_fuel_up_details,
+    DROP CONSTRAINT log_records_tire_change_details,
+    DROP CONSTRAINT log_records_brake_replacement_details,
+    DROP CONSTRAINT log_records_tire_rotation_details,
+    DROP CONSTRAINT log_records_fluids_details;
+
+ALTER TABLE log_records
+    ALTER COLUMN log_type TYPE TEXT,
+    ALTER COLUMN tire_rotation_type TYPE TEXT,
+    ALTER COLUMN tire_type TYPE TEXT,
+    ALTER COLUMN brake_location TYPE TEXT,
+    ALTER COLUMN brake_part TYPE TEXT,
+    ALTER COLUMN fluid_type TYPE TEXT;
+
+DROP TYPE fluid_type;
+DROP TYPE brake_component;
+DROP TYPE brake_location;
+DROP TYPE tire_type;
+DROP TYPE tire_rotation_type;
+DROP TYPE log_type;
@@ -0,0 +1,40 @@
+-- Add up migration script here
+
+CREATE TYPE log_type AS ENUM (
+    'fuel_up',
+    'tire_change',
+    'brake_replacement',
+    'tire_rotation',
+    'fluids',
+    'oil_change',
+    'repair',
+    'wiper_blade_replacement',
+    'battery_replacement'
+);
+CREATE TYPE tire_rotation_type AS ENUM ('front_rear', 'side', 'diagonal');
+CREATE TYPE tire_type AS ENUM ('summer', 'winter', 'all_season');
+CREATE TYPE brake_location AS ENUM ('front', 'rear', 'all');
+CREATE TYPE brake_component AS ENUM ('rotors', 'calipers', 'both');
+CREATE TYPE fluid_type AS ENUM ('wiper', 'transmission', 'brake', 'coolant');
+
+ALTER TABLE log_records
+    ALTER COLUMN log_type TYPE log_type USING log_type::log_type,
+    ALTER COLUMN tire_rotation_type TYPE tire_rotation_type
+        USING tire_rotation_type::tire_rotation_type,
+    ALTER COLUMN tire_type TYPE tire_type USING tire_type::tire_type,
+    ALTER COLUMN brake_location TYPE brake_location USING brake_location::brake_location,
+    ALTER COLUMN brake_part TYPE brake_component USING brake_part::brake_component,
+    ALTER COLUMN fluid_type TYPE fluid_type USING fluid_type::fluid_type;
+
+-- Each log type must carry its details
+ALTER TABLE log_records
+    ADD CONSTRAINT log_records_fuel_up_details
+        CHECK (log_type <> 'fuel_up' OR fuel_amount IS NOT NULL),
+    ADD CONSTRAINT log_records_tire_change_details
+        CHECK (log_type <> 'tire_change' OR (tire_type IS NOT NULL AND new_tires IS NOT NULL)),
+    ADD CONSTRAINT log_records_brake_replacement_details
+        CHECK (log_type <> 'brake_replacement' OR (brake_location IS NOT NULL AND brake_part IS NOT NULL)),
+    ADD CONSTRAINT log_records_tire_rotation_details
+        CHECK (log_type <> 'tire_rotation' OR tire_rotation_type IS NOT NULL),
+    ADD CONSTRAINT log_records_fluids_details
+        CHECK (log_type <> 'fluids' OR fluid_type IS NOT NULL);
@@ -0,0 +1,45 @@
+-- Add down migration script here
+
+ALTER TABLE log_records
+    ADD COLUMN fuel_amount REAL,
+    ADD COLUMN tire_rotation_type tire_rotation_type,
+    ADD COLUMN tire_type tire_type,
+    ADD COLUMN new_tires BOOLEAN,
+    ADD COLUMN brake_location brake_location,
+    ADD COLUMN brake_part brake_component,
+    ADD COLUMN fluid_type fluid_type;
+
+-- Moving details back must not count as a change to the log records
+ALTER TABLE log_records DISABLE TRIGGER USER;
+UPDATE log_records SET fuel_amount = d.fuel_amount
+    FROM fuel_ups d WHERE d.log_record_id = log_records.id;
+UPDATE log_records
+    SET tire_type = d.tire_type, new_tires = d.new_tires, tire_rotation_type = d.tire_rotation_type
+    FROM tire_changes d WHERE d.log_record_id = log_records.id;
+UPDATE log_records SET brake_location = d.brake_location, brake_part = d.brake_part
+    FROM brake_replacements d WHERE d.log_record_id = log_records.id;
+UPDATE log_records SET tire_rotation_type = d.tire_rotation_type
+    FROM tire_rotations d WHERE d.log_record_id = log_records.id;
+UPDATE log_records SET fluid_type = d.fluid_type
+    FROM fluid_changes d WHERE d.log_record_id = log_records.id;
+ALTER TABLE log_records ENABLE TRIGGER USER;
+
+ALTER TABLE log_records
+    ADD CONSTRAINT log_records_fuel_up_details
+        CHECK (log_type <> 'fuel_up' OR fuel_amount IS NOT NULL),
+    ADD CONSTRAINT log_records_tire_change_details
+        CHECK (log_type <> 'tire_change' OR (tire_type IS NOT NULL AND new_tires IS NOT NULL)),
+    ADD CONSTRAINT log_records_brake_replacement_details
+        CHECK (log_type <> 'brake_replacement' OR (brake_location IS NOT NULL AND brake_part IS NOT NULL)),
+    ADD CONSTRAINT log_records_tire_rotation_details
+        CHECK (log_type <> 'tire_rotation' OR tire_rotation_type IS NOT NULL),
+    ADD CONSTRAINT log_records_fluids_details
+        CHECK (log_type <> 'fluids' OR fluid_type IS NOT NULL);
+
+DROP TABLE fluid_changes;
+DROP TABLE tire_rotations;
+DROP TABLE brake_replacements;
+DROP TABLE tire_changes;
+DROP TABLE fuel_ups;
+
+ALTER TABLE log_records DROP CONSTRAINT log_records_id_log_type_key;
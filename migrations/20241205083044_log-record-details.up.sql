@@ -0,0 +1,74 @@
+-- Add up migration script here
+
+-- Details may only belong to a log record of their type
+ALTER TABLE log_records ADD CONSTRAINT log_records_id_log_type_key UNIQUE (id, log_type);
+
+CREATE TABLE fuel_ups (
+    log_record_id UUID PRIMARY KEY,
+    log_type log_type NOT NULL DEFAULT 'fuel_up' CHECK (log_type = 'fuel_up'),
+    fuel_amount REAL NOT NULL,
+    FOREIGN KEY (log_record_id, log_type)
+        REFERENCES log_records(id, log_type) ON DELETE CASCADE
+);
+
+CREATE TABLE tire_changes (
+    log_record_id UUID PRIMARY KEY,
+    log_type log_type NOT NULL DEFAULT 'tire_change' CHECK (log_type = 'tire_change'),
+    tire_type tire_type NOT NULL,
+    new_tires BOOLEAN NOT NULL,
+    tire_rotation_type tire_rotation_type,
+    FOREIGN KEY (log_record_id, log_type)
+        REFERENCES log_records(id, log_type) ON DELETE CASCADE
+);
+
+CREATE TABLE brake_replacements (
+    log_record_id UUID PRIMARY KEY,
+    log_type log_type NOT NULL DEFAULT 'brake_replacement'
+        CHECK (log_type = 'brake_replacement'),
+    brake_location brake_location NOT NULL,
+    brake_part brake_component NOT NULL,
+    FOREIGN KEY (log_record_id, log_type)
+        REFERENCES log_records(id, log_type) ON DELETE CASCADE
+);
+
+CREATE TABLE tire_rotations (
+    log_record_id UUID PRIMARY KEY,
+    log_type log_type NOT NULL DEFAULT 'tire_rotation' CHECK (log_type = 'tire_rotation'),
+    tire_rotation_type tire_rotation_type NOT NULL,
+    FOREIGN KEY (log_record_id, log_type)
+        REFERENCES log_records(id, log_type) ON DELETE CASCADE
+);
+
+CREATE TABLE fluid_changes (
+    log_record_id UUID PRIMARY KEY,
+    log_type log_type NOT NULL DEFAULT 'fluids' CHECK (log_type = 'fluids'),
+    fluid_type fluid_type NOT NULL,
+    FOREIGN KEY (log_record_id, log_type)
+        REFERENCES log_records(id, log_type) ON DELETE CASCADE
+);
+
+INSERT INTO fuel_ups (log_record_id, fuel_amount)
+    SELECT id, fuel_amount FROM log_records WHERE log_type = 'fuel_up';
+INSERT INTO tire_changes (log_record_id, tire_type, new_tires, tire_rotation_type)
+    SELECT id, tire_type, new_tires, tire_rotation_type
+    FROM log_records WHERE log_type = 'tire_change';
+INSERT INTO brake_replacements (log_record_id, brake_location, brake_part)
+    SELECT id, brake_location, brake_part FROM log_records WHERE log_type = 'brake_replacement';
+INSERT INTO tire_rotations (log_record_id, tire_rotation_type)
+    SELECT id, tire_rotation_type FROM log_records WHERE log_type = 'tire_rotation';
+INSERT INTO fluid_changes (log_record_id, fluid_type)
+    SELECT id, fluid_type FROM log_records WHERE log_type = 'fluids';
+
+ALTER TABLE log_records
+    DROP CONSTRAINT log_records_fuel_up_details,
+    DROP CONSTRAINT log_records_tire_change_details,
+    DROP CONSTRAINT log_records_brake_replacement_details,
+    DROP CONSTRAINT log_records_tire_rotation_details,
+    DROP CONSTRAINT log_records_fluids_details,
+    DROP COLUMN fuel_amount,
+    DROP COLUMN tire_rotation_type,
+    DROP COLUMN tire_type,
+    DROP COLUMN new_tires,
+    DROP COLUMN brake_location,
+    DROP COLUMN brake_part,
+    DROP COLUMN fluid_type;
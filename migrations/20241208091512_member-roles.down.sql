@@ -0,0 +1,3 @@
+-- Add down migration script here
+
+ALTER TABLE organisation_members DROP CONSTRAINT organisation_members_role_check;
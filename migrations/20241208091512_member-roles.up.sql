@@ -0,0 +1,4 @@
+-- Add up migration script here
+
+ALTER TABLE organisation_members
+    ADD CONSTRAINT organisation_members_role_check CHECK (role IN ('owner', 'admin', 'member'));
@@ -0,0 +1,6 @@
+-- Add up migration script here
+
+-- When the request holding the key claimed it. Claims of requests that never
+-- completed can be taken over once they are old enough.
+ALTER TABLE idempotency_keys
+    ADD COLUMN claimed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now();
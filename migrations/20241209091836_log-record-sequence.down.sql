@@ -0,0 +1,3 @@
+-- Add down migration script here
+
+ALTER TABLE log_records DROP COLUMN created_seq;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+
+-- Insertion order of log records, breaking ties between records sharing a
+-- log_date when telling a duplicate from its original
+ALTER TABLE log_records ADD COLUMN created_seq BIGINT GENERATED ALWAYS AS IDENTITY;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+DROP TRIGGER fluid_changes_log_record_details ON fluid_changes;
+DROP TRIGGER tire_rotations_log_record_details ON tire_rotations;
+DROP TRIGGER brake_replacements_log_record_details ON brake_replacements;
+DROP TRIGGER tire_changes_log_record_details ON tire_changes;
+DROP TRIGGER fuel_ups_log_record_details ON fuel_ups;
+DROP TRIGGER log_records_details ON log_records;
+DROP FUNCTION log_records_check_details();
@@ -0,0 +1,80 @@
+-- Add up migration script here
+
+-- Log records of a type with details must have exactly one details row, of
+-- their own type, and other log records none. Checked when transactions
+-- commit, since a record and its details are written by separate statements.
+CREATE FUNCTION log_records_check_details() RETURNS TRIGGER AS $$
+DECLARE
+    record_id UUID;
+    record_type log_type;
+    expected BIGINT;
+    matching BIGINT;
+    other BIGINT;
+BEGIN
+    IF TG_TABLE_NAME = 'log_records' THEN
+        record_id := NEW.id;
+    ELSIF TG_OP = 'DELETE' THEN
+        record_id := OLD.log_record_id;
+    ELSE
+        record_id := NEW.log_record_id;
+    END IF;
+
+    SELECT log_type INTO record_type FROM log_records WHERE id = record_id;
+    -- Details are deleted along with their log record
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    SELECT
+        count(*) FILTER (WHERE details.log_type = record_type),
+        count(*) FILTER (WHERE details.log_type <> record_type)
+    INTO matching, other
+    FROM (
+        SELECT log_type FROM fuel_ups WHERE log_record_id = record_id
+        UNION ALL
+        SELECT log_type FROM tire_changes WHERE log_record_id = record_id
+        UNION ALL
+        SELECT log_type FROM brake_replacements WHERE log_record_id = record_id
+        UNION ALL
+        SELECT log_type FROM tire_rotations WHERE log_record_id = record_id
+        UNION ALL
+        SELECT log_type FROM fluid_changes WHERE log_record_id = record_id
+    ) details;
+
+    expected := CASE
+        WHEN record_type IN ('fuel_up', 'tire_change', 'brake_replacement', 'tire_rotation', 'fluids')
+            THEN 1
+        ELSE 0
+    END;
+    IF other > 0 OR matching <> expected THEN
+        RAISE EXCEPTION 'log record % of type % does not have its details', record_id, record_type
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'log_records_details';
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER log_records_details
+    AFTER INSERT OR UPDATE OF id, log_type ON log_records
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION log_records_check_details();
+CREATE CONSTRAINT TRIGGER fuel_ups_log_record_details
+    AFTER INSERT OR UPDATE OR DELETE ON fuel_ups
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION log_records_check_details();
+CREATE CONSTRAINT TRIGGER tire_changes_log_record_details
+    AFTER INSERT OR UPDATE OR DELETE ON tire_changes
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION log_records_check_details();
+CREATE CONSTRAINT TRIGGER brake_replacements_log_record_details
+    AFTER INSERT OR UPDATE OR DELETE ON brake_replacements
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION log_records_check_details();
+CREATE CONSTRAINT TRIGGER tire_rotations_log_record_details
+    AFTER INSERT OR UPDATE OR DELETE ON tire_rotations
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION log_records_check_details();
+CREATE CONSTRAINT TRIGGER fluid_changes_log_record_details
+    AFTER INSERT OR UPDATE OR DELETE ON fluid_changes
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION log_records_check_details();
@@ -0,0 +1,3 @@
+DROP TABLE log_records;
+DROP TABLE vehicles;
+DROP TABLE users;
@@ -0,0 +1,65 @@
+-- SQLite schema for single-user deployments, equivalent to the Postgres
+-- schema without organisations, auditing, sync, idempotency keys or history.
+-- UUIDs are stored as 16 byte blobs and timestamps as RFC 3339 text in UTC,
+-- which sort chronologically. Versions are bumped by every UPDATE statement.
+
+CREATE TABLE users (
+    id BLOB PRIMARY KEY NOT NULL,
+    first_name TEXT NOT NULL,
+    last_name TEXT NOT NULL,
+    username TEXT NOT NULL UNIQUE,
+    email TEXT NOT NULL UNIQUE,
+    version INTEGER NOT NULL DEFAULT 1,
+    deleted_at TEXT
+);
+
+CREATE TABLE vehicles (
+    id BLOB PRIMARY KEY NOT NULL,
+    owner_id BLOB NOT NULL REFERENCES users(id),
+    make TEXT NOT NULL,
+    model TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    odometer_unit TEXT NOT NULL CHECK (odometer_unit IN ('km', 'mi')),
+    -- Organisations are only kept by Postgres
+    organisation_id BLOB CHECK (organisation_id IS NULL),
+    version INTEGER NOT NULL DEFAULT 1,
+    deleted_at TEXT
+);
+
+CREATE INDEX vehicles_owner_id ON vehicles (owner_id);
+
+-- Rather than in a table per log type, details live in the log record's row
+CREATE TABLE log_records (
+    id BLOB PRIMARY KEY NOT NULL,
+    vehicle_id BLOB NOT NULL REFERENCES vehicles(id),
+    log_date TEXT NOT NULL,
+    odometer INTEGER NOT NULL,
+    log_type TEXT NOT NULL CHECK (log_type IN (
+        'fuel_up',
+        'tire_rotation',
+        'tire_change',
+        'oil_change',
+        'repair',
+        'wiper_blade_replacement',
+        'battery_replacement',
+        'brake_replacement',
+        'fluids'
+    )),
+    notes TEXT,
+    fuel_amount REAL,
+    tire_rotation_type TEXT,
+    tire_type TEXT,
+    new_tires INTEGER,
+    brake_location TEXT,
+    brake_part TEXT,
+    fluid_type TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    deleted_at TEXT,
+    CHECK (log_type <> 'fuel_up' OR fuel_amount IS NOT NULL),
+    CHECK (log_type <> 'tire_change' OR (tire_type IS NOT NULL AND new_tires IS NOT NULL)),
+    CHECK (log_type <> 'brake_replacement' OR (brake_location IS NOT NULL AND brake_part IS NOT NULL)),
+    CHECK (log_type <> 'tire_rotation' OR tire_rotation_type IS NOT NULL),
+    CHECK (log_type <> 'fluids' OR fluid_type IS NOT NULL)
+);
+
+CREATE INDEX log_records_vehicle_id ON log_records (vehicle_id);
@@ -0,0 +1,51 @@
+PRAGMA defer_foreign_keys = ON;
+
+DROP TRIGGER log_records_record_purge;
+DROP TRIGGER vehicles_record_purge;
+DROP TRIGGER users_record_purge;
+DROP TABLE purged_rows;
+
+DROP TRIGGER log_records_record_update;
+DROP TRIGGER log_records_record_insert;
+DROP TRIGGER vehicles_record_update;
+DROP TRIGGER vehicles_record_insert;
+DROP TRIGGER users_record_update;
+DROP TRIGGER users_record_insert;
+
+DROP INDEX log_records_change_seq;
+DROP INDEX users_change_seq;
+ALTER TABLE log_records DROP COLUMN change_seq;
+ALTER TABLE log_records DROP COLUMN updated_at;
+ALTER TABLE users DROP COLUMN change_seq;
+ALTER TABLE users DROP COLUMN updated_at;
+DROP TABLE change_sequence;
+
+DROP TABLE idempotency_keys;
+DROP TABLE log_record_revisions;
+DROP TABLE audit_log;
+ALTER TABLE users DROP COLUMN is_admin;
+
+-- Vehicles leave their organisations, which are dropped with them
+CREATE TABLE vehicles_copy AS
+    SELECT id, owner_id, make, model, year, odometer_unit, version, deleted_at FROM vehicles;
+DROP TABLE vehicles;
+CREATE TABLE vehicles (
+    id BLOB PRIMARY KEY NOT NULL,
+    owner_id BLOB NOT NULL REFERENCES users(id),
+    make TEXT NOT NULL,
+    model TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    odometer_unit TEXT NOT NULL CHECK (odometer_unit IN ('km', 'mi')),
+    -- Organisations are only kept by Postgres
+    organisation_id BLOB CHECK (organisation_id IS NULL),
+    version INTEGER NOT NULL DEFAULT 1,
+    deleted_at TEXT
+);
+INSERT INTO vehicles (id, owner_id, make, model, year, odometer_unit, version, deleted_at)
+    SELECT * FROM vehicles_copy;
+DROP TABLE vehicles_copy;
+
+CREATE INDEX vehicles_owner_id ON vehicles (owner_id);
+
+DROP TABLE organisation_members;
+DROP TABLE organisations;
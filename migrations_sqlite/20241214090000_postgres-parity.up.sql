@@ -0,0 +1,197 @@
+-- Organisations, admins, the audit log, log record history, idempotency keys
+-- and the change feed, as kept by Postgres.
+
+-- Foreign keys are checked once the migration commits, so that the vehicles
+-- table can be rebuilt while log records reference it
+PRAGMA defer_foreign_keys = ON;
+
+CREATE TABLE organisations (
+    id BLOB PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE organisation_members (
+    organisation_id BLOB NOT NULL REFERENCES organisations(id) ON DELETE CASCADE,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('owner', 'admin', 'member')),
+    PRIMARY KEY (organisation_id, user_id)
+);
+
+-- SQLite can't drop the check keeping vehicles out of organisations, so the
+-- table is rebuilt without it
+CREATE TABLE vehicles_copy AS SELECT * FROM vehicles;
+DROP TABLE vehicles;
+CREATE TABLE vehicles (
+    id BLOB PRIMARY KEY NOT NULL,
+    owner_id BLOB NOT NULL REFERENCES users(id),
+    make TEXT NOT NULL,
+    model TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    odometer_unit TEXT NOT NULL CHECK (odometer_unit IN ('km', 'mi')),
+    organisation_id BLOB REFERENCES organisations(id) ON DELETE SET NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    deleted_at TEXT
+);
+INSERT INTO vehicles SELECT * FROM vehicles_copy;
+DROP TABLE vehicles_copy;
+
+CREATE INDEX vehicles_owner_id ON vehicles (owner_id);
+CREATE INDEX vehicles_organisation_id ON vehicles (organisation_id);
+
+ALTER TABLE users ADD COLUMN is_admin INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE audit_log (
+    id BLOB PRIMARY KEY NOT NULL,
+    actor_id BLOB,
+    resource_type TEXT NOT NULL,
+    resource_id BLOB,
+    action TEXT NOT NULL,
+    request_id TEXT,
+    occurred_at TEXT NOT NULL
+);
+
+CREATE INDEX audit_log_occurred_at ON audit_log (occurred_at);
+CREATE INDEX audit_log_resource ON audit_log (resource_type, resource_id);
+CREATE INDEX audit_log_actor_id ON audit_log (actor_id);
+
+-- Revisions are append-only, as in Postgres
+CREATE TABLE log_record_revisions (
+    id BLOB PRIMARY KEY NOT NULL,
+    log_record_id BLOB NOT NULL REFERENCES log_records(id) ON DELETE CASCADE,
+    action TEXT NOT NULL,
+    changed_by BLOB,
+    changed_at TEXT NOT NULL,
+    previous TEXT NOT NULL
+);
+
+CREATE INDEX log_record_revisions_log_record_id
+    ON log_record_revisions (log_record_id, changed_at);
+
+CREATE TRIGGER log_record_revisions_append_only
+    BEFORE UPDATE ON log_record_revisions
+BEGIN
+    SELECT RAISE(ABORT, 'log record revisions are append-only');
+END;
+
+CREATE TABLE idempotency_keys (
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    request TEXT NOT NULL,
+    -- NULL until the request that claimed the key has completed
+    resource_id BLOB,
+    created_at TEXT NOT NULL,
+    claimed_at TEXT NOT NULL,
+    PRIMARY KEY (scope, key)
+);
+
+-- SQLite has no snapshots to serve as sync tokens. Writes are serialised
+-- instead, so every written row is numbered from a sequence, which commits
+-- in order, and changes since a token are those numbered after it.
+CREATE TABLE change_sequence (
+    value INTEGER NOT NULL
+);
+INSERT INTO change_sequence (value) VALUES (0);
+
+ALTER TABLE users ADD COLUMN updated_at TEXT;
+ALTER TABLE users ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE vehicles ADD COLUMN updated_at TEXT;
+ALTER TABLE vehicles ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE log_records ADD COLUMN updated_at TEXT;
+ALTER TABLE log_records ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 0;
+
+UPDATE users SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
+UPDATE vehicles SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
+UPDATE log_records SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
+
+CREATE INDEX users_change_seq ON users (change_seq);
+CREATE INDEX vehicles_change_seq ON vehicles (change_seq);
+CREATE INDEX log_records_change_seq ON log_records (change_seq);
+
+-- Writes other than the numbering itself leave the number as it was
+CREATE TRIGGER users_record_insert AFTER INSERT ON users
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    UPDATE users
+    SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        change_seq = (SELECT value FROM change_sequence)
+    WHERE rowid = NEW.rowid;
+END;
+
+CREATE TRIGGER users_record_update AFTER UPDATE ON users
+    WHEN NEW.change_seq = OLD.change_seq
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    UPDATE users
+    SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        change_seq = (SELECT value FROM change_sequence)
+    WHERE rowid = NEW.rowid;
+END;
+
+CREATE TRIGGER vehicles_record_insert AFTER INSERT ON vehicles
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    UPDATE vehicles
+    SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        change_seq = (SELECT value FROM change_sequence)
+    WHERE rowid = NEW.rowid;
+END;
+
+CREATE TRIGGER vehicles_record_update AFTER UPDATE ON vehicles
+    WHEN NEW.change_seq = OLD.change_seq
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    UPDATE vehicles
+    SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        change_seq = (SELECT value FROM change_sequence)
+    WHERE rowid = NEW.rowid;
+END;
+
+CREATE TRIGGER log_records_record_insert AFTER INSERT ON log_records
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    UPDATE log_records
+    SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        change_seq = (SELECT value FROM change_sequence)
+    WHERE rowid = NEW.rowid;
+END;
+
+CREATE TRIGGER log_records_record_update AFTER UPDATE ON log_records
+    WHEN NEW.change_seq = OLD.change_seq
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    UPDATE log_records
+    SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        change_seq = (SELECT value FROM change_sequence)
+    WHERE rowid = NEW.rowid;
+END;
+
+-- Purged rows leave a tombstone behind so replicas can drop them too
+CREATE TABLE purged_rows (
+    resource_type TEXT NOT NULL,
+    resource_id BLOB NOT NULL,
+    purged_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    change_seq INTEGER NOT NULL
+);
+
+CREATE INDEX purged_rows_change_seq ON purged_rows (change_seq);
+
+CREATE TRIGGER users_record_purge AFTER DELETE ON users
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    INSERT INTO purged_rows (resource_type, resource_id, change_seq)
+    VALUES ('user', OLD.id, (SELECT value FROM change_sequence));
+END;
+
+CREATE TRIGGER vehicles_record_purge AFTER DELETE ON vehicles
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    INSERT INTO purged_rows (resource_type, resource_id, change_seq)
+    VALUES ('vehicle', OLD.id, (SELECT value FROM change_sequence));
+END;
+
+CREATE TRIGGER log_records_record_purge AFTER DELETE ON log_records
+BEGIN
+    UPDATE change_sequence SET value = value + 1;
+    INSERT INTO purged_rows (resource_type, resource_id, change_seq)
+    VALUES ('log_record', OLD.id, (SELECT value FROM change_sequence));
+END;
@@ -1,3 +1,11 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use config::ConfigError;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions, PgSslMode},
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+};
+
 use crate::{
     error::ApiError,
     types::{configuration::ServerHost, ServerPort},
@@ -9,12 +17,276 @@ use crate::{
 pub struct ServerConfig {
     pub port: ServerPort,
     pub host: ServerHost,
+    pub shutdown: ShutdownConfig,
+    pub proxy: ProxyConfig,
+    /// Serve HTTPS rather than HTTP
+    #[dummy(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+/// PEM files to serve HTTPS with, which are reloaded whenever they change
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// Certificate chain, starting with the server's certificate
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// Certificate authority of client certificates. When set, clients must
+    /// present a certificate it has signed (mutual TLS).
+    #[serde(default)]
+    pub ca: Option<PathBuf>,
+}
+
+/// The reverse proxy in front of the server
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, Default)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyConfig {
+    /// Accept the `X-User-Id` header, which the proxy must set from the user
+    /// it authenticated and strip from client requests. Nothing verifies the
+    /// header, so without such a proxy any client could claim to be anyone.
+    pub trusted: bool,
+}
+
+/// Stopping the server on SIGTERM or SIGINT
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ShutdownConfig {
+    /// Seconds in-flight requests may take to finish before being cut off
+    #[dummy(faker = "1..120")]
+    pub timeout: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { timeout: 30 }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, Default)]
 #[serde(deny_unknown_fields)]
 pub struct DatabaseConfig {
     pub url: String,
+    /// Apply pending migrations on startup
+    #[serde(default)]
+    pub migrate: bool,
+    /// Postgres replica serving the list and read endpoints of users,
+    /// vehicles and log records
+    #[serde(default)]
+    #[dummy(default)]
+    pub replica: Option<String>,
+    #[serde(default)]
+    pub pool: PoolConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    #[serde(default)]
+    pub ssl: SslConfig,
+}
+
+/// Size of the Postgres and SQLite connection pools
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct PoolConfig {
+    #[dummy(faker = "5..20")]
+    pub max: u32,
+    #[dummy(faker = "0..5")]
+    pub min: u32,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self { max: 5, min: 0 }
+    }
+}
+
+/// Database timeouts in seconds
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct TimeoutConfig {
+    /// How long to wait for a free connection
+    #[dummy(faker = "1..30")]
+    pub acquire: u64,
+    /// Close connections idle for longer
+    #[dummy(faker = "1..3600")]
+    pub idle: Option<u64>,
+    /// Close connections open for longer
+    #[dummy(faker = "1..3600")]
+    pub lifetime: Option<u64>,
+    /// Cancel statements running for longer, which only Postgres supports
+    #[dummy(faker = "1..3600")]
+    pub statement: Option<u64>,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            acquire: 5,
+            idle: None,
+            lifetime: None,
+            statement: None,
+        }
+    }
+}
+
+/// TLS of Postgres connections, overriding the `sslmode` and `sslrootcert`
+/// parameters of the urls
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, Default)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct SslConfig {
+    pub mode: Option<SslMode>,
+    /// Certificate authority to verify the server with
+    #[dummy(default)]
+    pub root: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl From<SslMode> for PgSslMode {
+    fn from(value: SslMode) -> Self {
+        match value {
+            SslMode::Disable => Self::Disable,
+            SslMode::Allow => Self::Allow,
+            SslMode::Prefer => Self::Prefer,
+            SslMode::Require => Self::Require,
+            SslMode::VerifyCa => Self::VerifyCa,
+            SslMode::VerifyFull => Self::VerifyFull,
+        }
+    }
+}
+
+/// Where users, vehicles and log records are stored, chosen by the scheme of
+/// the database url
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DatabaseBackend {
+    Postgres,
+    /// A `sqlite://` file
+    Sqlite,
+    /// The `memory://` url, which loses everything on exit
+    Memory,
+}
+
+impl DatabaseConfig {
+    pub fn backend(&self) -> DatabaseBackend {
+        if self.url.starts_with("memory://") {
+            DatabaseBackend::Memory
+        } else if self.url.starts_with("sqlite:") {
+            DatabaseBackend::Sqlite
+        } else {
+            DatabaseBackend::Postgres
+        }
+    }
+
+    /// Checks settings the types can't, so that misconfiguration stops the
+    /// server from starting rather than surfacing on the first request
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: &str| Err(ConfigError::Message(message.to_owned()));
+
+        if self.pool.max == 0 {
+            return invalid("database.pool.max must be at least 1");
+        }
+        if self.pool.min > self.pool.max {
+            return invalid("database.pool.min must not exceed database.pool.max");
+        }
+        if self.timeouts.acquire == 0 {
+            return invalid("database.timeouts.acquire must be at least 1 second");
+        }
+        if let Some(replica) = &self.replica {
+            if self.backend() != DatabaseBackend::Postgres {
+                return invalid("database.replica requires a Postgres database.url");
+            }
+            let is_postgres =
+                replica.starts_with("postgres://") || replica.starts_with("postgresql://");
+            if !is_postgres || PgConnectOptions::from_str(replica).is_err() {
+                return invalid("database.replica is not a valid Postgres url");
+            }
+        }
+        if let Some(root) = &self.ssl.root {
+            if !root.is_file() {
+                return Err(ConfigError::Message(format!(
+                    "database.ssl.root {} is not a file",
+                    root.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Options of the Postgres pools, shared by the primary and the replica
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.pool.max)
+            .min_connections(self.pool.min)
+            .acquire_timeout(Duration::from_secs(self.timeouts.acquire))
+            .idle_timeout(self.timeouts.idle.map(Duration::from_secs))
+            .max_lifetime(self.timeouts.lifetime.map(Duration::from_secs))
+    }
+
+    /// Options of connections to the Postgres database at `url`, which is
+    /// either [`Self::url`] or [`Self::replica`]
+    pub fn connect_options(&self, url: &str) -> Result<PgConnectOptions, sqlx::Error> {
+        let mut options = PgConnectOptions::from_str(url)?;
+        if let Some(mode) = self.ssl.mode {
+            options = options.ssl_mode(mode.into());
+        }
+        if let Some(root) = &self.ssl.root {
+            options = options.ssl_root_cert(root);
+        }
+        if let Some(statement) = self.timeouts.statement {
+            options = options.options([("statement_timeout", format!("{statement}s"))]);
+        }
+        Ok(options)
+    }
+
+    /// Whether the SQLite url names a database kept in memory, which every
+    /// connection of the pool shares
+    fn sqlite_in_memory(&self) -> bool {
+        self.url.contains(":memory:") || self.url.contains("mode=memory")
+    }
+
+    /// Options of the SQLite pool. An in-memory database is shared through
+    /// SQLite's shared cache, which fails conflicting writes at once rather
+    /// than waiting, so it keeps to a single connection.
+    pub fn sqlite_pool_options(&self) -> SqlitePoolOptions {
+        let (max, min) = if self.sqlite_in_memory() {
+            (1, self.pool.min.min(1))
+        } else {
+            (self.pool.max, self.pool.min)
+        };
+        SqlitePoolOptions::new()
+            .max_connections(max)
+            .min_connections(min)
+            .acquire_timeout(Duration::from_secs(self.timeouts.acquire))
+            .idle_timeout(self.timeouts.idle.map(Duration::from_secs))
+            .max_lifetime(self.timeouts.lifetime.map(Duration::from_secs))
+    }
+
+    /// Options of connections to the SQLite database, creating its file if
+    /// needed. Write-ahead logging lets readers carry on while one connection
+    /// writes, and a writer waits as long for the database lock as requests
+    /// wait for a connection.
+    pub fn sqlite_connect_options(&self) -> Result<SqliteConnectOptions, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(&self.url)?
+            .create_if_missing(true)
+            .busy_timeout(Duration::from_secs(self.timeouts.acquire));
+        if self.sqlite_in_memory() {
+            Ok(options)
+        } else {
+            Ok(options.journal_mode(SqliteJournalMode::Wal))
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, Default)]
@@ -108,7 +380,9 @@ pub fn read_config() -> Result<Configuration, ApiError> {
         )
         .build()?;
 
-    Ok(raw_config.try_deserialize::<Configuration>()?)
+    let config = raw_config.try_deserialize::<Configuration>()?;
+    config.database.validate()?;
+    Ok(config)
 }
 
 #[cfg(test)]
@@ -135,6 +409,132 @@ mod config_tests {
     use fake::{Fake, Faker};
     use test_utils::write_config_to_temp_yaml_file;
 
+    #[test_case::test_case("memory://" => DatabaseBackend::Memory; "memory")]
+    #[test_case::test_case("sqlite://fuel.db" => DatabaseBackend::Sqlite; "sqlite")]
+    #[test_case::test_case("sqlite::memory:" => DatabaseBackend::Sqlite; "sqlite in memory")]
+    #[test_case::test_case("postgres://postgres@localhost/fuel" => DatabaseBackend::Postgres; "postgres")]
+    fn detects_backend_from_url(url: &str) -> DatabaseBackend {
+        DatabaseConfig {
+            url: url.to_owned(),
+            ..Default::default()
+        }
+        .backend()
+    }
+
+    fn postgres_config() -> DatabaseConfig {
+        DatabaseConfig {
+            url: "postgres://postgres@localhost/fuel".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(postgres_config().validate().is_ok());
+    }
+
+    #[test_case::test_case(PoolConfig { max: 0, min: 0 }; "empty pool")]
+    #[test_case::test_case(PoolConfig { max: 2, min: 3 }; "minimum above maximum")]
+    fn rejects_pool_size(pool: PoolConfig) {
+        // Arrange
+        let config = DatabaseConfig {
+            pool,
+            ..postgres_config()
+        };
+
+        // Act
+        let res = config.validate();
+
+        // Assert
+        assert!(res.is_err());
+    }
+
+    #[test_case::test_case("memory://", "postgres://postgres@replica/fuel"; "replica of memory")]
+    #[test_case::test_case("sqlite://fuel.db", "postgres://postgres@replica/fuel"; "replica of sqlite")]
+    #[test_case::test_case("postgres://postgres@localhost/fuel", "mysql://replica"; "invalid replica url")]
+    fn rejects_replica(url: &str, replica: &str) {
+        // Arrange
+        let config = DatabaseConfig {
+            url: url.to_owned(),
+            replica: Some(replica.to_owned()),
+            ..Default::default()
+        };
+
+        // Act
+        let res = config.validate();
+
+        // Assert
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn rejects_missing_root_certificate() {
+        // Arrange
+        let config = DatabaseConfig {
+            ssl: SslConfig {
+                mode: Some(SslMode::VerifyFull),
+                root: Some(PathBuf::from("/nonexistent/root.crt")),
+            },
+            ..postgres_config()
+        };
+
+        // Act
+        let res = config.validate();
+
+        // Assert
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn pool_options_follow_config() {
+        // Arrange
+        let config = DatabaseConfig {
+            pool: PoolConfig { max: 12, min: 2 },
+            timeouts: TimeoutConfig {
+                acquire: 3,
+                idle: Some(60),
+                lifetime: None,
+                statement: Some(30),
+            },
+            ..postgres_config()
+        };
+
+        // Act
+        let options = config.pool_options();
+
+        // Assert
+        assert_eq!(options.get_max_connections(), 12);
+        assert_eq!(options.get_min_connections(), 2);
+        assert_eq!(options.get_acquire_timeout(), Duration::from_secs(3));
+        assert_eq!(options.get_idle_timeout(), Some(Duration::from_secs(60)));
+        assert_eq!(options.get_max_lifetime(), None);
+    }
+
+    #[test_case::test_case("sqlite://fuel.db" => (12, 2); "file")]
+    #[test_case::test_case("sqlite::memory:" => (1, 1); "in memory")]
+    fn sqlite_pool_options_follow_config(url: &str) -> (u32, u32) {
+        // Arrange
+        let config = DatabaseConfig {
+            url: url.to_owned(),
+            pool: PoolConfig { max: 12, min: 2 },
+            timeouts: TimeoutConfig {
+                acquire: 3,
+                idle: Some(60),
+                lifetime: None,
+                statement: None,
+            },
+            ..Default::default()
+        };
+
+        // Act
+        let options = config.sqlite_pool_options();
+
+        // Assert
+        assert_eq!(options.get_acquire_timeout(), Duration::from_secs(3));
+        assert_eq!(options.get_idle_timeout(), Some(Duration::from_secs(60)));
+        (options.get_max_connections(), options.get_min_connections())
+    }
+
     #[test_case::test_case(LogLevel::TRACE => "\"trace\"".to_owned())]
     #[test_case::test_case(LogLevel::DEBUG => "\"debug\"".to_owned())]
     #[test_case::test_case(LogLevel::INFO => "\"info\"".to_owned())]
@@ -227,6 +627,10 @@ mod config_tests {
                 ("VL__SERVER_PORT", Some(config.server.port.to_string())),
                 ("VL__SERVER_HOST", Some(config.server.host.to_string())),
                 ("VL__DATABASE_URL", Some(config.database.url.to_string())),
+                (
+                    "VL__DATABASE_MIGRATE",
+                    Some(config.database.migrate.to_string()),
+                ),
                 ("VL__LOG_LEVEL", Some(config.log.level.to_string())),
                 ("VL__LOG_FORMAT", Some(config.log.format.to_string())),
             ],
@@ -238,6 +642,7 @@ mod config_tests {
                 assert_eq!(loaded_config.server.port, config.server.port);
                 assert_eq!(loaded_config.server.host, config.server.host);
                 assert_eq!(loaded_config.database.url, config.database.url);
+                assert_eq!(loaded_config.database.migrate, config.database.migrate);
                 assert_eq!(loaded_config.log.level, config.log.level);
                 assert_eq!(loaded_config.log.format, config.log.format);
             },
@@ -262,7 +667,10 @@ mod config_tests {
                 assert_eq!(
                     loaded_config,
                     Configuration {
-                        database: DatabaseConfig { url: db_url },
+                        database: DatabaseConfig {
+                            url: db_url,
+                            ..Default::default()
+                        },
                         ..Default::default()
                     }
                 )
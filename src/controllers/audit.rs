@@ -0,0 +1,138 @@
+use uuid::Uuid;
+
+use crate::{
+    controllers::user::ensure_admin,
+    error::ApiError,
+    models::api::{ListAuditEntriesParams, ListAuditEntriesResponse},
+    repositories::{AuditRepository, UserRepository},
+    types::{AuditAction, AuditResource},
+};
+
+const DEFAULT_LIST_LIMIT: i64 = 100;
+const MAX_LIST_LIMIT: i64 = 1000;
+
+#[tracing::instrument(name = "audit_controller_record", skip(repository), err)]
+pub async fn record(
+    repository: &dyn AuditRepository,
+    actor_id: Option<Uuid>,
+    resource_type: AuditResource,
+    resource_id: Option<Uuid>,
+    action: AuditAction,
+    request_id: Option<&str>,
+) -> Result<(), ApiError> {
+    tracing::debug!("recording audit entry");
+    repository
+        .record(actor_id, resource_type, resource_id, action, request_id)
+        .await
+}
+
+#[tracing::instrument(name = "audit_controller_list", skip(users, repository), err)]
+pub async fn list(
+    users: &dyn UserRepository,
+    repository: &dyn AuditRepository,
+    actor_id: Option<Uuid>,
+    params: ListAuditEntriesParams,
+) -> Result<ListAuditEntriesResponse, ApiError> {
+    ensure_admin(users, actor_id).await?;
+
+    tracing::debug!("listing audit entries");
+    let params = ListAuditEntriesParams {
+        limit: Some(
+            params
+                .limit
+                .unwrap_or(DEFAULT_LIST_LIMIT)
+                .clamp(0, MAX_LIST_LIMIT),
+        ),
+        ..params
+    };
+    let entries = repository.list(&params).await?;
+    tracing::info!("number of audit entries found: {}", entries.len());
+
+    Ok(entries.into_iter().map(Into::into).collect())
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+    use crate::utils::test_utils::db::seed_user;
+    use sqlx::{query, PgPool};
+
+    async fn make_admin(pool: &PgPool, user_id: &Uuid) {
+        query("UPDATE users SET is_admin = true WHERE id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .expect("could not make user an admin");
+    }
+
+    #[sqlx::test]
+    async fn can_record_and_filter(pool: PgPool) {
+        // Arrange
+        let admin_id = seed_user(&pool).await;
+        make_admin(&pool, &admin_id).await;
+        let vehicle_id = Uuid::new_v4();
+        record(
+            &pool,
+            Some(admin_id),
+            AuditResource::Vehicle,
+            Some(vehicle_id),
+            AuditAction::Update,
+            Some("request-1"),
+        )
+        .await
+        .expect("could not record audit entry");
+        record(
+            &pool,
+            None,
+            AuditResource::User,
+            Some(Uuid::new_v4()),
+            AuditAction::Create,
+            None,
+        )
+        .await
+        .expect("could not record audit entry");
+
+        // Act
+        let res = list(
+            &pool,
+            &pool,
+            Some(admin_id),
+            ListAuditEntriesParams {
+                resource_type: Some(AuditResource::Vehicle),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("could not list audit entries");
+
+        // Assert
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].actor_id, Some(admin_id));
+        assert_eq!(res[0].resource_id, Some(vehicle_id));
+        assert_eq!(res[0].action, AuditAction::Update);
+        assert_eq!(res[0].request_id.as_deref(), Some("request-1"));
+    }
+
+    #[sqlx::test]
+    async fn list_requires_admin(pool: PgPool) {
+        // Arrange
+        let user_id = seed_user(&pool).await;
+
+        // Act
+        let anonymous = list(&pool, &pool, None, ListAuditEntriesParams::default())
+            .await
+            .expect_err("expected_failure_did_not_occur");
+        let non_admin = list(
+            &pool,
+            &pool,
+            Some(user_id),
+            ListAuditEntriesParams::default(),
+        )
+        .await
+        .expect_err("expected_failure_did_not_occur");
+
+        // Assert
+        assert!(matches!(anonymous, ApiError::AdminRequired));
+        assert!(matches!(non_admin, ApiError::AdminRequired));
+    }
+}
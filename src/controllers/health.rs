@@ -0,0 +1,74 @@
+use crate::{error::ApiError, models::api::HealthResponse, repositories::HealthRepository};
+
+/// Ready once the database, and its read replica if there is one, answer
+/// queries and have every embedded migration applied, so that new instances
+/// don't serve an outdated schema
+#[tracing::instrument(name = "health_controller_ready", skip_all, err)]
+pub async fn ready(
+    database: &dyn HealthRepository,
+    replica: Option<&dyn HealthRepository>,
+) -> Result<HealthResponse, ApiError> {
+    database.ready().await?;
+    if let Some(replica) = replica {
+        replica.ready().await.map_err(|e| match e {
+            ApiError::Unavailable(msg) => ApiError::Unavailable(format!("read replica: {msg}")),
+            e => e,
+        })?;
+    }
+
+    Ok(HealthResponse::ok())
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+    use crate::migrations;
+    use sqlx::{postgres::PgPoolOptions, query, PgPool};
+
+    #[sqlx::test]
+    async fn ready_when_migrated(pool: PgPool) {
+        // Act
+        let res = ready(&pool, None).await;
+
+        // Assert
+        assert_eq!(res.expect("should be ready"), HealthResponse::ok());
+    }
+
+    #[sqlx::test]
+    async fn not_ready_with_pending_migration(pool: PgPool) {
+        // Arrange
+        let latest = migrations::MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .expect("no migrations");
+        query("DELETE FROM _sqlx_migrations WHERE version = $1")
+            .bind(latest)
+            .execute(&pool)
+            .await
+            .expect("could not delete migration record");
+
+        // Act
+        let res = ready(&pool, None).await;
+
+        // Assert
+        assert!(
+            matches!(res, Err(ApiError::Unavailable(msg)) if msg.contains(&latest.to_string()))
+        );
+    }
+
+    #[sqlx::test]
+    async fn not_ready_without_replica(pool: PgPool) {
+        // Arrange
+        let replica = PgPoolOptions::new().connect_lazy_with((*pool.connect_options()).clone());
+        replica.close().await;
+
+        // Act
+        let res = ready(&pool, Some(&replica)).await;
+
+        // Assert
+        assert!(
+            matches!(res, Err(ApiError::Unavailable(msg)) if msg == "read replica: the database is unreachable")
+        );
+    }
+}
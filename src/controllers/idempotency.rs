@@ -0,0 +1,271 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::api::Idempotent,
+    repositories::{Claim, IdempotencyRepository},
+};
+
+/// What a request is identified by, besides its idempotency key
+#[derive(Serialize)]
+struct Request<'a, P, B> {
+    params: &'a P,
+    body: &'a B,
+}
+
+/// Runs `create` at most once per idempotency key within `scope`. Retries
+/// with the same key and request, made of its query `params` and `body`,
+/// replay the id of the originally created resource. Requests without a key
+/// always create.
+pub async fn create_once<P, B, F, Fut>(
+    repository: &dyn IdempotencyRepository,
+    scope: &'static str,
+    key: Option<String>,
+    params: P,
+    body: B,
+    create: F,
+) -> Result<Idempotent<Uuid>, ApiError>
+where
+    P: Serialize,
+    B: Serialize,
+    F: FnOnce(P, B) -> Fut,
+    Fut: Future<Output = Result<Uuid, ApiError>>,
+{
+    let Some(key) = key else {
+        return Ok(Idempotent {
+            response: create(params, body).await?,
+            replayed: false,
+        });
+    };
+
+    let request = serde_json::to_value(Request {
+        params: &params,
+        body: &body,
+    })
+    .map_err(|e| ApiError::Conversion(e.to_string()))?;
+    let claimed_at = match repository.claim(scope, &key, &request).await? {
+        Claim::New(claimed_at) => claimed_at,
+        Claim::Completed(resource_id) => {
+            tracing::info!(%resource_id, "replaying idempotent request");
+            return Ok(Idempotent {
+                response: resource_id,
+                replayed: true,
+            });
+        }
+    };
+
+    match create(params, body).await {
+        Ok(resource_id) => {
+            if let Err(e) = repository
+                .complete(scope, &key, &claimed_at, &resource_id)
+                .await
+            {
+                // The resource was created all the same, so it is reported.
+                // Releasing the key keeps retries from waiting out the lease.
+                tracing::error!(%resource_id, error = %e, "could not complete idempotent request");
+                release(repository, scope, &key, &claimed_at).await;
+            }
+            Ok(Idempotent {
+                response: resource_id,
+                replayed: false,
+            })
+        }
+        Err(e) => {
+            // Failed requests may be retried with the same key
+            release(repository, scope, &key, &claimed_at).await;
+            Err(e)
+        }
+    }
+}
+
+/// Gives up a claim, logging rather than returning a failure to do so, which
+/// only delays retries until the lease runs out
+async fn release(
+    repository: &dyn IdempotencyRepository,
+    scope: &'static str,
+    key: &str,
+    claimed_at: &DateTime<Utc>,
+) {
+    if let Err(e) = repository.release(scope, key, claimed_at).await {
+        tracing::error!(error = %e, "could not release idempotency key");
+    }
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+    use sqlx::{query, PgPool};
+    use tokio::sync::oneshot;
+
+    #[sqlx::test]
+    async fn dropped_request_releases_key_after_lease(pool: PgPool) {
+        // Arrange
+        let (claimed_tx, claimed_rx) = oneshot::channel();
+        let dropped = create_once(
+            &pool,
+            "tests",
+            Some("key".to_owned()),
+            (),
+            1,
+            |_, _| async move {
+                claimed_tx.send(()).expect("could not signal claim");
+                std::future::pending::<Result<Uuid, ApiError>>().await
+            },
+        );
+        tokio::select! {
+            _ = dropped => unreachable!("the request never completes"),
+            _ = claimed_rx => {}
+        }
+        let resource_id = Uuid::new_v4();
+
+        // Act
+        let in_progress_err = create_once(
+            &pool,
+            "tests",
+            Some("key".to_owned()),
+            (),
+            1,
+            |_, _| async { Ok(Uuid::new_v4()) },
+        )
+        .await
+        .expect_err("expected failure did not occur");
+        query("UPDATE idempotency_keys SET claimed_at = claimed_at - INTERVAL '1 minute'")
+            .execute(&pool)
+            .await
+            .expect("could not age claim");
+        let retried = create_once(
+            &pool,
+            "tests",
+            Some("key".to_owned()),
+            (),
+            1,
+            |_, _| async { Ok(resource_id) },
+        )
+        .await
+        .expect("could not take over key");
+        let replayed = create_once(
+            &pool,
+            "tests",
+            Some("key".to_owned()),
+            (),
+            1,
+            |_, _| async { Ok(Uuid::new_v4()) },
+        )
+        .await
+        .expect("could not replay request");
+
+        // Assert
+        assert!(matches!(in_progress_err, ApiError::Conflict(_)));
+        assert_eq!(retried.response, resource_id);
+        assert!(!retried.replayed);
+        assert_eq!(replayed.response, resource_id);
+        assert!(replayed.replayed);
+    }
+
+    #[sqlx::test]
+    async fn keys_released_while_claiming_are_claimed_again(pool: PgPool) {
+        // Arrange
+        let mut requests = tokio::task::JoinSet::new();
+        for _ in 0..20 {
+            let pool = pool.clone();
+            requests.spawn(async move {
+                create_once(
+                    &pool,
+                    "tests",
+                    Some("key".to_owned()),
+                    (),
+                    1,
+                    |_, _| async {
+                        Err::<Uuid, _>(ApiError::Conflict("could not create".to_owned()))
+                    },
+                )
+                .await
+            });
+        }
+
+        // Act
+        let mut errors = Vec::new();
+        while let Some(result) = requests.join_next().await {
+            errors.push(
+                result
+                    .expect("request panicked")
+                    .expect_err("expected failure did not occur"),
+            );
+        }
+
+        // Assert
+        for err in errors {
+            assert!(
+                matches!(err, ApiError::Conflict(_)),
+                "unexpected error: {err}"
+            );
+        }
+    }
+
+    /// Idempotency keys whose requests can never be completed
+    #[derive(Debug)]
+    struct FailingCompletions(PgPool);
+
+    #[axum::async_trait]
+    impl IdempotencyRepository for FailingCompletions {
+        async fn claim(
+            &self,
+            scope: &'static str,
+            key: &str,
+            request: &serde_json::Value,
+        ) -> Result<Claim, ApiError> {
+            self.0.claim(scope, key, request).await
+        }
+
+        async fn complete(
+            &self,
+            _scope: &'static str,
+            _key: &str,
+            _claimed_at: &DateTime<Utc>,
+            _resource_id: &Uuid,
+        ) -> Result<(), ApiError> {
+            Err(ApiError::Unavailable("could not complete".to_owned()))
+        }
+
+        async fn release(
+            &self,
+            scope: &'static str,
+            key: &str,
+            claimed_at: &DateTime<Utc>,
+        ) -> Result<(), ApiError> {
+            self.0.release(scope, key, claimed_at).await
+        }
+    }
+
+    #[sqlx::test]
+    async fn failed_completion_reports_resource_and_releases_key(pool: PgPool) {
+        // Arrange
+        let resource_id = Uuid::new_v4();
+        let repository = FailingCompletions(pool.clone());
+
+        // Act
+        let created = create_once(
+            &repository,
+            "tests",
+            Some("key".to_owned()),
+            (),
+            1,
+            |_, _| async { Ok(resource_id) },
+        )
+        .await
+        .expect("could not create resource");
+        let keys = query("SELECT key FROM idempotency_keys")
+            .fetch_all(&pool)
+            .await
+            .expect("could not read idempotency keys");
+
+        // Assert
+        assert_eq!(created.response, resource_id);
+        assert!(!created.replayed);
+        assert!(keys.is_empty());
+    }
+}
@@ -1,244 +1,256 @@
-use sqlx::{postgres::PgRow, query, query_as, FromRow, PgPool, QueryBuilder, Row};
+use axum::http::StatusCode;
 use uuid::Uuid;
 
 use crate::{
     error::ApiError,
     models::{
         api::{
-            CreateLogRecordBody, CreateLogRecordResponse, DeleteLogRecordResponse,
-            ListLogRecordsResponse, ReadLogRecordResponse, UpdateLogRecordBody,
-            UpdateLogRecordResponse,
+            BatchLogRecordsBody, BatchLogRecordsResponse, CreateLogRecordBody,
+            CreateLogRecordParams, CreateLogRecordResponse, DeleteLogRecordResponse,
+            ListLogRecordDuplicatesParams, ListLogRecordDuplicatesResponse,
+            ListLogRecordRevisionsResponse, ListLogRecordsResponse, LogRecordOperation,
+            LogRecordOperationResult, PatchLogRecordBody, PatchLogRecordResponse,
+            PurgeLogRecordResponse, ReadLogRecordResponse, RestoreLogRecordResponse,
+            UpdateLogRecordBody, UpdateLogRecordResponse,
         },
         db::LogRecord as DbLogRecord,
     },
-    types::LogType,
+    repositories::LogRecordRepository,
+    types::{EntityTags, Validate},
 };
 
-#[tracing::instrument(name = "log_record_controller_read", skip(pool), err)]
-pub async fn read(pool: &PgPool, id: &Uuid) -> Result<ReadLogRecordResponse, ApiError> {
+#[tracing::instrument(name = "log_record_controller_read", skip(repository), err)]
+pub async fn read(
+    repository: &dyn LogRecordRepository,
+    id: &Uuid,
+) -> Result<ReadLogRecordResponse, ApiError> {
     tracing::debug!("reading log record");
-    let sql = "SELECT * FROM log_records WHERE id = $1";
-    let log_record = query_as::<_, DbLogRecord>(sql)
-        .bind(id)
-        .fetch_one(pool)
-        .await?;
+    let log_record = repository.find(id).await?;
     tracing::info!(?log_record, "found log record");
     log_record.try_into()
 }
 
-#[tracing::instrument(name = "log_record_controller_list", skip(pool), err)]
-pub async fn list(pool: &PgPool) -> Result<ListLogRecordsResponse, ApiError> {
+#[tracing::instrument(name = "log_record_controller_list", skip(repository), err)]
+pub async fn list(
+    repository: &dyn LogRecordRepository,
+) -> Result<ListLogRecordsResponse, ApiError> {
     tracing::debug!("listing log records");
-    let sql = "SELECT * FROM log_records";
-    let log_records = sqlx::query_as::<_, DbLogRecord>(sql)
-        .fetch_all(pool)
-        .await?;
+    let log_records = repository.list().await?;
     tracing::info!("number of log records found: {}", log_records.len());
     log_records.into_iter().map(TryInto::try_into).collect()
 }
 
-#[tracing::instrument(name = "log_record_controller_create", skip(pool), err)]
+#[tracing::instrument(name = "log_record_controller_create", skip(repository), err)]
 pub async fn create(
-    pool: &PgPool,
+    repository: &dyn LogRecordRepository,
     body: CreateLogRecordBody,
+    params: CreateLogRecordParams,
 ) -> Result<CreateLogRecordResponse, ApiError> {
     tracing::debug!("creating log record");
+    body.validate()?;
     let log_record = DbLogRecord::from_api_type(&Uuid::new_v4(), body)?;
-
-    // Initialize query builder with INSERT statement
-    let mut qb = QueryBuilder::<sqlx::Postgres>::new("INSERT INTO log_records(");
-    let mut separated = qb.separated(", ");
-
-    // Add common fields
-    separated.push("id");
-    separated.push("vehicle_id");
-    separated.push("log_date");
-    separated.push("odometer");
-    separated.push("log_type");
-    separated.push("notes");
-
-    // Conditionally add columns based on log type
-    tracing::debug!("building query for log_type={}", log_record.log_type);
-    match log_record.log_type {
-        LogType::FuelUp { .. } => {
-            separated.push("fuel_amount");
-        }
-        LogType::TireChange { ref rotation, .. } => {
-            separated.push("tire_type");
-            separated.push("new_tires");
-            if rotation.is_some() {
-                separated.push("tire_rotation_type");
-            }
-        }
-        LogType::BrakeReplacement { .. } => {
-            separated.push("brake_location");
-            separated.push("brake_part");
-        }
-        LogType::Fluids(_) => {
-            separated.push("fluid_type");
-        }
-        LogType::TireRotation(_) => {
-            separated.push("tire_rotation_type");
-        }
-        _ => {}
-    }
-
-    // Begin VALUES
-    separated.push_unseparated(") VALUES (");
-
-    // Push bindings for common required fields
-    separated.push_bind_unseparated(log_record.id);
-    separated.push_bind(log_record.vehicle_id);
-    separated.push_bind(log_record.date);
-    separated.push_bind(log_record.odometer);
-    separated.push_bind(log_record.log_type.to_string());
-    separated.push_bind(log_record.notes);
-
-    // Push bindings for type-specific fields
-    match log_record.log_type {
-        LogType::FuelUp { fuel_amount } => {
-            separated.push_bind(fuel_amount);
-        }
-        LogType::TireChange {
-            rotation,
-            tire_type,
-            new,
-        } => {
-            separated.push_bind(tire_type);
-            separated.push_bind(new);
-            if let Some(rotation_type) = rotation {
-                separated.push_bind(rotation_type);
-            }
-        }
-        LogType::BrakeReplacement {
-            location,
-            component,
-        } => {
-            separated.push_bind(location);
-            separated.push_bind(component);
-        }
-        LogType::Fluids(fluid_type) => {
-            separated.push_bind(fluid_type);
-        }
-        LogType::TireRotation(rotation_type) => {
-            separated.push_bind(rotation_type);
-        }
-        _ => {}
-    }
-
-    // Terminate statement
-    separated.push_unseparated(") RETURNING id");
-
-    // Build and execute
-    let query_to_execute = qb.build();
-    let res = query_to_execute.fetch_one(pool).await?;
-    tracing::debug!(?res, "new log_record row inserted");
-
-    let id = res.try_get::<Uuid, _>("id")?;
+    let id = repository
+        .insert(&log_record, params.allow_duplicate)
+        .await?;
+    tracing::debug!(?log_record, "new log record inserted");
 
     Ok(CreateLogRecordResponse { id })
 }
 
-#[tracing::instrument(name = "log_record_controller_update", skip(pool), err)]
+#[tracing::instrument(name = "log_record_controller_duplicates", skip(repository), err)]
+pub async fn duplicates(
+    repository: &dyn LogRecordRepository,
+    params: ListLogRecordDuplicatesParams,
+) -> Result<ListLogRecordDuplicatesResponse, ApiError> {
+    tracing::debug!("listing suspected duplicate log records");
+    let duplicates = repository.duplicates(params.vehicle_id).await?;
+    tracing::info!("number of suspected duplicates found: {}", duplicates.len());
+    duplicates.into_iter().map(TryInto::try_into).collect()
+}
+
+#[tracing::instrument(name = "log_record_controller_update", skip(repository), err)]
 pub async fn update(
-    pool: &PgPool,
+    repository: &dyn LogRecordRepository,
     log_record_id: &Uuid,
     body: UpdateLogRecordBody,
+    changed_by: Option<Uuid>,
+    if_match: Option<EntityTags>,
 ) -> Result<UpdateLogRecordResponse, ApiError> {
-    tracing::debug!("reading existing value to determine log type");
-    let existing_val = read(pool, log_record_id).await?;
-    if std::mem::discriminant(&body.log_type) == std::mem::discriminant(&existing_val.log_type) {
-        tracing::debug!(?body.log_type, "incoming log_type matches existing type");
-        let log_record = DbLogRecord::from_api_type(log_record_id, body)?;
-        let mut qb = QueryBuilder::<sqlx::Postgres>::new("UPDATE log_records SET ");
-        let mut separated = qb.separated(", ");
-
-        separated.push("log_date = ");
-        separated.push_bind_unseparated(log_record.date);
-        separated.push("odometer = ");
-        separated.push_bind_unseparated(log_record.odometer);
-        separated.push("log_type = ");
-        separated.push_bind_unseparated(log_record.log_type.to_string());
-        separated.push("notes = ");
-        separated.push_bind_unseparated(log_record.notes);
-
-        // Push bindings for type-specific fields
-        match log_record.log_type {
-            LogType::FuelUp { fuel_amount } => {
-                separated.push("fuel_amount = ");
-                separated.push_bind_unseparated(fuel_amount);
-            }
-            LogType::TireChange {
-                rotation,
-                tire_type,
-                new,
-            } => {
-                separated.push("tire_type = ");
-                separated.push_bind_unseparated(tire_type);
-                separated.push("new_tires = ");
-                separated.push_bind_unseparated(new);
-                if let Some(rotation_type) = rotation {
-                    separated.push("tire_rotation_type = ");
-                    separated.push_bind_unseparated(rotation_type);
-                }
-            }
-            LogType::BrakeReplacement {
-                location,
-                component,
-            } => {
-                separated.push("brake_location = ");
-                separated.push_bind_unseparated(location);
-                separated.push("brake_part = ");
-                separated.push_bind_unseparated(component);
-            }
-            LogType::Fluids(fluid_type) => {
-                separated.push("fluid_type = ");
-                separated.push_bind_unseparated(fluid_type);
-            }
-            LogType::TireRotation(rotation_type) => {
-                separated.push("tire_rotation_type = ");
-                separated.push_bind_unseparated(rotation_type);
-            }
-            _ => {}
-        }
-
-        qb.push(" WHERE id = ");
-        qb.push_bind(log_record_id);
-        qb.push(" RETURNING *");
+    body.validate()?;
+    let log_record = DbLogRecord::from_api_type(log_record_id, body)?;
+    let updated_record = repository
+        .update(&log_record, changed_by, if_match.as_ref())
+        .await?;
 
-        let q = qb.build();
-        let updated_record = <DbLogRecord as FromRow<PgRow>>::from_row(&q.fetch_one(pool).await?)?;
+    updated_record.try_into()
+}
 
-        updated_record.try_into()
-    } else {
-        // If types don't match, record shouldn't be updated
-        tracing::debug!(?existing_val.log_type, ?body.log_type, "incoming log_type is different from existing type");
-        Err(ApiError::WrongLogRecordType)
-    }
+#[tracing::instrument(name = "log_record_controller_patch", skip(repository), err)]
+pub async fn patch(
+    repository: &dyn LogRecordRepository,
+    log_record_id: &Uuid,
+    body: PatchLogRecordBody,
+    changed_by: Option<Uuid>,
+    if_match: Option<EntityTags>,
+) -> Result<PatchLogRecordResponse, ApiError> {
+    tracing::debug!("patching log record");
+    let existing_val = read(repository, log_record_id).await?;
+    // The patch applies to the version read, so a concurrent write fails it
+    // rather than being overwritten
+    let if_match = if_match.unwrap_or(EntityTags::Versions(vec![existing_val.version]));
+    update(
+        repository,
+        log_record_id,
+        body.apply_to(&existing_val)?,
+        changed_by,
+        Some(if_match),
+    )
+    .await
 }
 
-#[tracing::instrument(name = "log_record_controller_delete", skip(pool), err)]
+#[tracing::instrument(name = "log_record_controller_delete", skip(repository), err)]
 pub async fn delete(
-    pool: &PgPool,
+    repository: &dyn LogRecordRepository,
     log_record_id: &Uuid,
+    changed_by: Option<Uuid>,
+    if_match: Option<EntityTags>,
 ) -> Result<DeleteLogRecordResponse, ApiError> {
     tracing::debug!("deleting log record");
-    let sql = "DELETE FROM log_records WHERE id = $1 RETURNING *";
-    let res = query(sql)
-        .bind(log_record_id)
-        .fetch_one(pool)
-        .await
-        .map(|_| DeleteLogRecordResponse)?;
+    repository
+        .delete(log_record_id, changed_by, if_match.as_ref())
+        .await?;
     tracing::info!("log record deleted");
-    Ok(res)
+
+    Ok(DeleteLogRecordResponse)
+}
+
+#[tracing::instrument(name = "log_record_controller_history", skip(repository), err)]
+pub async fn history(
+    repository: &dyn LogRecordRepository,
+    log_record_id: &Uuid,
+) -> Result<ListLogRecordRevisionsResponse, ApiError> {
+    tracing::debug!("listing log record revisions");
+    let revisions = repository.history(log_record_id).await?;
+    tracing::info!("number of revisions found: {}", revisions.len());
+
+    Ok(revisions.into_iter().map(Into::into).collect())
+}
+
+#[tracing::instrument(name = "log_record_controller_restore", skip(repository), err)]
+pub async fn restore(
+    repository: &dyn LogRecordRepository,
+    log_record_id: &Uuid,
+) -> Result<RestoreLogRecordResponse, ApiError> {
+    tracing::debug!("restoring log record");
+    let restored_record = repository.restore(log_record_id).await?;
+    tracing::info!(?restored_record, "log record restored");
+
+    restored_record.try_into()
+}
+
+#[tracing::instrument(name = "log_record_controller_purge", skip(repository), err)]
+pub async fn purge(
+    repository: &dyn LogRecordRepository,
+    log_record_id: &Uuid,
+) -> Result<PurgeLogRecordResponse, ApiError> {
+    tracing::debug!("purging log record");
+    repository.purge(log_record_id).await?;
+    tracing::info!("log record purged");
+
+    Ok(PurgeLogRecordResponse)
+}
+
+/// The most operations accepted in a single batch
+const MAX_BATCH_OPERATIONS: usize = 100;
+
+#[tracing::instrument(name = "log_record_controller_batch", skip(repository, body), err)]
+pub async fn batch(
+    repository: &dyn LogRecordRepository,
+    body: BatchLogRecordsBody,
+    changed_by: Option<Uuid>,
+) -> Result<BatchLogRecordsResponse, ApiError> {
+    tracing::debug!(
+        "running batch of {} log record operations",
+        body.operations.len()
+    );
+    if body.operations.len() > MAX_BATCH_OPERATIONS {
+        return Err(ApiError::InvalidRequest(format!(
+            "a batch may contain at most {MAX_BATCH_OPERATIONS} operations"
+        )));
+    }
+
+    // A failed operation is undone by the batch without affecting the others
+    let batch = repository.begin().await?;
+    let mut results = Vec::with_capacity(body.operations.len());
+    let mut failed = false;
+    for operation in body.operations {
+        if failed && body.atomic {
+            results.push(LogRecordOperationResult::skipped());
+            continue;
+        }
+        let result = run_operation(batch.log_records(), operation, changed_by).await;
+        failed |= !result.is_success();
+        results.push(result);
+    }
+
+    // Dropping the batch rolls it back
+    let committed = !(failed && body.atomic);
+    if committed {
+        batch.commit().await?;
+    }
+    tracing::info!(committed, "log record batch finished");
+
+    Ok(BatchLogRecordsResponse { committed, results })
+}
+
+async fn run_operation(
+    repository: &dyn LogRecordRepository,
+    operation: LogRecordOperation,
+    changed_by: Option<Uuid>,
+) -> LogRecordOperationResult {
+    let if_match =
+        |version: Option<i64>| version.map(|version| EntityTags::Versions(vec![version]));
+    let outcome = match operation {
+        LogRecordOperation::Create {
+            body,
+            allow_duplicate,
+        } => create(repository, body, CreateLogRecordParams { allow_duplicate })
+            .await
+            .map(|res| (StatusCode::CREATED, res.id)),
+        LogRecordOperation::Update { id, body, version } => {
+            update(repository, &id, body, changed_by, if_match(version))
+                .await
+                .map(|_| (StatusCode::OK, id))
+        }
+        LogRecordOperation::Patch { id, body, version } => {
+            patch(repository, &id, body, changed_by, if_match(version))
+                .await
+                .map(|_| (StatusCode::OK, id))
+        }
+        LogRecordOperation::Delete { id, version } => {
+            delete(repository, &id, changed_by, if_match(version))
+                .await
+                .map(|_| (StatusCode::NO_CONTENT, id))
+        }
+    };
+
+    match outcome {
+        Ok((status, id)) => LogRecordOperationResult::succeeded(status, id),
+        Err(e) => LogRecordOperationResult::failed(e),
+    }
 }
 
 #[cfg(test)]
 mod database_tests {
     use super::*;
-    use crate::utils::test_utils::db::seed_user_and_vehicle;
+    use crate::{
+        types::{LogType, RevisionAction},
+        utils::test_utils::db::seed_user_and_vehicle,
+    };
+    use chrono::{Duration, Utc};
     use fake::{Fake, Faker};
     use itertools::izip;
+    use sqlx::{query, PgPool, Row};
 
     #[sqlx::test]
     async fn can_create_and_read(pool: PgPool) {
@@ -254,9 +266,13 @@ mod database_tests {
         };
 
         // Act
-        let res = create(&pool, log_record_body.clone())
-            .await
-            .expect("could not create resource");
+        let res = create(
+            &pool,
+            log_record_body.clone(),
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
         let created_result = read(&pool, &res.id).await.expect("could not read resource");
 
         // Assert
@@ -294,12 +310,20 @@ mod database_tests {
         };
 
         // Act
-        create(&pool, log_record_body_1.clone())
-            .await
-            .expect("could not create resource");
-        create(&pool, log_record_body_2.clone())
-            .await
-            .expect("could not create resource");
+        create(
+            &pool,
+            log_record_body_1.clone(),
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
+        create(
+            &pool,
+            log_record_body_2.clone(),
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
         let created_result = list(&pool).await.expect("could not list resources");
 
         // Assert
@@ -341,10 +365,14 @@ mod database_tests {
         };
 
         // Act
-        let res = create(&pool, initial_log_record_body.clone())
-            .await
-            .expect("could not create resource");
-        let updated_result = update(&pool, &res.id, updated_log_record_body.clone())
+        let res = create(
+            &pool,
+            initial_log_record_body.clone(),
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
+        let updated_result = update(&pool, &res.id, updated_log_record_body.clone(), None, None)
             .await
             .expect("could not update resource");
 
@@ -362,6 +390,39 @@ mod database_tests {
         assert_eq!(updated_result.log_type, updated_log_record_body.log_type);
     }
 
+    #[sqlx::test]
+    async fn can_patch_type_specific_fields(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let initial_log_record_body = CreateLogRecordBody {
+            vehicle_id,
+            log_type: LogType::FuelUp { fuel_amount: 20.0 },
+            ..Faker.fake()
+        };
+        let patch_body = serde_json::from_value(serde_json::json!({"fuel_amount": 42.5}))
+            .expect("could not deserialize patch");
+
+        // Act
+        let res = create(
+            &pool,
+            initial_log_record_body.clone(),
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
+        let patched_result = patch(&pool, &res.id, patch_body, None, None)
+            .await
+            .expect("could not patch resource");
+
+        // Assert
+        assert_eq!(
+            patched_result.log_type,
+            LogType::FuelUp { fuel_amount: 42.5 }
+        );
+        assert_eq!(patched_result.odometer, initial_log_record_body.odometer);
+        assert_eq!(patched_result.notes, initial_log_record_body.notes);
+    }
+
     #[sqlx::test]
     async fn can_delete(pool: PgPool) {
         // Arrange
@@ -372,11 +433,15 @@ mod database_tests {
         };
 
         // Act
-        let res = create(&pool, log_record_body.clone())
-            .await
-            .expect("could not create resource");
+        let res = create(
+            &pool,
+            log_record_body.clone(),
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
         read(&pool, &res.id).await.expect("could not read resource");
-        delete(&pool, &res.id)
+        delete(&pool, &res.id, None, None)
             .await
             .expect("could not read resource");
         let created_result = read(&pool, &res.id)
@@ -385,4 +450,346 @@ mod database_tests {
 
         assert!(matches!(created_result, ApiError::ResourceNotFound));
     }
+
+    #[sqlx::test]
+    async fn update_and_delete_record_revisions(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let changed_by = Uuid::new_v4();
+        let log_type = Faker.fake::<LogType>();
+        let initial_log_record_body = CreateLogRecordBody {
+            vehicle_id,
+            log_type: log_type.clone(),
+            ..Faker.fake()
+        };
+        let updated_log_record_body = CreateLogRecordBody {
+            vehicle_id,
+            log_type,
+            ..Faker.fake()
+        };
+        let res = create(
+            &pool,
+            initial_log_record_body,
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
+        let original = read(&pool, &res.id).await.expect("could not read resource");
+
+        // Act
+        let updated = update(
+            &pool,
+            &res.id,
+            updated_log_record_body,
+            Some(changed_by),
+            None,
+        )
+        .await
+        .expect("could not update resource");
+        delete(&pool, &res.id, None, None)
+            .await
+            .expect("could not delete resource");
+        let revisions = history(&pool, &res.id)
+            .await
+            .expect("could not read history");
+
+        // Assert
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].action, RevisionAction::Update);
+        assert_eq!(revisions[0].changed_by, Some(changed_by));
+        assert_eq!(
+            revisions[0].previous,
+            serde_json::to_value(&original).expect("could not serialize")
+        );
+        assert_eq!(revisions[1].action, RevisionAction::Delete);
+        assert_eq!(revisions[1].changed_by, None);
+        assert_eq!(
+            revisions[1].previous,
+            serde_json::to_value(&updated).expect("could not serialize")
+        );
+    }
+
+    #[sqlx::test]
+    async fn can_change_log_type(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let res = create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                log_type: LogType::FuelUp { fuel_amount: 30.0 },
+                ..Faker.fake()
+            },
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
+        let log_type = LogType::BrakeReplacement {
+            location: Faker.fake(),
+            component: Faker.fake(),
+        };
+        let body = CreateLogRecordBody {
+            vehicle_id,
+            log_type: log_type.clone(),
+            ..Faker.fake()
+        };
+
+        // Act
+        let updated_result = update(&pool, &res.id, body, None, None)
+            .await
+            .expect("could not update resource");
+        let fuel_ups = query("SELECT count(*) AS count FROM fuel_ups WHERE log_record_id = $1")
+            .bind(res.id)
+            .fetch_one(&pool)
+            .await
+            .expect("could not read fuel ups from db")
+            .get::<i64, _>("count");
+
+        // Assert
+        assert_eq!(updated_result.log_type, log_type);
+        assert_eq!(fuel_ups, 0);
+    }
+
+    #[sqlx::test]
+    async fn create_rejects_duplicates(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let body = CreateLogRecordBody {
+            vehicle_id,
+            date: Some(Utc::now()),
+            ..Faker.fake()
+        };
+        let existing = create(&pool, body.clone(), CreateLogRecordParams::default())
+            .await
+            .expect("could not create resource");
+        let double_tap = CreateLogRecordBody {
+            date: body.date.map(|date| date + Duration::hours(1)),
+            ..body.clone()
+        };
+
+        // Act
+        let res = create(&pool, double_tap, CreateLogRecordParams::default()).await;
+
+        // Assert
+        assert!(
+            matches!(res, Err(ApiError::DuplicateLogRecord { existing_id }) if existing_id == existing.id)
+        );
+    }
+
+    #[sqlx::test]
+    async fn create_allows_duplicates_when_asked(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let existing_date = Some(Utc::now());
+        let body = CreateLogRecordBody {
+            vehicle_id,
+            date: existing_date,
+            ..Faker.fake()
+        };
+        let existing = create(&pool, body.clone(), CreateLogRecordParams::default())
+            .await
+            .expect("could not create resource");
+
+        // Dated later so that the existing record is reported as the original
+        let later = CreateLogRecordBody {
+            date: existing_date.map(|date| date + Duration::hours(1)),
+            ..body
+        };
+
+        // Act
+        let duplicate = create(
+            &pool,
+            later,
+            CreateLogRecordParams {
+                allow_duplicate: true,
+            },
+        )
+        .await
+        .expect("could not create resource");
+        let suspected = duplicates(&pool, ListLogRecordDuplicatesParams::default())
+            .await
+            .expect("could not list duplicates");
+
+        // Assert
+        assert_eq!(suspected.len(), 1);
+        assert_eq!(suspected[0].log_record_id, duplicate.id);
+        assert_eq!(suspected[0].duplicate_of, existing.id);
+    }
+
+    #[sqlx::test]
+    async fn records_outside_window_are_not_duplicates(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let body = CreateLogRecordBody {
+            vehicle_id,
+            date: Some(Utc::now()),
+            ..Faker.fake()
+        };
+        create(&pool, body.clone(), CreateLogRecordParams::default())
+            .await
+            .expect("could not create resource");
+        let later = CreateLogRecordBody {
+            date: body.date.map(|date| date + Duration::days(2)),
+            ..body
+        };
+
+        // Act
+        let res = create(&pool, later, CreateLogRecordParams::default()).await;
+        let suspected = duplicates(
+            &pool,
+            ListLogRecordDuplicatesParams {
+                vehicle_id: Some(vehicle_id),
+            },
+        )
+        .await
+        .expect("could not list duplicates");
+
+        // Assert
+        assert!(res.is_ok());
+        assert!(suspected.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn duplicates_sharing_a_date_follow_insertion_order(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let body = CreateLogRecordBody {
+            vehicle_id,
+            date: Some(Utc::now()),
+            ..Faker.fake()
+        };
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            let created = create(
+                &pool,
+                body.clone(),
+                CreateLogRecordParams {
+                    allow_duplicate: true,
+                },
+            )
+            .await
+            .expect("could not create resource");
+            ids.push(created.id);
+        }
+
+        // Act
+        let res = create(&pool, body, CreateLogRecordParams::default()).await;
+        let suspected = duplicates(&pool, ListLogRecordDuplicatesParams::default())
+            .await
+            .expect("could not list duplicates");
+
+        // Assert
+        assert!(
+            matches!(res, Err(ApiError::DuplicateLogRecord { existing_id }) if existing_id == ids[0])
+        );
+        assert_eq!(
+            suspected
+                .iter()
+                .map(|duplicate| duplicate.log_record_id)
+                .collect::<Vec<_>>(),
+            ids[1..]
+        );
+        assert!(suspected
+            .iter()
+            .all(|duplicate| duplicate.duplicate_of == ids[0]));
+    }
+
+    #[sqlx::test]
+    async fn batch_keeps_successful_operations(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let existing = create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                ..Faker.fake()
+            },
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
+        let body = BatchLogRecordsBody {
+            atomic: false,
+            operations: vec![
+                LogRecordOperation::Create {
+                    body: CreateLogRecordBody {
+                        vehicle_id,
+                        ..Faker.fake()
+                    },
+                    allow_duplicate: false,
+                },
+                LogRecordOperation::Delete {
+                    id: Uuid::new_v4(),
+                    version: None,
+                },
+                LogRecordOperation::Delete {
+                    id: existing.id,
+                    version: None,
+                },
+            ],
+        };
+
+        // Act
+        let res = batch(&pool, body, None).await.expect("could not run batch");
+        let created = read(&pool, &res.results[0].id.expect("created record has no id")).await;
+        let deleted = read(&pool, &existing.id).await;
+
+        // Assert
+        assert!(res.committed);
+        let statuses = res.results.iter().map(|r| r.status).collect::<Vec<_>>();
+        assert_eq!(statuses, vec![201, 404, 204]);
+        assert!(created.is_ok());
+        assert!(matches!(deleted, Err(ApiError::ResourceNotFound)));
+    }
+
+    #[sqlx::test]
+    async fn atomic_batch_rolls_back_on_failure(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let existing = create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                ..Faker.fake()
+            },
+            CreateLogRecordParams::default(),
+        )
+        .await
+        .expect("could not create resource");
+        let body = BatchLogRecordsBody {
+            atomic: true,
+            operations: vec![
+                LogRecordOperation::Delete {
+                    id: existing.id,
+                    version: None,
+                },
+                LogRecordOperation::Update {
+                    id: existing.id,
+                    body: UpdateLogRecordBody {
+                        vehicle_id,
+                        ..Faker.fake()
+                    },
+                    version: None,
+                },
+                LogRecordOperation::Create {
+                    body: CreateLogRecordBody {
+                        vehicle_id,
+                        ..Faker.fake()
+                    },
+                    allow_duplicate: false,
+                },
+            ],
+        };
+
+        // Act
+        let res = batch(&pool, body, None).await.expect("could not run batch");
+        let log_records = list(&pool).await.expect("could not list resources");
+
+        // Assert
+        assert!(!res.committed);
+        let statuses = res.results.iter().map(|r| r.status).collect::<Vec<_>>();
+        assert_eq!(statuses, vec![204, 404, 424]);
+        assert_eq!(log_records.len(), 1);
+        assert_eq!(log_records[0].id, existing.id);
+    }
 }
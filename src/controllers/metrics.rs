@@ -0,0 +1,102 @@
+use sqlx::PgPool;
+
+use crate::{
+    error::ApiError,
+    metrics::{LogTypeLabels, Metrics},
+    models::api::MetricsResponse,
+    repositories::Repositories,
+};
+
+/// Refreshes the metrics that are read rather than recorded, then renders
+/// all of them. Connection pool metrics are only reported with Postgres.
+#[tracing::instrument(name = "metrics_controller_render", skip_all, err)]
+pub async fn render(
+    repositories: &Repositories,
+    pool: Option<&PgPool>,
+    metrics: &Metrics,
+) -> Result<MetricsResponse, ApiError> {
+    let vehicles = repositories.vehicles.count().await?;
+    metrics.vehicles.set(vehicles);
+
+    let log_records = repositories.log_records.count_by_type().await?;
+    // Log types without records any more must not keep their last count
+    metrics.log_records.clear();
+    for (log_type, count) in log_records {
+        metrics
+            .log_records
+            .get_or_create(&LogTypeLabels {
+                log_type: log_type.to_string(),
+            })
+            .set(count);
+    }
+
+    if let Some(pool) = pool {
+        metrics.pool_connections.set(pool.size().into());
+        metrics
+            .pool_idle_connections
+            .set(i64::try_from(pool.num_idle()).unwrap_or(i64::MAX));
+    }
+
+    metrics
+        .encode()
+        .map(MetricsResponse)
+        .map_err(|e| ApiError::Conversion(e.to_string()))
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+    use crate::{
+        controllers::log_record, models::api::CreateLogRecordBody,
+        utils::test_utils::db::seed_user_and_vehicle,
+    };
+    use fake::{Fake, Faker};
+
+    #[sqlx::test]
+    async fn counts_active_vehicles_and_log_records(pool: PgPool) {
+        // Arrange
+        let metrics = Metrics::new();
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let body = CreateLogRecordBody {
+            vehicle_id,
+            ..Faker.fake()
+        };
+        let log_type = body.log_type.to_string();
+        log_record::create(&pool, body, Default::default())
+            .await
+            .expect("could not create log record");
+
+        // Act
+        let res = render(&Repositories::postgres(&pool), Some(&pool), &metrics)
+            .await
+            .expect("could not render");
+
+        // Assert
+        assert!(res.0.contains("fuel_logger_vehicles 1\n"));
+        assert!(res.0.contains(&format!(
+            "fuel_logger_log_records{{log_type=\"{log_type}\"}} 1\n"
+        )));
+        assert!(res.0.ends_with("# EOF\n"));
+    }
+
+    #[sqlx::test]
+    async fn forgets_log_types_without_records(pool: PgPool) {
+        // Arrange
+        let metrics = Metrics::new();
+        metrics
+            .log_records
+            .get_or_create(&LogTypeLabels {
+                log_type: "gone".to_owned(),
+            })
+            .set(3);
+
+        // Act
+        let res = render(&Repositories::postgres(&pool), Some(&pool), &metrics)
+            .await
+            .expect("could not render");
+
+        // Assert
+        assert!(!res.0.contains("gone"));
+        assert!(res.0.contains("fuel_logger_vehicles 0\n"));
+    }
+}
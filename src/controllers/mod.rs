@@ -1,3 +1,9 @@
+pub mod audit;
+pub mod health;
+pub mod idempotency;
 pub mod log_record;
+pub mod metrics;
+pub mod organisation;
+pub mod sync;
 pub mod user;
 pub mod vehicle;
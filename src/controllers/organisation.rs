@@ -0,0 +1,612 @@
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{
+        api::{
+            AddOrganisationMemberBody, AddOrganisationMemberResponse, CreateOrganisationBody,
+            CreateOrganisationResponse, DeleteOrganisationResponse, ListLogRecordsResponse,
+            ListOrganisationMembersResponse, ListOrganisationsResponse, ListVehiclesResponse,
+            ReadOrganisationResponse, RemoveOrganisationMemberResponse, UpdateOrganisationBody,
+            UpdateOrganisationMemberBody, UpdateOrganisationMemberResponse,
+            UpdateOrganisationResponse,
+        },
+        db::{Organisation as DbOrganisation, OrganisationMember as DbOrganisationMember},
+    },
+    repositories::OrganisationRepository,
+};
+
+#[tracing::instrument(name = "organisation_controller_read", skip(repository), err)]
+pub async fn read(
+    repository: &dyn OrganisationRepository,
+    id: &Uuid,
+) -> Result<ReadOrganisationResponse, ApiError> {
+    tracing::debug!("reading organisation");
+    let organisation = repository.find(id).await?;
+    tracing::info!(?organisation, "organisation found");
+    Ok(organisation.into())
+}
+
+#[tracing::instrument(name = "organisation_controller_list", skip(repository), err)]
+pub async fn list(
+    repository: &dyn OrganisationRepository,
+) -> Result<ListOrganisationsResponse, ApiError> {
+    tracing::debug!("listing organisations");
+    let organisations = repository.list().await?;
+    tracing::info!("number of organisations found: {}", organisations.len());
+    Ok(organisations.into_iter().map(Into::into).collect())
+}
+
+/// Creates an organisation owned by the acting user, so that every
+/// organisation starts with someone able to manage it
+#[tracing::instrument(name = "organisation_controller_create", skip(repository), err)]
+pub async fn create(
+    repository: &dyn OrganisationRepository,
+    owner_id: &Uuid,
+    body: CreateOrganisationBody,
+) -> Result<CreateOrganisationResponse, ApiError> {
+    tracing::debug!("creating organisation");
+    let organisation = DbOrganisation::from_api_type(&Uuid::new_v4(), body);
+    let id = repository.insert(&organisation, owner_id).await?;
+    tracing::info!(%id, "new organisation created");
+
+    Ok(CreateOrganisationResponse { id })
+}
+
+#[tracing::instrument(name = "organisation_controller_update", skip(repository), err)]
+pub async fn update(
+    repository: &dyn OrganisationRepository,
+    actor_id: Option<Uuid>,
+    organisation_id: &Uuid,
+    body: UpdateOrganisationBody,
+) -> Result<UpdateOrganisationResponse, ApiError> {
+    tracing::debug!("updating organisation");
+    let organisation = DbOrganisation::from_api_type(organisation_id, body);
+    let updated_organisation = repository.update(&organisation, actor_id).await?;
+    tracing::info!(?updated_organisation, "organisation updated");
+
+    Ok(updated_organisation.into())
+}
+
+#[tracing::instrument(name = "organisation_controller_delete", skip(repository), err)]
+pub async fn delete(
+    repository: &dyn OrganisationRepository,
+    actor_id: Option<Uuid>,
+    organisation_id: &Uuid,
+) -> Result<DeleteOrganisationResponse, ApiError> {
+    tracing::debug!("deleting organisation");
+    repository.delete(organisation_id, actor_id).await?;
+    tracing::info!("organisation deleted");
+    Ok(DeleteOrganisationResponse)
+}
+
+#[tracing::instrument(name = "organisation_controller_list_members", skip(repository), err)]
+pub async fn list_members(
+    repository: &dyn OrganisationRepository,
+    organisation_id: &Uuid,
+) -> Result<ListOrganisationMembersResponse, ApiError> {
+    tracing::debug!("listing organisation members");
+    let members = repository.list_members(organisation_id).await?;
+    tracing::info!("number of organisation members found: {}", members.len());
+    Ok(members.into_iter().map(Into::into).collect())
+}
+
+#[tracing::instrument(name = "organisation_controller_add_member", skip(repository), err)]
+pub async fn add_member(
+    repository: &dyn OrganisationRepository,
+    actor_id: Option<Uuid>,
+    organisation_id: &Uuid,
+    body: AddOrganisationMemberBody,
+) -> Result<AddOrganisationMemberResponse, ApiError> {
+    tracing::debug!("adding organisation member");
+    let member = DbOrganisationMember {
+        organisation_id: *organisation_id,
+        user_id: body.user_id,
+        role: body.role.unwrap_or_default(),
+    };
+    let member = repository.add_member(&member, actor_id).await?;
+    tracing::info!(?member, "organisation member added");
+
+    Ok(AddOrganisationMemberResponse(member.into()))
+}
+
+#[tracing::instrument(name = "organisation_controller_update_member", skip(repository), err)]
+pub async fn update_member(
+    repository: &dyn OrganisationRepository,
+    actor_id: Option<Uuid>,
+    organisation_id: &Uuid,
+    user_id: &Uuid,
+    body: UpdateOrganisationMemberBody,
+) -> Result<UpdateOrganisationMemberResponse, ApiError> {
+    tracing::debug!("updating organisation member");
+    let member = DbOrganisationMember {
+        organisation_id: *organisation_id,
+        user_id: *user_id,
+        role: body.role,
+    };
+    let member = repository.update_member(&member, actor_id).await?;
+    tracing::info!(?member, "organisation member updated");
+
+    Ok(member.into())
+}
+
+#[tracing::instrument(name = "organisation_controller_remove_member", skip(repository), err)]
+pub async fn remove_member(
+    repository: &dyn OrganisationRepository,
+    actor_id: Option<Uuid>,
+    organisation_id: &Uuid,
+    user_id: &Uuid,
+) -> Result<RemoveOrganisationMemberResponse, ApiError> {
+    tracing::debug!("removing organisation member");
+    repository
+        .remove_member(organisation_id, user_id, actor_id)
+        .await?;
+    tracing::info!("organisation member removed");
+    Ok(RemoveOrganisationMemberResponse)
+}
+
+#[tracing::instrument(name = "organisation_controller_list_vehicles", skip(repository), err)]
+pub async fn list_vehicles(
+    repository: &dyn OrganisationRepository,
+    actor_id: Option<Uuid>,
+    organisation_id: &Uuid,
+) -> Result<ListVehiclesResponse, ApiError> {
+    tracing::debug!("listing organisation vehicles");
+    let vehicles = repository.list_vehicles(organisation_id, actor_id).await?;
+    tracing::info!("number of organisation vehicles found: {}", vehicles.len());
+    vehicles.into_iter().map(TryInto::try_into).collect()
+}
+
+#[tracing::instrument(
+    name = "organisation_controller_list_log_records",
+    skip(repository),
+    err
+)]
+pub async fn list_log_records(
+    repository: &dyn OrganisationRepository,
+    actor_id: Option<Uuid>,
+    organisation_id: &Uuid,
+) -> Result<ListLogRecordsResponse, ApiError> {
+    tracing::debug!("listing organisation log records");
+    let log_records = repository
+        .list_log_records(organisation_id, actor_id)
+        .await?;
+    tracing::info!(
+        "number of organisation log records found: {}",
+        log_records.len()
+    );
+    log_records.into_iter().map(TryInto::try_into).collect()
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+    use crate::{
+        models::api::CreateVehicleBody,
+        types::MemberRole,
+        utils::test_utils::db::{seed_organisation, seed_user},
+    };
+    use fake::{Fake, Faker};
+    use sqlx::PgPool;
+
+    async fn seed_member(
+        pool: &PgPool,
+        organisation_id: &Uuid,
+        owner_id: &Uuid,
+        role: MemberRole,
+    ) -> Uuid {
+        let user_id = seed_user(pool).await;
+        add_member(
+            pool,
+            Some(*owner_id),
+            organisation_id,
+            AddOrganisationMemberBody {
+                user_id,
+                role: Some(role),
+            },
+        )
+        .await
+        .expect("could not add member");
+        user_id
+    }
+
+    #[sqlx::test]
+    async fn can_create_and_read(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_body = Faker.fake::<CreateOrganisationBody>();
+
+        // Act
+        let res = create(&pool, &owner_id, organisation_body.clone())
+            .await
+            .expect("could not create resource");
+        let created_result = read(&pool, &res.id).await.expect("could not read resource");
+        let members = list_members(&pool, &res.id)
+            .await
+            .expect("could not list members");
+
+        // Assert
+        assert_eq!(created_result.name, organisation_body.name);
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].user_id, owner_id);
+        assert_eq!(members[0].role, MemberRole::Owner);
+    }
+
+    #[sqlx::test]
+    async fn can_create_and_list(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_body_1 = Faker.fake::<CreateOrganisationBody>();
+        let organisation_body_2 = Faker.fake::<CreateOrganisationBody>();
+
+        // Act
+        create(&pool, &owner_id, organisation_body_1.clone())
+            .await
+            .expect("could not create resource");
+        create(&pool, &owner_id, organisation_body_2.clone())
+            .await
+            .expect("could not create resource");
+        let created_result = list(&pool).await.expect("could not list resources");
+
+        // Assert
+        assert_eq!(created_result.len(), 2);
+        for (created_item, body_item) in created_result
+            .iter()
+            .zip(vec![organisation_body_1, organisation_body_2])
+        {
+            assert_eq!(created_item.name, body_item.name);
+        }
+    }
+
+    #[sqlx::test]
+    async fn can_update(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let updated_organisation_body = Faker.fake::<UpdateOrganisationBody>();
+
+        // Act
+        let updated_result = update(
+            &pool,
+            Some(owner_id),
+            &organisation_id,
+            updated_organisation_body.clone(),
+        )
+        .await
+        .expect("could not update resource");
+
+        // Assert
+        assert_eq!(updated_result.name, updated_organisation_body.name);
+    }
+
+    #[sqlx::test]
+    async fn can_delete(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+
+        // Act
+        delete(&pool, Some(owner_id), &organisation_id)
+            .await
+            .expect("could not delete resource");
+        let created_result = read(&pool, &organisation_id)
+            .await
+            .expect_err("expected_failure_did_not_occur");
+
+        // Assert
+        assert!(matches!(created_result, ApiError::ResourceNotFound));
+    }
+
+    #[sqlx::test]
+    async fn can_manage_members(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let user_id = seed_user(&pool).await;
+
+        // Act
+        let added = add_member(
+            &pool,
+            Some(owner_id),
+            &organisation_id,
+            AddOrganisationMemberBody {
+                user_id,
+                role: None,
+            },
+        )
+        .await
+        .expect("could not add member");
+        let updated = update_member(
+            &pool,
+            Some(owner_id),
+            &organisation_id,
+            &user_id,
+            UpdateOrganisationMemberBody {
+                role: MemberRole::Admin,
+            },
+        )
+        .await
+        .expect("could not update member");
+        let members_before_removal = list_members(&pool, &organisation_id)
+            .await
+            .expect("could not list members");
+        remove_member(&pool, Some(owner_id), &organisation_id, &user_id)
+            .await
+            .expect("could not remove member");
+        let members_after_removal = list_members(&pool, &organisation_id)
+            .await
+            .expect("could not list members");
+
+        // Assert
+        assert_eq!(added.0.role, MemberRole::Member);
+        assert_eq!(updated.role, MemberRole::Admin);
+        assert_eq!(members_before_removal.len(), 2);
+        assert_eq!(members_after_removal.len(), 1);
+        assert_eq!(members_after_removal[0].user_id, owner_id);
+    }
+
+    #[sqlx::test]
+    async fn members_can_leave(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let user_id = seed_member(&pool, &organisation_id, &owner_id, MemberRole::Member).await;
+
+        // Act
+        remove_member(&pool, Some(user_id), &organisation_id, &user_id)
+            .await
+            .expect("could not leave organisation");
+        let members = list_members(&pool, &organisation_id)
+            .await
+            .expect("could not list members");
+
+        // Assert
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].user_id, owner_id);
+    }
+
+    #[sqlx::test]
+    async fn members_cannot_manage_organisation(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let user_id = seed_member(&pool, &organisation_id, &owner_id, MemberRole::Member).await;
+        let other_id = seed_user(&pool).await;
+
+        // Act
+        let update_err = update(&pool, Some(user_id), &organisation_id, Faker.fake())
+            .await
+            .expect_err("expected failure did not occur");
+        let add_err = add_member(
+            &pool,
+            Some(user_id),
+            &organisation_id,
+            AddOrganisationMemberBody {
+                user_id: other_id,
+                role: None,
+            },
+        )
+        .await
+        .expect_err("expected failure did not occur");
+        let remove_err = remove_member(&pool, Some(user_id), &organisation_id, &owner_id)
+            .await
+            .expect_err("expected failure did not occur");
+        let anonymous_err = delete(&pool, None, &organisation_id)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(
+            update_err,
+            ApiError::OrganisationRoleRequired(ref roles) if roles == "owner or admin"
+        ));
+        assert!(matches!(
+            add_err,
+            ApiError::OrganisationRoleRequired(ref roles) if roles == "owner or admin"
+        ));
+        assert!(matches!(
+            remove_err,
+            ApiError::OrganisationRoleRequired(ref roles) if roles == "owner or admin"
+        ));
+        assert!(matches!(
+            anonymous_err,
+            ApiError::OrganisationRoleRequired(ref roles) if roles == "owner"
+        ));
+    }
+
+    #[sqlx::test]
+    async fn admins_cannot_manage_owners(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let admin_id = seed_member(&pool, &organisation_id, &owner_id, MemberRole::Admin).await;
+        let user_id = seed_member(&pool, &organisation_id, &owner_id, MemberRole::Member).await;
+
+        // Act
+        let promote_err = update_member(
+            &pool,
+            Some(admin_id),
+            &organisation_id,
+            &user_id,
+            UpdateOrganisationMemberBody {
+                role: MemberRole::Owner,
+            },
+        )
+        .await
+        .expect_err("expected failure did not occur");
+        let remove_err = remove_member(&pool, Some(admin_id), &organisation_id, &owner_id)
+            .await
+            .expect_err("expected failure did not occur");
+        let delete_err = delete(&pool, Some(admin_id), &organisation_id)
+            .await
+            .expect_err("expected failure did not occur");
+        remove_member(&pool, Some(admin_id), &organisation_id, &user_id)
+            .await
+            .expect("could not remove member");
+
+        // Assert
+        assert!(matches!(
+            promote_err,
+            ApiError::OrganisationRoleRequired(ref roles) if roles == "owner"
+        ));
+        assert!(matches!(
+            remove_err,
+            ApiError::OrganisationRoleRequired(ref roles) if roles == "owner"
+        ));
+        assert!(matches!(
+            delete_err,
+            ApiError::OrganisationRoleRequired(ref roles) if roles == "owner"
+        ));
+    }
+
+    #[sqlx::test]
+    async fn keeps_last_owner(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+
+        // Act
+        let leave_err = remove_member(&pool, Some(owner_id), &organisation_id, &owner_id)
+            .await
+            .expect_err("expected failure did not occur");
+        let demote_err = update_member(
+            &pool,
+            Some(owner_id),
+            &organisation_id,
+            &owner_id,
+            UpdateOrganisationMemberBody {
+                role: MemberRole::Admin,
+            },
+        )
+        .await
+        .expect_err("expected failure did not occur");
+        let other_owner_id =
+            seed_member(&pool, &organisation_id, &owner_id, MemberRole::Owner).await;
+        remove_member(&pool, Some(owner_id), &organisation_id, &owner_id)
+            .await
+            .expect("could not leave organisation");
+        let members = list_members(&pool, &organisation_id)
+            .await
+            .expect("could not list members");
+
+        // Assert
+        assert!(matches!(leave_err, ApiError::Conflict(_)));
+        assert!(matches!(demote_err, ApiError::Conflict(_)));
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].user_id, other_owner_id);
+    }
+
+    #[sqlx::test]
+    async fn refuses_removing_member_owning_vehicles(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let user_id = seed_member(&pool, &organisation_id, &owner_id, MemberRole::Member).await;
+        let vehicle_id = crate::controllers::vehicle::create(
+            &pool,
+            Some(owner_id),
+            CreateVehicleBody {
+                owner_id: user_id,
+                organisation_id: Some(organisation_id),
+                ..Faker.fake()
+            },
+        )
+        .await
+        .expect("could not create fleet vehicle")
+        .id;
+
+        // Act
+        let err = remove_member(&pool, Some(owner_id), &organisation_id, &user_id)
+            .await
+            .expect_err("expected failure did not occur");
+        let members = list_members(&pool, &organisation_id)
+            .await
+            .expect("could not list members");
+
+        // Assert
+        assert!(matches!(
+            err,
+            ApiError::DependentResources { ref resource, ref ids }
+                if resource == "vehicles" && ids == &vec![vehicle_id]
+        ));
+        assert_eq!(members.len(), 2);
+    }
+
+    #[sqlx::test]
+    async fn lists_vehicles_and_log_records_of_organisation(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let user_id = seed_member(&pool, &organisation_id, &owner_id, MemberRole::Member).await;
+        let fleet_vehicle_id = crate::controllers::vehicle::create(
+            &pool,
+            Some(owner_id),
+            CreateVehicleBody {
+                owner_id: user_id,
+                organisation_id: Some(organisation_id),
+                ..Faker.fake()
+            },
+        )
+        .await
+        .expect("could not create fleet vehicle")
+        .id;
+        crate::controllers::vehicle::create(
+            &pool,
+            Some(user_id),
+            CreateVehicleBody {
+                owner_id: user_id,
+                ..Faker.fake()
+            },
+        )
+        .await
+        .expect("could not create personal vehicle");
+        crate::controllers::log_record::create(
+            &pool,
+            crate::models::api::CreateLogRecordBody {
+                vehicle_id: fleet_vehicle_id,
+                ..Faker.fake()
+            },
+            Default::default(),
+        )
+        .await
+        .expect("could not create log record");
+
+        // Act
+        let vehicles = list_vehicles(&pool, Some(user_id), &organisation_id)
+            .await
+            .expect("could not list vehicles");
+        let log_records = list_log_records(&pool, Some(user_id), &organisation_id)
+            .await
+            .expect("could not list log records");
+
+        // Assert
+        assert_eq!(vehicles.len(), 1);
+        assert_eq!(vehicles[0].id, fleet_vehicle_id);
+        assert_eq!(log_records.len(), 1);
+        assert_eq!(log_records[0].vehicle_id, fleet_vehicle_id);
+    }
+
+    #[sqlx::test]
+    async fn rejects_vehicle_owned_by_non_member(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let organisation_id = seed_organisation(&pool, &owner_id).await;
+        let user_id = seed_user(&pool).await;
+
+        // Act
+        let err = crate::controllers::vehicle::create(
+            &pool,
+            Some(owner_id),
+            CreateVehicleBody {
+                owner_id: user_id,
+                organisation_id: Some(organisation_id),
+                ..Faker.fake()
+            },
+        )
+        .await
+        .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(err, ApiError::NotOrganisationMember));
+    }
+}
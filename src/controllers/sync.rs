@@ -0,0 +1,217 @@
+use crate::{
+    error::ApiError,
+    models::api::{
+        ReadLogRecordResponse, ReadUserResponse, ReadVehicleResponse, SyncChanges, SyncParams,
+        SyncResponse, SyncedRecord,
+    },
+    repositories::SyncRepository,
+};
+
+#[tracing::instrument(name = "sync_controller_changes", skip(repository), err)]
+pub async fn changes(
+    repository: &dyn SyncRepository,
+    params: SyncParams,
+) -> Result<SyncResponse, ApiError> {
+    tracing::debug!("reading changes");
+    let changes = repository.changes(params.since.as_deref()).await?;
+
+    let users = SyncChanges {
+        changed: changes
+            .users
+            .changed
+            .into_iter()
+            .map(|(user, updated_at)| {
+                let user = ReadUserResponse::from(user);
+                SyncedRecord {
+                    version: user.version,
+                    record: user,
+                    updated_at,
+                }
+            })
+            .collect(),
+        deleted: changes.users.deleted,
+    };
+    let vehicles = SyncChanges {
+        changed: changes
+            .vehicles
+            .changed
+            .into_iter()
+            .map(|(vehicle, updated_at)| {
+                ReadVehicleResponse::try_from(vehicle).map(|vehicle| SyncedRecord {
+                    version: vehicle.version,
+                    record: vehicle,
+                    updated_at,
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        deleted: changes.vehicles.deleted,
+    };
+    let log_records = SyncChanges {
+        changed: changes
+            .log_records
+            .changed
+            .into_iter()
+            .map(|(log_record, updated_at)| {
+                ReadLogRecordResponse::try_from(log_record).map(|log_record| SyncedRecord {
+                    version: log_record.version,
+                    record: log_record,
+                    updated_at,
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        deleted: changes.log_records.deleted,
+    };
+
+    tracing::info!(
+        users = users.changed.len(),
+        vehicles = vehicles.changed.len(),
+        log_records = log_records.changed.len(),
+        "read changes"
+    );
+    Ok(SyncResponse {
+        token: changes.token,
+        users,
+        vehicles,
+        log_records,
+    })
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+    use crate::{
+        controllers::{log_record, user, vehicle},
+        models::api::{CreateLogRecordBody, DeleteVehicleParams},
+        utils::test_utils::db::seed_user_and_vehicle,
+    };
+    use fake::{Fake, Faker};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn full_sync_returns_active_records(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+
+        // Act
+        let res = changes(&pool, SyncParams::default())
+            .await
+            .expect("could not sync");
+
+        // Assert
+        assert_eq!(res.users.changed.len(), 1);
+        assert_eq!(res.vehicles.changed.len(), 1);
+        assert_eq!(res.vehicles.changed[0].record.id, vehicle_id);
+        assert_eq!(res.vehicles.changed[0].version, 1);
+        assert!(res.log_records.changed.is_empty());
+        assert!(res.vehicles.deleted.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn incremental_sync_returns_changes_since_token(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let first = changes(&pool, SyncParams::default())
+            .await
+            .expect("could not sync");
+        let created = log_record::create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                ..Faker.fake()
+            },
+            Default::default(),
+        )
+        .await
+        .expect("could not create log record");
+        let user_id = user::create(&pool, Faker.fake())
+            .await
+            .expect("could not create user")
+            .id;
+
+        // Act
+        let second = changes(
+            &pool,
+            SyncParams {
+                since: Some(first.token),
+            },
+        )
+        .await
+        .expect("could not sync");
+        let third = changes(
+            &pool,
+            SyncParams {
+                since: Some(second.token.clone()),
+            },
+        )
+        .await
+        .expect("could not sync");
+
+        // Assert
+        assert_eq!(second.log_records.changed.len(), 1);
+        assert_eq!(second.log_records.changed[0].record.id, created.id);
+        assert_eq!(second.users.changed.len(), 1);
+        assert_eq!(second.users.changed[0].record.id, user_id);
+        assert!(second.vehicles.changed.is_empty());
+        assert!(third.users.changed.is_empty());
+        assert!(third.log_records.changed.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn deleted_and_purged_records_are_reported(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let log_record_id = log_record::create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                ..Faker.fake()
+            },
+            Default::default(),
+        )
+        .await
+        .expect("could not create log record")
+        .id;
+        let first = changes(&pool, SyncParams::default())
+            .await
+            .expect("could not sync");
+        log_record::delete(&pool, &log_record_id, None, None)
+            .await
+            .expect("could not delete log record");
+        log_record::purge(&pool, &log_record_id)
+            .await
+            .expect("could not purge log record");
+        vehicle::delete(&pool, &vehicle_id, DeleteVehicleParams::default(), None)
+            .await
+            .expect("could not delete vehicle");
+
+        // Act
+        let res = changes(
+            &pool,
+            SyncParams {
+                since: Some(first.token),
+            },
+        )
+        .await
+        .expect("could not sync");
+
+        // Assert
+        assert_eq!(res.log_records.deleted, vec![log_record_id]);
+        assert_eq!(res.vehicles.deleted, vec![vehicle_id]);
+        assert!(res.vehicles.changed.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn rejects_invalid_token(pool: PgPool) {
+        // Act
+        let res = changes(
+            &pool,
+            SyncParams {
+                since: Some("yesterday".to_owned()),
+            },
+        )
+        .await;
+
+        // Assert
+        assert!(matches!(res, Err(ApiError::InvalidRequest(_))));
+    }
+}
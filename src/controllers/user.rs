@@ -1,114 +1,158 @@
-use sqlx::{query, query_as, PgPool, Row};
 use uuid::Uuid;
 
 use crate::{
     error::ApiError,
     models::{
         api::{
-            CreateUserBody, CreateUserResponse, DeleteUserResponse, ListUsersResponse,
-            ReadUserResponse, UpdateUserBody, UpdateUserResponse,
+            CreateUserBody, CreateUserResponse, DeleteUserParams, DeleteUserResponse,
+            ListUsersResponse, PatchUserBody, PatchUserResponse, PurgeUserResponse,
+            ReadUserResponse, RestoreUserResponse, UpdateUserBody, UpdateUserResponse,
         },
         db::User as DbUser,
     },
+    repositories::UserRepository,
+    types::{EntityTags, Validate},
 };
 
-#[tracing::instrument(name = "user_controller_read", skip(pool), err)]
-pub async fn read(pool: &PgPool, id: &Uuid) -> Result<ReadUserResponse, ApiError> {
+#[tracing::instrument(name = "user_controller_read", skip(repository), err)]
+pub async fn read(
+    repository: &dyn UserRepository,
+    id: &Uuid,
+) -> Result<ReadUserResponse, ApiError> {
     tracing::debug!("reading user");
-    let sql = "SELECT * FROM users WHERE id = $1";
-    let user = query_as::<_, DbUser>(sql).bind(id).fetch_one(pool).await?;
+    let user = repository.find(id).await?;
     tracing::info!(?user, "user found");
     Ok(user.into())
 }
 
-#[tracing::instrument(name = "user_controller_list", skip(pool), err)]
-pub async fn list(pool: &PgPool) -> Result<ListUsersResponse, ApiError> {
+#[tracing::instrument(name = "user_controller_list", skip(repository), err)]
+pub async fn list(repository: &dyn UserRepository) -> Result<ListUsersResponse, ApiError> {
     tracing::debug!("listing users");
-    let sql = "SELECT * FROM users";
-    let users = sqlx::query_as::<_, DbUser>(sql).fetch_all(pool).await?;
+    let users = repository.list().await?;
     tracing::info!("number of users found: {}", users.len());
     Ok(users.into_iter().map(Into::into).collect())
 }
 
-#[tracing::instrument(name = "user_controller_create", skip(pool), err)]
-pub async fn create(pool: &PgPool, body: CreateUserBody) -> Result<CreateUserResponse, ApiError> {
+#[tracing::instrument(name = "user_controller_create", skip(repository), err)]
+pub async fn create(
+    repository: &dyn UserRepository,
+    body: CreateUserBody,
+) -> Result<CreateUserResponse, ApiError> {
     tracing::debug!("creating user");
+    body.validate()?;
     let user = DbUser::from_api_type(&Uuid::new_v4(), body);
-    let sql = "
-        INSERT INTO users (
-            first_name, 
-            last_name, 
-            username, 
-            email
-        ) VALUES (
-            $1, 
-            $2, 
-            $3, 
-            $4
-        ) RETURNING id";
-
-    let res = query(sql)
-        .bind(user.first_name)
-        .bind(user.last_name)
-        .bind(user.username)
-        .bind(user.email)
-        .fetch_one(pool)
-        .await?;
-
-    tracing::info!(?res, "new user created");
-    let id = res.try_get::<Uuid, _>("id")?;
+    let id = repository.insert(&user).await?;
+    tracing::info!(%id, "new user created");
 
     Ok(CreateUserResponse { id })
 }
 
-#[tracing::instrument(name = "user_controller_update", skip(pool), err)]
+#[tracing::instrument(name = "user_controller_update", skip(repository), err)]
 pub async fn update(
-    pool: &PgPool,
+    repository: &dyn UserRepository,
     user_id: &Uuid,
     body: UpdateUserBody,
+    if_match: Option<EntityTags>,
 ) -> Result<UpdateUserResponse, ApiError> {
     tracing::debug!("updating user");
+    body.validate()?;
     let user = DbUser::from_api_type(user_id, body);
-    let sql = "
-        UPDATE users 
-        SET 
-            first_name = $1, 
-            last_name = $2, 
-            username = $3, 
-            email = $4
-        WHERE id = $5 
-        RETURNING *";
-    let updated_user = query_as::<_, DbUser>(sql)
-        .bind(user.first_name)
-        .bind(user.last_name)
-        .bind(user.username)
-        .bind(user.email)
-        .bind(user_id)
-        .fetch_one(pool)
-        .await?;
+    let updated_user = repository.update(&user, if_match.as_ref()).await?;
     tracing::info!(?updated_user, "user updated");
 
     Ok(updated_user.into())
 }
 
-#[tracing::instrument(name = "user_controller_delete", skip(pool), err)]
-pub async fn delete(pool: &PgPool, user_id: &Uuid) -> Result<DeleteUserResponse, ApiError> {
+#[tracing::instrument(name = "user_controller_patch", skip(repository), err)]
+pub async fn patch(
+    repository: &dyn UserRepository,
+    user_id: &Uuid,
+    body: PatchUserBody,
+    if_match: Option<EntityTags>,
+) -> Result<PatchUserResponse, ApiError> {
+    tracing::debug!("patching user");
+    let existing_user = read(repository, user_id).await?;
+    // The patch applies to the version read, so a concurrent write fails it
+    // rather than being overwritten
+    let if_match = if_match.unwrap_or(EntityTags::Versions(vec![existing_user.version]));
+    update(
+        repository,
+        user_id,
+        body.apply_to(&existing_user)?,
+        Some(if_match),
+    )
+    .await
+}
+
+#[tracing::instrument(name = "user_controller_delete", skip(repository), err)]
+pub async fn delete(
+    repository: &dyn UserRepository,
+    user_id: &Uuid,
+    params: DeleteUserParams,
+    if_match: Option<EntityTags>,
+) -> Result<DeleteUserResponse, ApiError> {
     tracing::debug!("deleting user");
-    let sql = "DELETE FROM users where id = $1";
-    let res = query(sql).bind(user_id).execute(pool).await?;
-    if res.rows_affected() < 1 {
-        tracing::error!("no user was deleted");
-        Err(ApiError::ResourceNotFound)
+    repository
+        .delete(user_id, &params, if_match.as_ref())
+        .await?;
+    tracing::info!("user deleted");
+    Ok(DeleteUserResponse)
+}
+
+#[tracing::instrument(name = "user_controller_restore", skip(repository), err)]
+pub async fn restore(
+    repository: &dyn UserRepository,
+    user_id: &Uuid,
+) -> Result<RestoreUserResponse, ApiError> {
+    tracing::debug!("restoring user");
+    let restored_user = repository.restore(user_id).await?;
+    tracing::info!(?restored_user, "user restored");
+
+    Ok(restored_user.into())
+}
+
+#[tracing::instrument(name = "user_controller_purge", skip(repository), err)]
+pub async fn purge(
+    repository: &dyn UserRepository,
+    user_id: &Uuid,
+) -> Result<PurgeUserResponse, ApiError> {
+    tracing::debug!("purging user");
+    repository.purge(user_id).await?;
+    tracing::info!("user purged");
+
+    Ok(PurgeUserResponse)
+}
+
+/// Ensure that the acting user is an active admin. Anonymous requests are
+/// never admins.
+#[tracing::instrument(name = "user_controller_ensure_admin", skip(repository), err)]
+pub async fn ensure_admin(
+    repository: &dyn UserRepository,
+    actor_id: Option<Uuid>,
+) -> Result<(), ApiError> {
+    let Some(actor_id) = actor_id else {
+        tracing::debug!("anonymous request is not an admin");
+        return Err(ApiError::AdminRequired);
+    };
+
+    if repository.is_admin(&actor_id).await? {
+        Ok(())
     } else {
-        tracing::info!("user deleted");
-        Ok(DeleteUserResponse)
+        tracing::debug!("user is not an admin");
+        Err(ApiError::AdminRequired)
     }
 }
 
 #[cfg(test)]
 mod database_tests {
     use super::*;
+    use crate::{
+        models::api::CreateLogRecordBody,
+        types::DeletionMode,
+        utils::test_utils::db::{seed_user, seed_user_and_vehicle},
+    };
     use fake::{Fake, Faker};
+    use sqlx::PgPool;
 
     #[sqlx::test]
     async fn can_create_and_read(pool: PgPool) {
@@ -163,7 +207,7 @@ mod database_tests {
         let res = create(&pool, initial_user_body.clone())
             .await
             .expect("could not create resource");
-        let updated_result = update(&pool, &res.id, updated_user_body.clone())
+        let updated_result = update(&pool, &res.id, updated_user_body.clone(), None)
             .await
             .expect("could not update resource");
 
@@ -174,6 +218,99 @@ mod database_tests {
         assert_eq!(updated_result.email, updated_user_body.email);
     }
 
+    #[sqlx::test]
+    async fn can_patch(pool: PgPool) {
+        // Arrange
+        let initial_user_body = Faker.fake::<CreateUserBody>();
+        let username = Faker.fake::<String>();
+        let patch_body = serde_json::from_value(serde_json::json!({"username": username}))
+            .expect("could not deserialize patch");
+
+        // Act
+        let res = create(&pool, initial_user_body.clone())
+            .await
+            .expect("could not create resource");
+        let patched_result = patch(&pool, &res.id, patch_body, None)
+            .await
+            .expect("could not patch resource");
+
+        // Assert
+        assert_eq!(patched_result.username, username);
+        assert_eq!(patched_result.first_name, initial_user_body.first_name);
+        assert_eq!(patched_result.last_name, initial_user_body.last_name);
+        assert_eq!(patched_result.email, initial_user_body.email);
+    }
+
+    /// Users whose reads are immediately followed by a concurrent update
+    #[derive(Debug)]
+    struct RacingUsers(PgPool);
+
+    #[axum::async_trait]
+    impl UserRepository for RacingUsers {
+        async fn find(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+            let user = UserRepository::find(&self.0, id).await?;
+            UserRepository::update(&self.0, &user, None).await?;
+            Ok(user)
+        }
+
+        async fn list(&self) -> Result<Vec<DbUser>, ApiError> {
+            UserRepository::list(&self.0).await
+        }
+
+        async fn insert(&self, user: &DbUser) -> Result<Uuid, ApiError> {
+            UserRepository::insert(&self.0, user).await
+        }
+
+        async fn update(
+            &self,
+            user: &DbUser,
+            if_match: Option<&EntityTags>,
+        ) -> Result<DbUser, ApiError> {
+            UserRepository::update(&self.0, user, if_match).await
+        }
+
+        async fn delete(
+            &self,
+            id: &Uuid,
+            params: &DeleteUserParams,
+            if_match: Option<&EntityTags>,
+        ) -> Result<(), ApiError> {
+            UserRepository::delete(&self.0, id, params, if_match).await
+        }
+
+        async fn restore(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+            UserRepository::restore(&self.0, id).await
+        }
+
+        async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+            UserRepository::purge(&self.0, id).await
+        }
+
+        async fn is_admin(&self, id: &Uuid) -> Result<bool, ApiError> {
+            self.0.is_admin(id).await
+        }
+    }
+
+    #[sqlx::test]
+    async fn patch_fails_after_concurrent_write(pool: PgPool) {
+        // Arrange
+        let user_id = seed_user(&pool).await;
+        let patch_body = serde_json::from_value(serde_json::json!({"username": "patched"}))
+            .expect("could not deserialize patch");
+
+        // Act
+        let err = patch(&RacingUsers(pool.clone()), &user_id, patch_body, None)
+            .await
+            .expect_err("expected failure did not occur");
+        let user = read(&pool, &user_id)
+            .await
+            .expect("could not read resource");
+
+        // Assert
+        assert!(matches!(err, ApiError::PreconditionFailed));
+        assert_ne!(user.username, "patched");
+    }
+
     #[sqlx::test]
     async fn can_delete(pool: PgPool) {
         // Arrange
@@ -184,7 +321,7 @@ mod database_tests {
             .await
             .expect("could not create resource");
         read(&pool, &res.id).await.expect("could not read resource");
-        delete(&pool, &res.id)
+        delete(&pool, &res.id, DeleteUserParams::default(), None)
             .await
             .expect("could not read resource");
         let created_result = read(&pool, &res.id)
@@ -193,4 +330,183 @@ mod database_tests {
 
         assert!(matches!(created_result, ApiError::ResourceNotFound));
     }
+
+    #[sqlx::test]
+    async fn delete_refuses_when_user_owns_vehicles(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let owner_id = crate::controllers::vehicle::read(&pool, &vehicle_id)
+            .await
+            .expect("could not read vehicle")
+            .owner_id;
+
+        // Act
+        let err = delete(&pool, &owner_id, DeleteUserParams::default(), None)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(err, ApiError::DependentResources { ids, .. } if ids == vec![vehicle_id]));
+        read(&pool, &owner_id)
+            .await
+            .expect("user should not have been deleted");
+    }
+
+    #[sqlx::test]
+    async fn delete_cascades_to_vehicles_and_log_records(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let owner_id = crate::controllers::vehicle::read(&pool, &vehicle_id)
+            .await
+            .expect("could not read vehicle")
+            .owner_id;
+        let log_record_id = crate::controllers::log_record::create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                ..Faker.fake()
+            },
+            Default::default(),
+        )
+        .await
+        .expect("could not create log record")
+        .id;
+
+        // Act
+        delete(
+            &pool,
+            &owner_id,
+            DeleteUserParams {
+                mode: DeletionMode::Cascade,
+                reassign_to: None,
+            },
+            None,
+        )
+        .await
+        .expect("could not delete resource");
+
+        // Assert
+        assert!(matches!(
+            crate::controllers::vehicle::read(&pool, &vehicle_id).await,
+            Err(ApiError::ResourceNotFound)
+        ));
+        assert!(matches!(
+            crate::controllers::log_record::read(&pool, &log_record_id).await,
+            Err(ApiError::ResourceNotFound)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn delete_reassigns_vehicles(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let owner_id = crate::controllers::vehicle::read(&pool, &vehicle_id)
+            .await
+            .expect("could not read vehicle")
+            .owner_id;
+        let new_owner_id = seed_user(&pool).await;
+
+        // Act
+        delete(
+            &pool,
+            &owner_id,
+            DeleteUserParams {
+                mode: DeletionMode::Reassign,
+                reassign_to: Some(new_owner_id),
+            },
+            None,
+        )
+        .await
+        .expect("could not delete resource");
+
+        // Assert
+        let vehicle = crate::controllers::vehicle::read(&pool, &vehicle_id)
+            .await
+            .expect("vehicle should still exist");
+        assert_eq!(vehicle.owner_id, new_owner_id);
+    }
+
+    #[sqlx::test]
+    async fn delete_reassign_requires_target(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let owner_id = crate::controllers::vehicle::read(&pool, &vehicle_id)
+            .await
+            .expect("could not read vehicle")
+            .owner_id;
+
+        // Act
+        let err = delete(
+            &pool,
+            &owner_id,
+            DeleteUserParams {
+                mode: DeletionMode::Reassign,
+                reassign_to: None,
+            },
+            None,
+        )
+        .await
+        .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(err, ApiError::InvalidRequest(_)));
+    }
+
+    #[sqlx::test]
+    async fn restore_brings_back_cascaded_vehicles(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let owner_id = crate::controllers::vehicle::read(&pool, &vehicle_id)
+            .await
+            .expect("could not read vehicle")
+            .owner_id;
+        delete(
+            &pool,
+            &owner_id,
+            DeleteUserParams {
+                mode: DeletionMode::Cascade,
+                reassign_to: None,
+            },
+            None,
+        )
+        .await
+        .expect("could not delete resource");
+
+        // Act
+        restore(&pool, &owner_id)
+            .await
+            .expect("could not restore resource");
+
+        // Assert
+        read(&pool, &owner_id)
+            .await
+            .expect("user should have been restored");
+        crate::controllers::vehicle::read(&pool, &vehicle_id)
+            .await
+            .expect("vehicle should have been restored");
+    }
+
+    #[sqlx::test]
+    async fn purge_requires_soft_deletion(pool: PgPool) {
+        // Arrange
+        let user_id = seed_user(&pool).await;
+
+        // Act
+        let err = purge(&pool, &user_id)
+            .await
+            .expect_err("expected failure did not occur");
+        delete(&pool, &user_id, DeleteUserParams::default(), None)
+            .await
+            .expect("could not delete resource");
+        purge(&pool, &user_id)
+            .await
+            .expect("could not purge resource");
+
+        // Assert
+        assert!(matches!(err, ApiError::Conflict(_)));
+        assert!(matches!(
+            restore(&pool, &user_id).await,
+            Err(ApiError::ResourceNotFound)
+        ));
+    }
 }
@@ -1,120 +1,140 @@
-use sqlx::{query, query_as, PgPool, Row};
 use uuid::Uuid;
 
 use crate::{
     error::ApiError,
     models::{
         api::{
-            CreateVehicleBody, CreateVehicleResponse, DeleteVehicleResponse, ListVehiclesResponse,
-            ReadVehicleResponse, UpdateVehicleBody, UpdateVehicleResponse,
+            CreateVehicleBody, CreateVehicleResponse, DeleteVehicleParams, DeleteVehicleResponse,
+            ListVehiclesResponse, PatchVehicleBody, PatchVehicleResponse, PurgeVehicleResponse,
+            ReadVehicleResponse, RestoreVehicleResponse, UpdateVehicleBody, UpdateVehicleResponse,
         },
         db::Vehicle as DbVehicle,
     },
+    repositories::VehicleRepository,
+    types::{EntityTags, Validate},
 };
 
-#[tracing::instrument(name = "vehicle_controller_read", skip(pool), err)]
-pub async fn read(pool: &PgPool, id: &Uuid) -> Result<ReadVehicleResponse, ApiError> {
+#[tracing::instrument(name = "vehicle_controller_read", skip(repository), err)]
+pub async fn read(
+    repository: &dyn VehicleRepository,
+    id: &Uuid,
+) -> Result<ReadVehicleResponse, ApiError> {
     tracing::debug!("reading vehicle");
-    let sql = "SELECT * FROM vehicles WHERE id = $1";
-    let vehicle = query_as::<_, DbVehicle>(sql)
-        .bind(id)
-        .fetch_one(pool)
-        .await?;
+    let vehicle = repository.find(id).await?;
     tracing::info!(?vehicle, "vehicle found");
     vehicle.try_into()
 }
 
-#[tracing::instrument(name = "vehicle_controller_list", skip(pool), err)]
-pub async fn list(pool: &PgPool) -> Result<ListVehiclesResponse, ApiError> {
+#[tracing::instrument(name = "vehicle_controller_list", skip(repository), err)]
+pub async fn list(repository: &dyn VehicleRepository) -> Result<ListVehiclesResponse, ApiError> {
     tracing::debug!("listing vehicles");
-    let sql = "SELECT * FROM vehicles";
-    let vehicles = sqlx::query_as::<_, DbVehicle>(sql).fetch_all(pool).await?;
+    let vehicles = repository.list().await?;
     vehicles.into_iter().map(TryInto::try_into).collect()
 }
 
-#[tracing::instrument(name = "vehicle_controller_create", skip(pool), err)]
+#[tracing::instrument(name = "vehicle_controller_create", skip(repository), err)]
 pub async fn create(
-    pool: &PgPool,
+    repository: &dyn VehicleRepository,
+    actor_id: Option<Uuid>,
     body: CreateVehicleBody,
 ) -> Result<CreateVehicleResponse, ApiError> {
     tracing::debug!("creating vehicle");
+    body.validate()?;
     let vehicle = DbVehicle::from_api_type(&Uuid::new_v4(), body);
-    let sql = "
-        INSERT INTO vehicles (
-            owner_id,
-            make, 
-            model, 
-            year, 
-            odometer_unit
-        ) VALUES (
-            $1, 
-            $2, 
-            $3, 
-            $4,
-            $5
-        ) RETURNING id";
-
-    let res = query(sql)
-        .bind(vehicle.owner_id)
-        .bind(vehicle.make)
-        .bind(vehicle.model)
-        .bind(vehicle.year)
-        .bind(vehicle.odometer_unit)
-        .fetch_one(pool)
-        .await?;
-
-    let id = res.try_get::<Uuid, _>("id")?;
+    let id = repository.insert(&vehicle, actor_id).await?;
 
     Ok(CreateVehicleResponse { id })
 }
 
-#[tracing::instrument(name = "vehicle_controller_update", skip(pool), err)]
+#[tracing::instrument(name = "vehicle_controller_update", skip(repository), err)]
 pub async fn update(
-    pool: &PgPool,
+    repository: &dyn VehicleRepository,
+    actor_id: Option<Uuid>,
     vehicle_id: &Uuid,
     body: UpdateVehicleBody,
+    if_match: Option<EntityTags>,
 ) -> Result<UpdateVehicleResponse, ApiError> {
     tracing::debug!("updating vehicle");
+    body.validate()?;
     let vehicle = DbVehicle::from_api_type(vehicle_id, body);
-    let sql = "
-        UPDATE vehicles 
-        SET 
-            make = $1, 
-            model = $2, 
-            year = $3, 
-            odometer_unit = $4
-        WHERE id = $5
-        RETURNING *";
-    let updated_vehicle = query_as::<_, DbVehicle>(sql)
-        .bind(vehicle.make)
-        .bind(vehicle.model)
-        .bind(vehicle.year)
-        .bind(vehicle.odometer_unit)
-        .bind(vehicle.id)
-        .fetch_one(pool)
+    let updated_vehicle = repository
+        .update(&vehicle, if_match.as_ref(), actor_id)
         .await?;
 
     updated_vehicle.try_into()
 }
 
-#[tracing::instrument(name = "vehicle_controller_delete", skip(pool), err)]
-pub async fn delete(pool: &PgPool, vehicle_id: &Uuid) -> Result<DeleteVehicleResponse, ApiError> {
+#[tracing::instrument(name = "vehicle_controller_patch", skip(repository), err)]
+pub async fn patch(
+    repository: &dyn VehicleRepository,
+    actor_id: Option<Uuid>,
+    vehicle_id: &Uuid,
+    body: PatchVehicleBody,
+    if_match: Option<EntityTags>,
+) -> Result<PatchVehicleResponse, ApiError> {
+    tracing::debug!("patching vehicle");
+    let existing_vehicle = read(repository, vehicle_id).await?;
+    // The patch applies to the version read, so a concurrent write fails it
+    // rather than being overwritten
+    let if_match = if_match.unwrap_or(EntityTags::Versions(vec![existing_vehicle.version]));
+    update(
+        repository,
+        actor_id,
+        vehicle_id,
+        body.apply_to(&existing_vehicle)?,
+        Some(if_match),
+    )
+    .await
+}
+
+#[tracing::instrument(name = "vehicle_controller_delete", skip(repository), err)]
+pub async fn delete(
+    repository: &dyn VehicleRepository,
+    vehicle_id: &Uuid,
+    params: DeleteVehicleParams,
+    if_match: Option<EntityTags>,
+) -> Result<DeleteVehicleResponse, ApiError> {
     tracing::debug!("deleting vehicle");
-    let sql = "DELETE FROM vehicles WHERE id = $1 RETURNING *";
-    let res = query(sql)
-        .bind(vehicle_id)
-        .fetch_one(pool)
-        .await
-        .map(|_| DeleteVehicleResponse)?;
+    repository
+        .delete(vehicle_id, params.cascade, if_match.as_ref())
+        .await?;
     tracing::info!("vehicle deleted");
-    Ok(res)
+    Ok(DeleteVehicleResponse)
+}
+
+#[tracing::instrument(name = "vehicle_controller_restore", skip(repository), err)]
+pub async fn restore(
+    repository: &dyn VehicleRepository,
+    vehicle_id: &Uuid,
+) -> Result<RestoreVehicleResponse, ApiError> {
+    tracing::debug!("restoring vehicle");
+    let restored_vehicle = repository.restore(vehicle_id).await?;
+    tracing::info!(?restored_vehicle, "vehicle restored");
+
+    restored_vehicle.try_into()
+}
+
+#[tracing::instrument(name = "vehicle_controller_purge", skip(repository), err)]
+pub async fn purge(
+    repository: &dyn VehicleRepository,
+    vehicle_id: &Uuid,
+) -> Result<PurgeVehicleResponse, ApiError> {
+    tracing::debug!("purging vehicle");
+    repository.purge(vehicle_id).await?;
+    tracing::info!("vehicle purged");
+
+    Ok(PurgeVehicleResponse)
 }
 
 #[cfg(test)]
 mod database_tests {
     use super::*;
-    use crate::utils::test_utils::db::seed_user;
+    use crate::{
+        models::api::CreateLogRecordBody,
+        utils::test_utils::db::{seed_user, seed_user_and_vehicle},
+    };
     use fake::{Fake, Faker};
+    use sqlx::PgPool;
 
     #[sqlx::test]
     async fn can_create_and_read(pool: PgPool) {
@@ -126,7 +146,7 @@ mod database_tests {
         };
 
         // Act
-        let res = create(&pool, vehicle_body.clone())
+        let res = create(&pool, None, vehicle_body.clone())
             .await
             .expect("could not create resource");
         let created_result = read(&pool, &res.id).await.expect("could not read resource");
@@ -156,10 +176,10 @@ mod database_tests {
         };
 
         // Act
-        create(&pool, vehicle_body_1.clone())
+        create(&pool, None, vehicle_body_1.clone())
             .await
             .expect("could not create resource");
-        create(&pool, vehicle_body_2.clone())
+        create(&pool, None, vehicle_body_2.clone())
             .await
             .expect("could not create resource");
         let created_result = list(&pool).await.expect("could not list resources");
@@ -195,10 +215,10 @@ mod database_tests {
         };
 
         // Act
-        let res = create(&pool, initial_vehicle_body.clone())
+        let res = create(&pool, None, initial_vehicle_body.clone())
             .await
             .expect("could not create resource");
-        let updated_result = update(&pool, &res.id, updated_vehicle_body.clone())
+        let updated_result = update(&pool, None, &res.id, updated_vehicle_body.clone(), None)
             .await
             .expect("could not update resource");
 
@@ -213,6 +233,109 @@ mod database_tests {
         );
     }
 
+    #[sqlx::test]
+    async fn can_patch(pool: PgPool) {
+        // Arrange
+        let owner_id = seed_user(&pool).await;
+        let initial_vehicle_body = CreateVehicleBody {
+            owner_id,
+            ..Faker.fake()
+        };
+        let patch_body = serde_json::from_value(serde_json::json!({"year": 2001}))
+            .expect("could not deserialize patch");
+
+        // Act
+        let res = create(&pool, None, initial_vehicle_body.clone())
+            .await
+            .expect("could not create resource");
+        let patched_result = patch(&pool, None, &res.id, patch_body, None)
+            .await
+            .expect("could not patch resource");
+
+        // Assert
+        assert_eq!(patched_result.year, 2001);
+        assert_eq!(patched_result.make, initial_vehicle_body.make);
+        assert_eq!(patched_result.model, initial_vehicle_body.model);
+        assert_eq!(patched_result.owner_id, owner_id);
+    }
+
+    /// Vehicles whose reads are immediately followed by a concurrent update
+    #[derive(Debug)]
+    struct RacingVehicles(PgPool);
+
+    #[axum::async_trait]
+    impl VehicleRepository for RacingVehicles {
+        async fn find(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+            let vehicle = VehicleRepository::find(&self.0, id).await?;
+            VehicleRepository::update(&self.0, &vehicle, None, None).await?;
+            Ok(vehicle)
+        }
+
+        async fn list(&self) -> Result<Vec<DbVehicle>, ApiError> {
+            VehicleRepository::list(&self.0).await
+        }
+
+        async fn insert(
+            &self,
+            vehicle: &DbVehicle,
+            actor_id: Option<Uuid>,
+        ) -> Result<Uuid, ApiError> {
+            VehicleRepository::insert(&self.0, vehicle, actor_id).await
+        }
+
+        async fn update(
+            &self,
+            vehicle: &DbVehicle,
+            if_match: Option<&EntityTags>,
+            actor_id: Option<Uuid>,
+        ) -> Result<DbVehicle, ApiError> {
+            VehicleRepository::update(&self.0, vehicle, if_match, actor_id).await
+        }
+
+        async fn delete(
+            &self,
+            id: &Uuid,
+            cascade: bool,
+            if_match: Option<&EntityTags>,
+        ) -> Result<(), ApiError> {
+            VehicleRepository::delete(&self.0, id, cascade, if_match).await
+        }
+
+        async fn restore(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+            VehicleRepository::restore(&self.0, id).await
+        }
+
+        async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+            VehicleRepository::purge(&self.0, id).await
+        }
+
+        async fn count(&self) -> Result<i64, ApiError> {
+            VehicleRepository::count(&self.0).await
+        }
+    }
+
+    #[sqlx::test]
+    async fn patch_fails_after_concurrent_write(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let patch_body = serde_json::from_value(serde_json::json!({"year": 2001}))
+            .expect("could not deserialize patch");
+
+        // Act
+        let err = patch(
+            &RacingVehicles(pool.clone()),
+            None,
+            &vehicle_id,
+            patch_body,
+            None,
+        )
+        .await
+        .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(err, ApiError::PreconditionFailed));
+    }
+
     #[sqlx::test]
     async fn can_delete(pool: PgPool) {
         // Arrange
@@ -223,11 +346,11 @@ mod database_tests {
         };
 
         // Act
-        let res = create(&pool, vehicle_body.clone())
+        let res = create(&pool, None, vehicle_body.clone())
             .await
             .expect("could not create resource");
         read(&pool, &res.id).await.expect("could not read resource");
-        delete(&pool, &res.id)
+        delete(&pool, &res.id, DeleteVehicleParams::default(), None)
             .await
             .expect("could not read resource");
         let created_result = read(&pool, &res.id)
@@ -236,4 +359,68 @@ mod database_tests {
 
         assert!(matches!(created_result, ApiError::ResourceNotFound));
     }
+
+    #[sqlx::test]
+    async fn delete_refuses_when_vehicle_has_log_records(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let log_record_id = crate::controllers::log_record::create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                ..Faker.fake()
+            },
+            Default::default(),
+        )
+        .await
+        .expect("could not create log record")
+        .id;
+
+        // Act
+        let err = delete(&pool, &vehicle_id, DeleteVehicleParams::default(), None)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(
+            matches!(err, ApiError::DependentResources { ids, .. } if ids == vec![log_record_id])
+        );
+    }
+
+    #[sqlx::test]
+    async fn delete_cascades_to_log_records(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let log_record_id = crate::controllers::log_record::create(
+            &pool,
+            CreateLogRecordBody {
+                vehicle_id,
+                ..Faker.fake()
+            },
+            Default::default(),
+        )
+        .await
+        .expect("could not create log record")
+        .id;
+
+        // Act
+        delete(
+            &pool,
+            &vehicle_id,
+            DeleteVehicleParams { cascade: true },
+            None,
+        )
+        .await
+        .expect("could not delete resource");
+
+        // Assert
+        assert!(matches!(
+            read(&pool, &vehicle_id).await,
+            Err(ApiError::ResourceNotFound)
+        ));
+        assert!(matches!(
+            crate::controllers::log_record::read(&pool, &log_record_id).await,
+            Err(ApiError::ResourceNotFound)
+        ));
+    }
 }
@@ -1,13 +1,24 @@
 use axum::{
     extract::rejection::JsonRejection,
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use sqlx::postgres::PgDatabaseError;
+use uuid::Uuid;
+
+use crate::types::FieldError;
 
 const POSTGRES_UNIQUE_VIOLATION: &str = "23505";
+const POSTGRES_FOREIGN_KEY_VIOLATION: &str = "23503";
+/// `SQLITE_BUSY`, which SQLite extends with the reason in the upper bits
+const SQLITE_BUSY: i32 = 5;
+
+/// Media type of error responses, see RFC 7807
+pub const PROBLEM_JSON: &str = "application/problem+json";
+/// Prefix of every problem `type`, followed by [`ApiError::problem_type`]
+pub const PROBLEM_TYPE_PREFIX: &str = "urn:fuel-logger:problem:";
 
 #[derive(Debug, thiserror::Error)]
 pub enum ApiError {
@@ -21,10 +32,49 @@ pub enum ApiError {
     ResourceNotFound,
 
     #[error("the requested inputs violate a unique constraint")]
-    UniqueConstraintViolation { detail: Option<String> },
+    UniqueConstraintViolation {
+        constraint: Option<String>,
+        detail: Option<String>,
+    },
+
+    #[error("the vehicle owner is not a member of the organisation")]
+    NotOrganisationMember,
+
+    #[error("the acting user must be an organisation {0}")]
+    OrganisationRoleRequired(String),
+
+    #[error("the resource has been modified since it was read")]
+    PreconditionFailed,
 
-    #[error("log record is of the wrong type and can't be updated")]
-    WrongLogRecordType,
+    #[error("admin privileges are required")]
+    AdminRequired,
+
+    #[error("the resource still has dependent {resource}")]
+    DependentResources { resource: String, ids: Vec<Uuid> },
+
+    #[error("the log record looks like a duplicate of an existing one")]
+    DuplicateLogRecord { existing_id: Uuid },
+
+    #[error("the requested inputs violate a foreign key constraint")]
+    ForeignKeyViolation {
+        constraint: Option<String>,
+        detail: Option<String>,
+    },
+
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    #[error("the request body is invalid")]
+    Validation(Vec<FieldError>),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    Unavailable(String),
+
+    #[error("{0}")]
+    Unsupported(String),
 
     #[error("{0}")]
     Configuration(#[from] config::ConfigError),
@@ -40,12 +90,32 @@ impl From<sqlx::Error> for ApiError {
                 if let Some(pg_err) = db_err.try_downcast_ref::<PgDatabaseError>() {
                     match pg_err.code() {
                         POSTGRES_UNIQUE_VIOLATION => ApiError::UniqueConstraintViolation {
+                            constraint: pg_err.constraint().map(ToOwned::to_owned),
+                            detail: pg_err.detail().map(ToOwned::to_owned),
+                        },
+                        POSTGRES_FOREIGN_KEY_VIOLATION => ApiError::ForeignKeyViolation {
+                            constraint: pg_err.constraint().map(ToOwned::to_owned),
                             detail: pg_err.detail().map(ToOwned::to_owned),
                         },
                         _ => ApiError::Database(value),
                     }
+                } else if sqlite_busy(db_err.code().as_deref()) {
+                    // Another connection kept the write lock past the busy
+                    // timeout, or wrote since this transaction began reading
+                    ApiError::Unavailable("the database is busy, try again".to_owned())
                 } else {
-                    ApiError::Database(value)
+                    match db_err.kind() {
+                        sqlx::error::ErrorKind::UniqueViolation => {
+                            sqlite_unique_violation(db_err.message())
+                        }
+                        sqlx::error::ErrorKind::ForeignKeyViolation => {
+                            ApiError::ForeignKeyViolation {
+                                constraint: None,
+                                detail: None,
+                            }
+                        }
+                        _ => ApiError::Database(value),
+                    }
                 }
             }
             _ => ApiError::Database(value),
@@ -53,9 +123,102 @@ impl From<sqlx::Error> for ApiError {
     }
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, msg) = match self {
+/// Whether a SQLite result code is `SQLITE_BUSY` or one of its extended codes
+fn sqlite_busy(code: Option<&str>) -> bool {
+    code.and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| code & 0xff == SQLITE_BUSY)
+}
+
+/// SQLite only names the violating columns, as in `UNIQUE constraint failed:
+/// users.username`, so the constraint and detail are given the names Postgres
+/// would use
+fn sqlite_unique_violation(message: &str) -> ApiError {
+    let Some(columns) = message.strip_prefix("UNIQUE constraint failed: ") else {
+        return ApiError::UniqueConstraintViolation {
+            constraint: None,
+            detail: None,
+        };
+    };
+    let (tables, columns): (Vec<_>, Vec<_>) = columns
+        .split(", ")
+        .filter_map(|column| column.split_once('.'))
+        .unzip();
+
+    ApiError::UniqueConstraintViolation {
+        constraint: tables
+            .first()
+            .map(|table| format!("{table}_{}_key", columns.join("_"))),
+        detail: Some(format!("Key ({}) already exists.", columns.join(", "))),
+    }
+}
+
+impl ApiError {
+    /// Stable identifier for the kind of error, reported as the problem `type`
+    pub fn problem_type(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "database-error",
+            Self::Conversion(_) => "conversion-error",
+            Self::ResourceNotFound => "not-found",
+            Self::UniqueConstraintViolation { .. } => "unique-violation",
+            Self::NotOrganisationMember => "not-organisation-member",
+            Self::OrganisationRoleRequired(_) => "organisation-role-required",
+            Self::PreconditionFailed => "precondition-failed",
+            Self::AdminRequired => "admin-required",
+            Self::DependentResources { .. } => "dependent-resources",
+            Self::DuplicateLogRecord { .. } => "duplicate-log-record",
+            Self::ForeignKeyViolation { .. } => "foreign-key-violation",
+            Self::InvalidRequest(_) => "invalid-request",
+            Self::Validation(_) => "validation-failed",
+            Self::Conflict(_) => "conflict",
+            Self::Unavailable(_) => "unavailable",
+            Self::Unsupported(_) => "unsupported",
+            Self::Configuration(_) => "configuration-error",
+            Self::JsonError(_) => "malformed-body",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "Database error",
+            Self::Conversion(_) => "Conversion error",
+            Self::ResourceNotFound => "Resource not found",
+            Self::UniqueConstraintViolation { .. } => "Unique constraint violated",
+            Self::NotOrganisationMember => "Not an organisation member",
+            Self::OrganisationRoleRequired(_) => "Organisation role required",
+            Self::PreconditionFailed => "Precondition failed",
+            Self::AdminRequired => "Admin privileges required",
+            Self::DependentResources { .. } => "Resource has dependents",
+            Self::DuplicateLogRecord { .. } => "Duplicate log record",
+            Self::ForeignKeyViolation { .. } => "Foreign key constraint violated",
+            Self::InvalidRequest(_) => "Invalid request",
+            Self::Validation(_) => "Validation failed",
+            Self::Conflict(_) => "Conflict",
+            Self::Unavailable(_) => "Service unavailable",
+            Self::Unsupported(_) => "Not supported",
+            Self::Configuration(_) => "Configuration error",
+            Self::JsonError(_) => "Malformed request body",
+        }
+    }
+
+    /// The status code and problem document the error is reported with
+    pub fn into_status_and_body(self) -> (StatusCode, serde_json::Value) {
+        let mut body = json!({
+            "type": format!("{PROBLEM_TYPE_PREFIX}{}", self.problem_type()),
+            "title": self.title(),
+        });
+        match &self {
+            Self::DependentResources { ids, .. } => body["dependents"] = json!(ids),
+            Self::DuplicateLogRecord { existing_id } => body["existing_id"] = json!(existing_id),
+            Self::Validation(errors) => body["errors"] = json!(errors),
+            Self::UniqueConstraintViolation { constraint, detail }
+            | Self::ForeignKeyViolation { constraint, detail } => {
+                body["constraint"] = json!(constraint);
+                body["fields"] = json!(detail.as_deref().map(key_fields).unwrap_or_default());
+            }
+            _ => {}
+        }
+
+        let (status, detail) = match self {
             Self::Database(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "database error".to_owned(),
@@ -65,22 +228,172 @@ impl IntoResponse for ApiError {
                 "problem converting types".to_owned(),
             ),
             Self::ResourceNotFound => (StatusCode::NOT_FOUND, "resource not found".to_owned()),
-            Self::UniqueConstraintViolation { detail } => (
+            Self::UniqueConstraintViolation { detail, .. } => (
                 StatusCode::CONFLICT,
                 detail.unwrap_or("unknown violation".to_owned()),
             ),
-            Self::WrongLogRecordType => (StatusCode::BAD_REQUEST, self.to_string()),
+            Self::NotOrganisationMember => (StatusCode::FORBIDDEN, self.to_string()),
+            Self::OrganisationRoleRequired(_) => (StatusCode::FORBIDDEN, self.to_string()),
+            Self::AdminRequired => (StatusCode::FORBIDDEN, self.to_string()),
+            Self::PreconditionFailed => (StatusCode::PRECONDITION_FAILED, self.to_string()),
+            Self::DependentResources { .. } => (StatusCode::CONFLICT, self.to_string()),
+            Self::DuplicateLogRecord { .. } => (StatusCode::CONFLICT, self.to_string()),
+            Self::ForeignKeyViolation { detail, .. } => {
+                // Deleting a row others refer to conflicts with them, while
+                // referring to a missing row is a mistake of the request
+                let status = if detail.as_deref().is_some_and(still_referenced) {
+                    StatusCode::CONFLICT
+                } else {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                };
+                (status, detail.unwrap_or("unknown violation".to_owned()))
+            }
+            Self::InvalidRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Self::Validation(_) => (StatusCode::UNPROCESSABLE_ENTITY, self.to_string()),
+            Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            Self::Unavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
+            Self::Unsupported(msg) => (StatusCode::NOT_IMPLEMENTED, msg),
             Self::Configuration(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "problem parsing configuration".to_owned(),
             ),
             Self::JsonError(e) => (e.status(), e.body_text()),
         };
+        body["status"] = json!(status.as_u16());
+        body["detail"] = json!(detail);
+
+        (status, body)
+    }
+}
+
+/// Pulls the column names out of a constraint violation detail such as
+/// `Key (username)=(bob) already exists.`
+fn key_fields(detail: &str) -> Vec<String> {
+    detail
+        .strip_prefix("Key (")
+        .and_then(|rest| rest.split_once(')'))
+        .map(|(columns, _)| columns.split(", ").map(ToOwned::to_owned).collect())
+        .unwrap_or_default()
+}
+
+/// Whether a foreign key violation detail such as `Key (id)=(1) is still
+/// referenced from table "vehicles".` is about deleting a referenced row.
+/// Only Postgres reports details, and SQLite violations come from requests
+/// referring to missing rows.
+fn still_referenced(detail: &str) -> bool {
+    detail.contains("is still referenced")
+}
 
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, body) = self.into_status_and_body();
         (
             status,
-            Json(json!({"error_msg": msg, "code": status.as_u16()})),
+            [(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))],
+            Json(body),
         )
             .into_response()
     }
 }
+
+#[cfg(test)]
+mod problem_tests {
+    use super::*;
+
+    #[test]
+    fn reports_problem_document() {
+        // Arrange
+        let error = ApiError::ResourceNotFound;
+
+        // Act
+        let (status, body) = error.into_status_and_body();
+
+        // Assert
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            body,
+            json!({
+                "type": "urn:fuel-logger:problem:not-found",
+                "title": "Resource not found",
+                "status": 404,
+                "detail": "resource not found",
+            })
+        );
+    }
+
+    #[test]
+    fn reports_violated_constraint_and_fields() {
+        // Arrange
+        let error = ApiError::UniqueConstraintViolation {
+            constraint: Some("users_email_key".to_owned()),
+            detail: Some("Key (email)=(a@b.c) already exists.".to_owned()),
+        };
+
+        // Act
+        let (status, body) = error.into_status_and_body();
+
+        // Assert
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["type"], "urn:fuel-logger:problem:unique-violation");
+        assert_eq!(body["constraint"], "users_email_key");
+        assert_eq!(body["fields"], json!(["email"]));
+    }
+
+    #[test_case::test_case(
+        Some("Key (owner_id)=(1) is not present in table \"users\".") => StatusCode::UNPROCESSABLE_ENTITY;
+        "missing reference"
+    )]
+    #[test_case::test_case(
+        Some("Key (id)=(1) is still referenced from table \"vehicles\".") => StatusCode::CONFLICT;
+        "still referenced"
+    )]
+    #[test_case::test_case(None => StatusCode::UNPROCESSABLE_ENTITY; "without detail")]
+    fn reports_foreign_key_violation(detail: Option<&str>) -> StatusCode {
+        let error = ApiError::ForeignKeyViolation {
+            constraint: None,
+            detail: detail.map(ToOwned::to_owned),
+        };
+
+        error.into_status_and_body().0
+    }
+
+    #[test]
+    fn sets_problem_content_type() {
+        // Act
+        let response = ApiError::AdminRequired.into_response();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+    }
+
+    #[test_case::test_case(Some("5") => true; "busy")]
+    #[test_case::test_case(Some("517") => true; "busy snapshot")]
+    #[test_case::test_case(Some("6") => false; "locked")]
+    #[test_case::test_case(Some("23505") => false; "postgres code")]
+    #[test_case::test_case(None => false; "no code")]
+    fn detects_sqlite_busy(code: Option<&str>) -> bool {
+        sqlite_busy(code)
+    }
+
+    #[test_case::test_case("Key (username)=(bob) already exists." => vec!["username"]; "single")]
+    #[test_case::test_case("Key (org_id, user_id)=(1, 2) already exists." => vec!["org_id", "user_id"]; "composite")]
+    #[test_case::test_case("Key (username) already exists." => vec!["username"]; "without values")]
+    #[test_case::test_case("something else" => Vec::<String>::new(); "unrecognised")]
+    fn parses_key_fields(detail: &str) -> Vec<String> {
+        key_fields(detail)
+    }
+
+    #[test]
+    fn names_sqlite_unique_violation_like_postgres() {
+        // Act
+        let error = sqlite_unique_violation("UNIQUE constraint failed: users.email");
+
+        // Assert
+        let ApiError::UniqueConstraintViolation { constraint, detail } = error else {
+            panic!("expected a unique constraint violation, got {error:?}");
+        };
+        assert_eq!(constraint.as_deref(), Some("users_email_key"));
+        assert_eq!(detail.as_deref(), Some("Key (email) already exists."));
+    }
+}
@@ -0,0 +1,72 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use utoipa::{
+    openapi::{
+        path::{Parameter, ParameterIn},
+        KnownFormat,
+    },
+    IntoParams,
+};
+use uuid::Uuid;
+
+use crate::{error::ApiError, extractors::header_parameter};
+
+pub const ACTOR_HEADER: &str = "x-user-id";
+
+/// The user on whose behalf a request is made, as given by the `X-User-Id`
+/// header. Requests without the header are anonymous.
+///
+/// The server doesn't authenticate anyone, so the header is only accepted when
+/// [`TrustedProxy`] says an authenticating proxy sets it. It identifies
+/// users for auditing and roles, but is no stronger than that proxy.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Actor(pub Option<Uuid>);
+
+/// Whether requests come through a proxy trusted to set the `X-User-Id`
+/// header, added to requests as an extension by the router
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TrustedProxy(pub bool);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Actor
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(ACTOR_HEADER) else {
+            return Ok(Self(None));
+        };
+        let TrustedProxy(trusted) = parts
+            .extensions
+            .get::<TrustedProxy>()
+            .copied()
+            .unwrap_or_default();
+        if !trusted {
+            tracing::error!("{ACTOR_HEADER} header sent without a trusted proxy");
+            return Err(ApiError::InvalidRequest(format!(
+                "{ACTOR_HEADER} header is only accepted from a trusted proxy"
+            )));
+        }
+
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| value.parse::<Uuid>().ok())
+            .map(|user_id| Self(Some(user_id)))
+            .ok_or_else(|| {
+                tracing::error!("invalid {ACTOR_HEADER} header: {value:?}");
+                ApiError::InvalidRequest(format!("{ACTOR_HEADER} header must be a valid UUID"))
+            })
+    }
+}
+
+impl IntoParams for Actor {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![header_parameter(
+            ACTOR_HEADER,
+            "Id of the user on whose behalf the request is made",
+            Some(KnownFormat::Uuid),
+        )]
+    }
+}
@@ -1,9 +1,14 @@
+use std::error::Error;
+
 use axum::{
     async_trait,
-    extract::{rejection::JsonRejection, FromRequest, Request},
+    extract::{
+        rejection::{JsonDataError, JsonRejection},
+        FromRequest, Request,
+    },
 };
 
-use crate::error::ApiError;
+use crate::{error::ApiError, types::FieldError};
 
 pub struct Json<T>(pub T);
 
@@ -20,8 +25,102 @@ where
             Ok(value) => Ok(Self(value.0)),
             Err(rejection) => {
                 tracing::error!("problem extracting JSON request body: {rejection}");
-                Err(ApiError::JsonError(rejection))
+                match &rejection {
+                    JsonRejection::JsonDataError(e) => match field_error(e) {
+                        Some(field_error) => Err(ApiError::Validation(vec![field_error])),
+                        None => Err(ApiError::JsonError(rejection)),
+                    },
+                    _ => Err(ApiError::JsonError(rejection)),
+                }
             }
         }
     }
 }
+
+/// Recovers the path of the field that could not be deserialized
+fn field_error(rejection: &JsonDataError) -> Option<FieldError> {
+    rejection
+        .source()?
+        .source()?
+        .downcast_ref::<serde_path_to_error::Error<serde_json::Error>>()
+        .map(FieldError::from_path_error)
+}
+
+#[cfg(test)]
+mod rejection_tests {
+    use super::*;
+    use axum::{body::Body, http::header::CONTENT_TYPE};
+    use serde_json::json;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Outer {
+        inner: Vec<Inner>,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Inner {
+        year: u16,
+        make: String,
+    }
+
+    async fn extract(body: serde_json::Value) -> Result<Outer, ApiError> {
+        let request = Request::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .expect("could not build request");
+        Json::<Outer>::from_request(request, &())
+            .await
+            .map(|Json(outer)| outer)
+    }
+
+    #[tokio::test]
+    async fn reports_path_of_invalid_field() {
+        // Act
+        let res =
+            extract(json!({"inner": [{"year": 2000, "make": "a"}, {"year": -1, "make": "b"}]}))
+                .await;
+
+        // Assert
+        match res {
+            Err(ApiError::Validation(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].field, "inner[1].year");
+                assert_eq!(errors[0].code, "invalid_value");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_path_of_missing_field() {
+        // Act
+        let res = extract(json!({"inner": [{"year": 2000}]})).await;
+
+        // Assert
+        match res {
+            Err(ApiError::Validation(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].field, "inner[0].make");
+                assert_eq!(errors[0].code, "required");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn syntax_errors_are_not_field_errors() {
+        // Arrange
+        let request = Request::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from("{"))
+            .expect("could not build request");
+
+        // Act
+        let res = Json::<Outer>::from_request(request, &()).await;
+
+        // Assert
+        assert!(matches!(res, Err(ApiError::JsonError(_))));
+    }
+}
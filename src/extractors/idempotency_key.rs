@@ -0,0 +1,52 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+use utoipa::{
+    openapi::path::{Parameter, ParameterIn},
+    IntoParams,
+};
+
+use crate::{error::ApiError, extractors::header_parameter};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const MAX_KEY_LENGTH: usize = 255;
+
+/// The client-chosen key of an `Idempotency-Key` header, under which the
+/// outcome of a create request is remembered so that retries can replay it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdempotencyKey(pub Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IdempotencyKey
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(Self(None));
+        };
+
+        value
+            .to_str()
+            .ok()
+            .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LENGTH)
+            .map(|key| Self(Some(key.to_owned())))
+            .ok_or_else(|| {
+                tracing::error!("invalid {IDEMPOTENCY_KEY_HEADER} header: {value:?}");
+                ApiError::InvalidRequest(format!(
+                    "{IDEMPOTENCY_KEY_HEADER} header must be between 1 and {MAX_KEY_LENGTH} ASCII characters"
+                ))
+            })
+    }
+}
+
+impl IntoParams for IdempotencyKey {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![header_parameter(
+            IDEMPOTENCY_KEY_HEADER,
+            "Key under which the outcome is remembered for a day so that retries are replayed",
+            None,
+        )]
+    }
+}
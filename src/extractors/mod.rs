@@ -1 +1,24 @@
+pub mod actor;
 pub mod custom_json;
+pub mod idempotency_key;
+pub mod precondition;
+
+use utoipa::openapi::{
+    path::{Parameter, ParameterBuilder, ParameterIn},
+    schema::{KnownFormat, ObjectBuilder, SchemaFormat, Type},
+    Required,
+};
+
+/// Documents the optional request header an extractor reads
+fn header_parameter(name: &str, description: &str, format: Option<KnownFormat>) -> Parameter {
+    let schema = ObjectBuilder::new()
+        .schema_type(Type::String)
+        .format(format.map(SchemaFormat::KnownFormat));
+    ParameterBuilder::new()
+        .name(name)
+        .parameter_in(ParameterIn::Header)
+        .required(Required::False)
+        .description(Some(description))
+        .schema(Some(schema))
+        .build()
+}
@@ -0,0 +1,104 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+        request::Parts,
+        HeaderName, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+
+use utoipa::{
+    openapi::path::{Parameter, ParameterIn},
+    IntoParams,
+};
+
+use crate::{
+    error::ApiError,
+    extractors::header_parameter,
+    types::{entity_tag::entity_tag, EntityTags},
+};
+
+/// The entity tags of the `If-Match` header, which writes must match
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IfMatch(pub Option<EntityTags>);
+
+/// The entity tags of the `If-None-Match` header, which reads are compared
+/// against to avoid resending unchanged resources
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IfNoneMatch(pub Option<EntityTags>);
+
+impl IfNoneMatch {
+    /// Responds with 304 Not Modified when the client already holds this
+    /// version of the resource, and with the resource itself otherwise
+    pub fn respond(self, version: i64, resource: impl IntoResponse) -> Response {
+        match self.0 {
+            Some(tags) if tags.matches(version) => {
+                (StatusCode::NOT_MODIFIED, [(ETAG, entity_tag(version))]).into_response()
+            }
+            _ => resource.into_response(),
+        }
+    }
+}
+
+fn entity_tags(
+    parts: &Parts,
+    header: HeaderName,
+    parse: fn(&str) -> EntityTags,
+) -> Result<Option<EntityTags>, ApiError> {
+    parts
+        .headers
+        .get(&header)
+        .map(|value| {
+            let value = value.to_str().map_err(|_| {
+                ApiError::InvalidRequest(format!("{header} header must be valid ASCII"))
+            })?;
+            Ok(parse(value))
+        })
+        .transpose()
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        entity_tags(parts, IF_MATCH, EntityTags::parse).map(Self)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfNoneMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        entity_tags(parts, IF_NONE_MATCH, EntityTags::parse_weak).map(Self)
+    }
+}
+
+impl IntoParams for IfMatch {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![header_parameter(
+            IF_MATCH.as_str(),
+            "Only write if the resource still has one of these entity tags",
+            None,
+        )]
+    }
+}
+
+impl IntoParams for IfNoneMatch {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![header_parameter(
+            IF_NONE_MATCH.as_str(),
+            "Respond with 304 Not Modified if the resource still has one of these entity tags",
+            None,
+        )]
+    }
+}
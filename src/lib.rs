@@ -2,31 +2,187 @@ pub mod configuration;
 pub mod controllers;
 pub mod error;
 pub mod extractors;
+pub mod metrics;
+pub mod middleware;
+pub mod migrations;
 pub mod models;
+pub mod repositories;
 pub mod routes;
+pub mod shutdown;
+pub mod tls;
 pub mod types;
 pub mod utils;
 
-use axum::Router;
-use routes::{log_records, users, vehicles};
-use sqlx::PgPool;
+use axum::{
+    middleware::{from_fn, from_fn_with_state},
+    Extension, Router,
+};
+use extractors::actor::TrustedProxy;
+use middleware::{
+    audit::{audit, AuditState},
+    metrics::track_requests,
+    problem::annotate_problem,
+};
+use repositories::{
+    memory::InMemory, AuditRepository, IdempotencyRepository, LogRecordRepository,
+    OrganisationRepository, Repositories, SyncRepository, UserRepository, VehicleRepository,
+};
+use routes::{
+    audit as audit_routes, health, log_records, metrics as metrics_routes, openapi, organisations,
+    sync, users, vehicles,
+};
+use sqlx::{PgPool, SqlitePool};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use types::AuditResource;
 
 #[derive(Clone, Debug)]
 pub struct AppState {
-    db: PgPool,
+    /// Only set with Postgres, whose connection pool is reported by the
+    /// metrics
+    db: Option<PgPool>,
+    repositories: Repositories,
+    /// Read replica of the Postgres database, serving lists and reads
+    replica: Option<Repositories>,
+    metrics: metrics::Metrics,
+    /// Accept the `X-User-Id` header, set by an authenticating proxy
+    trusted_proxy: bool,
+}
+
+impl AppState {
+    pub fn postgres(pool: &PgPool) -> Self {
+        Self {
+            db: Some(pool.clone()),
+            repositories: Repositories::postgres(pool),
+            replica: None,
+            metrics: metrics::Metrics::new(),
+            trusted_proxy: false,
+        }
+    }
+
+    /// Serves lists and reads of users, vehicles and log records from a read
+    /// replica, which may lag behind the primary database
+    pub fn with_replica(self, replica: &PgPool) -> Self {
+        Self {
+            replica: Some(Repositories::postgres(replica)),
+            ..self
+        }
+    }
+
+    /// State whose resources are kept by SQLite
+    pub fn sqlite(pool: &SqlitePool) -> Self {
+        Self {
+            db: None,
+            repositories: Repositories::sqlite(pool),
+            replica: None,
+            metrics: metrics::Metrics::new(),
+            trusted_proxy: false,
+        }
+    }
+
+    /// State whose resources live in memory until the process exits
+    pub fn in_memory(store: &InMemory) -> Self {
+        Self {
+            db: None,
+            repositories: Repositories::in_memory(store),
+            replica: None,
+            metrics: metrics::Metrics::new(),
+            trusted_proxy: false,
+        }
+    }
+
+    /// Accepts the `X-User-Id` header when `trusted`, which is only safe
+    /// behind a proxy that sets it from the user it authenticated
+    pub fn with_trusted_proxy(self, trusted: bool) -> Self {
+        Self {
+            trusted_proxy: trusted,
+            ..self
+        }
+    }
+
+    fn users(&self) -> &dyn UserRepository {
+        &*self.repositories.users
+    }
+
+    fn vehicles(&self) -> &dyn VehicleRepository {
+        &*self.repositories.vehicles
+    }
+
+    fn log_records(&self) -> &dyn LogRecordRepository {
+        &*self.repositories.log_records
+    }
+
+    fn organisations(&self) -> &dyn OrganisationRepository {
+        &*self.repositories.organisations
+    }
+
+    fn audit(&self) -> &dyn AuditRepository {
+        &*self.repositories.audit
+    }
+
+    fn idempotency(&self) -> &dyn IdempotencyRepository {
+        &*self.repositories.idempotency
+    }
+
+    fn sync(&self) -> &dyn SyncRepository {
+        &*self.repositories.sync
+    }
+
+    pub fn metrics(&self) -> &metrics::Metrics {
+        &self.metrics
+    }
+
+    /// The repositories lists and reads are served from
+    fn readers(&self) -> &Repositories {
+        self.replica.as_ref().unwrap_or(&self.repositories)
+    }
 }
 
-#[tracing::instrument(name = "build_main_router", skip_all)]
 pub fn build_router(pool: &PgPool) -> Router {
+    build_router_with_state(AppState::postgres(pool))
+}
+
+#[tracing::instrument(name = "build_main_router", skip_all)]
+pub fn build_router_with_state(state: AppState) -> Router {
     tracing::debug!("building main router");
-    let state = AppState { db: pool.clone() };
+    let audit_log = &state.repositories.audit;
+    let metrics = &state.metrics;
     Router::new()
-        .nest("/users", users::build_router())
-        .nest("/vehicles", vehicles::build_router())
-        .nest("/log_records", log_records::build_router())
+        .nest(
+            "/users",
+            users::build_router().route_layer(from_fn_with_state(
+                AuditState::new(audit_log.clone(), AuditResource::User, metrics),
+                audit,
+            )),
+        )
+        .nest(
+            "/vehicles",
+            vehicles::build_router().route_layer(from_fn_with_state(
+                AuditState::new(audit_log.clone(), AuditResource::Vehicle, metrics),
+                audit,
+            )),
+        )
+        .nest(
+            "/log_records",
+            log_records::build_router().route_layer(from_fn_with_state(
+                AuditState::new(audit_log.clone(), AuditResource::LogRecord, metrics),
+                audit,
+            )),
+        )
+        .nest("/organisations", organisations::build_router())
+        .nest("/audit", audit_routes::build_router())
+        .nest("/sync", sync::build_router())
+        .merge(health::build_router())
+        .merge(metrics_routes::build_router())
+        .merge(openapi::build_router())
+        // Wraps every route, so the matched route template is known
+        .layer(from_fn_with_state(state.metrics.clone(), track_requests))
+        .layer(Extension(TrustedProxy(state.trusted_proxy)))
         .with_state(state)
         .layer(
             tower_http::trace::TraceLayer::new_for_http()
                 .on_request(tower_http::trace::DefaultOnRequest::new().level(tracing::Level::INFO)),
         )
+        .layer(from_fn(annotate_problem))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
 }
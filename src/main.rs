@@ -1,13 +1,17 @@
 use anyhow::Context;
-use axum::serve;
 use clap::Parser;
 use fuel_logger_rs::{
-    build_router,
-    configuration::{read_config, Configuration, LogFormat},
+    build_router_with_state,
+    configuration::{read_config, Configuration, DatabaseBackend, DatabaseConfig, LogFormat},
+    migrations,
+    repositories::memory::InMemory,
+    routes::openapi::ApiDoc,
+    shutdown, tls, AppState,
 };
-use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgPool, SqlitePool};
 use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use utoipa::OpenApi;
 
 #[derive(clap::Parser, Debug)]
 #[command(version)]
@@ -18,36 +22,226 @@ struct Cli {
     /// Print a sample configuration to stdout and then exit
     #[arg(long)]
     init_config: bool,
+
+    /// Print the OpenAPI document to stdout and then exit
+    #[arg(long)]
+    openapi: bool,
+
+    /// Apply pending migrations before serving, regardless of configuration
+    #[arg(long)]
+    migrate: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-async fn run(config: Configuration) -> anyhow::Result<()> {
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .acquire_timeout(Duration::from_secs(5))
-        .connect(&config.database.url)
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Manage the database schema with the migrations embedded in this binary
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum MigrateAction {
+    /// Apply every pending migration
+    Up,
+    /// Revert the latest migration
+    Down {
+        /// Revert every migration newer than this version instead
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List every migration and whether it has been applied
+    Status,
+}
+
+/// Connects to the Postgres database at `url`, the primary or the replica
+async fn connect(config: &DatabaseConfig, url: &str) -> anyhow::Result<PgPool> {
+    let options = config
+        .connect_options(url)
+        .context("invalid database url")?;
+    config
+        .pool_options()
+        .connect_with(options)
         .await
-        .context("can't connect to database")?;
+        .context("can't connect to database")
+}
 
-    // Build main app router
-    let app = build_router(&pool);
+/// Opens the SQLite database, creating the file if needed, and brings its
+/// schema up to date
+async fn connect_sqlite(config: &DatabaseConfig) -> anyhow::Result<SqlitePool> {
+    let options = config
+        .sqlite_connect_options()
+        .context("invalid SQLite database url")?;
+    let pool = config
+        .sqlite_pool_options()
+        .connect_with(options)
+        .await
+        .context("can't open SQLite database")?;
+    migrations::SQLITE_MIGRATOR
+        .run(&pool)
+        .await
+        .context("failed to apply SQLite migrations")?;
+    Ok(pool)
+}
 
-    // let port = env::var("PORT").unwrap_or("3000".to_owned());
-    let addr = format!("{}:{}", config.server.host, config.server.port);
+async fn migrate(config: Configuration, action: MigrateAction) -> anyhow::Result<()> {
+    match config.database.backend() {
+        DatabaseBackend::Postgres => {}
+        DatabaseBackend::Sqlite => {
+            anyhow::bail!("SQLite migrations are applied automatically on startup")
+        }
+        DatabaseBackend::Memory => anyhow::bail!("the in-memory database has no migrations"),
+    }
+    let pool = connect(&config.database, &config.database.url).await?;
+    match action {
+        MigrateAction::Up => {
+            migrations::up(&pool)
+                .await
+                .context("failed to apply migrations")?;
+            tracing::info!("applied pending migrations");
+        }
+        MigrateAction::Down { target } => {
+            let reverted = migrations::down(&pool, target)
+                .await
+                .context("failed to revert migrations")?;
+            tracing::info!(?reverted, "reverted migrations");
+        }
+        MigrateAction::Status => {
+            for migration in migrations::status(&pool)
+                .await
+                .context("failed to read migrations")?
+            {
+                println!("{migration}");
+            }
+        }
+    }
+    Ok(())
+}
 
-    // run our app with hyper, listening globally on port 3000
+/// Connection pools to close once the server has stopped
+#[derive(Default)]
+struct Pools {
+    postgres: Vec<PgPool>,
+    sqlite: Option<SqlitePool>,
+}
+
+impl Pools {
+    /// Closes every pool, waiting a while for connections still held by
+    /// abandoned requests
+    async fn close(self) {
+        let close = async {
+            for pool in self.postgres {
+                pool.close().await;
+            }
+            if let Some(pool) = self.sqlite {
+                pool.close().await;
+            }
+        };
+        if tokio::time::timeout(POOL_CLOSE_TIMEOUT, close)
+            .await
+            .is_err()
+        {
+            tracing::warn!("gave up waiting for database connections to close");
+        }
+    }
+}
+
+/// How long closing the connection pools may take after the server stopped
+const POOL_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn run(config: Configuration, migrate: bool) -> anyhow::Result<()> {
+    // Build main app state
+    let mut pools = Pools::default();
+    let state = match config.database.backend() {
+        DatabaseBackend::Memory => {
+            tracing::warn!("keeping data in memory, it will be lost on exit");
+            AppState::in_memory(&InMemory::default())
+        }
+        DatabaseBackend::Sqlite => {
+            let pool = connect_sqlite(&config.database).await?;
+            tracing::info!("storing data in SQLite");
+            let state = AppState::sqlite(&pool);
+            pools.sqlite = Some(pool);
+            state
+        }
+        DatabaseBackend::Postgres => {
+            let pool = connect(&config.database, &config.database.url).await?;
+            if migrate || config.database.migrate {
+                migrations::up(&pool)
+                    .await
+                    .context("failed to apply migrations")?;
+                tracing::info!("applied pending migrations");
+            }
+            let mut state = AppState::postgres(&pool);
+            pools.postgres.push(pool);
+            if let Some(replica_url) = &config.database.replica {
+                let replica = connect(&config.database, replica_url)
+                    .await
+                    .context("can't connect to read replica")?;
+                tracing::info!("serving lists and reads from the read replica");
+                state = state.with_replica(&replica);
+                pools.postgres.push(replica);
+            }
+            state
+        }
+    };
+    if config.server.proxy.trusted {
+        tracing::info!("accepting the X-User-Id header from the trusted proxy");
+    }
+    let state = state.with_trusted_proxy(config.server.proxy.trusted);
+    let metrics = state.metrics().clone();
+    let app = build_router_with_state(state);
+
+    let addr = format!("{}:{}", config.server.host, config.server.port);
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
         .context("failed to create TCP listener")?;
-    tracing::info!("serving application on: {addr}");
-    serve(listener, app)
-        .await
-        .context("failed to serve axum app")?;
+    let deadline = Duration::from_secs(config.server.shutdown.timeout);
+    let summary = match &config.server.tls {
+        Some(tls_config) => {
+            let tls = tls::load(tls_config).context("can't load TLS certificates")?;
+            let reloader = tls::watch(tls_config.clone(), tls.clone(), tls::RELOAD_INTERVAL);
+            tracing::info!(
+                mutual = tls_config.ca.is_some(),
+                "serving application over HTTPS on: {addr}"
+            );
+            let summary =
+                shutdown::serve_tls(listener, app, tls, shutdown::signal(), deadline, &metrics)
+                    .await;
+            reloader.abort();
+            summary
+        }
+        None => {
+            tracing::info!("serving application on: {addr}");
+            shutdown::serve(listener, app, shutdown::signal(), deadline, &metrics).await
+        }
+    }
+    .context("failed to serve axum app")?;
+
+    pools.close().await;
+    tracing::info!(
+        drain_time = ?summary.drain_time,
+        abandoned_requests = summary.abandoned,
+        "server stopped"
+    );
     Ok(())
 }
 
 #[tracing::instrument]
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli_args = Cli::parse();
+
+    // The OpenAPI document doesn't depend on configuration
+    if cli_args.openapi {
+        println!("{}", ApiDoc::openapi().to_pretty_json()?);
+        return Ok(());
+    }
+
     // Load config
     let config = read_config().context("failed to load configuration")?;
 
@@ -74,12 +268,15 @@ async fn main() -> anyhow::Result<()> {
         .context("registering tracing subscriber")?;
 
     // Handle CLI command
-    let cli_args = Cli::parse();
     if cli_args.init_config {
         print!(include_str!("../dev/example-config.yml"));
-        Ok(())
-    } else {
-        tracing::debug!("running application");
-        run(config).await
+        return Ok(());
+    }
+    match cli_args.command {
+        Some(Command::Migrate { action }) => migrate(config, action).await,
+        None => {
+            tracing::debug!("running application");
+            run(config, cli_args.migrate).await
+        }
     }
 }
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{exponential_buckets, Histogram},
+    },
+    registry::Registry,
+};
+
+/// Labels of request metrics. `route` is the matched route template, such as
+/// `/users/:user_id`, so that ids don't explode the number of series.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct RequestLabels {
+    pub method: String,
+    pub route: String,
+    pub status: u16,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct LogTypeLabels {
+    pub log_type: String,
+}
+
+/// The metrics served at `/metrics`. Request metrics are recorded as requests
+/// are handled, the rest are read when scraped.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    registry: Arc<Registry>,
+    pub requests: Family<RequestLabels, Counter>,
+    pub request_duration: Family<RequestLabels, Histogram, fn() -> Histogram>,
+    pub requests_in_flight: Gauge,
+    pub pool_connections: Gauge,
+    pub pool_idle_connections: Gauge,
+    pub vehicles: Gauge,
+    pub log_records: Family<LogTypeLabels, Gauge>,
+    pub audit_write_failures: Counter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let requests = Family::<RequestLabels, Counter>::default();
+        // 5ms up to about 10s
+        let request_duration =
+            Family::<RequestLabels, Histogram, fn() -> Histogram>::new_with_constructor(|| {
+                Histogram::new(exponential_buckets(0.005, 2.0, 12))
+            });
+        let requests_in_flight = Gauge::default();
+        let pool_connections = Gauge::default();
+        let pool_idle_connections = Gauge::default();
+        let vehicles = Gauge::default();
+        let log_records = Family::<LogTypeLabels, Gauge>::default();
+        let audit_write_failures = Counter::default();
+
+        let mut registry = Registry::with_prefix("fuel_logger");
+        registry.register(
+            "http_requests",
+            "Number of HTTP requests handled",
+            requests.clone(),
+        );
+        registry.register(
+            "http_request_duration_seconds",
+            "Time taken to handle HTTP requests",
+            request_duration.clone(),
+        );
+        registry.register(
+            "http_requests_in_flight",
+            "Number of HTTP requests being handled",
+            requests_in_flight.clone(),
+        );
+        registry.register(
+            "db_pool_connections",
+            "Number of open database connections",
+            pool_connections.clone(),
+        );
+        registry.register(
+            "db_pool_idle_connections",
+            "Number of idle database connections",
+            pool_idle_connections.clone(),
+        );
+        registry.register("vehicles", "Number of active vehicles", vehicles.clone());
+        registry.register(
+            "log_records",
+            "Number of active log records by log type",
+            log_records.clone(),
+        );
+        registry.register(
+            "audit_write_failures",
+            "Number of audit entries that could not be recorded",
+            audit_write_failures.clone(),
+        );
+
+        Self {
+            registry: Arc::new(registry),
+            requests,
+            request_duration,
+            requests_in_flight,
+            pool_connections,
+            pool_idle_connections,
+            vehicles,
+            log_records,
+            audit_write_failures,
+        }
+    }
+
+    /// Renders every metric in the OpenMetrics text format
+    pub fn encode(&self) -> Result<String, std::fmt::Error> {
+        let mut buffer = String::new();
+        prometheus_client::encoding::text::encode(&mut buffer, &self.registry)?;
+        Ok(buffer)
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{RawPathParams, Request, State},
+    http::{header::LOCATION, Method},
+    middleware::Next,
+    response::Response,
+};
+use prometheus_client::metrics::counter::Counter;
+use uuid::Uuid;
+
+use crate::{
+    controllers::audit as controller,
+    extractors::actor::Actor,
+    metrics::Metrics,
+    models::api::idempotency::REPLAYED_HEADER,
+    repositories::AuditRepository,
+    types::{AuditAction, AuditResource},
+};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[derive(Debug, Clone)]
+pub struct AuditState {
+    repository: Arc<dyn AuditRepository>,
+    resource_type: AuditResource,
+    /// Counts entries that couldn't be recorded
+    failures: Counter,
+}
+
+impl AuditState {
+    pub fn new(
+        repository: Arc<dyn AuditRepository>,
+        resource_type: AuditResource,
+        metrics: &Metrics,
+    ) -> Self {
+        Self {
+            repository,
+            resource_type,
+            failures: metrics.audit_write_failures.clone(),
+        }
+    }
+}
+
+/// Records an audit entry for every successful mutating request handled by
+/// the router this is layered onto. Must be added with `route_layer` so that
+/// path parameters are available. Batches audit each of their operations
+/// themselves.
+///
+/// Entries are recorded once the request has been handled, so one that can't
+/// be written is missing from the audit log. Such failures are logged and
+/// counted by the `fuel_logger_audit_write_failures_total` metric.
+pub async fn audit(
+    State(state): State<AuditState>,
+    Actor(actor): Actor,
+    path_params: RawPathParams,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(action) = action_for(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+    let path_id = path_params
+        .iter()
+        .find_map(|(_, value)| value.parse::<Uuid>().ok());
+
+    let response = next.run(request).await;
+    // Replayed requests were audited when they first ran
+    if !response.status().is_success() || response.headers().contains_key(REPLAYED_HEADER) {
+        return response;
+    }
+
+    // Created resources are only identified by the response
+    let resource_id = path_id.or_else(|| {
+        response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|location| location.rsplit('/').next())
+            .and_then(|id| id.parse::<Uuid>().ok())
+    });
+
+    if let Err(e) = controller::record(
+        &*state.repository,
+        actor,
+        state.resource_type,
+        resource_id,
+        action,
+        request_id.as_deref(),
+    )
+    .await
+    {
+        state.failures.inc();
+        tracing::error!(?e, "could not record audit entry");
+    }
+
+    response
+}
+
+fn action_for(method: &Method, path: &str) -> Option<AuditAction> {
+    match *method {
+        Method::POST if path.ends_with("/batch") => None,
+        Method::POST if path.ends_with("/restore") => Some(AuditAction::Restore),
+        Method::DELETE if path.ends_with("/purge") => Some(AuditAction::Purge),
+        Method::POST => Some(AuditAction::Create),
+        Method::PUT | Method::PATCH => Some(AuditAction::Update),
+        Method::DELETE => Some(AuditAction::Delete),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod action_tests {
+    use super::*;
+
+    #[test_case::test_case(Method::GET, "/users" => None)]
+    #[test_case::test_case(Method::POST, "/users" => Some(AuditAction::Create))]
+    #[test_case::test_case(Method::PUT, "/users/1" => Some(AuditAction::Update))]
+    #[test_case::test_case(Method::PATCH, "/users/1" => Some(AuditAction::Update))]
+    #[test_case::test_case(Method::DELETE, "/users/1" => Some(AuditAction::Delete))]
+    #[test_case::test_case(Method::POST, "/users/1/restore" => Some(AuditAction::Restore))]
+    #[test_case::test_case(Method::DELETE, "/users/1/purge" => Some(AuditAction::Purge))]
+    #[test_case::test_case(Method::POST, "/log_records/batch" => None)]
+    fn maps_requests_to_actions(method: Method, path: &str) -> Option<AuditAction> {
+        action_for(&method, path)
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use prometheus_client::metrics::gauge::Gauge;
+
+use crate::metrics::{Metrics, RequestLabels};
+
+/// Label for requests that didn't match any route
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Counts a request as in flight until dropped, which also covers requests
+/// cut off before responding
+struct InFlight(Gauge);
+
+impl InFlight {
+    fn start(gauge: &Gauge) -> Self {
+        gauge.inc();
+        Self(gauge.clone())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// Counts and times every request by method, matched route and status
+pub async fn track_requests(
+    State(metrics): State<Metrics>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = matched_path
+        .as_ref()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+        .to_owned();
+    let start = Instant::now();
+    let in_flight = InFlight::start(&metrics.requests_in_flight);
+
+    let response = next.run(request).await;
+    drop(in_flight);
+
+    let labels = RequestLabels {
+        method,
+        route,
+        status: response.status().as_u16(),
+    };
+    metrics.requests.get_or_create(&labels).inc();
+    metrics
+        .request_duration
+        .get_or_create(&labels)
+        .observe(start.elapsed().as_secs_f64());
+
+    response
+}
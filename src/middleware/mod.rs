@@ -0,0 +1,3 @@
+pub mod audit;
+pub mod metrics;
+pub mod problem;
@@ -0,0 +1,51 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::header::{CONTENT_LENGTH, CONTENT_TYPE},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::json;
+
+use crate::{error::PROBLEM_JSON, middleware::audit::REQUEST_ID_HEADER};
+
+/// Problem documents are small, anything larger is passed through untouched
+const MAX_PROBLEM_BYTES: usize = 64 * 1024;
+
+/// Adds the request path and id to problem documents so that clients can
+/// quote them when reporting an error. Must be layered inside the layer that
+/// sets the request id.
+pub async fn annotate_problem(request: Request, next: Next) -> Response {
+    let instance = request.uri().path().to_owned();
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+
+    let response = next.run(request).await;
+    let is_problem = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value == PROBLEM_JSON);
+    if !is_problem {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_PROBLEM_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!(?e, "could not read problem document");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(mut problem) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    problem["instance"] = json!(instance);
+    problem["request_id"] = json!(request_id);
+
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(problem.to_string()))
+}
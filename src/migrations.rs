@@ -0,0 +1,189 @@
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migration, Migrator},
+    query_scalar, PgConnection, PgPool,
+};
+
+/// The migrations in `migrations/`, embedded at build time
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// The migrations in `migrations_sqlite/`, applied whenever the SQLite backend
+/// starts
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+/// Whether an embedded migration has been applied to the database
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+impl std::fmt::Display for MigrationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({})",
+            self.version,
+            self.description,
+            if self.applied { "applied" } else { "pending" }
+        )
+    }
+}
+
+/// Embedded migrations that move the schema forward, oldest first
+fn up_migrations() -> impl Iterator<Item = &'static Migration> {
+    up_migrations_of(&MIGRATOR)
+}
+
+fn up_migrations_of(migrator: &'static Migrator) -> impl Iterator<Item = &'static Migration> {
+    migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+}
+
+/// Versions of the successfully applied migrations, oldest first. Fails if
+/// migrations have never been run.
+pub async fn applied_versions(conn: &mut PgConnection) -> Result<Vec<i64>, sqlx::Error> {
+    query_scalar("SELECT version FROM _sqlx_migrations WHERE success ORDER BY version")
+        .fetch_all(conn)
+        .await
+}
+
+/// Versions of the embedded migrations missing from `applied`
+pub fn pending(applied: &[i64]) -> Vec<i64> {
+    pending_of(&MIGRATOR, applied)
+}
+
+/// Versions of the migrations of `migrator` missing from `applied`
+pub fn pending_of(migrator: &'static Migrator, applied: &[i64]) -> Vec<i64> {
+    up_migrations_of(migrator)
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect()
+}
+
+/// Applies every pending migration
+#[tracing::instrument(name = "migrations_up", skip_all, err)]
+pub async fn up(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+/// Reverts every applied migration newer than `target`, or only the latest
+/// one without a target. Returns the reverted versions, newest first.
+#[tracing::instrument(name = "migrations_down", skip(pool), err)]
+pub async fn down(pool: &PgPool, target: Option<i64>) -> Result<Vec<i64>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = applied_versions(&mut conn).await?;
+    let target = target.unwrap_or_else(|| applied.iter().rev().nth(1).copied().unwrap_or(0));
+
+    MIGRATOR.undo(&mut *conn, target).await?;
+    Ok(applied
+        .into_iter()
+        .rev()
+        .filter(|version| *version > target)
+        .collect())
+}
+
+/// Lists every embedded migration and whether it has been applied
+#[tracing::instrument(name = "migrations_status", skip_all, err)]
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = applied_versions(&mut conn).await?;
+
+    Ok(up_migrations()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+
+    fn versions() -> Vec<i64> {
+        up_migrations().map(|migration| migration.version).collect()
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn status_of_empty_database(pool: PgPool) {
+        // Act
+        let res = status(&pool).await.expect("could not read status");
+
+        // Assert
+        assert_eq!(res.len(), versions().len());
+        assert!(res.iter().all(|migration| !migration.applied));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn up_applies_every_migration(pool: PgPool) {
+        // Act
+        up(&pool).await.expect("could not migrate");
+
+        // Assert
+        let res = status(&pool).await.expect("could not read status");
+        assert!(res.iter().all(|migration| migration.applied));
+        let mut conn = pool.acquire().await.expect("could not acquire connection");
+        let applied = applied_versions(&mut conn)
+            .await
+            .expect("could not read applied migrations");
+        assert!(pending(&applied).is_empty());
+    }
+
+    #[sqlx::test]
+    async fn down_reverts_latest_migration(pool: PgPool) {
+        // Arrange
+        let versions = versions();
+        let latest = *versions.last().expect("no migrations");
+
+        // Act
+        let reverted = down(&pool, None).await.expect("could not revert");
+
+        // Assert
+        assert_eq!(reverted, vec![latest]);
+        let mut conn = pool.acquire().await.expect("could not acquire connection");
+        let applied = applied_versions(&mut conn)
+            .await
+            .expect("could not read applied migrations");
+        assert_eq!(pending(&applied), vec![latest]);
+    }
+
+    #[sqlx::test]
+    async fn down_to_target_then_up(pool: PgPool) {
+        // Arrange
+        let versions = versions();
+        let target = versions[0];
+
+        // Act
+        let reverted = down(&pool, Some(target)).await.expect("could not revert");
+        up(&pool).await.expect("could not migrate");
+
+        // Assert
+        assert_eq!(
+            reverted,
+            versions[1..].iter().rev().copied().collect::<Vec<_>>()
+        );
+        let res = status(&pool).await.expect("could not read status");
+        assert!(res.iter().all(|migration| migration.applied));
+    }
+
+    #[test]
+    fn formats_status() {
+        // Arrange
+        let status = MigrationStatus {
+            version: 20240804040621,
+            description: "initial-structure".to_owned(),
+            applied: false,
+        };
+
+        // Act
+        let res = status.to_string();
+
+        // Assert
+        assert_eq!(res, "20240804040621 initial-structure (pending)");
+    }
+}
@@ -0,0 +1,152 @@
+use std::ops::Deref;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    models::db::AuditEntry as DbAuditEntry,
+    types::{AuditAction, AuditResource},
+};
+
+// List
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, fake::Dummy, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListAuditEntriesParams {
+    pub actor_id: Option<Uuid>,
+    pub resource_type: Option<AuditResource>,
+    pub resource_id: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    /// Only include entries that occurred at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only include entries that occurred before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Maximum number of entries, 100 by default and at most 1000
+    #[dummy(faker = "1..100")]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ReadAuditEntryResponse {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub resource_type: AuditResource,
+    pub resource_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<DbAuditEntry> for ReadAuditEntryResponse {
+    fn from(value: DbAuditEntry) -> Self {
+        Self {
+            id: value.id,
+            actor_id: value.actor_id,
+            resource_type: value.resource_type,
+            resource_id: value.resource_id,
+            action: value.action,
+            request_id: value.request_id,
+            occurred_at: value.occurred_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ListAuditEntriesResponse(Vec<ReadAuditEntryResponse>);
+
+impl Deref for ListAuditEntriesResponse {
+    type Target = Vec<ReadAuditEntryResponse>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<ReadAuditEntryResponse> for ListAuditEntriesResponse {
+    fn from_iter<T: IntoIterator<Item = ReadAuditEntryResponse>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoResponse for ListAuditEntriesResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use super::*;
+    use fake::{Fake, Faker};
+    use serde_json::json;
+
+    mod list {
+        use super::*;
+
+        mod request {
+            use super::*;
+
+            #[test]
+            fn deserializes_correctly() {
+                // Arrange
+                let expected = Faker.fake::<ListAuditEntriesParams>();
+                let json = json!({
+                    "actor_id": expected.actor_id,
+                    "resource_type": expected.resource_type,
+                    "resource_id": expected.resource_id,
+                    "action": expected.action,
+                    "since": expected.since,
+                    "until": expected.until,
+                    "limit": expected.limit,
+                });
+
+                // Act
+                let deserialized = serde_json::from_value::<ListAuditEntriesParams>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(deserialized, expected);
+            }
+
+            #[test]
+            fn deserializes_empty_filters() {
+                // Act
+                let deserialized = serde_json::from_value::<ListAuditEntriesParams>(json!({}))
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(deserialized, ListAuditEntriesParams::default());
+            }
+        }
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let entry = Faker.fake::<ReadAuditEntryResponse>();
+                let response = ListAuditEntriesResponse(vec![entry.clone()]);
+                let expected = json!([{
+                    "id": entry.id,
+                    "actor_id": entry.actor_id,
+                    "resource_type": entry.resource_type,
+                    "resource_id": entry.resource_id,
+                    "action": entry.action,
+                    "request_id": entry.request_id,
+                    "occurred_at": entry.occurred_at,
+                }]);
+
+                // Act
+                let serialized = serde_json::to_value(&response).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct HealthResponse {
+    pub status: &'static str,
+}
+
+impl HealthResponse {
+    pub fn ok() -> Self {
+        Self { status: "ok" }
+    }
+}
+
+impl IntoResponse for HealthResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct VersionResponse {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+}
+
+impl VersionResponse {
+    /// The version of the running build
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("GIT_SHA"),
+        }
+    }
+}
+
+impl IntoResponse for VersionResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
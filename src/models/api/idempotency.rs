@@ -0,0 +1,30 @@
+use axum::response::{IntoResponse, Response};
+
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// The outcome of a request that may be a replay of an earlier request made
+/// with the same idempotency key. Replays are marked with a header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Idempotent<T> {
+    pub response: T,
+    pub replayed: bool,
+}
+
+impl<T> Idempotent<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Idempotent<U> {
+        Idempotent {
+            response: f(self.response),
+            replayed: self.replayed,
+        }
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for Idempotent<T> {
+    fn into_response(self) -> Response {
+        if self.replayed {
+            ([(REPLAYED_HEADER, "true")], self.response).into_response()
+        } else {
+            self.response.into_response()
+        }
+    }
+}
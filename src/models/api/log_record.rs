@@ -1,8 +1,14 @@
 use std::ops::Deref;
 
-use crate::{error::ApiError, models::db::LogRecord as DbLogRecord, types::log_type::LogType};
+use crate::{
+    error::{ApiError, PROBLEM_TYPE_PREFIX},
+    models::{api::Problem, db::LogRecord as DbLogRecord},
+    types::{
+        entity_tag::entity_tag, log_type::LogType, AuditAction, MergePatch, Validate, Validator,
+    },
+};
 use axum::{
-    http::StatusCode,
+    http::{header::ETAG, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -10,7 +16,9 @@ use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 // Create
-#[derive(Debug, Clone, PartialEq, serde::Deserialize, fake::Dummy)]
+#[derive(
+    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, utoipa::ToSchema,
+)]
 pub struct CreateLogRecordBody {
     pub date: Option<DateTime<Utc>>,
     pub vehicle_id: Uuid,
@@ -21,7 +29,36 @@ pub struct CreateLogRecordBody {
     pub notes: Option<String>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+impl Validate for CreateLogRecordBody {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        // Odometer readings are stored as a Postgres INTEGER
+        validator.range("odometer", self.odometer, 0, i32::MAX as u32);
+        if let LogType::FuelUp { fuel_amount } = self.log_type {
+            validator.non_negative("fuel_amount", fuel_amount);
+        }
+        validator.finish()
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    utoipa::IntoParams,
+)]
+#[into_params(parameter_in = Query)]
+pub struct CreateLogRecordParams {
+    /// Create the log record even if it looks like a duplicate of an existing one
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct CreateLogRecordResponse {
     pub id: Uuid,
 }
@@ -38,7 +75,7 @@ impl IntoResponse for CreateLogRecordResponse {
 }
 
 // Read
-#[derive(Debug, Clone, PartialEq, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct ReadLogRecordResponse {
     pub id: Uuid,
     pub vehicle_id: Uuid,
@@ -48,16 +85,24 @@ pub struct ReadLogRecordResponse {
     #[dummy(faker = "0..500000")]
     pub odometer: u32,
     pub notes: Option<String>,
+    /// Sent as the `ETag` header rather than in the body
+    #[serde(skip)]
+    pub version: i64,
 }
 
 impl IntoResponse for ReadLogRecordResponse {
     fn into_response(self) -> Response {
-        (StatusCode::OK, Json(self)).into_response()
+        (
+            StatusCode::OK,
+            [(ETAG, entity_tag(self.version))],
+            Json(self),
+        )
+            .into_response()
     }
 }
 
 // List
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct ListLogRecordsResponse(Vec<ReadLogRecordResponse>);
 
 impl Deref for ListLogRecordsResponse {
@@ -95,6 +140,7 @@ impl TryFrom<DbLogRecord> for ReadLogRecordResponse {
             odometer: u32::try_from(value.odometer)
                 .map_err(|e| ApiError::Conversion(e.to_string()))?,
             notes: value.notes,
+            version: value.version,
         })
     }
 }
@@ -109,8 +155,12 @@ impl FromIterator<ReadLogRecordResponse> for ListLogRecordsResponse {
 pub type UpdateLogRecordBody = CreateLogRecordBody;
 pub type UpdateLogRecordResponse = ReadLogRecordResponse;
 
+// Patch
+pub type PatchLogRecordBody = MergePatch;
+pub type PatchLogRecordResponse = ReadLogRecordResponse;
+
 // Delete
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct DeleteLogRecordResponse;
 
 impl IntoResponse for DeleteLogRecordResponse {
@@ -119,6 +169,126 @@ impl IntoResponse for DeleteLogRecordResponse {
     }
 }
 
+// Restore
+pub type RestoreLogRecordResponse = ReadLogRecordResponse;
+
+// Purge
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct PurgeLogRecordResponse;
+
+impl IntoResponse for PurgeLogRecordResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::NO_CONTENT).into_response()
+    }
+}
+
+// Batch
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+pub struct BatchLogRecordsBody {
+    /// Roll back every operation if any one of them fails
+    #[serde(default)]
+    pub atomic: bool,
+    pub operations: Vec<LogRecordOperation>,
+}
+
+/// A single write within a batch. `version` is checked like an `If-Match`
+/// header when given.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum LogRecordOperation {
+    Create {
+        body: CreateLogRecordBody,
+        #[serde(default)]
+        allow_duplicate: bool,
+    },
+    Update {
+        id: Uuid,
+        body: UpdateLogRecordBody,
+        version: Option<i64>,
+    },
+    Patch {
+        id: Uuid,
+        body: PatchLogRecordBody,
+        version: Option<i64>,
+    },
+    Delete {
+        id: Uuid,
+        version: Option<i64>,
+    },
+}
+
+impl LogRecordOperation {
+    pub fn audit_action(&self) -> AuditAction {
+        match self {
+            Self::Create { .. } => AuditAction::Create,
+            Self::Update { .. } | Self::Patch { .. } => AuditAction::Update,
+            Self::Delete { .. } => AuditAction::Delete,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct LogRecordOperationResult {
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    /// The error body the operation would have failed with on its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Problem>)]
+    pub error: Option<serde_json::Value>,
+}
+
+impl LogRecordOperationResult {
+    pub fn succeeded(status: StatusCode, id: Uuid) -> Self {
+        Self {
+            status: status.as_u16(),
+            id: Some(id),
+            error: None,
+        }
+    }
+
+    pub fn failed(error: ApiError) -> Self {
+        let (status, body) = error.into_status_and_body();
+        Self {
+            status: status.as_u16(),
+            id: None,
+            error: Some(body),
+        }
+    }
+
+    /// Not attempted because an earlier operation of an atomic batch failed
+    pub fn skipped() -> Self {
+        let status = StatusCode::FAILED_DEPENDENCY;
+        Self {
+            status: status.as_u16(),
+            id: None,
+            error: Some(serde_json::json!({
+                "type": format!("{PROBLEM_TYPE_PREFIX}operation-skipped"),
+                "title": "Operation skipped",
+                "status": status.as_u16(),
+                "detail": "an earlier operation in the batch failed",
+            })),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct BatchLogRecordsResponse {
+    /// Whether the successful operations were kept
+    pub committed: bool,
+    pub results: Vec<LogRecordOperationResult>,
+}
+
+impl IntoResponse for BatchLogRecordsResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
 #[cfg(test)]
 mod serde_tests {
     use super::*;
@@ -341,4 +511,103 @@ mod serde_tests {
             }
         }
     }
+
+    mod batch {
+        use super::*;
+
+        mod request {
+            use super::*;
+
+            #[test]
+            fn deserializes_mixed_operations() {
+                // Arrange
+                let id = Faker.fake::<Uuid>();
+                let vehicle_id = Faker.fake::<Uuid>();
+                let json = json!({
+                    "atomic": true,
+                    "operations": [
+                        {
+                            "op": "create",
+                            "body": {
+                                "vehicle_id": vehicle_id,
+                                "odometer": 1000,
+                                "log_type": "oil_change",
+                            },
+                        },
+                        {"op": "patch", "id": id, "body": {"notes": null}, "version": 2},
+                        {"op": "delete", "id": id},
+                    ],
+                });
+
+                // Act
+                let deserialized = serde_json::from_value::<BatchLogRecordsBody>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert!(deserialized.atomic);
+                assert_eq!(deserialized.operations.len(), 3);
+                assert!(matches!(
+                    &deserialized.operations[0],
+                    LogRecordOperation::Create { body, allow_duplicate: false }
+                        if body.vehicle_id == vehicle_id && body.log_type == LogType::OilChange
+                ));
+                assert!(matches!(
+                    &deserialized.operations[1],
+                    LogRecordOperation::Patch { id: patched, version: Some(2), .. } if *patched == id
+                ));
+                assert_eq!(
+                    deserialized.operations[2],
+                    LogRecordOperation::Delete { id, version: None }
+                );
+            }
+        }
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let id = Faker.fake::<Uuid>();
+                let response = BatchLogRecordsResponse {
+                    committed: false,
+                    results: vec![
+                        LogRecordOperationResult::succeeded(StatusCode::CREATED, id),
+                        LogRecordOperationResult::failed(ApiError::ResourceNotFound),
+                        LogRecordOperationResult::skipped(),
+                    ],
+                };
+                let expected = json!({
+                    "committed": false,
+                    "results": [
+                        {"status": 201, "id": id},
+                        {
+                            "status": 404,
+                            "error": {
+                                "type": "urn:fuel-logger:problem:not-found",
+                                "title": "Resource not found",
+                                "status": 404,
+                                "detail": "resource not found",
+                            },
+                        },
+                        {
+                            "status": 424,
+                            "error": {
+                                "type": "urn:fuel-logger:problem:operation-skipped",
+                                "title": "Operation skipped",
+                                "status": 424,
+                                "detail": "an earlier operation in the batch failed",
+                            },
+                        },
+                    ],
+                });
+
+                // Act
+                let serialized = serde_json::to_value(&response).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
 }
@@ -0,0 +1,127 @@
+use std::ops::Deref;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{error::ApiError, models::db::LogRecordDuplicate as DbLogRecordDuplicate};
+
+// Read
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ReadLogRecordDuplicateResponse {
+    pub log_record_id: Uuid,
+    /// The earliest log record this one appears to duplicate
+    pub duplicate_of: Uuid,
+    pub vehicle_id: Uuid,
+    pub log_type: String,
+    pub odometer: u32,
+    pub date: DateTime<Utc>,
+}
+
+impl IntoResponse for ReadLogRecordDuplicateResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+impl TryFrom<DbLogRecordDuplicate> for ReadLogRecordDuplicateResponse {
+    type Error = ApiError;
+    fn try_from(value: DbLogRecordDuplicate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            log_record_id: value.log_record_id,
+            duplicate_of: value.duplicate_of,
+            vehicle_id: value.vehicle_id,
+            log_type: value.log_type.to_string(),
+            odometer: u32::try_from(value.odometer)
+                .map_err(|e| ApiError::Conversion(e.to_string()))?,
+            date: value.log_date,
+        })
+    }
+}
+
+// List
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, fake::Dummy, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListLogRecordDuplicatesParams {
+    pub vehicle_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ListLogRecordDuplicatesResponse(Vec<ReadLogRecordDuplicateResponse>);
+
+impl Deref for ListLogRecordDuplicatesResponse {
+    type Target = Vec<ReadLogRecordDuplicateResponse>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<ReadLogRecordDuplicateResponse> for ListLogRecordDuplicatesResponse {
+    fn from_iter<T: IntoIterator<Item = ReadLogRecordDuplicateResponse>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoResponse for ListLogRecordDuplicatesResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use super::*;
+    use fake::{Fake, Faker};
+    use serde_json::json;
+
+    mod list {
+        use super::*;
+
+        mod params {
+            use super::*;
+
+            #[test]
+            fn deserializes_empty() {
+                // Arrange
+                let json = json!({});
+
+                // Act
+                let deserialized = serde_json::from_value::<ListLogRecordDuplicatesParams>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(deserialized, ListLogRecordDuplicatesParams::default());
+            }
+        }
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let duplicate = Faker.fake::<ReadLogRecordDuplicateResponse>();
+                let response = ListLogRecordDuplicatesResponse(vec![duplicate.clone()]);
+                let expected = json!([{
+                    "log_record_id": duplicate.log_record_id,
+                    "duplicate_of": duplicate.duplicate_of,
+                    "vehicle_id": duplicate.vehicle_id,
+                    "log_type": duplicate.log_type,
+                    "odometer": duplicate.odometer,
+                    "date": duplicate.date,
+                }]);
+
+                // Act
+                let serialized = serde_json::to_value(&response).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+}
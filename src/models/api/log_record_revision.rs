@@ -0,0 +1,106 @@
+use std::ops::Deref;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{models::db::LogRecordRevision as DbLogRecordRevision, types::RevisionAction};
+
+// Read
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ReadLogRecordRevisionResponse {
+    pub id: Uuid,
+    pub log_record_id: Uuid,
+    pub action: RevisionAction,
+    pub changed_by: Option<Uuid>,
+    pub changed_at: DateTime<Utc>,
+    /// The log record as it was before the change
+    #[dummy(default)]
+    pub previous: serde_json::Value,
+}
+
+impl IntoResponse for ReadLogRecordRevisionResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+impl From<DbLogRecordRevision> for ReadLogRecordRevisionResponse {
+    fn from(value: DbLogRecordRevision) -> Self {
+        Self {
+            id: value.id,
+            log_record_id: value.log_record_id,
+            action: value.action,
+            changed_by: value.changed_by,
+            changed_at: value.changed_at,
+            previous: value.previous,
+        }
+    }
+}
+
+// List
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ListLogRecordRevisionsResponse(Vec<ReadLogRecordRevisionResponse>);
+
+impl Deref for ListLogRecordRevisionsResponse {
+    type Target = Vec<ReadLogRecordRevisionResponse>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<ReadLogRecordRevisionResponse> for ListLogRecordRevisionsResponse {
+    fn from_iter<T: IntoIterator<Item = ReadLogRecordRevisionResponse>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoResponse for ListLogRecordRevisionsResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use super::*;
+    use fake::{Fake, Faker};
+    use serde_json::json;
+
+    mod list {
+        use super::*;
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let revision = ReadLogRecordRevisionResponse {
+                    previous: json!({"odometer": 1000}),
+                    ..Faker.fake()
+                };
+                let response = ListLogRecordRevisionsResponse(vec![revision.clone()]);
+                let expected = json!([{
+                    "id": revision.id,
+                    "log_record_id": revision.log_record_id,
+                    "action": revision.action,
+                    "changed_by": revision.changed_by,
+                    "changed_at": revision.changed_at,
+                    "previous": {"odometer": 1000},
+                }]);
+
+                // Act
+                let serialized = serde_json::to_value(&response).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+}
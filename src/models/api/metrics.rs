@@ -0,0 +1,22 @@
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Metrics in the OpenMetrics text format, as scraped by Prometheus
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsResponse(pub String);
+
+impl IntoResponse for MetricsResponse {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::OK,
+            [(CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)],
+            self.0,
+        )
+            .into_response()
+    }
+}
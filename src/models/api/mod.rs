@@ -1,18 +1,57 @@
+pub mod audit;
+pub mod health;
+pub mod idempotency;
 pub mod log_record;
+pub mod log_record_duplicate;
+pub mod log_record_revision;
+pub mod metrics;
+pub mod organisation;
+pub mod problem;
+pub mod sync;
 pub mod user;
 pub mod vehicle;
 
+pub use audit::{ListAuditEntriesParams, ListAuditEntriesResponse, ReadAuditEntryResponse};
+
+pub use health::{HealthResponse, VersionResponse};
+
+pub use idempotency::Idempotent;
+
 pub use log_record::{
-    CreateLogRecordBody, CreateLogRecordResponse, DeleteLogRecordResponse, ListLogRecordsResponse,
-    ReadLogRecordResponse, UpdateLogRecordBody, UpdateLogRecordResponse,
+    BatchLogRecordsBody, BatchLogRecordsResponse, CreateLogRecordBody, CreateLogRecordParams,
+    CreateLogRecordResponse, DeleteLogRecordResponse, ListLogRecordsResponse, LogRecordOperation,
+    LogRecordOperationResult, PatchLogRecordBody, PatchLogRecordResponse, PurgeLogRecordResponse,
+    ReadLogRecordResponse, RestoreLogRecordResponse, UpdateLogRecordBody, UpdateLogRecordResponse,
 };
 
+pub use log_record_duplicate::{
+    ListLogRecordDuplicatesParams, ListLogRecordDuplicatesResponse, ReadLogRecordDuplicateResponse,
+};
+
+pub use log_record_revision::{ListLogRecordRevisionsResponse, ReadLogRecordRevisionResponse};
+
+pub use metrics::MetricsResponse;
+
+pub use organisation::{
+    AddOrganisationMemberBody, AddOrganisationMemberResponse, CreateOrganisationBody,
+    CreateOrganisationResponse, DeleteOrganisationResponse, ListOrganisationMembersResponse,
+    ListOrganisationsResponse, ReadOrganisationMemberResponse, ReadOrganisationResponse,
+    RemoveOrganisationMemberResponse, UpdateOrganisationBody, UpdateOrganisationMemberBody,
+    UpdateOrganisationMemberResponse, UpdateOrganisationResponse,
+};
+
+pub use problem::Problem;
+
+pub use sync::{SyncChanges, SyncParams, SyncResponse, SyncedRecord};
+
 pub use user::{
-    CreateUserBody, CreateUserResponse, DeleteUserResponse, ListUsersResponse, ReadUserResponse,
+    CreateUserBody, CreateUserResponse, DeleteUserParams, DeleteUserResponse, ListUsersResponse,
+    PatchUserBody, PatchUserResponse, PurgeUserResponse, ReadUserResponse, RestoreUserResponse,
     UpdateUserBody, UpdateUserResponse,
 };
 
 pub use vehicle::{
-    CreateVehicleBody, CreateVehicleResponse, DeleteVehicleResponse, ListVehiclesResponse,
-    ReadVehicleResponse, UpdateVehicleBody, UpdateVehicleResponse,
+    CreateVehicleBody, CreateVehicleResponse, DeleteVehicleParams, DeleteVehicleResponse,
+    ListVehiclesResponse, PatchVehicleBody, PatchVehicleResponse, PurgeVehicleResponse,
+    ReadVehicleResponse, RestoreVehicleResponse, UpdateVehicleBody, UpdateVehicleResponse,
 };
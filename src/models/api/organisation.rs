@@ -0,0 +1,358 @@
+use std::ops::Deref;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    models::db::{Organisation as DbOrganisation, OrganisationMember as DbOrganisationMember},
+    types::MemberRole,
+};
+
+// Create
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, fake::Dummy, utoipa::ToSchema)]
+pub struct CreateOrganisationBody {
+    #[dummy(faker = "fake::faker::company::en::CompanyName()")]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct CreateOrganisationResponse {
+    pub id: Uuid,
+}
+
+impl IntoResponse for CreateOrganisationResponse {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::CREATED,
+            [("location", format!("/organisations/{}", self.id))],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+// Read
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ReadOrganisationResponse {
+    pub id: Uuid,
+    #[dummy(faker = "fake::faker::company::en::CompanyName()")]
+    pub name: String,
+}
+
+impl IntoResponse for ReadOrganisationResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+impl From<DbOrganisation> for ReadOrganisationResponse {
+    fn from(value: DbOrganisation) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+        }
+    }
+}
+
+// List
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ListOrganisationsResponse(Vec<ReadOrganisationResponse>);
+
+impl Deref for ListOrganisationsResponse {
+    type Target = Vec<ReadOrganisationResponse>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<ReadOrganisationResponse> for ListOrganisationsResponse {
+    fn from_iter<T: IntoIterator<Item = ReadOrganisationResponse>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoResponse for ListOrganisationsResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+// Update
+pub type UpdateOrganisationBody = CreateOrganisationBody;
+pub type UpdateOrganisationResponse = ReadOrganisationResponse;
+
+// Delete
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct DeleteOrganisationResponse;
+
+impl IntoResponse for DeleteOrganisationResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::NO_CONTENT).into_response()
+    }
+}
+
+// Members
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, fake::Dummy, utoipa::ToSchema)]
+pub struct AddOrganisationMemberBody {
+    pub user_id: Uuid,
+    pub role: Option<MemberRole>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, fake::Dummy, utoipa::ToSchema)]
+pub struct UpdateOrganisationMemberBody {
+    pub role: MemberRole,
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ReadOrganisationMemberResponse {
+    pub organisation_id: Uuid,
+    pub user_id: Uuid,
+    pub role: MemberRole,
+}
+
+impl IntoResponse for ReadOrganisationMemberResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+impl From<DbOrganisationMember> for ReadOrganisationMemberResponse {
+    fn from(value: DbOrganisationMember) -> Self {
+        Self {
+            organisation_id: value.organisation_id,
+            user_id: value.user_id,
+            role: value.role,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct AddOrganisationMemberResponse(pub ReadOrganisationMemberResponse);
+
+impl IntoResponse for AddOrganisationMemberResponse {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::CREATED,
+            [(
+                "location",
+                format!(
+                    "/organisations/{}/members/{}",
+                    self.0.organisation_id, self.0.user_id
+                ),
+            )],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+pub type UpdateOrganisationMemberResponse = ReadOrganisationMemberResponse;
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct ListOrganisationMembersResponse(Vec<ReadOrganisationMemberResponse>);
+
+impl Deref for ListOrganisationMembersResponse {
+    type Target = Vec<ReadOrganisationMemberResponse>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<ReadOrganisationMemberResponse> for ListOrganisationMembersResponse {
+    fn from_iter<T: IntoIterator<Item = ReadOrganisationMemberResponse>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoResponse for ListOrganisationMembersResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct RemoveOrganisationMemberResponse;
+
+impl IntoResponse for RemoveOrganisationMemberResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::NO_CONTENT).into_response()
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use super::*;
+    use fake::{Fake, Faker};
+    use serde_json::json;
+
+    mod create {
+        use super::*;
+
+        mod request {
+            use super::*;
+
+            #[test]
+            fn deserializes_correctly() {
+                // Arrange
+                let expected = Faker.fake::<CreateOrganisationBody>();
+
+                let json = json!({
+                    "name": expected.name,
+                });
+
+                // Act
+                let deserialized = serde_json::from_value::<CreateOrganisationBody>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(deserialized, expected);
+            }
+        }
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let sample_record = Faker.fake::<CreateOrganisationResponse>();
+                let expected = json!({
+                    "id": sample_record.id
+                });
+
+                // Act
+                let serialized = serde_json::to_value(&sample_record).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+
+    mod read {
+        use super::*;
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let sample_record = Faker.fake::<ReadOrganisationResponse>();
+
+                let expected = json!({
+                    "id": sample_record.id,
+                    "name": sample_record.name,
+                });
+
+                // Act
+                let serialized = serde_json::to_value(&sample_record).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+
+    mod members {
+        use super::*;
+
+        mod request {
+            use super::*;
+
+            #[test]
+            fn deserializes_with_role() {
+                // Arrange
+                let expected = AddOrganisationMemberBody {
+                    role: Some(Faker.fake()),
+                    ..Faker.fake()
+                };
+
+                let json = json!({
+                    "user_id": expected.user_id,
+                    "role": expected.role,
+                });
+
+                // Act
+                let deserialized = serde_json::from_value::<AddOrganisationMemberBody>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(deserialized, expected);
+            }
+
+            #[test]
+            fn deserializes_without_role() {
+                // Arrange
+                let user_id = Faker.fake::<Uuid>();
+
+                let json = json!({
+                    "user_id": user_id,
+                });
+
+                // Act
+                let deserialized = serde_json::from_value::<AddOrganisationMemberBody>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(
+                    deserialized,
+                    AddOrganisationMemberBody {
+                        user_id,
+                        role: None
+                    }
+                );
+            }
+        }
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let sample_record = Faker.fake::<ReadOrganisationMemberResponse>();
+
+                let expected = json!({
+                    "organisation_id": sample_record.organisation_id,
+                    "user_id": sample_record.user_id,
+                    "role": sample_record.role,
+                });
+
+                // Act
+                let serialized = serde_json::to_value(&sample_record).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+
+    mod delete {
+        use super::*;
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let sample_record = Faker.fake::<DeleteOrganisationResponse>();
+
+                let expected = serde_json::Value::Null;
+
+                // Act
+                let serialized = serde_json::to_value(&sample_record).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use uuid::Uuid;
+
+use crate::types::FieldError;
+
+/// An `application/problem+json` error document (RFC 7807). Only used to
+/// document the bodies `ApiError` is reported with.
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+#[response(
+    description = "The request failed, see the problem `type`",
+    content_type = "application/problem+json"
+)]
+pub struct Problem {
+    /// Stable identifier of the kind of error, `urn:fuel-logger:problem:<kind>`
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    /// Human readable explanation of this occurrence
+    pub detail: String,
+    /// Path of the request that failed
+    pub instance: Option<String>,
+    pub request_id: Option<String>,
+    /// Violated database constraint, for unique and foreign key violations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<String>,
+    /// Fields covered by the violated constraint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<String>>,
+    /// Invalid fields, for validation failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+    /// Resources preventing a deletion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependents: Option<Vec<Uuid>>,
+    /// The record a suspected duplicate log record duplicates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing_id: Option<Uuid>,
+}
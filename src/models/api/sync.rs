@@ -0,0 +1,114 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{ReadLogRecordResponse, ReadUserResponse, ReadVehicleResponse};
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SyncParams {
+    /// The token of the previous sync. Omitted for a full sync.
+    pub since: Option<String>,
+}
+
+/// A changed resource, along with the version to send in `If-Match` when
+/// writing it back
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct SyncedRecord<T> {
+    #[serde(flatten)]
+    pub record: T,
+    pub version: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct SyncChanges<T> {
+    /// Resources created, updated or restored since the token
+    pub changed: Vec<SyncedRecord<T>>,
+    /// Resources deleted or purged since the token
+    pub deleted: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct SyncResponse {
+    /// Pass as `since` on the next sync to receive only later changes
+    pub token: String,
+    pub users: SyncChanges<ReadUserResponse>,
+    pub vehicles: SyncChanges<ReadVehicleResponse>,
+    pub log_records: SyncChanges<ReadLogRecordResponse>,
+}
+
+impl IntoResponse for SyncResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use super::*;
+    use fake::{Fake, Faker};
+    use serde_json::json;
+
+    mod sync {
+        use super::*;
+
+        mod response {
+            use super::*;
+
+            #[test]
+            fn serializes_correctly() {
+                // Arrange
+                let user = Faker.fake::<ReadUserResponse>();
+                let updated_at = Faker.fake::<DateTime<Utc>>();
+                let deleted_id = Faker.fake::<Uuid>();
+                let response = SyncResponse {
+                    token: "740:740:".to_owned(),
+                    users: SyncChanges {
+                        changed: vec![SyncedRecord {
+                            record: user.clone(),
+                            version: 3,
+                            updated_at,
+                        }],
+                        deleted: vec![],
+                    },
+                    vehicles: SyncChanges {
+                        changed: vec![],
+                        deleted: vec![deleted_id],
+                    },
+                    log_records: SyncChanges {
+                        changed: vec![],
+                        deleted: vec![],
+                    },
+                };
+                let expected = json!({
+                    "token": "740:740:",
+                    "users": {
+                        "changed": [{
+                            "id": user.id,
+                            "first_name": user.first_name,
+                            "last_name": user.last_name,
+                            "username": user.username,
+                            "email": user.email,
+                            "version": 3,
+                            "updated_at": updated_at,
+                        }],
+                        "deleted": [],
+                    },
+                    "vehicles": {"changed": [], "deleted": [deleted_id]},
+                    "log_records": {"changed": [], "deleted": []},
+                });
+
+                // Act
+                let serialized = serde_json::to_value(&response).expect("could not serialize");
+
+                // Assert
+                assert_eq!(serialized, expected);
+            }
+        }
+    }
+}
@@ -1,15 +1,21 @@
 use std::ops::Deref;
 
 use axum::{
-    http::StatusCode,
+    http::{header::ETAG, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use uuid::Uuid;
 
-use crate::models::db::User as DbUser;
+use crate::{
+    error::ApiError,
+    models::db::User as DbUser,
+    types::{entity_tag::entity_tag, DeletionMode, MergePatch, Validate, Validator},
+};
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize, fake::Dummy)]
+#[derive(
+    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, utoipa::ToSchema,
+)]
 pub struct CreateUserBody {
     #[dummy(faker = "fake::faker::name::en::FirstName()")]
     pub first_name: String,
@@ -21,7 +27,18 @@ pub struct CreateUserBody {
     pub email: String,
 }
 
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+impl Validate for CreateUserBody {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .required("first_name", &self.first_name)
+            .required("last_name", &self.last_name)
+            .required("username", &self.username)
+            .email("email", &self.email)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct CreateUserResponse {
     pub id: Uuid,
 }
@@ -37,7 +54,7 @@ impl IntoResponse for CreateUserResponse {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct ReadUserResponse {
     pub id: Uuid,
     #[dummy(faker = "fake::faker::name::en::FirstName()")]
@@ -48,11 +65,19 @@ pub struct ReadUserResponse {
     pub username: String,
     #[dummy(faker = "fake::faker::internet::en::FreeEmail()")]
     pub email: String,
+    /// Sent as the `ETag` header rather than in the body
+    #[serde(skip)]
+    pub version: i64,
 }
 
 impl IntoResponse for ReadUserResponse {
     fn into_response(self) -> Response {
-        (StatusCode::OK, Json(self)).into_response()
+        (
+            StatusCode::OK,
+            [(ETAG, entity_tag(self.version))],
+            Json(self),
+        )
+            .into_response()
     }
 }
 
@@ -64,11 +89,12 @@ impl From<DbUser> for ReadUserResponse {
             last_name: value.last_name,
             username: value.username,
             email: value.email,
+            version: value.version,
         }
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct ListUsersResponse(Vec<ReadUserResponse>);
 
 impl Deref for ListUsersResponse {
@@ -93,7 +119,19 @@ impl FromIterator<ReadUserResponse> for ListUsersResponse {
 pub type UpdateUserBody = CreateUserBody;
 pub type UpdateUserResponse = ReadUserResponse;
 
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+// Patch
+pub type PatchUserBody = MergePatch;
+pub type PatchUserResponse = ReadUserResponse;
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, fake::Dummy, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteUserParams {
+    #[serde(default)]
+    pub mode: DeletionMode,
+    pub reassign_to: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct DeleteUserResponse;
 
 impl IntoResponse for DeleteUserResponse {
@@ -102,6 +140,19 @@ impl IntoResponse for DeleteUserResponse {
     }
 }
 
+// Restore
+pub type RestoreUserResponse = ReadUserResponse;
+
+// Purge
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct PurgeUserResponse;
+
+impl IntoResponse for PurgeUserResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::NO_CONTENT).into_response()
+    }
+}
+
 #[cfg(test)]
 mod serde_tests {
     use super::*;
@@ -266,6 +317,43 @@ mod serde_tests {
     mod delete {
         use super::*;
 
+        mod request {
+            use super::*;
+
+            #[test]
+            fn deserializes_with_defaults() {
+                // Arrange
+                let json = json!({});
+
+                // Act
+                let deserialized = serde_json::from_value::<DeleteUserParams>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(deserialized, DeleteUserParams::default());
+            }
+
+            #[test]
+            fn deserializes_reassignment() {
+                // Arrange
+                let reassign_to = Faker.fake::<Uuid>();
+                let json = json!({"mode": "reassign", "reassign_to": reassign_to});
+
+                // Act
+                let deserialized = serde_json::from_value::<DeleteUserParams>(json)
+                    .expect("could not deserialize");
+
+                // Assert
+                assert_eq!(
+                    deserialized,
+                    DeleteUserParams {
+                        mode: DeletionMode::Reassign,
+                        reassign_to: Some(reassign_to)
+                    }
+                );
+            }
+        }
+
         mod response {
             use super::*;
 
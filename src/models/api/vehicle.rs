@@ -1,27 +1,51 @@
 use std::ops::Deref;
 
-use crate::{error::ApiError, models::db::Vehicle as DbVehicle, types::OdometerUnit};
+use crate::{
+    error::ApiError,
+    models::db::Vehicle as DbVehicle,
+    types::{entity_tag::entity_tag, MergePatch, OdometerUnit, Validate, Validator},
+};
 use axum::{
-    http::StatusCode,
+    http::{header::ETAG, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use chrono::{Datelike, Utc};
 use uuid::Uuid;
 
 // Create
-#[derive(Debug, Clone, serde::Deserialize, fake::Dummy, PartialEq)]
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, fake::Dummy, PartialEq, utoipa::ToSchema,
+)]
 pub struct CreateVehicleBody {
     pub owner_id: Uuid,
     #[dummy(faker = "fake::faker::company::en::CompanyName()")]
     pub make: String,
     #[dummy(faker = "fake::faker::company::en::Buzzword()")]
     pub model: String,
-    #[dummy(faker = "1950..2030")]
+    #[dummy(faker = "1950..2025")]
     pub year: u16,
     pub odometer_unit: Option<OdometerUnit>,
+    #[dummy(default)]
+    pub organisation_id: Option<Uuid>,
+}
+
+/// The year the first car was built
+const EARLIEST_VEHICLE_YEAR: u16 = 1886;
+
+impl Validate for CreateVehicleBody {
+    fn validate(&self) -> Result<(), ApiError> {
+        // Next year's models go on sale before the year starts
+        let latest_year = u16::try_from(Utc::now().year() + 1).unwrap_or(u16::MAX);
+        Validator::new()
+            .required("make", &self.make)
+            .required("model", &self.model)
+            .range("year", self.year, EARLIEST_VEHICLE_YEAR, latest_year)
+            .finish()
+    }
 }
 
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct CreateVehicleResponse {
     pub id: Uuid,
 }
@@ -38,7 +62,7 @@ impl IntoResponse for CreateVehicleResponse {
 }
 
 // Read
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct ReadVehicleResponse {
     pub id: Uuid,
     pub owner_id: Uuid,
@@ -46,10 +70,14 @@ pub struct ReadVehicleResponse {
     pub make: String,
     #[dummy(faker = "fake::faker::company::en::Buzzword()")]
     pub model: String,
-    #[dummy(faker = "1950..2030")]
+    #[dummy(faker = "1950..2025")]
     pub year: u16,
     // TODO: Add owner_id
     pub odometer_unit: OdometerUnit,
+    pub organisation_id: Option<Uuid>,
+    /// Sent as the `ETag` header rather than in the body
+    #[serde(skip)]
+    pub version: i64,
 }
 
 impl TryFrom<DbVehicle> for ReadVehicleResponse {
@@ -62,18 +90,25 @@ impl TryFrom<DbVehicle> for ReadVehicleResponse {
             model: value.model,
             year: u16::try_from(value.year).map_err(|e| ApiError::Conversion(e.to_string()))?,
             odometer_unit: value.odometer_unit,
+            organisation_id: value.organisation_id,
+            version: value.version,
         })
     }
 }
 
 impl IntoResponse for ReadVehicleResponse {
     fn into_response(self) -> Response {
-        (StatusCode::OK, Json(self)).into_response()
+        (
+            StatusCode::OK,
+            [(ETAG, entity_tag(self.version))],
+            Json(self),
+        )
+            .into_response()
     }
 }
 
 // List
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct ListVehiclesResponse(Vec<ReadVehicleResponse>);
 
 impl Deref for ListVehiclesResponse {
@@ -100,8 +135,19 @@ impl IntoResponse for ListVehiclesResponse {
 pub type UpdateVehicleBody = CreateVehicleBody;
 pub type UpdateVehicleResponse = ReadVehicleResponse;
 
+// Patch
+pub type PatchVehicleBody = MergePatch;
+pub type PatchVehicleResponse = ReadVehicleResponse;
+
 // Delete
-#[derive(Debug, Clone, serde::Serialize, fake::Dummy)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, fake::Dummy, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteVehicleParams {
+    #[serde(default)]
+    pub cascade: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
 pub struct DeleteVehicleResponse;
 
 impl IntoResponse for DeleteVehicleResponse {
@@ -110,6 +156,19 @@ impl IntoResponse for DeleteVehicleResponse {
     }
 }
 
+// Restore
+pub type RestoreVehicleResponse = ReadVehicleResponse;
+
+// Purge
+#[derive(Debug, Clone, serde::Serialize, fake::Dummy, utoipa::ToSchema)]
+pub struct PurgeVehicleResponse;
+
+impl IntoResponse for PurgeVehicleResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::NO_CONTENT).into_response()
+    }
+}
+
 #[cfg(test)]
 mod serde_tests {
     use super::*;
@@ -204,6 +263,7 @@ mod serde_tests {
                     "year": sample_record.year,
                     "id": sample_record.id,
                     "odometer_unit": sample_record.odometer_unit,
+                    "organisation_id": sample_record.organisation_id,
                 });
 
                 // Act
@@ -272,6 +332,7 @@ mod serde_tests {
                     model,
                     year,
                     odometer_unit,
+                    organisation_id: None,
                 };
 
                 // Act
@@ -303,6 +364,7 @@ mod serde_tests {
                     model,
                     year,
                     odometer_unit: None,
+                    organisation_id: None,
                 };
 
                 // Act
@@ -329,6 +391,7 @@ mod serde_tests {
                     "year": sample_record.year,
                     "id": sample_record.id,
                     "odometer_unit": sample_record.odometer_unit,
+                    "organisation_id": sample_record.organisation_id,
                 });
 
                 // Act
@@ -343,6 +406,16 @@ mod serde_tests {
     mod delete {
         use super::*;
 
+        mod request {
+            use super::*;
+
+            #[test_case::test_case(json!({}) => DeleteVehicleParams { cascade: false } ; "default")]
+            #[test_case::test_case(json!({"cascade": true}) => DeleteVehicleParams { cascade: true } ; "cascade")]
+            fn deserializes_correctly(value: serde_json::Value) -> DeleteVehicleParams {
+                serde_json::from_value(value).expect("could not deserialize")
+            }
+        }
+
         mod response {
             use super::*;
 
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::types::{AuditAction, AuditResource};
+
+#[derive(Debug, Clone, PartialEq, fake::Dummy, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub resource_type: AuditResource,
+    pub resource_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
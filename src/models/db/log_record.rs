@@ -1,23 +1,27 @@
 use crate::{
     error::ApiError,
     models::api::CreateLogRecordBody as ApiCreateLogRecordBody,
-    types::{BrakeComponent, BrakeLocation, FluidType, LogType, TireRotationType, TireType},
+    types::{
+        BrakeComponent, BrakeLocation, FluidType, LogType, LogTypeName, TireRotationType, TireType,
+    },
 };
 use chrono::{DateTime, Utc};
-use sqlx::{postgres::PgRow, FromRow, Row};
+use sqlx::{ColumnIndex, Decode, FromRow, Row, Type};
 use uuid::Uuid;
 
-// TODO: Convert this to better reflect table structure with Options instead of LogType struct
-#[derive(Debug, Clone, fake::Dummy, sqlx::FromRow)]
+/// A log record and its details, which are stored in the table of its log
+/// type. Read and written through [`crate::repositories::LogRecordRepository`].
+#[derive(Debug, Clone, fake::Dummy)]
 pub struct LogRecord {
     pub id: Uuid,
     pub vehicle_id: Uuid,
     pub date: DateTime<Utc>,
-    #[sqlx(flatten)]
     pub log_type: LogType,
     #[dummy(faker = "100..1000000")]
     pub odometer: i32,
     pub notes: Option<String>,
+    #[dummy(expr = "1")]
+    pub version: i64,
 }
 
 impl PartialEq for LogRecord {
@@ -28,6 +32,7 @@ impl PartialEq for LogRecord {
             && self.log_type == other.log_type
             && self.odometer == other.odometer
             && self.notes == other.notes
+            && self.version == other.version
     }
 }
 
@@ -45,22 +50,13 @@ impl LogRecord {
                 ApiError::Conversion("could not convert odometer reading into i32".to_owned())
             })?,
             notes: body.notes,
+            // Assigned by the database
+            version: 1,
         })
     }
 
-    pub fn log_type(&self) -> String {
-        match self.log_type {
-            LogType::FuelUp { .. } => "fuel_up",
-            LogType::TireRotation(_) => "tire_rotation",
-            LogType::TireChange { .. } => "tire_change",
-            LogType::Fluids(_) => "fluids",
-            LogType::WiperBladeReplacement => "wiper_blade_replacement",
-            LogType::Repair => "repair",
-            LogType::BrakeReplacement { .. } => "brake_replacement",
-            LogType::BatteryReplacement => "battery_replacement",
-            LogType::OilChange => "oil_change",
-        }
-        .to_owned()
+    pub fn log_type(&self) -> LogTypeName {
+        LogTypeName::from(&self.log_type)
     }
 
     pub fn fuel_amount(&self) -> Option<f32> {
@@ -120,42 +116,55 @@ impl LogRecord {
     }
 }
 
-impl<'r> FromRow<'r, PgRow> for LogRecord {
-    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+/// Decodes a log record joined with its details, as read from Postgres or from
+/// the single log record table of SQLite
+impl<'r, R: Row> FromRow<'r, R> for LogRecord
+where
+    &'r str: ColumnIndex<R>,
+    Uuid: Decode<'r, R::Database> + Type<R::Database>,
+    DateTime<Utc>: Decode<'r, R::Database> + Type<R::Database>,
+    i32: Decode<'r, R::Database> + Type<R::Database>,
+    i64: Decode<'r, R::Database> + Type<R::Database>,
+    f32: Decode<'r, R::Database> + Type<R::Database>,
+    bool: Decode<'r, R::Database> + Type<R::Database>,
+    String: Decode<'r, R::Database> + Type<R::Database>,
+    LogTypeName: Decode<'r, R::Database> + Type<R::Database>,
+    TireRotationType: Decode<'r, R::Database> + Type<R::Database>,
+    TireType: Decode<'r, R::Database> + Type<R::Database>,
+    BrakeLocation: Decode<'r, R::Database> + Type<R::Database>,
+    BrakeComponent: Decode<'r, R::Database> + Type<R::Database>,
+    FluidType: Decode<'r, R::Database> + Type<R::Database>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
         let id: Uuid = row.try_get("id")?;
         let date: DateTime<Utc> = row.try_get("log_date")?;
         let vehicle_id: Uuid = row.try_get("vehicle_id")?;
         let odometer = row.try_get::<i32, _>("odometer")?;
-        let notes = row.try_get::<String, _>("notes").ok();
-        let log_type_name: &str = row.try_get("log_type")?;
-        let log_type_enum = match log_type_name {
-            "fuel_up" => {
+        let notes = row.try_get::<Option<String>, _>("notes")?;
+        let version = row.try_get::<i64, _>("version")?;
+        let log_type_enum = match row.try_get("log_type")? {
+            LogTypeName::FuelUp => {
                 let fuel_amount = row.try_get("fuel_amount")?;
                 LogType::FuelUp { fuel_amount }
             }
-            "tire_rotation" => {
+            LogTypeName::TireRotation => {
                 let rotation_type = row.try_get("tire_rotation_type")?;
                 LogType::TireRotation(rotation_type)
             }
-            "tire_change" => LogType::TireChange {
+            LogTypeName::TireChange => LogType::TireChange {
                 rotation: row.try_get("tire_rotation_type")?,
                 tire_type: row.try_get("tire_type")?,
                 new: row.try_get("new_tires")?,
             },
-            "oil_change" => LogType::OilChange,
-            "repair" => LogType::Repair,
-            "wiper_blade_replacement" => LogType::WiperBladeReplacement,
-            "battery_replacement" => LogType::BatteryReplacement,
-            "brake_replacement" => LogType::BrakeReplacement {
+            LogTypeName::OilChange => LogType::OilChange,
+            LogTypeName::Repair => LogType::Repair,
+            LogTypeName::WiperBladeReplacement => LogType::WiperBladeReplacement,
+            LogTypeName::BatteryReplacement => LogType::BatteryReplacement,
+            LogTypeName::BrakeReplacement => LogType::BrakeReplacement {
                 location: row.try_get("brake_location")?,
                 component: row.try_get("brake_part")?,
             },
-            "fluids" => LogType::Fluids(row.try_get("fluid_type")?),
-            _ => {
-                return Err(sqlx::Error::Decode(
-                    format!("unrecognized log_type: {log_type_name}").into(),
-                ))
-            }
+            LogTypeName::Fluids => LogType::Fluids(row.try_get("fluid_type")?),
         };
 
         Ok(Self {
@@ -165,6 +174,7 @@ impl<'r> FromRow<'r, PgRow> for LogRecord {
             odometer,
             notes,
             log_type: log_type_enum,
+            version,
         })
     }
 }
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::types::LogTypeName;
+
+/// A log record suspected to duplicate an earlier one
+#[derive(Debug, Clone, PartialEq, fake::Dummy, sqlx::FromRow)]
+pub struct LogRecordDuplicate {
+    pub log_record_id: Uuid,
+    pub duplicate_of: Uuid,
+    pub vehicle_id: Uuid,
+    pub log_type: LogTypeName,
+    pub odometer: i32,
+    pub log_date: DateTime<Utc>,
+}
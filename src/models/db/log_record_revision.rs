@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::types::RevisionAction;
+
+#[derive(Debug, Clone, PartialEq, fake::Dummy, sqlx::FromRow)]
+pub struct LogRecordRevision {
+    pub id: Uuid,
+    pub log_record_id: Uuid,
+    pub action: RevisionAction,
+    pub changed_by: Option<Uuid>,
+    pub changed_at: DateTime<Utc>,
+    #[dummy(default)]
+    pub previous: serde_json::Value,
+}
@@ -1,7 +1,15 @@
+pub mod audit;
 pub mod log_record;
+pub mod log_record_duplicate;
+pub mod log_record_revision;
+pub mod organisation;
 pub mod user;
 pub mod vehicle;
 
+pub use audit::AuditEntry;
 pub use log_record::LogRecord;
+pub use log_record_duplicate::LogRecordDuplicate;
+pub use log_record_revision::LogRecordRevision;
+pub use organisation::{Organisation, OrganisationMember};
 pub use user::User;
 pub use vehicle::Vehicle;
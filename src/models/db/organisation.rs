@@ -0,0 +1,26 @@
+use uuid::Uuid;
+
+use crate::{models::api::CreateOrganisationBody as ApiCreateOrganisationBody, types::MemberRole};
+
+#[derive(Debug, Clone, PartialEq, fake::Dummy, sqlx::FromRow)]
+pub struct Organisation {
+    pub id: Uuid,
+    #[dummy(faker = "fake::faker::company::en::CompanyName()")]
+    pub name: String,
+}
+
+impl Organisation {
+    pub fn from_api_type(organisation_id: &Uuid, body: ApiCreateOrganisationBody) -> Self {
+        Self {
+            id: *organisation_id,
+            name: body.name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, fake::Dummy, sqlx::FromRow)]
+pub struct OrganisationMember {
+    pub organisation_id: Uuid,
+    pub user_id: Uuid,
+    pub role: MemberRole,
+}
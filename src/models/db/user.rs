@@ -13,6 +13,8 @@ pub struct User {
     pub username: String,
     #[dummy(faker = "fake::faker::internet::en::FreeEmail()")]
     pub email: String,
+    #[dummy(expr = "1")]
+    pub version: i64,
 }
 
 impl User {
@@ -23,6 +25,8 @@ impl User {
             last_name: body.last_name,
             username: body.username,
             email: body.email,
+            // Assigned by the database
+            version: 1,
         }
     }
 }
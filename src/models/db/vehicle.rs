@@ -10,9 +10,13 @@ pub struct Vehicle {
     pub make: String,
     #[dummy(faker = "fake::faker::company::en::Buzzword()")]
     pub model: String,
-    #[dummy(faker = "1950..2030")]
+    #[dummy(faker = "1950..2025")]
     pub year: i32,
     pub odometer_unit: OdometerUnit,
+    #[dummy(default)]
+    pub organisation_id: Option<Uuid>,
+    #[dummy(expr = "1")]
+    pub version: i64,
 }
 
 impl Vehicle {
@@ -24,6 +28,9 @@ impl Vehicle {
             model: body.model,
             year: body.year.into(),
             odometer_unit: body.odometer_unit.unwrap_or_default(),
+            organisation_id: body.organisation_id,
+            // Assigned by the database
+            version: 1,
         }
     }
 }
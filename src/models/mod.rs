@@ -2,4 +2,9 @@ pub mod api;
 pub mod db;
 
 pub use api::*;
-pub use db::{LogRecord as DbLogRecord, User as DbUser, Vehicle as DbVehicle};
+pub use db::{
+    AuditEntry as DbAuditEntry, LogRecord as DbLogRecord,
+    LogRecordDuplicate as DbLogRecordDuplicate, LogRecordRevision as DbLogRecordRevision,
+    Organisation as DbOrganisation, OrganisationMember as DbOrganisationMember, User as DbUser,
+    Vehicle as DbVehicle,
+};
@@ -0,0 +1,1822 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use axum::async_trait;
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use tokio::sync::{Mutex, MutexGuard, OwnedMutexGuard};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{
+        api::{DeleteUserParams, ListAuditEntriesParams, ReadLogRecordResponse},
+        db::{
+            AuditEntry as DbAuditEntry, LogRecord as DbLogRecord,
+            LogRecordDuplicate as DbLogRecordDuplicate, LogRecordRevision as DbLogRecordRevision,
+            Organisation as DbOrganisation, OrganisationMember as DbOrganisationMember,
+            User as DbUser, Vehicle as DbVehicle,
+        },
+    },
+    repositories::{
+        AuditRepository, ChangeSet, Changes, Claim, HealthRepository, IdempotencyRepository,
+        LogRecordBatch, LogRecordRepository, OrganisationRepository, SyncRepository,
+        UserRepository, VehicleRepository, CLAIM_LEASE_SECONDS, DUPLICATE_WINDOW_HOURS,
+        IDEMPOTENCY_KEY_TTL_HOURS,
+    },
+    types::{
+        permissions::{
+            ensure_can_add, ensure_can_change, ensure_can_remove, ensure_role, MANAGERS, MEMBERS,
+        },
+        AuditAction, AuditResource, DeletionMode, EntityTags, LogTypeName, MemberRole,
+        RevisionAction, SequenceToken,
+    },
+};
+
+/// Keeps every resource in memory until the process exits, for tests and
+/// demos without a database. Enforces the constraints of the database schema.
+#[derive(Debug, Clone, Default)]
+pub struct InMemory {
+    store: Arc<Mutex<Store>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Store {
+    users: Vec<Row<DbUser>>,
+    vehicles: Vec<Row<DbVehicle>>,
+    log_records: Vec<Row<DbLogRecord>>,
+    /// Ids of the users granted admin rights
+    admins: Vec<Uuid>,
+    organisations: Vec<DbOrganisation>,
+    members: Vec<DbOrganisationMember>,
+    /// Revisions of log records, oldest first
+    revisions: Vec<DbLogRecordRevision>,
+    /// Audit entries, oldest first
+    audit_log: Vec<DbAuditEntry>,
+    idempotency_keys: Vec<IdempotencyKey>,
+    /// Tombstones of purged rows, for the change feed
+    purged: Vec<Purged>,
+}
+
+/// A stored resource, which stays stored while soft deleted
+#[derive(Debug, Clone)]
+struct Row<T> {
+    value: T,
+    deleted_at: Option<DateTime<Utc>>,
+    updated_at: DateTime<Utc>,
+    /// Number of the last write, see [`next_change`]
+    change: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Purged {
+    resource_type: AuditResource,
+    id: Uuid,
+    change: u64,
+}
+
+#[derive(Debug, Clone)]
+struct IdempotencyKey {
+    scope: &'static str,
+    key: String,
+    request: serde_json::Value,
+    resource_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    claimed_at: DateTime<Utc>,
+}
+
+/// Writes are numbered for the change feed, whose tokens are the number of
+/// the last write. Numbers are shared by every store of the process, so a
+/// store's writes are numbered in order, though not consecutively.
+static CHANGES: AtomicU64 = AtomicU64::new(0);
+
+fn next_change() -> u64 {
+    CHANGES.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+trait Versioned {
+    fn id(&self) -> Uuid;
+    fn version(&self) -> i64;
+    fn version_mut(&mut self) -> &mut i64;
+}
+
+impl Versioned for DbUser {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i64 {
+        self.version
+    }
+
+    fn version_mut(&mut self) -> &mut i64 {
+        &mut self.version
+    }
+}
+
+impl Versioned for DbVehicle {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i64 {
+        self.version
+    }
+
+    fn version_mut(&mut self) -> &mut i64 {
+        &mut self.version
+    }
+}
+
+impl Versioned for DbLogRecord {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i64 {
+        self.version
+    }
+
+    fn version_mut(&mut self) -> &mut i64 {
+        &mut self.version
+    }
+}
+
+impl<T: Versioned> Row<T> {
+    fn new(mut value: T) -> Self {
+        *value.version_mut() = 1;
+        Self {
+            value,
+            deleted_at: None,
+            updated_at: now(),
+            change: next_change(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.deleted_at.is_none()
+    }
+
+    /// Bumps the version after a write, as the database does on every update
+    fn touch(&mut self) {
+        *self.value.version_mut() += 1;
+        self.updated_at = now();
+        self.change = next_change();
+    }
+
+    fn set_deleted_at(&mut self, deleted_at: Option<DateTime<Utc>>) {
+        self.deleted_at = deleted_at;
+        self.touch();
+    }
+}
+
+/// Finds a row whether or not it is soft deleted
+fn stored<'a, T: Versioned>(rows: &'a [Row<T>], id: &Uuid) -> Result<&'a Row<T>, ApiError> {
+    rows.iter()
+        .find(|row| row.value.id() == *id)
+        .ok_or(ApiError::ResourceNotFound)
+}
+
+fn stored_mut<'a, T: Versioned>(
+    rows: &'a mut [Row<T>],
+    id: &Uuid,
+) -> Result<&'a mut Row<T>, ApiError> {
+    rows.iter_mut()
+        .find(|row| row.value.id() == *id)
+        .ok_or(ApiError::ResourceNotFound)
+}
+
+/// Finds an active row matching the `If-Match` tags, if there are any
+fn active_mut<'a, T: Versioned>(
+    rows: &'a mut [Row<T>],
+    id: &Uuid,
+    if_match: Option<&EntityTags>,
+) -> Result<&'a mut Row<T>, ApiError> {
+    let row = stored_mut(rows, id)?;
+    if !row.is_active() {
+        return Err(ApiError::ResourceNotFound);
+    }
+    if let Some(if_match) = if_match {
+        if_match.ensure_matches(row.value.version())?;
+    }
+    Ok(row)
+}
+
+/// Ensure that a row exists and is not soft deleted
+fn ensure_active<T: Versioned>(rows: &[Row<T>], id: &Uuid) -> Result<(), ApiError> {
+    if stored(rows, id)?.is_active() {
+        Ok(())
+    } else {
+        Err(ApiError::ResourceNotFound)
+    }
+}
+
+fn active<T: Versioned + Clone>(rows: &[Row<T>]) -> Vec<T> {
+    rows.iter()
+        .filter(|row| row.is_active())
+        .map(|row| row.value.clone())
+        .collect()
+}
+
+/// Timestamps are stored with the precision of the database
+fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(6)
+}
+
+impl InMemory {
+    async fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().await
+    }
+
+    /// Grants an active user admin rights, which the API can't do
+    pub async fn grant_admin(&self, user_id: &Uuid) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        store.ensure_active_user(user_id)?;
+        if !store.admins.contains(user_id) {
+            store.admins.push(*user_id);
+        }
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Emulates the unique constraints on usernames and emails, which also
+    /// cover soft deleted users
+    fn ensure_unique(&self, user: &DbUser) -> Result<(), ApiError> {
+        let others = self.users.iter().filter(|row| row.value.id != user.id);
+        for row in others {
+            let (column, value) = if row.value.username == user.username {
+                ("username", &user.username)
+            } else if row.value.email == user.email {
+                ("email", &user.email)
+            } else {
+                continue;
+            };
+            return Err(ApiError::UniqueConstraintViolation {
+                constraint: Some(format!("users_{column}_key")),
+                detail: Some(format!("Key ({column})=({value}) already exists.")),
+            });
+        }
+        Ok(())
+    }
+
+    /// Ids of the active vehicles owned by a user
+    fn owned_vehicle_ids(&self, owner_id: &Uuid) -> Vec<Uuid> {
+        self.vehicles
+            .iter()
+            .filter(|row| row.is_active() && row.value.owner_id == *owner_id)
+            .map(|row| row.value.id)
+            .collect()
+    }
+
+    /// Ids of the active log records of a vehicle
+    fn log_record_ids(&self, vehicle_id: &Uuid) -> Vec<Uuid> {
+        self.log_records
+            .iter()
+            .filter(|row| row.is_active() && row.value.vehicle_id == *vehicle_id)
+            .map(|row| row.value.id)
+            .collect()
+    }
+
+    /// Finds the earliest active log record for the same vehicle with the
+    /// same type and odometer reading, dated within the duplicate window.
+    /// Records are kept in insertion order, which breaks ties on the date.
+    fn find_duplicate(&self, log_record: &DbLogRecord) -> Option<Uuid> {
+        let window = Duration::hours(DUPLICATE_WINDOW_HOURS.into());
+        self.log_records
+            .iter()
+            .filter(|row| row.is_active())
+            .map(|row| &row.value)
+            .filter(|existing| {
+                existing.vehicle_id == log_record.vehicle_id
+                    && existing.log_type() == log_record.log_type()
+                    && existing.odometer == log_record.odometer
+                    && (existing.date - log_record.date).abs() <= window
+            })
+            .min_by_key(|existing| existing.date)
+            .map(|existing| existing.id)
+    }
+
+    /// Pairs each active log record with the earliest active record it
+    /// duplicates, ordered by vehicle and date
+    fn duplicates(&self, vehicle_id: Option<Uuid>) -> Vec<DbLogRecordDuplicate> {
+        let window = Duration::hours(DUPLICATE_WINDOW_HOURS.into());
+        // The position of a record stands in for its insertion order
+        let records = self
+            .log_records
+            .iter()
+            .filter(|row| row.is_active())
+            .map(|row| &row.value)
+            .enumerate()
+            .collect::<Vec<_>>();
+        let mut duplicates = records
+            .iter()
+            .filter(|(_, record)| vehicle_id.map_or(true, |id| record.vehicle_id == id))
+            .filter_map(|&(index, record)| {
+                let (_, original) = records
+                    .iter()
+                    .filter(|&&(other_index, other)| {
+                        other.vehicle_id == record.vehicle_id
+                            && other.log_type() == record.log_type()
+                            && other.odometer == record.odometer
+                            && other.date >= record.date - window
+                            && (other.date, other_index) < (record.date, index)
+                    })
+                    .min_by_key(|&&(other_index, other)| (other.date, other_index))?;
+                let duplicate = DbLogRecordDuplicate {
+                    log_record_id: record.id,
+                    duplicate_of: original.id,
+                    vehicle_id: record.vehicle_id,
+                    log_type: record.log_type(),
+                    odometer: record.odometer,
+                    log_date: record.date,
+                };
+                Some((index, duplicate))
+            })
+            .collect::<Vec<_>>();
+        duplicates
+            .sort_by_key(|(index, duplicate)| (duplicate.vehicle_id, duplicate.log_date, *index));
+        duplicates
+            .into_iter()
+            .map(|(_, duplicate)| duplicate)
+            .collect()
+    }
+
+    /// Like a `FOR SHARE` lookup, only active users may be referenced
+    fn ensure_active_user(&self, id: &Uuid) -> Result<(), ApiError> {
+        match stored(&self.users, id) {
+            Ok(row) if row.is_active() => Ok(()),
+            _ => Err(ApiError::ResourceNotFound),
+        }
+    }
+
+    fn organisation(&self, id: &Uuid) -> Result<&DbOrganisation, ApiError> {
+        self.organisations
+            .iter()
+            .find(|organisation| organisation.id == *id)
+            .ok_or(ApiError::ResourceNotFound)
+    }
+
+    /// Emulates the unique constraint on organisation names
+    fn ensure_unique_name(&self, organisation: &DbOrganisation) -> Result<(), ApiError> {
+        let taken = self
+            .organisations
+            .iter()
+            .any(|other| other.id != organisation.id && other.name == organisation.name);
+        if taken {
+            return Err(ApiError::UniqueConstraintViolation {
+                constraint: Some("organisations_name_key".to_owned()),
+                detail: Some(format!(
+                    "Key (name)=({}) already exists.",
+                    organisation.name
+                )),
+            });
+        }
+        Ok(())
+    }
+
+    /// The role of a user in an organisation, if they belong to it
+    fn role(&self, organisation_id: &Uuid, user_id: Option<Uuid>) -> Option<MemberRole> {
+        self.members
+            .iter()
+            .find(|member| {
+                member.organisation_id == *organisation_id && Some(member.user_id) == user_id
+            })
+            .map(|member| member.role)
+    }
+
+    /// Whether the organisation has an owner besides `user_id`
+    fn has_other_owner(&self, organisation_id: &Uuid, user_id: &Uuid) -> bool {
+        self.members.iter().any(|member| {
+            member.organisation_id == *organisation_id
+                && member.user_id != *user_id
+                && member.role == MemberRole::Owner
+        })
+    }
+
+    /// Ensure that the given user belongs to the organisation, as required of
+    /// any user owning a vehicle within it
+    fn ensure_member(&self, organisation_id: &Uuid, user_id: &Uuid) -> Result<(), ApiError> {
+        if self.role(organisation_id, Some(*user_id)).is_some() {
+            Ok(())
+        } else {
+            tracing::debug!("user is not a member of the organisation");
+            Err(ApiError::NotOrganisationMember)
+        }
+    }
+
+    /// Ensure that the acting user may move a vehicle from the organisation
+    /// `from` to `to`, either of which may be none. They must be an owner or
+    /// admin of each organisation the vehicle leaves or joins.
+    fn ensure_can_move(
+        &self,
+        from: Option<Uuid>,
+        to: Option<Uuid>,
+        actor_id: Option<Uuid>,
+    ) -> Result<(), ApiError> {
+        if from == to {
+            return Ok(());
+        }
+        for organisation_id in [from, to].into_iter().flatten() {
+            ensure_role(self.role(&organisation_id, actor_id), MANAGERS)?;
+        }
+        Ok(())
+    }
+
+    /// Appends the log record's current state to its revision history
+    fn record_revision(
+        &mut self,
+        previous: DbLogRecord,
+        action: RevisionAction,
+        changed_by: Option<Uuid>,
+    ) -> Result<(), ApiError> {
+        let previous = ReadLogRecordResponse::try_from(previous)?;
+        let snapshot =
+            serde_json::to_value(&previous).map_err(|e| ApiError::Conversion(e.to_string()))?;
+        self.revisions.push(DbLogRecordRevision {
+            id: Uuid::new_v4(),
+            log_record_id: previous.id,
+            action,
+            changed_by,
+            changed_at: now(),
+            previous: snapshot,
+        });
+        Ok(())
+    }
+
+    fn tombstone(&mut self, resource_type: AuditResource, ids: &[Uuid]) {
+        self.purged.extend(ids.iter().map(|id| Purged {
+            resource_type,
+            id: *id,
+            change: next_change(),
+        }));
+    }
+
+    /// Permanently deletes log records along with their history
+    fn purge_log_records(&mut self, ids: &[Uuid]) {
+        self.log_records.retain(|row| !ids.contains(&row.value.id));
+        self.revisions
+            .retain(|revision| !ids.contains(&revision.log_record_id));
+        self.tombstone(AuditResource::LogRecord, ids);
+    }
+
+    /// Permanently deletes vehicles along with their log records
+    fn purge_vehicles(&mut self, ids: &[Uuid]) {
+        let log_record_ids = self
+            .log_records
+            .iter()
+            .filter(|row| ids.contains(&row.value.vehicle_id))
+            .map(|row| row.value.id)
+            .collect::<Vec<_>>();
+        self.purge_log_records(&log_record_ids);
+        self.vehicles.retain(|row| !ids.contains(&row.value.id));
+        self.tombstone(AuditResource::Vehicle, ids);
+    }
+
+    /// Permanently deletes a user along with their vehicles and memberships
+    fn purge_user(&mut self, id: &Uuid) {
+        let vehicle_ids = self
+            .vehicles
+            .iter()
+            .filter(|row| row.value.owner_id == *id)
+            .map(|row| row.value.id)
+            .collect::<Vec<_>>();
+        self.purge_vehicles(&vehicle_ids);
+        self.members.retain(|member| member.user_id != *id);
+        self.admins.retain(|admin_id| admin_id != id);
+        self.users.retain(|row| row.value.id != *id);
+        self.tombstone(AuditResource::User, &[*id]);
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemory {
+    async fn find(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+        let store = self.store().await;
+        let row = stored(&store.users, id)?;
+        if !row.is_active() {
+            return Err(ApiError::ResourceNotFound);
+        }
+        Ok(row.value.clone())
+    }
+
+    async fn list(&self) -> Result<Vec<DbUser>, ApiError> {
+        Ok(active(&self.store().await.users))
+    }
+
+    async fn insert(&self, user: &DbUser) -> Result<Uuid, ApiError> {
+        let mut store = self.store().await;
+        store.ensure_unique(user)?;
+        store.users.push(Row::new(user.clone()));
+        Ok(user.id)
+    }
+
+    async fn update(
+        &self,
+        user: &DbUser,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbUser, ApiError> {
+        let mut store = self.store().await;
+        active_mut(&mut store.users, &user.id, if_match)?;
+        store.ensure_unique(user)?;
+
+        let row = stored_mut(&mut store.users, &user.id)?;
+        row.value = DbUser {
+            version: row.value.version,
+            ..user.clone()
+        };
+        row.touch();
+        Ok(row.value.clone())
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        params: &DeleteUserParams,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        active_mut(&mut store.users, id, if_match)?;
+        let deleted_at = Some(now());
+
+        let vehicle_ids = store.owned_vehicle_ids(id);
+        if !vehicle_ids.is_empty() {
+            match params.mode {
+                DeletionMode::Restrict => {
+                    tracing::error!(?vehicle_ids, "user still owns vehicles");
+                    return Err(ApiError::DependentResources {
+                        resource: "vehicles".to_owned(),
+                        ids: vehicle_ids,
+                    });
+                }
+                DeletionMode::Cascade => {
+                    // Cascaded rows share the user's deletion timestamp, so
+                    // they can be restored along with it
+                    for row in store.log_records.iter_mut().filter(|row| {
+                        row.is_active() && vehicle_ids.contains(&row.value.vehicle_id)
+                    }) {
+                        row.set_deleted_at(deleted_at);
+                    }
+                    for row in store
+                        .vehicles
+                        .iter_mut()
+                        .filter(|row| vehicle_ids.contains(&row.value.id))
+                    {
+                        row.set_deleted_at(deleted_at);
+                    }
+                }
+                DeletionMode::Reassign => {
+                    let new_owner_id = params.reassign_to.ok_or_else(|| {
+                        ApiError::InvalidRequest(
+                            "reassign_to is required when reassigning vehicles".to_owned(),
+                        )
+                    })?;
+                    if new_owner_id == *id {
+                        return Err(ApiError::InvalidRequest(
+                            "vehicles can't be reassigned to the user being deleted".to_owned(),
+                        ));
+                    }
+                    active_mut(&mut store.users, &new_owner_id, None)?;
+                    // Fleet vehicles may only be handed to members of their organisation
+                    for row in store
+                        .vehicles
+                        .iter()
+                        .filter(|row| vehicle_ids.contains(&row.value.id))
+                    {
+                        if let Some(organisation_id) = row.value.organisation_id {
+                            store.ensure_member(&organisation_id, &new_owner_id)?;
+                        }
+                    }
+
+                    for row in store
+                        .vehicles
+                        .iter_mut()
+                        .filter(|row| vehicle_ids.contains(&row.value.id))
+                    {
+                        row.value.owner_id = new_owner_id;
+                        row.touch();
+                    }
+                }
+            }
+        }
+
+        stored_mut(&mut store.users, id)?.set_deleted_at(deleted_at);
+        Ok(())
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+        let mut store = self.store().await;
+        let deleted_at = stored(&store.users, id)?.deleted_at;
+        if deleted_at.is_none() {
+            return Err(ApiError::ResourceNotFound);
+        }
+
+        // Restore vehicles and log records which were deleted along with the user
+        let vehicle_ids = store
+            .vehicles
+            .iter()
+            .filter(|row| row.value.owner_id == *id)
+            .map(|row| row.value.id)
+            .collect::<Vec<_>>();
+        for row in store.log_records.iter_mut().filter(|row| {
+            row.deleted_at == deleted_at && vehicle_ids.contains(&row.value.vehicle_id)
+        }) {
+            row.set_deleted_at(None);
+        }
+        for row in store
+            .vehicles
+            .iter_mut()
+            .filter(|row| row.deleted_at == deleted_at && row.value.owner_id == *id)
+        {
+            row.set_deleted_at(None);
+        }
+
+        let row = stored_mut(&mut store.users, id)?;
+        row.set_deleted_at(None);
+        Ok(row.value.clone())
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        if stored(&store.users, id)?.is_active() {
+            return Err(ApiError::Conflict(
+                "user must be deleted before it can be purged".to_owned(),
+            ));
+        }
+        let vehicle_ids = store.owned_vehicle_ids(id);
+        if !vehicle_ids.is_empty() {
+            tracing::error!(?vehicle_ids, "user still owns vehicles");
+            return Err(ApiError::DependentResources {
+                resource: "vehicles".to_owned(),
+                ids: vehicle_ids,
+            });
+        }
+
+        store.purge_user(id);
+        Ok(())
+    }
+
+    async fn is_admin(&self, id: &Uuid) -> Result<bool, ApiError> {
+        let store = self.store().await;
+        Ok(store.admins.contains(id) && store.ensure_active_user(id).is_ok())
+    }
+}
+
+#[async_trait]
+impl VehicleRepository for InMemory {
+    async fn find(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+        let store = self.store().await;
+        let row = stored(&store.vehicles, id)?;
+        if !row.is_active() {
+            return Err(ApiError::ResourceNotFound);
+        }
+        Ok(row.value.clone())
+    }
+
+    async fn list(&self) -> Result<Vec<DbVehicle>, ApiError> {
+        Ok(active(&self.store().await.vehicles))
+    }
+
+    async fn insert(&self, vehicle: &DbVehicle, actor_id: Option<Uuid>) -> Result<Uuid, ApiError> {
+        let mut store = self.store().await;
+        store.ensure_can_move(None, vehicle.organisation_id, actor_id)?;
+        ensure_active(&store.users, &vehicle.owner_id)?;
+        if let Some(organisation_id) = vehicle.organisation_id {
+            store.ensure_member(&organisation_id, &vehicle.owner_id)?;
+        }
+        store.vehicles.push(Row::new(vehicle.clone()));
+        Ok(vehicle.id)
+    }
+
+    async fn update(
+        &self,
+        vehicle: &DbVehicle,
+        if_match: Option<&EntityTags>,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbVehicle, ApiError> {
+        let mut store = self.store().await;
+        // Ownership can't be changed by an update, so check the stored owner
+        let stored = &active_mut(&mut store.vehicles, &vehicle.id, if_match)?.value;
+        let (owner_id, stored_organisation_id) = (stored.owner_id, stored.organisation_id);
+        store.ensure_can_move(stored_organisation_id, vehicle.organisation_id, actor_id)?;
+        if let Some(organisation_id) = vehicle.organisation_id {
+            store.ensure_member(&organisation_id, &owner_id)?;
+        }
+
+        let row = stored_mut(&mut store.vehicles, &vehicle.id)?;
+        row.value = DbVehicle {
+            owner_id: row.value.owner_id,
+            version: row.value.version,
+            ..vehicle.clone()
+        };
+        row.touch();
+        Ok(row.value.clone())
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        cascade: bool,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        active_mut(&mut store.vehicles, id, if_match)?;
+        let deleted_at = Some(now());
+
+        let log_record_ids = store.log_record_ids(id);
+        if !log_record_ids.is_empty() {
+            if !cascade {
+                tracing::error!("vehicle still has log records");
+                return Err(ApiError::DependentResources {
+                    resource: "log_records".to_owned(),
+                    ids: log_record_ids,
+                });
+            }
+            for row in store
+                .log_records
+                .iter_mut()
+                .filter(|row| log_record_ids.contains(&row.value.id))
+            {
+                row.set_deleted_at(deleted_at);
+            }
+        }
+
+        stored_mut(&mut store.vehicles, id)?.set_deleted_at(deleted_at);
+        Ok(())
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+        let mut store = self.store().await;
+        let vehicle = stored(&store.vehicles, id)?;
+        let deleted_at = vehicle.deleted_at;
+        if deleted_at.is_none() {
+            return Err(ApiError::ResourceNotFound);
+        }
+        if !stored(&store.users, &vehicle.value.owner_id)?.is_active() {
+            return Err(ApiError::Conflict(
+                "the vehicle's owner is deleted and must be restored first".to_owned(),
+            ));
+        }
+
+        // Restore log records which were deleted along with the vehicle
+        for row in store
+            .log_records
+            .iter_mut()
+            .filter(|row| row.deleted_at == deleted_at && row.value.vehicle_id == *id)
+        {
+            row.set_deleted_at(None);
+        }
+
+        let row = stored_mut(&mut store.vehicles, id)?;
+        row.set_deleted_at(None);
+        Ok(row.value.clone())
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        if stored(&store.vehicles, id)?.is_active() {
+            return Err(ApiError::Conflict(
+                "vehicle must be deleted before it can be purged".to_owned(),
+            ));
+        }
+        let log_record_ids = store.log_record_ids(id);
+        if !log_record_ids.is_empty() {
+            tracing::error!("vehicle still has log records");
+            return Err(ApiError::DependentResources {
+                resource: "log_records".to_owned(),
+                ids: log_record_ids,
+            });
+        }
+
+        store.purge_vehicles(&[*id]);
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<i64, ApiError> {
+        let count = self
+            .store()
+            .await
+            .vehicles
+            .iter()
+            .filter(|row| row.is_active())
+            .count();
+        Ok(i64::try_from(count).unwrap_or(i64::MAX))
+    }
+}
+
+#[async_trait]
+impl LogRecordRepository for InMemory {
+    async fn find(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        let store = self.store().await;
+        let row = stored(&store.log_records, id)?;
+        if !row.is_active() {
+            return Err(ApiError::ResourceNotFound);
+        }
+        Ok(row.value.clone())
+    }
+
+    async fn list(&self) -> Result<Vec<DbLogRecord>, ApiError> {
+        Ok(active(&self.store().await.log_records))
+    }
+
+    async fn insert(
+        &self,
+        log_record: &DbLogRecord,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, ApiError> {
+        let mut store = self.store().await;
+        ensure_active(&store.vehicles, &log_record.vehicle_id)?;
+        let log_record = DbLogRecord {
+            date: log_record.date.trunc_subsecs(6),
+            ..log_record.clone()
+        };
+        if !allow_duplicate {
+            if let Some(existing_id) = store.find_duplicate(&log_record) {
+                tracing::info!(%existing_id, "log record looks like a duplicate");
+                return Err(ApiError::DuplicateLogRecord { existing_id });
+            }
+        }
+
+        let id = log_record.id;
+        store.log_records.push(Row::new(log_record));
+        Ok(id)
+    }
+
+    async fn update(
+        &self,
+        log_record: &DbLogRecord,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbLogRecord, ApiError> {
+        let mut store = self.store().await;
+        let existing = active_mut(&mut store.log_records, &log_record.id, if_match)?
+            .value
+            .clone();
+        store.record_revision(existing, RevisionAction::Update, changed_by)?;
+
+        let row = stored_mut(&mut store.log_records, &log_record.id)?;
+        row.value = DbLogRecord {
+            vehicle_id: row.value.vehicle_id,
+            date: log_record.date.trunc_subsecs(6),
+            version: row.value.version,
+            ..log_record.clone()
+        };
+        row.touch();
+        Ok(row.value.clone())
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        let existing = active_mut(&mut store.log_records, id, if_match)?
+            .value
+            .clone();
+        store.record_revision(existing, RevisionAction::Delete, changed_by)?;
+
+        stored_mut(&mut store.log_records, id)?.set_deleted_at(Some(now()));
+        Ok(())
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        let mut store = self.store().await;
+        let log_record = stored(&store.log_records, id)?;
+        if log_record.is_active() {
+            return Err(ApiError::ResourceNotFound);
+        }
+        if !stored(&store.vehicles, &log_record.value.vehicle_id)?.is_active() {
+            return Err(ApiError::Conflict(
+                "the log record's vehicle is deleted and must be restored first".to_owned(),
+            ));
+        }
+
+        let row = stored_mut(&mut store.log_records, id)?;
+        row.set_deleted_at(None);
+        Ok(row.value.clone())
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        if stored(&store.log_records, id)?.is_active() {
+            return Err(ApiError::Conflict(
+                "log record must be deleted before it can be purged".to_owned(),
+            ));
+        }
+        store.purge_log_records(&[*id]);
+        Ok(())
+    }
+
+    async fn count_by_type(&self) -> Result<Vec<(LogTypeName, i64)>, ApiError> {
+        let mut counts: Vec<(LogTypeName, i64)> = Vec::new();
+        for row in self
+            .store()
+            .await
+            .log_records
+            .iter()
+            .filter(|row| row.is_active())
+        {
+            let log_type = row.value.log_type();
+            match counts.iter_mut().find(|(counted, _)| *counted == log_type) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((log_type, 1)),
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn duplicates(
+        &self,
+        vehicle_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecordDuplicate>, ApiError> {
+        Ok(self.store().await.duplicates(vehicle_id))
+    }
+
+    async fn history(&self, id: &Uuid) -> Result<Vec<DbLogRecordRevision>, ApiError> {
+        let store = self.store().await;
+        stored(&store.log_records, id)?;
+        Ok(store
+            .revisions
+            .iter()
+            .filter(|revision| revision.log_record_id == *id)
+            .cloned()
+            .collect())
+    }
+
+    async fn begin(&self) -> Result<Box<dyn LogRecordBatch>, ApiError> {
+        let store = Arc::clone(&self.store).lock_owned().await;
+        let copy = InMemory {
+            store: Arc::new(Mutex::new(store.clone())),
+        };
+        Ok(Box::new(InMemoryBatch { copy, store }))
+    }
+}
+
+/// A batch of log record writes made to a copy of the store, which replaces
+/// the store once committed. The store stays locked until the batch ends.
+#[derive(Debug)]
+struct InMemoryBatch {
+    copy: InMemory,
+    store: OwnedMutexGuard<Store>,
+}
+
+#[async_trait]
+impl LogRecordRepository for InMemoryBatch {
+    async fn find(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        LogRecordRepository::find(&self.copy, id).await
+    }
+
+    async fn list(&self) -> Result<Vec<DbLogRecord>, ApiError> {
+        LogRecordRepository::list(&self.copy).await
+    }
+
+    async fn insert(
+        &self,
+        log_record: &DbLogRecord,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, ApiError> {
+        LogRecordRepository::insert(&self.copy, log_record, allow_duplicate).await
+    }
+
+    async fn update(
+        &self,
+        log_record: &DbLogRecord,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbLogRecord, ApiError> {
+        LogRecordRepository::update(&self.copy, log_record, changed_by, if_match).await
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        LogRecordRepository::delete(&self.copy, id, changed_by, if_match).await
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        LogRecordRepository::restore(&self.copy, id).await
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        LogRecordRepository::purge(&self.copy, id).await
+    }
+
+    async fn count_by_type(&self) -> Result<Vec<(LogTypeName, i64)>, ApiError> {
+        self.copy.count_by_type().await
+    }
+
+    async fn duplicates(
+        &self,
+        vehicle_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecordDuplicate>, ApiError> {
+        self.copy.duplicates(vehicle_id).await
+    }
+
+    async fn history(&self, id: &Uuid) -> Result<Vec<DbLogRecordRevision>, ApiError> {
+        self.copy.history(id).await
+    }
+
+    async fn begin(&self) -> Result<Box<dyn LogRecordBatch>, ApiError> {
+        Err(ApiError::Unsupported(
+            "log record batches can't be nested".to_owned(),
+        ))
+    }
+}
+
+#[async_trait]
+impl LogRecordBatch for InMemoryBatch {
+    async fn commit(mut self: Box<Self>) -> Result<(), ApiError> {
+        let copy = std::mem::take(&mut *self.copy.store().await);
+        *self.store = copy;
+        Ok(())
+    }
+
+    fn log_records(&self) -> &dyn LogRecordRepository {
+        self
+    }
+}
+
+#[async_trait]
+impl OrganisationRepository for InMemory {
+    async fn find(&self, id: &Uuid) -> Result<DbOrganisation, ApiError> {
+        Ok(self.store().await.organisation(id)?.clone())
+    }
+
+    async fn list(&self) -> Result<Vec<DbOrganisation>, ApiError> {
+        Ok(self.store().await.organisations.clone())
+    }
+
+    async fn insert(
+        &self,
+        organisation: &DbOrganisation,
+        owner_id: &Uuid,
+    ) -> Result<Uuid, ApiError> {
+        let mut store = self.store().await;
+        store.ensure_active_user(owner_id)?;
+        store.ensure_unique_name(organisation)?;
+
+        let id = Uuid::new_v4();
+        store.organisations.push(DbOrganisation {
+            id,
+            ..organisation.clone()
+        });
+        store.members.push(DbOrganisationMember {
+            organisation_id: id,
+            user_id: *owner_id,
+            role: MemberRole::Owner,
+        });
+        Ok(id)
+    }
+
+    async fn update(
+        &self,
+        organisation: &DbOrganisation,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisation, ApiError> {
+        let mut store = self.store().await;
+        store.organisation(&organisation.id)?;
+        ensure_role(store.role(&organisation.id, actor_id), MANAGERS)?;
+        store.ensure_unique_name(organisation)?;
+
+        let updated_organisation = store
+            .organisations
+            .iter_mut()
+            .find(|existing| existing.id == organisation.id)
+            .ok_or(ApiError::ResourceNotFound)?;
+        updated_organisation.name.clone_from(&organisation.name);
+        Ok(updated_organisation.clone())
+    }
+
+    async fn delete(&self, id: &Uuid, actor_id: Option<Uuid>) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        store.organisation(id)?;
+        ensure_role(store.role(id, actor_id), &[MemberRole::Owner])?;
+
+        store
+            .organisations
+            .retain(|organisation| organisation.id != *id);
+        store.members.retain(|member| member.organisation_id != *id);
+        // Like the foreign key, vehicles are kept outside of any organisation
+        for row in store
+            .vehicles
+            .iter_mut()
+            .filter(|row| row.value.organisation_id == Some(*id))
+        {
+            row.value.organisation_id = None;
+            row.touch();
+        }
+        Ok(())
+    }
+
+    async fn list_members(
+        &self,
+        organisation_id: &Uuid,
+    ) -> Result<Vec<DbOrganisationMember>, ApiError> {
+        let store = self.store().await;
+        store.organisation(organisation_id)?;
+        Ok(store
+            .members
+            .iter()
+            .filter(|member| member.organisation_id == *organisation_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn add_member(
+        &self,
+        member: &DbOrganisationMember,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisationMember, ApiError> {
+        let mut store = self.store().await;
+        store.organisation(&member.organisation_id)?;
+        ensure_can_add(store.role(&member.organisation_id, actor_id), member.role)?;
+        store.ensure_active_user(&member.user_id)?;
+        if store
+            .role(&member.organisation_id, Some(member.user_id))
+            .is_some()
+        {
+            return Err(ApiError::UniqueConstraintViolation {
+                constraint: Some("organisation_members_pkey".to_owned()),
+                detail: Some(format!(
+                    "Key (organisation_id, user_id)=({}, {}) already exists.",
+                    member.organisation_id, member.user_id
+                )),
+            });
+        }
+
+        store.members.push(member.clone());
+        Ok(member.clone())
+    }
+
+    async fn update_member(
+        &self,
+        member: &DbOrganisationMember,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisationMember, ApiError> {
+        let organisation_id = &member.organisation_id;
+        let mut store = self.store().await;
+        store.organisation(organisation_id)?;
+        let actor_role = store.role(organisation_id, actor_id);
+        let role = store.role(organisation_id, Some(member.user_id));
+        let other_owner = store.has_other_owner(organisation_id, &member.user_id);
+        ensure_can_change(actor_role, role, member.role, other_owner)?;
+
+        let updated_member = store
+            .members
+            .iter_mut()
+            .find(|existing| {
+                existing.organisation_id == *organisation_id && existing.user_id == member.user_id
+            })
+            .ok_or(ApiError::ResourceNotFound)?;
+        updated_member.role = member.role;
+        Ok(updated_member.clone())
+    }
+
+    async fn remove_member(
+        &self,
+        organisation_id: &Uuid,
+        user_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        store.organisation(organisation_id)?;
+        let actor_role = store.role(organisation_id, actor_id);
+        let role = store.role(organisation_id, Some(*user_id));
+        let other_owner = store.has_other_owner(organisation_id, user_id);
+        let vehicle_ids = store
+            .vehicles
+            .iter()
+            .filter(|row| {
+                row.is_active()
+                    && row.value.organisation_id == Some(*organisation_id)
+                    && row.value.owner_id == *user_id
+            })
+            .map(|row| row.value.id)
+            .collect();
+        ensure_can_remove(
+            actor_id == Some(*user_id),
+            actor_role,
+            role,
+            other_owner,
+            vehicle_ids,
+        )?;
+
+        store.members.retain(|member| {
+            member.organisation_id != *organisation_id || member.user_id != *user_id
+        });
+        Ok(())
+    }
+
+    async fn list_vehicles(
+        &self,
+        organisation_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<DbVehicle>, ApiError> {
+        let store = self.store().await;
+        store.organisation(organisation_id)?;
+        ensure_role(store.role(organisation_id, actor_id), MEMBERS)?;
+        Ok(store
+            .vehicles
+            .iter()
+            .filter(|row| row.is_active() && row.value.organisation_id == Some(*organisation_id))
+            .map(|row| row.value.clone())
+            .collect())
+    }
+
+    async fn list_log_records(
+        &self,
+        organisation_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecord>, ApiError> {
+        let store = self.store().await;
+        store.organisation(organisation_id)?;
+        ensure_role(store.role(organisation_id, actor_id), MEMBERS)?;
+        let vehicle_ids = store
+            .vehicles
+            .iter()
+            .filter(|row| row.is_active() && row.value.organisation_id == Some(*organisation_id))
+            .map(|row| row.value.id)
+            .collect::<Vec<_>>();
+        Ok(store
+            .log_records
+            .iter()
+            .filter(|row| row.is_active() && vehicle_ids.contains(&row.value.vehicle_id))
+            .map(|row| row.value.clone())
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AuditRepository for InMemory {
+    async fn record(
+        &self,
+        actor_id: Option<Uuid>,
+        resource_type: AuditResource,
+        resource_id: Option<Uuid>,
+        action: AuditAction,
+        request_id: Option<&str>,
+    ) -> Result<(), ApiError> {
+        self.store().await.audit_log.push(DbAuditEntry {
+            id: Uuid::new_v4(),
+            actor_id,
+            resource_type,
+            resource_id,
+            action,
+            request_id: request_id.map(ToOwned::to_owned),
+            occurred_at: now(),
+        });
+        Ok(())
+    }
+
+    async fn list(&self, params: &ListAuditEntriesParams) -> Result<Vec<DbAuditEntry>, ApiError> {
+        let limit = params
+            .limit
+            .map_or(usize::MAX, |limit| usize::try_from(limit).unwrap_or(0));
+        Ok(self
+            .store()
+            .await
+            .audit_log
+            .iter()
+            .rev()
+            .filter(|entry| {
+                params
+                    .actor_id
+                    .map_or(true, |id| entry.actor_id == Some(id))
+                    && params
+                        .resource_type
+                        .map_or(true, |resource_type| entry.resource_type == resource_type)
+                    && params
+                        .resource_id
+                        .map_or(true, |id| entry.resource_id == Some(id))
+                    && params.action.map_or(true, |action| entry.action == action)
+                    && params
+                        .since
+                        .map_or(true, |since| entry.occurred_at >= since)
+                    && params.until.map_or(true, |until| entry.occurred_at < until)
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for InMemory {
+    async fn claim(
+        &self,
+        scope: &'static str,
+        key: &str,
+        request: &serde_json::Value,
+    ) -> Result<Claim, ApiError> {
+        let mut store = self.store().await;
+        let claimed_at = now();
+        let expired = claimed_at - Duration::hours(IDEMPOTENCY_KEY_TTL_HOURS.into());
+        store.idempotency_keys.retain(|existing| {
+            existing.scope != scope || existing.key != key || existing.created_at >= expired
+        });
+
+        let lease = Duration::milliseconds((CLAIM_LEASE_SECONDS * 1000.0) as i64);
+        let existing = store
+            .idempotency_keys
+            .iter_mut()
+            .find(|existing| existing.scope == scope && existing.key == key);
+        match existing {
+            None => {
+                store.idempotency_keys.push(IdempotencyKey {
+                    scope,
+                    key: key.to_owned(),
+                    request: request.clone(),
+                    resource_id: None,
+                    created_at: claimed_at,
+                    claimed_at,
+                });
+                Ok(Claim::New(claimed_at))
+            }
+            // An abandoned claim is taken over
+            Some(existing)
+                if existing.resource_id.is_none() && existing.claimed_at < claimed_at - lease =>
+            {
+                existing.request = request.clone();
+                existing.created_at = claimed_at;
+                existing.claimed_at = claimed_at;
+                Ok(Claim::New(claimed_at))
+            }
+            Some(existing) if existing.request != *request => Err(ApiError::Conflict(
+                "the idempotency key was already used for a different request".to_owned(),
+            )),
+            Some(existing) => existing.resource_id.map(Claim::Completed).ok_or_else(|| {
+                ApiError::Conflict(
+                    "a request with the same idempotency key is still in progress".to_owned(),
+                )
+            }),
+        }
+    }
+
+    async fn complete(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+        resource_id: &Uuid,
+    ) -> Result<(), ApiError> {
+        let mut store = self.store().await;
+        let claim = store.idempotency_keys.iter_mut().find(|existing| {
+            existing.scope == scope && existing.key == key && existing.claimed_at == *claimed_at
+        });
+        match claim {
+            Some(claim) => claim.resource_id = Some(*resource_id),
+            None => tracing::warn!("idempotency key was taken over before the request completed"),
+        }
+        Ok(())
+    }
+
+    async fn release(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        self.store().await.idempotency_keys.retain(|existing| {
+            existing.scope != scope || existing.key != key || existing.claimed_at != *claimed_at
+        });
+        Ok(())
+    }
+}
+
+/// Reads the rows written since the token, and the ids of those deleted or
+/// purged since. A full sync has nothing to delete.
+fn changes<T: Versioned + Clone>(
+    rows: &[Row<T>],
+    purged: &[Purged],
+    resource_type: AuditResource,
+    since: Option<SequenceToken>,
+) -> Changes<T> {
+    let is_new = |change: u64| since.map_or(true, |since| change > since.0);
+    let changed = rows
+        .iter()
+        .filter(|row| row.is_active() && is_new(row.change))
+        .map(|row| (row.value.clone(), row.updated_at))
+        .collect();
+    if since.is_none() {
+        return Changes {
+            changed,
+            deleted: Vec::new(),
+        };
+    }
+
+    let deleted = rows
+        .iter()
+        .filter(|row| !row.is_active() && is_new(row.change))
+        .map(|row| row.value.id())
+        .chain(
+            purged
+                .iter()
+                .filter(|tombstone| {
+                    tombstone.resource_type == resource_type && is_new(tombstone.change)
+                })
+                .map(|tombstone| tombstone.id),
+        )
+        .collect();
+    Changes { changed, deleted }
+}
+
+#[async_trait]
+impl SyncRepository for InMemory {
+    async fn changes(&self, since: Option<&str>) -> Result<ChangeSet, ApiError> {
+        let since = since.map(SequenceToken::parse).transpose()?;
+
+        // Writes are numbered while the store is locked, so every write
+        // numbered up to the token is already visible
+        let store = self.store().await;
+        let token = SequenceToken(CHANGES.load(Ordering::SeqCst));
+        Ok(ChangeSet {
+            token: token.to_string(),
+            users: changes(&store.users, &store.purged, AuditResource::User, since),
+            vehicles: changes(
+                &store.vehicles,
+                &store.purged,
+                AuditResource::Vehicle,
+                since,
+            ),
+            log_records: changes(
+                &store.log_records,
+                &store.purged,
+                AuditResource::LogRecord,
+                since,
+            ),
+        })
+    }
+}
+
+/// Memory is always at hand and has no schema to migrate
+#[async_trait]
+impl HealthRepository for InMemory {
+    async fn ready(&self) -> Result<(), ApiError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod memory_tests {
+    use super::*;
+    use crate::types::LogType;
+    use fake::{Fake, Faker};
+
+    async fn seed_user_and_vehicle(store: &InMemory) -> (DbUser, DbVehicle) {
+        let user = Faker.fake::<DbUser>();
+        UserRepository::insert(store, &user)
+            .await
+            .expect("could not insert user");
+        let vehicle = DbVehicle {
+            owner_id: user.id,
+            organisation_id: None,
+            ..Faker.fake()
+        };
+        VehicleRepository::insert(store, &vehicle, None)
+            .await
+            .expect("could not insert vehicle");
+        (user, vehicle)
+    }
+
+    async fn seed_log_record(store: &InMemory, vehicle_id: Uuid) -> DbLogRecord {
+        let log_record = DbLogRecord {
+            vehicle_id,
+            ..Faker.fake()
+        };
+        LogRecordRepository::insert(store, &log_record, true)
+            .await
+            .expect("could not insert log record");
+        log_record
+    }
+
+    #[tokio::test]
+    async fn usernames_are_unique() {
+        // Arrange
+        let store = InMemory::default();
+        let user = Faker.fake::<DbUser>();
+        UserRepository::insert(&store, &user)
+            .await
+            .expect("could not insert user");
+        let other = DbUser {
+            username: user.username.clone(),
+            ..Faker.fake()
+        };
+
+        // Act
+        let err = UserRepository::insert(&store, &other)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(
+            err,
+            ApiError::UniqueConstraintViolation { constraint: Some(constraint), .. }
+                if constraint == "users_username_key"
+        ));
+    }
+
+    #[tokio::test]
+    async fn vehicles_need_an_existing_owner() {
+        // Arrange
+        let store = InMemory::default();
+        let vehicle = DbVehicle {
+            organisation_id: None,
+            ..Faker.fake()
+        };
+
+        // Act
+        let err = VehicleRepository::insert(&store, &vehicle, None)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(err, ApiError::ResourceNotFound));
+    }
+
+    #[tokio::test]
+    async fn fleet_vehicles_need_a_member_as_owner() {
+        // Arrange
+        let store = InMemory::default();
+        let (user, _) = seed_user_and_vehicle(&store).await;
+        let owner = Faker.fake::<DbUser>();
+        UserRepository::insert(&store, &owner)
+            .await
+            .expect("could not insert user");
+        let organisation_id = OrganisationRepository::insert(&store, &Faker.fake(), &owner.id)
+            .await
+            .expect("could not insert organisation");
+        let vehicle = DbVehicle {
+            owner_id: user.id,
+            organisation_id: Some(organisation_id),
+            ..Faker.fake()
+        };
+
+        // Act
+        let err = VehicleRepository::insert(&store, &vehicle, Some(owner.id))
+            .await
+            .expect_err("expected failure did not occur");
+        let member = DbOrganisationMember {
+            organisation_id,
+            user_id: user.id,
+            role: MemberRole::Member,
+        };
+        store
+            .add_member(&member, Some(owner.id))
+            .await
+            .expect("could not add member");
+        VehicleRepository::insert(&store, &vehicle, Some(owner.id))
+            .await
+            .expect("could not insert vehicle");
+
+        // Assert
+        assert!(matches!(err, ApiError::NotOrganisationMember));
+        let vehicles = store
+            .list_vehicles(&organisation_id, Some(owner.id))
+            .await
+            .expect("could not list vehicles");
+        assert_eq!(vehicles.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn batches_are_kept_only_once_committed() {
+        // Arrange
+        let store = InMemory::default();
+        let (_, vehicle) = seed_user_and_vehicle(&store).await;
+        let rolled_back = DbLogRecord {
+            vehicle_id: vehicle.id,
+            ..Faker.fake()
+        };
+        let committed = DbLogRecord {
+            vehicle_id: vehicle.id,
+            ..Faker.fake()
+        };
+
+        // Act
+        let batch = store.begin().await.expect("could not begin batch");
+        LogRecordRepository::insert(batch.log_records(), &rolled_back, true)
+            .await
+            .expect("could not insert log record");
+        drop(batch);
+        let batch = store.begin().await.expect("could not begin batch");
+        LogRecordRepository::insert(batch.log_records(), &committed, true)
+            .await
+            .expect("could not insert log record");
+        batch.commit().await.expect("could not commit batch");
+
+        // Assert
+        let ids = LogRecordRepository::list(&store)
+            .await
+            .expect("could not list log records")
+            .into_iter()
+            .map(|log_record| log_record.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![committed.id]);
+    }
+
+    #[tokio::test]
+    async fn sync_reports_writes_after_the_token() {
+        // Arrange
+        let store = InMemory::default();
+        let (_, vehicle) = seed_user_and_vehicle(&store).await;
+        let deleted = seed_log_record(&store, vehicle.id).await;
+        let purged = seed_log_record(&store, vehicle.id).await;
+        LogRecordRepository::delete(&store, &purged.id, None, None)
+            .await
+            .expect("could not delete log record");
+        let token = store
+            .changes(None)
+            .await
+            .expect("could not read changes")
+            .token;
+
+        // Act
+        LogRecordRepository::delete(&store, &deleted.id, None, None)
+            .await
+            .expect("could not delete log record");
+        LogRecordRepository::purge(&store, &purged.id)
+            .await
+            .expect("could not purge log record");
+        let created = seed_log_record(&store, vehicle.id).await;
+        let changes = store
+            .changes(Some(&token))
+            .await
+            .expect("could not read changes");
+
+        // Assert
+        assert!(changes.users.changed.is_empty());
+        assert!(changes.vehicles.changed.is_empty());
+        let changed = changes
+            .log_records
+            .changed
+            .iter()
+            .map(|(log_record, _)| log_record.id)
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![created.id]);
+        assert_eq!(changes.log_records.deleted, vec![deleted.id, purged.id]);
+    }
+
+    #[tokio::test]
+    async fn writes_bump_the_version() {
+        // Arrange
+        let store = InMemory::default();
+        let (user, _) = seed_user_and_vehicle(&store).await;
+        let stale = EntityTags::Versions(vec![1]);
+
+        // Act
+        let updated = UserRepository::update(&store, &user, Some(&stale))
+            .await
+            .expect("could not update user");
+        let err = UserRepository::update(&store, &user, Some(&stale))
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert_eq!(updated.version, 2);
+        assert!(matches!(err, ApiError::PreconditionFailed));
+    }
+
+    #[tokio::test]
+    async fn cascaded_deletes_are_restored_together() {
+        // Arrange
+        let store = InMemory::default();
+        let (user, vehicle) = seed_user_and_vehicle(&store).await;
+        let log_record = seed_log_record(&store, vehicle.id).await;
+        let params = DeleteUserParams {
+            mode: DeletionMode::Cascade,
+            reassign_to: None,
+        };
+        UserRepository::delete(&store, &user.id, &params, None)
+            .await
+            .expect("could not delete user");
+        let deleted = LogRecordRepository::find(&store, &log_record.id).await;
+
+        // Act
+        UserRepository::restore(&store, &user.id)
+            .await
+            .expect("could not restore user");
+
+        // Assert
+        assert!(matches!(deleted, Err(ApiError::ResourceNotFound)));
+        VehicleRepository::find(&store, &vehicle.id)
+            .await
+            .expect("vehicle should have been restored");
+        LogRecordRepository::find(&store, &log_record.id)
+            .await
+            .expect("log record should have been restored");
+    }
+
+    #[tokio::test]
+    async fn purge_requires_soft_deletion() {
+        // Arrange
+        let store = InMemory::default();
+        let (_, vehicle) = seed_user_and_vehicle(&store).await;
+        let log_record = seed_log_record(&store, vehicle.id).await;
+
+        // Act
+        let err = LogRecordRepository::purge(&store, &log_record.id)
+            .await
+            .expect_err("expected failure did not occur");
+        LogRecordRepository::delete(&store, &log_record.id, None, None)
+            .await
+            .expect("could not delete log record");
+        LogRecordRepository::purge(&store, &log_record.id)
+            .await
+            .expect("could not purge log record");
+
+        // Assert
+        assert!(matches!(err, ApiError::Conflict(_)));
+        assert!(matches!(
+            LogRecordRepository::restore(&store, &log_record.id).await,
+            Err(ApiError::ResourceNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_duplicate_log_records() {
+        // Arrange
+        let store = InMemory::default();
+        let (_, vehicle) = seed_user_and_vehicle(&store).await;
+        let existing = seed_log_record(&store, vehicle.id).await;
+        let double_tap = DbLogRecord {
+            id: Uuid::new_v4(),
+            date: existing.date + Duration::hours(1),
+            ..existing.clone()
+        };
+
+        // Act
+        let err = LogRecordRepository::insert(&store, &double_tap, false)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(
+            matches!(err, ApiError::DuplicateLogRecord { existing_id } if existing_id == existing.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicates_sharing_a_date_follow_insertion_order() {
+        // Arrange
+        let store = InMemory::default();
+        let (_, vehicle) = seed_user_and_vehicle(&store).await;
+        let existing = seed_log_record(&store, vehicle.id).await;
+        for _ in 0..4 {
+            let same_date = DbLogRecord {
+                id: Uuid::new_v4(),
+                ..existing.clone()
+            };
+            LogRecordRepository::insert(&store, &same_date, true)
+                .await
+                .expect("could not insert log record");
+        }
+        let double_tap = DbLogRecord {
+            id: Uuid::new_v4(),
+            ..existing.clone()
+        };
+
+        // Act
+        let err = LogRecordRepository::insert(&store, &double_tap, false)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(
+            matches!(err, ApiError::DuplicateLogRecord { existing_id } if existing_id == existing.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_active_log_records_by_type() {
+        // Arrange
+        let store = InMemory::default();
+        let (_, vehicle) = seed_user_and_vehicle(&store).await;
+        for _ in 0..2 {
+            let log_record = DbLogRecord {
+                vehicle_id: vehicle.id,
+                log_type: LogType::OilChange,
+                ..Faker.fake()
+            };
+            LogRecordRepository::insert(&store, &log_record, true)
+                .await
+                .expect("could not insert log record");
+        }
+        let deleted = seed_log_record(&store, vehicle.id).await;
+        LogRecordRepository::delete(&store, &deleted.id, None, None)
+            .await
+            .expect("could not delete log record");
+
+        // Act
+        let counts = LogRecordRepository::count_by_type(&store)
+            .await
+            .expect("could not count log records");
+
+        // Assert
+        assert_eq!(counts, vec![(LogTypeName::OilChange, 2)]);
+    }
+}
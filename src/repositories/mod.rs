@@ -0,0 +1,432 @@
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, SqlitePool};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{
+        api::{DeleteUserParams, ListAuditEntriesParams},
+        db::{
+            AuditEntry as DbAuditEntry, LogRecord as DbLogRecord,
+            LogRecordDuplicate as DbLogRecordDuplicate, LogRecordRevision as DbLogRecordRevision,
+            Organisation as DbOrganisation, OrganisationMember as DbOrganisationMember,
+            User as DbUser, Vehicle as DbVehicle,
+        },
+    },
+    types::{AuditAction, AuditResource, EntityTags, LogTypeName},
+};
+
+/// How far apart the dates of two otherwise identical log records may be for
+/// them to be considered duplicates
+pub const DUPLICATE_WINDOW_HOURS: i32 = 24;
+
+/// Seconds a request may hold an idempotency key without completing before a
+/// retry takes the key over. Requests dropped or interrupted by a crash never
+/// release their key, so they would otherwise block retries until it expires.
+pub const CLAIM_LEASE_SECONDS: f64 = 30.0;
+
+/// Hours an idempotency key is remembered for
+pub const IDEMPOTENCY_KEY_TTL_HOURS: i32 = 24;
+
+/// Storage of users. Writes guarded by `if_match` fail unless it matches the
+/// stored version; missing and soft deleted users are not found.
+#[async_trait]
+pub trait UserRepository: std::fmt::Debug + Send + Sync {
+    /// Reads an active user
+    async fn find(&self, id: &Uuid) -> Result<DbUser, ApiError>;
+
+    /// Reads every active user
+    async fn list(&self) -> Result<Vec<DbUser>, ApiError>;
+
+    /// Writes a new user, returning its id
+    async fn insert(&self, user: &DbUser) -> Result<Uuid, ApiError>;
+
+    /// Overwrites an active user
+    async fn update(
+        &self,
+        user: &DbUser,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbUser, ApiError>;
+
+    /// Soft deletes an active user, handling the vehicles they own according
+    /// to `params`
+    async fn delete(
+        &self,
+        id: &Uuid,
+        params: &DeleteUserParams,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError>;
+
+    /// Undoes the soft deletion of a user, along with the vehicles and log
+    /// records deleted with them
+    async fn restore(&self, id: &Uuid) -> Result<DbUser, ApiError>;
+
+    /// Permanently deletes a soft deleted user with everything they own
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError>;
+
+    /// Whether the user is active and has been granted admin rights
+    async fn is_admin(&self, id: &Uuid) -> Result<bool, ApiError>;
+}
+
+/// Storage of vehicles, following the conventions of [`UserRepository`]
+#[async_trait]
+pub trait VehicleRepository: std::fmt::Debug + Send + Sync {
+    /// Reads an active vehicle
+    async fn find(&self, id: &Uuid) -> Result<DbVehicle, ApiError>;
+
+    /// Reads every active vehicle
+    async fn list(&self) -> Result<Vec<DbVehicle>, ApiError>;
+
+    /// Writes a new vehicle, returning its id. Creating it within an
+    /// organisation takes the acting user to be one of its owners or admins.
+    async fn insert(&self, vehicle: &DbVehicle, actor_id: Option<Uuid>) -> Result<Uuid, ApiError>;
+
+    /// Overwrites an active vehicle. Its owner is never changed. Moving it
+    /// into or out of an organisation takes the acting user to be one of the
+    /// owners or admins of each organisation it joins or leaves.
+    async fn update(
+        &self,
+        vehicle: &DbVehicle,
+        if_match: Option<&EntityTags>,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbVehicle, ApiError>;
+
+    /// Soft deletes an active vehicle, along with its log records if
+    /// `cascade` is set
+    async fn delete(
+        &self,
+        id: &Uuid,
+        cascade: bool,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError>;
+
+    /// Undoes the soft deletion of a vehicle, along with the log records
+    /// deleted with it
+    async fn restore(&self, id: &Uuid) -> Result<DbVehicle, ApiError>;
+
+    /// Permanently deletes a soft deleted vehicle with its log records
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError>;
+
+    /// Counts the active vehicles
+    async fn count(&self) -> Result<i64, ApiError>;
+}
+
+/// Storage of log records, following the conventions of [`UserRepository`]
+#[async_trait]
+pub trait LogRecordRepository: std::fmt::Debug + Send + Sync {
+    /// Reads an active log record
+    async fn find(&self, id: &Uuid) -> Result<DbLogRecord, ApiError>;
+
+    /// Reads every active log record
+    async fn list(&self) -> Result<Vec<DbLogRecord>, ApiError>;
+
+    /// Writes a new log record, returning its id. Unless `allow_duplicate`
+    /// is set, records duplicating an active one are refused.
+    async fn insert(
+        &self,
+        log_record: &DbLogRecord,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, ApiError>;
+
+    /// Overwrites an active log record, which may change its type
+    async fn update(
+        &self,
+        log_record: &DbLogRecord,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbLogRecord, ApiError>;
+
+    /// Soft deletes an active log record
+    async fn delete(
+        &self,
+        id: &Uuid,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError>;
+
+    /// Undoes the soft deletion of a log record whose vehicle is active
+    async fn restore(&self, id: &Uuid) -> Result<DbLogRecord, ApiError>;
+
+    /// Permanently deletes a soft deleted log record
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError>;
+
+    /// Counts the active log records of each type, omitting types without any
+    async fn count_by_type(&self) -> Result<Vec<(LogTypeName, i64)>, ApiError>;
+
+    /// Reads the active log records suspected to duplicate an earlier one,
+    /// each paired with the earliest record it duplicates
+    async fn duplicates(
+        &self,
+        vehicle_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecordDuplicate>, ApiError>;
+
+    /// Reads the earlier revisions of a log record, oldest first. History
+    /// outlives soft deletion, so only purged records are not found.
+    async fn history(&self, id: &Uuid) -> Result<Vec<DbLogRecordRevision>, ApiError>;
+
+    /// Starts a batch, whose writes are kept only once it is committed. A
+    /// failed write within the batch is undone without affecting the others.
+    async fn begin(&self) -> Result<Box<dyn LogRecordBatch>, ApiError>;
+}
+
+/// Log records as seen by a batch, which is rolled back when dropped without
+/// being committed. Batches can't be nested.
+#[async_trait]
+pub trait LogRecordBatch: LogRecordRepository {
+    /// Keeps the writes made within the batch
+    async fn commit(self: Box<Self>) -> Result<(), ApiError>;
+
+    /// Borrows the batch as the repository its writes are made through
+    fn log_records(&self) -> &dyn LogRecordRepository;
+}
+
+/// Storage of organisations and their members. Writes are made on behalf of
+/// an acting user, who must hold the role they require in the organisation,
+/// as checked by [`crate::types::permissions`].
+#[async_trait]
+pub trait OrganisationRepository: std::fmt::Debug + Send + Sync {
+    /// Reads an organisation
+    async fn find(&self, id: &Uuid) -> Result<DbOrganisation, ApiError>;
+
+    /// Reads every organisation
+    async fn list(&self) -> Result<Vec<DbOrganisation>, ApiError>;
+
+    /// Writes a new organisation with an active user as its owner, returning
+    /// its id
+    async fn insert(
+        &self,
+        organisation: &DbOrganisation,
+        owner_id: &Uuid,
+    ) -> Result<Uuid, ApiError>;
+
+    /// Renames an organisation, which its owners and admins may do
+    async fn update(
+        &self,
+        organisation: &DbOrganisation,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisation, ApiError>;
+
+    /// Deletes an organisation, which only its owners may do. Its vehicles
+    /// are kept, outside of any organisation.
+    async fn delete(&self, id: &Uuid, actor_id: Option<Uuid>) -> Result<(), ApiError>;
+
+    /// Reads the members of an organisation
+    async fn list_members(
+        &self,
+        organisation_id: &Uuid,
+    ) -> Result<Vec<DbOrganisationMember>, ApiError>;
+
+    /// Adds an active user to an organisation
+    async fn add_member(
+        &self,
+        member: &DbOrganisationMember,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisationMember, ApiError>;
+
+    /// Changes the role of a member
+    async fn update_member(
+        &self,
+        member: &DbOrganisationMember,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisationMember, ApiError>;
+
+    /// Removes a member, who may also remove themselves
+    async fn remove_member(
+        &self,
+        organisation_id: &Uuid,
+        user_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<(), ApiError>;
+
+    /// Reads the active vehicles of an organisation, which its members may do
+    async fn list_vehicles(
+        &self,
+        organisation_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<DbVehicle>, ApiError>;
+
+    /// Reads the active log records of an organisation's active vehicles,
+    /// which its members may do
+    async fn list_log_records(
+        &self,
+        organisation_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecord>, ApiError>;
+}
+
+/// Storage of the audit log, which is append-only
+#[async_trait]
+pub trait AuditRepository: std::fmt::Debug + Send + Sync {
+    /// Appends an entry, dated now
+    async fn record(
+        &self,
+        actor_id: Option<Uuid>,
+        resource_type: AuditResource,
+        resource_id: Option<Uuid>,
+        action: AuditAction,
+        request_id: Option<&str>,
+    ) -> Result<(), ApiError>;
+
+    /// Reads the entries matching `params`, newest first and at most
+    /// `params.limit` of them
+    async fn list(&self, params: &ListAuditEntriesParams) -> Result<Vec<DbAuditEntry>, ApiError>;
+}
+
+/// Outcome of claiming an idempotency key
+#[derive(Debug)]
+pub enum Claim {
+    /// The key is held by this request since the given time
+    New(DateTime<Utc>),
+    /// An earlier request with the key created this resource
+    Completed(Uuid),
+}
+
+/// Storage of idempotency keys, each identifying a request within a scope
+#[async_trait]
+pub trait IdempotencyRepository: std::fmt::Debug + Send + Sync {
+    /// Claims a key for a new request, unless an earlier request with the
+    /// same key created a resource or still holds it. Keys expire after
+    /// [`IDEMPOTENCY_KEY_TTL_HOURS`], and claims that didn't complete within
+    /// [`CLAIM_LEASE_SECONDS`] are taken over. Reusing a key for a different
+    /// request conflicts.
+    async fn claim(
+        &self,
+        scope: &'static str,
+        key: &str,
+        request: &serde_json::Value,
+    ) -> Result<Claim, ApiError>;
+
+    /// Records the created resource, unless the claim was taken over meanwhile
+    async fn complete(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+        resource_id: &Uuid,
+    ) -> Result<(), ApiError>;
+
+    /// Gives up a claim, so the request may be retried with the same key
+    async fn release(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+    ) -> Result<(), ApiError>;
+}
+
+/// Resources of one type written since a position in the change feed
+#[derive(Debug)]
+pub struct Changes<T> {
+    /// Active resources, with the time they were last written
+    pub changed: Vec<(T, DateTime<Utc>)>,
+    /// Ids of resources deleted or purged. A full sync has none.
+    pub deleted: Vec<Uuid>,
+}
+
+/// Everything written since a position in the change feed, read at once
+#[derive(Debug)]
+pub struct ChangeSet {
+    /// The position the changes were read at
+    pub token: String,
+    pub users: Changes<DbUser>,
+    pub vehicles: Changes<DbVehicle>,
+    pub log_records: Changes<DbLogRecord>,
+}
+
+/// The change feed of users, vehicles and log records. Tokens are opaque to
+/// clients, and each backend only accepts its own.
+#[async_trait]
+pub trait SyncRepository: std::fmt::Debug + Send + Sync {
+    /// Reads the changes since `since`, or every active resource without it
+    async fn changes(&self, since: Option<&str>) -> Result<ChangeSet, ApiError>;
+}
+
+/// Readiness of the storage behind the other repositories
+#[async_trait]
+pub trait HealthRepository: std::fmt::Debug + Send + Sync {
+    /// Fails with [`ApiError::Unavailable`] unless the storage answers queries
+    /// and its schema has every embedded migration applied
+    async fn ready(&self) -> Result<(), ApiError>;
+}
+
+/// Reports a database that can't be queried as unavailable rather than as
+/// an internal error
+fn unreachable(e: sqlx::Error) -> ApiError {
+    tracing::warn!(?e, "could not query the database");
+    ApiError::Unavailable("the database is unreachable".to_owned())
+}
+
+/// Fails unless every embedded migration has been applied
+fn ensure_migrated(pending: &[i64]) -> Result<(), ApiError> {
+    if pending.is_empty() {
+        return Ok(());
+    }
+    tracing::warn!(?pending, "migrations are pending");
+    let pending = pending.iter().map(i64::to_string).collect::<Vec<_>>();
+    Err(ApiError::Unavailable(format!(
+        "migrations are pending: {}",
+        pending.join(", ")
+    )))
+}
+
+/// The repositories the API reads and writes its resources through
+#[derive(Debug, Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub vehicles: Arc<dyn VehicleRepository>,
+    pub log_records: Arc<dyn LogRecordRepository>,
+    pub organisations: Arc<dyn OrganisationRepository>,
+    pub audit: Arc<dyn AuditRepository>,
+    pub idempotency: Arc<dyn IdempotencyRepository>,
+    pub sync: Arc<dyn SyncRepository>,
+    pub health: Arc<dyn HealthRepository>,
+}
+
+impl Repositories {
+    pub fn postgres(pool: &PgPool) -> Self {
+        Self {
+            users: Arc::new(pool.clone()),
+            vehicles: Arc::new(pool.clone()),
+            log_records: Arc::new(pool.clone()),
+            organisations: Arc::new(pool.clone()),
+            audit: Arc::new(pool.clone()),
+            idempotency: Arc::new(pool.clone()),
+            sync: Arc::new(pool.clone()),
+            health: Arc::new(pool.clone()),
+        }
+    }
+
+    pub fn sqlite(pool: &SqlitePool) -> Self {
+        Self {
+            users: Arc::new(pool.clone()),
+            vehicles: Arc::new(pool.clone()),
+            log_records: Arc::new(pool.clone()),
+            organisations: Arc::new(pool.clone()),
+            audit: Arc::new(pool.clone()),
+            idempotency: Arc::new(pool.clone()),
+            sync: Arc::new(pool.clone()),
+            health: Arc::new(pool.clone()),
+        }
+    }
+
+    /// Repositories sharing a single in-memory store
+    pub fn in_memory(store: &memory::InMemory) -> Self {
+        Self {
+            users: Arc::new(store.clone()),
+            vehicles: Arc::new(store.clone()),
+            log_records: Arc::new(store.clone()),
+            organisations: Arc::new(store.clone()),
+            audit: Arc::new(store.clone()),
+            idempotency: Arc::new(store.clone()),
+            sync: Arc::new(store.clone()),
+            health: Arc::new(store.clone()),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use axum::async_trait;
+use sqlx::{query, PgPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{api::ListAuditEntriesParams, db::AuditEntry as DbAuditEntry},
+    repositories::AuditRepository,
+    types::{AuditAction, AuditResource},
+};
+
+#[async_trait]
+impl AuditRepository for PgPool {
+    async fn record(
+        &self,
+        actor_id: Option<Uuid>,
+        resource_type: AuditResource,
+        resource_id: Option<Uuid>,
+        action: AuditAction,
+        request_id: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let sql = "
+            INSERT INTO audit_log(actor_id, resource_type, resource_id, action, request_id)
+            VALUES ($1, $2, $3, $4, $5)";
+        query(sql)
+            .bind(actor_id)
+            .bind(resource_type)
+            .bind(resource_id)
+            .bind(action)
+            .bind(request_id)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn list(&self, params: &ListAuditEntriesParams) -> Result<Vec<DbAuditEntry>, ApiError> {
+        let mut qb = QueryBuilder::<sqlx::Postgres>::new("SELECT * FROM audit_log WHERE true");
+        if let Some(actor_id) = params.actor_id {
+            qb.push(" AND actor_id = ").push_bind(actor_id);
+        }
+        if let Some(resource_type) = params.resource_type {
+            qb.push(" AND resource_type = ").push_bind(resource_type);
+        }
+        if let Some(resource_id) = params.resource_id {
+            qb.push(" AND resource_id = ").push_bind(resource_id);
+        }
+        if let Some(action) = params.action {
+            qb.push(" AND action = ").push_bind(action);
+        }
+        if let Some(since) = params.since {
+            qb.push(" AND occurred_at >= ").push_bind(since);
+        }
+        if let Some(until) = params.until {
+            qb.push(" AND occurred_at < ").push_bind(until);
+        }
+        qb.push(" ORDER BY occurred_at DESC LIMIT ")
+            .push_bind(params.limit);
+
+        Ok(qb.build_query_as::<DbAuditEntry>().fetch_all(self).await?)
+    }
+}
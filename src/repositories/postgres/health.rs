@@ -0,0 +1,25 @@
+use axum::async_trait;
+use sqlx::{query, PgPool};
+
+use crate::{
+    error::ApiError,
+    migrations,
+    repositories::{ensure_migrated, unreachable, HealthRepository},
+};
+
+#[async_trait]
+impl HealthRepository for PgPool {
+    async fn ready(&self) -> Result<(), ApiError> {
+        let mut conn = self.acquire().await.map_err(unreachable)?;
+        query("SELECT 1")
+            .execute(&mut *conn)
+            .await
+            .map_err(unreachable)?;
+
+        let applied = migrations::applied_versions(&mut conn).await.map_err(|e| {
+            tracing::warn!(?e, "could not read applied migrations");
+            ApiError::Unavailable("migrations have not been applied".to_owned())
+        })?;
+        ensure_migrated(&migrations::pending(&applied))
+    }
+}
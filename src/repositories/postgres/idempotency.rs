@@ -0,0 +1,117 @@
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query, PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    repositories::{Claim, IdempotencyRepository, CLAIM_LEASE_SECONDS, IDEMPOTENCY_KEY_TTL_HOURS},
+};
+
+#[async_trait]
+impl IdempotencyRepository for PgPool {
+    async fn claim(
+        &self,
+        scope: &'static str,
+        key: &str,
+        request: &serde_json::Value,
+    ) -> Result<Claim, ApiError> {
+        query(
+            "DELETE FROM idempotency_keys
+            WHERE scope = $1 AND key = $2 AND created_at < now() - make_interval(hours => $3)",
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(IDEMPOTENCY_KEY_TTL_HOURS)
+        .execute(self)
+        .await?;
+
+        // The key may be released between the upsert and the read, in which
+        // case it is free to claim again
+        loop {
+            let claimed = query(
+                "INSERT INTO idempotency_keys (scope, key, request) VALUES ($1, $2, $3)
+                ON CONFLICT (scope, key) DO UPDATE
+                SET request = EXCLUDED.request, created_at = now(), claimed_at = now()
+                WHERE idempotency_keys.resource_id IS NULL
+                    AND idempotency_keys.claimed_at < now() - make_interval(secs => $4)
+                RETURNING claimed_at",
+            )
+            .bind(scope)
+            .bind(key)
+            .bind(request)
+            .bind(CLAIM_LEASE_SECONDS)
+            .fetch_optional(self)
+            .await?;
+            if let Some(row) = claimed {
+                return Ok(Claim::New(row.try_get("claimed_at")?));
+            }
+
+            let existing = query(
+                "SELECT resource_id, request = $3 AS same_request FROM idempotency_keys
+                WHERE scope = $1 AND key = $2",
+            )
+            .bind(scope)
+            .bind(key)
+            .bind(request)
+            .fetch_optional(self)
+            .await?;
+            let Some(existing) = existing else {
+                tracing::debug!("idempotency key was released while claiming it");
+                continue;
+            };
+
+            if !existing.try_get::<bool, _>("same_request")? {
+                return Err(ApiError::Conflict(
+                    "the idempotency key was already used for a different request".to_owned(),
+                ));
+            }
+            return existing
+                .try_get::<Option<Uuid>, _>("resource_id")?
+                .map(Claim::Completed)
+                .ok_or_else(|| {
+                    ApiError::Conflict(
+                        "a request with the same idempotency key is still in progress".to_owned(),
+                    )
+                });
+        }
+    }
+
+    async fn complete(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+        resource_id: &Uuid,
+    ) -> Result<(), ApiError> {
+        let res = query(
+            "UPDATE idempotency_keys SET resource_id = $4
+            WHERE scope = $1 AND key = $2 AND claimed_at = $3",
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(claimed_at)
+        .bind(resource_id)
+        .execute(self)
+        .await?;
+        if res.rows_affected() < 1 {
+            tracing::warn!("idempotency key was taken over before the request completed");
+        }
+        Ok(())
+    }
+
+    async fn release(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        query("DELETE FROM idempotency_keys WHERE scope = $1 AND key = $2 AND claimed_at = $3")
+            .bind(scope)
+            .bind(key)
+            .bind(claimed_at)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,778 @@
+use axum::async_trait;
+use sqlx::{
+    postgres::PgArguments, query, query_as, query_scalar, Acquire, PgConnection, PgExecutor,
+    PgPool, Postgres, Transaction,
+};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{
+        api::ReadLogRecordResponse,
+        db::{
+            LogRecord as DbLogRecord, LogRecordDuplicate as DbLogRecordDuplicate,
+            LogRecordRevision as DbLogRecordRevision,
+        },
+    },
+    repositories::{
+        postgres::vehicle::ensure_vehicle, LogRecordBatch, LogRecordRepository,
+        DUPLICATE_WINDOW_HOURS,
+    },
+    types::{EntityTags, LogType, LogTypeName, RevisionAction},
+};
+
+/// Selects log records joined with their type-specific details, under the
+/// column names [`DbLogRecord`] reads. Columns of `log_records` must be
+/// qualified in any appended clause, as detail tables also have a `log_type`.
+pub const SELECT: &str = "
+    SELECT
+        log_records.*,
+        fuel_ups.fuel_amount,
+        tire_changes.tire_type,
+        tire_changes.new_tires,
+        COALESCE(tire_rotations.tire_rotation_type, tire_changes.tire_rotation_type)
+            AS tire_rotation_type,
+        brake_replacements.brake_location,
+        brake_replacements.brake_part,
+        fluid_changes.fluid_type
+    FROM log_records
+    LEFT JOIN fuel_ups ON fuel_ups.log_record_id = log_records.id
+    LEFT JOIN tire_changes ON tire_changes.log_record_id = log_records.id
+    LEFT JOIN brake_replacements ON brake_replacements.log_record_id = log_records.id
+    LEFT JOIN tire_rotations ON tire_rotations.log_record_id = log_records.id
+    LEFT JOIN fluid_changes ON fluid_changes.log_record_id = log_records.id";
+
+/// The table holding the details of a log type, if it has any
+fn details_table(log_type: LogTypeName) -> Option<&'static str> {
+    match log_type {
+        LogTypeName::FuelUp => Some("fuel_ups"),
+        LogTypeName::TireChange => Some("tire_changes"),
+        LogTypeName::BrakeReplacement => Some("brake_replacements"),
+        LogTypeName::TireRotation => Some("tire_rotations"),
+        LogTypeName::Fluids => Some("fluid_changes"),
+        LogTypeName::OilChange
+        | LogTypeName::Repair
+        | LogTypeName::WiperBladeReplacement
+        | LogTypeName::BatteryReplacement => None,
+    }
+}
+
+/// Reads an active log record
+pub async fn find(executor: impl PgExecutor<'_>, id: &Uuid) -> Result<DbLogRecord, sqlx::Error> {
+    let sql = format!("{SELECT} WHERE log_records.id = $1 AND log_records.deleted_at IS NULL");
+    query_as::<_, DbLogRecord>(&sql)
+        .bind(id)
+        .fetch_one(executor)
+        .await
+}
+
+/// Reads an active log record and locks it for the rest of the transaction
+pub async fn find_for_update(
+    executor: impl PgExecutor<'_>,
+    id: &Uuid,
+) -> Result<DbLogRecord, sqlx::Error> {
+    let sql = format!(
+        "{SELECT} WHERE log_records.id = $1 AND log_records.deleted_at IS NULL
+        FOR UPDATE OF log_records"
+    );
+    query_as::<_, DbLogRecord>(&sql)
+        .bind(id)
+        .fetch_one(executor)
+        .await
+}
+
+/// Reads every active log record
+pub async fn list(executor: impl PgExecutor<'_>) -> Result<Vec<DbLogRecord>, sqlx::Error> {
+    let sql = format!("{SELECT} WHERE log_records.deleted_at IS NULL");
+    query_as::<_, DbLogRecord>(&sql).fetch_all(executor).await
+}
+
+/// Reads the active log records of an organisation's active vehicles
+pub async fn list_by_organisation(
+    executor: impl PgExecutor<'_>,
+    organisation_id: &Uuid,
+) -> Result<Vec<DbLogRecord>, sqlx::Error> {
+    let sql = format!(
+        "{SELECT}
+        JOIN vehicles ON vehicles.id = log_records.vehicle_id
+        WHERE vehicles.organisation_id = $1
+            AND vehicles.deleted_at IS NULL
+            AND log_records.deleted_at IS NULL"
+    );
+    query_as::<_, DbLogRecord>(&sql)
+        .bind(organisation_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// Writes the details of a log record to the table of its type
+async fn insert_details(
+    conn: &mut PgConnection,
+    id: &Uuid,
+    log_type: &LogType,
+) -> Result<(), sqlx::Error> {
+    let statement: sqlx::query::Query<'_, sqlx::Postgres, PgArguments> = match log_type {
+        LogType::FuelUp { fuel_amount } => {
+            query("INSERT INTO fuel_ups (log_record_id, fuel_amount) VALUES ($1, $2)")
+                .bind(id)
+                .bind(fuel_amount)
+        }
+        LogType::TireChange {
+            rotation,
+            tire_type,
+            new,
+        } => query(
+            "INSERT INTO tire_changes (log_record_id, tire_type, new_tires, tire_rotation_type)
+            VALUES ($1, $2, $3, $4)",
+        )
+        .bind(id)
+        .bind(tire_type)
+        .bind(new)
+        .bind(rotation),
+        LogType::BrakeReplacement {
+            location,
+            component,
+        } => query(
+            "INSERT INTO brake_replacements (log_record_id, brake_location, brake_part)
+            VALUES ($1, $2, $3)",
+        )
+        .bind(id)
+        .bind(location)
+        .bind(component),
+        LogType::TireRotation(rotation_type) => {
+            query("INSERT INTO tire_rotations (log_record_id, tire_rotation_type) VALUES ($1, $2)")
+                .bind(id)
+                .bind(rotation_type)
+        }
+        LogType::Fluids(fluid_type) => {
+            query("INSERT INTO fluid_changes (log_record_id, fluid_type) VALUES ($1, $2)")
+                .bind(id)
+                .bind(fluid_type)
+        }
+        LogType::OilChange
+        | LogType::Repair
+        | LogType::WiperBladeReplacement
+        | LogType::BatteryReplacement => return Ok(()),
+    };
+    statement.execute(conn).await?;
+    Ok(())
+}
+
+/// Writes a new log record and its details. Run inside a transaction, so
+/// that a failure leaves no record without its details.
+pub async fn insert(conn: &mut PgConnection, log_record: &DbLogRecord) -> Result<(), sqlx::Error> {
+    let sql = "
+        INSERT INTO log_records (id, vehicle_id, log_date, odometer, log_type, notes)
+        VALUES ($1, $2, $3, $4, $5, $6)";
+    query(sql)
+        .bind(log_record.id)
+        .bind(log_record.vehicle_id)
+        .bind(log_record.date)
+        .bind(log_record.odometer)
+        .bind(log_record.log_type())
+        .bind(&log_record.notes)
+        .execute(&mut *conn)
+        .await?;
+    insert_details(conn, &log_record.id, &log_record.log_type).await
+}
+
+/// Writes a new log record of an active vehicle unless it duplicates an
+/// active one and `allow_duplicate` is unset
+pub async fn create(
+    conn: &mut PgConnection,
+    log_record: &DbLogRecord,
+    allow_duplicate: bool,
+) -> Result<Uuid, ApiError> {
+    let mut tx = conn.begin().await?;
+    ensure_vehicle(&mut *tx, &log_record.vehicle_id).await?;
+    if !allow_duplicate {
+        // Serialise creates per vehicle so that double-taps cannot both pass the check
+        query("SELECT pg_advisory_xact_lock(hashtext($1::text))")
+            .bind(log_record.vehicle_id)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(existing_id) = find_duplicate(&mut *tx, log_record).await? {
+            tracing::info!(%existing_id, "log record looks like a duplicate");
+            return Err(ApiError::DuplicateLogRecord { existing_id });
+        }
+    }
+
+    insert(&mut tx, log_record).await?;
+    tx.commit().await?;
+
+    Ok(log_record.id)
+}
+
+/// Finds the earliest active log record for the same vehicle with the same
+/// type and odometer reading, dated within the duplicate window. Records
+/// sharing a date are ordered by insertion.
+async fn find_duplicate(
+    executor: impl PgExecutor<'_>,
+    log_record: &DbLogRecord,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let sql = "
+        SELECT id FROM log_records
+        WHERE vehicle_id = $1
+            AND log_type = $2
+            AND odometer = $3
+            AND log_date BETWEEN $4 - make_interval(hours => $5) AND $4 + make_interval(hours => $5)
+            AND deleted_at IS NULL
+        ORDER BY log_date, created_seq
+        LIMIT 1";
+    query_scalar::<_, Uuid>(sql)
+        .bind(log_record.vehicle_id)
+        .bind(log_record.log_type())
+        .bind(log_record.odometer)
+        .bind(log_record.date)
+        .bind(DUPLICATE_WINDOW_HOURS)
+        .fetch_optional(executor)
+        .await
+}
+
+/// Appends the log record's current state to its revision history
+async fn record_revision(
+    executor: impl PgExecutor<'_>,
+    previous: DbLogRecord,
+    action: RevisionAction,
+    changed_by: Option<Uuid>,
+) -> Result<(), ApiError> {
+    let previous = ReadLogRecordResponse::try_from(previous)?;
+    let snapshot =
+        serde_json::to_value(&previous).map_err(|e| ApiError::Conversion(e.to_string()))?;
+    // Unlike now(), the clock moves on within a transaction, which keeps the
+    // revisions of a batch in the order they were made
+    let sql = "
+        INSERT INTO log_record_revisions(log_record_id, action, changed_by, previous, changed_at)
+        VALUES ($1, $2, $3, $4, clock_timestamp())";
+    query(sql)
+        .bind(previous.id)
+        .bind(action)
+        .bind(changed_by)
+        .bind(snapshot)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Overwrites an active log record, keeping its previous state in the
+/// revision history
+pub async fn update(
+    conn: &mut PgConnection,
+    log_record: &DbLogRecord,
+    changed_by: Option<Uuid>,
+    if_match: Option<&EntityTags>,
+) -> Result<DbLogRecord, ApiError> {
+    let mut tx = conn.begin().await?;
+    let existing = find_for_update(&mut *tx, &log_record.id).await?;
+    if let Some(if_match) = if_match {
+        if_match.ensure_matches(existing.version)?;
+    }
+    let previous_type = existing.log_type();
+    if previous_type != log_record.log_type() {
+        tracing::debug!(%previous_type, "converting log record to a new type");
+    }
+    record_revision(&mut *tx, existing, RevisionAction::Update, changed_by).await?;
+
+    let updated = write(&mut tx, previous_type, log_record).await?;
+    tx.commit().await?;
+
+    Ok(updated)
+}
+
+/// Overwrites a log record locked by the caller, replacing its details of
+/// `previous_type` with those of its new type
+async fn write(
+    conn: &mut PgConnection,
+    previous_type: LogTypeName,
+    log_record: &DbLogRecord,
+) -> Result<DbLogRecord, sqlx::Error> {
+    if let Some(table) = details_table(previous_type) {
+        let sql = format!("DELETE FROM {table} WHERE log_record_id = $1");
+        query(&sql).bind(log_record.id).execute(&mut *conn).await?;
+    }
+
+    let sql = "
+        UPDATE log_records
+        SET log_date = $1, odometer = $2, log_type = $3, notes = $4
+        WHERE id = $5";
+    query(sql)
+        .bind(log_record.date)
+        .bind(log_record.odometer)
+        .bind(log_record.log_type())
+        .bind(&log_record.notes)
+        .bind(log_record.id)
+        .execute(&mut *conn)
+        .await?;
+    insert_details(conn, &log_record.id, &log_record.log_type).await?;
+
+    find(conn, &log_record.id).await
+}
+
+/// Soft deletes an active log record, keeping its previous state in the
+/// revision history
+pub async fn delete(
+    conn: &mut PgConnection,
+    id: &Uuid,
+    changed_by: Option<Uuid>,
+    if_match: Option<&EntityTags>,
+) -> Result<(), ApiError> {
+    let mut tx = conn.begin().await?;
+    let existing = find_for_update(&mut *tx, id).await?;
+    if let Some(if_match) = if_match {
+        if_match.ensure_matches(existing.version)?;
+    }
+    record_revision(&mut *tx, existing, RevisionAction::Delete, changed_by).await?;
+
+    let sql = "UPDATE log_records SET deleted_at = now() WHERE id = $1";
+    query(sql).bind(id).execute(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Undoes the soft deletion of a log record whose vehicle is active
+pub async fn restore(conn: &mut PgConnection, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+    let sql = "
+        SELECT vehicles.deleted_at IS NULL AS vehicle_active
+        FROM log_records
+        JOIN vehicles ON vehicles.id = log_records.vehicle_id
+        WHERE log_records.id = $1 AND log_records.deleted_at IS NOT NULL";
+    let vehicle_active = query_scalar::<_, bool>(sql)
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !vehicle_active {
+        return Err(ApiError::Conflict(
+            "the log record's vehicle is deleted and must be restored first".to_owned(),
+        ));
+    }
+
+    let sql = "UPDATE log_records SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL";
+    let res = query(sql).bind(id).execute(&mut *conn).await?;
+    if res.rows_affected() == 0 {
+        return Err(ApiError::ResourceNotFound);
+    }
+
+    Ok(find(conn, id).await?)
+}
+
+/// Permanently deletes a soft deleted log record
+pub async fn purge(conn: &mut PgConnection, id: &Uuid) -> Result<(), ApiError> {
+    let sql = "SELECT deleted_at IS NOT NULL AS deleted FROM log_records WHERE id = $1";
+    let deleted = query_scalar::<_, bool>(sql)
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !deleted {
+        return Err(ApiError::Conflict(
+            "log record must be deleted before it can be purged".to_owned(),
+        ));
+    }
+
+    let sql = "DELETE FROM log_records WHERE id = $1 AND deleted_at IS NOT NULL";
+    query(sql).bind(id).execute(conn).await?;
+
+    Ok(())
+}
+
+/// Counts the active log records of each type
+pub async fn count_by_type(
+    executor: impl PgExecutor<'_>,
+) -> Result<Vec<(LogTypeName, i64)>, sqlx::Error> {
+    let sql = "
+        SELECT log_type, count(*) FROM log_records
+        WHERE deleted_at IS NULL
+        GROUP BY log_type";
+    query_as::<_, (LogTypeName, i64)>(sql)
+        .fetch_all(executor)
+        .await
+}
+
+/// Reads the active log records suspected to duplicate an earlier one
+pub async fn duplicates(
+    executor: impl PgExecutor<'_>,
+    vehicle_id: Option<Uuid>,
+) -> Result<Vec<DbLogRecordDuplicate>, sqlx::Error> {
+    // Each record is paired with the earliest record it duplicates, so the
+    // original of a group of duplicates is never reported itself. Records
+    // sharing a date are ordered by insertion.
+    let sql = "
+        SELECT
+            d.id AS log_record_id,
+            o.id AS duplicate_of,
+            d.vehicle_id,
+            d.log_type,
+            d.odometer,
+            d.log_date
+        FROM log_records d
+        JOIN LATERAL (
+            SELECT id FROM log_records o
+            WHERE o.vehicle_id = d.vehicle_id
+                AND o.log_type = d.log_type
+                AND o.odometer = d.odometer
+                AND o.log_date >= d.log_date - make_interval(hours => $2)
+                AND (o.log_date, o.created_seq) < (d.log_date, d.created_seq)
+                AND o.deleted_at IS NULL
+            ORDER BY o.log_date, o.created_seq
+            LIMIT 1
+        ) o ON true
+        WHERE d.deleted_at IS NULL
+            AND ($1::uuid IS NULL OR d.vehicle_id = $1)
+        ORDER BY d.vehicle_id, d.log_date, d.created_seq";
+    query_as::<_, DbLogRecordDuplicate>(sql)
+        .bind(vehicle_id)
+        .bind(DUPLICATE_WINDOW_HOURS)
+        .fetch_all(executor)
+        .await
+}
+
+/// Reads the revisions of a log record, which may be soft deleted
+pub async fn history(
+    conn: &mut PgConnection,
+    id: &Uuid,
+) -> Result<Vec<DbLogRecordRevision>, sqlx::Error> {
+    let sql = "SELECT id FROM log_records WHERE id = $1";
+    query(sql).bind(id).fetch_one(&mut *conn).await?;
+
+    let sql = "
+        SELECT * FROM log_record_revisions
+        WHERE log_record_id = $1
+        ORDER BY changed_at, id";
+    query_as::<_, DbLogRecordRevision>(sql)
+        .bind(id)
+        .fetch_all(conn)
+        .await
+}
+
+#[async_trait]
+impl LogRecordRepository for PgPool {
+    async fn find(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        Ok(find(self, id).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbLogRecord>, ApiError> {
+        Ok(list(self).await?)
+    }
+
+    async fn insert(
+        &self,
+        log_record: &DbLogRecord,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, ApiError> {
+        create(&mut *self.acquire().await?, log_record, allow_duplicate).await
+    }
+
+    async fn update(
+        &self,
+        log_record: &DbLogRecord,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbLogRecord, ApiError> {
+        update(
+            &mut *self.acquire().await?,
+            log_record,
+            changed_by,
+            if_match,
+        )
+        .await
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        delete(&mut *self.acquire().await?, id, changed_by, if_match).await
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        restore(&mut *self.acquire().await?, id).await
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        purge(&mut *self.acquire().await?, id).await
+    }
+
+    async fn count_by_type(&self) -> Result<Vec<(LogTypeName, i64)>, ApiError> {
+        Ok(count_by_type(self).await?)
+    }
+
+    async fn duplicates(
+        &self,
+        vehicle_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecordDuplicate>, ApiError> {
+        Ok(duplicates(self, vehicle_id).await?)
+    }
+
+    async fn history(&self, id: &Uuid) -> Result<Vec<DbLogRecordRevision>, ApiError> {
+        Ok(history(&mut *self.acquire().await?, id).await?)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn LogRecordBatch>, ApiError> {
+        Ok(Box::new(PgBatch(Mutex::new(Acquire::begin(self).await?))))
+    }
+}
+
+/// A batch of log record writes made in one transaction. Each write opens a
+/// savepoint of its own, so a failed one is rolled back by itself.
+#[derive(Debug)]
+pub struct PgBatch(Mutex<Transaction<'static, Postgres>>);
+
+#[async_trait]
+impl LogRecordRepository for PgBatch {
+    async fn find(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        Ok(find(&mut **self.0.lock().await, id).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbLogRecord>, ApiError> {
+        Ok(list(&mut **self.0.lock().await).await?)
+    }
+
+    async fn insert(
+        &self,
+        log_record: &DbLogRecord,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, ApiError> {
+        create(&mut **self.0.lock().await, log_record, allow_duplicate).await
+    }
+
+    async fn update(
+        &self,
+        log_record: &DbLogRecord,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbLogRecord, ApiError> {
+        update(&mut **self.0.lock().await, log_record, changed_by, if_match).await
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        delete(&mut **self.0.lock().await, id, changed_by, if_match).await
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        restore(&mut **self.0.lock().await, id).await
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        purge(&mut **self.0.lock().await, id).await
+    }
+
+    async fn count_by_type(&self) -> Result<Vec<(LogTypeName, i64)>, ApiError> {
+        Ok(count_by_type(&mut **self.0.lock().await).await?)
+    }
+
+    async fn duplicates(
+        &self,
+        vehicle_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecordDuplicate>, ApiError> {
+        Ok(duplicates(&mut **self.0.lock().await, vehicle_id).await?)
+    }
+
+    async fn history(&self, id: &Uuid) -> Result<Vec<DbLogRecordRevision>, ApiError> {
+        Ok(history(&mut **self.0.lock().await, id).await?)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn LogRecordBatch>, ApiError> {
+        Err(ApiError::Unsupported(
+            "log record batches can't be nested".to_owned(),
+        ))
+    }
+}
+
+#[async_trait]
+impl LogRecordBatch for PgBatch {
+    async fn commit(self: Box<Self>) -> Result<(), ApiError> {
+        Ok(self.0.into_inner().commit().await?)
+    }
+
+    fn log_records(&self) -> &dyn LogRecordRepository {
+        self
+    }
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+    use crate::utils::test_utils::db::seed_user_and_vehicle;
+    use chrono::{DateTime, SubsecRound, Utc};
+    use fake::{Fake, Faker};
+    use sqlx::PgPool;
+
+    async fn seed(pool: &PgPool, log_type: LogType) -> DbLogRecord {
+        let vehicle_id = seed_user_and_vehicle(pool).await;
+        // Dates are stored with microsecond precision, and rounding a faked
+        // date may carry it into the next millisecond
+        let log_record = DbLogRecord {
+            vehicle_id,
+            log_type,
+            date: Faker.fake::<DateTime<Utc>>().trunc_subsecs(6),
+            ..Faker.fake()
+        };
+        let mut tx = pool.begin().await.expect("could not begin transaction");
+        insert(&mut tx, &log_record)
+            .await
+            .expect("could not insert log record");
+        tx.commit().await.expect("could not commit");
+        log_record
+    }
+
+    async fn count_details(pool: &PgPool, table: &str, id: &Uuid) -> i64 {
+        let sql = format!("SELECT count(*) FROM {table} WHERE log_record_id = $1");
+        query_scalar(&sql)
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .expect("could not count details")
+    }
+
+    #[sqlx::test]
+    async fn insert_then_find_every_log_type(pool: PgPool) {
+        for _ in 0..20 {
+            // Arrange
+            let log_record = seed(&pool, Faker.fake()).await;
+
+            // Act
+            let found = find(&pool, &log_record.id)
+                .await
+                .expect("could not find log record");
+
+            // Assert
+            assert_eq!(found, log_record);
+        }
+    }
+
+    #[sqlx::test]
+    async fn insert_writes_details_table(pool: PgPool) {
+        // Arrange
+        let log_record = seed(
+            &pool,
+            LogType::TireChange {
+                rotation: None,
+                tire_type: Faker.fake(),
+                new: true,
+            },
+        )
+        .await;
+
+        // Act
+        let tire_changes = count_details(&pool, "tire_changes", &log_record.id).await;
+        let tire_rotations = count_details(&pool, "tire_rotations", &log_record.id).await;
+
+        // Assert
+        assert_eq!(tire_changes, 1);
+        assert_eq!(tire_rotations, 0);
+    }
+
+    #[sqlx::test]
+    async fn update_moves_details_to_new_type(pool: PgPool) {
+        // Arrange
+        let log_record = seed(&pool, LogType::FuelUp { fuel_amount: 30.0 }).await;
+        let changed = DbLogRecord {
+            log_type: LogType::Fluids(Faker.fake()),
+            version: 2,
+            ..log_record.clone()
+        };
+
+        // Act
+        let mut conn = pool.acquire().await.expect("could not acquire connection");
+        let updated = update(&mut conn, &changed, None, None)
+            .await
+            .expect("could not update log record");
+
+        // Assert
+        assert_eq!(updated, changed);
+        assert_eq!(count_details(&pool, "fuel_ups", &log_record.id).await, 0);
+        assert_eq!(
+            count_details(&pool, "fluid_changes", &log_record.id).await,
+            1
+        );
+    }
+
+    #[sqlx::test]
+    async fn details_must_match_log_type(pool: PgPool) {
+        // Arrange
+        let log_record = seed(&pool, LogType::OilChange).await;
+
+        // Act
+        let res = query("INSERT INTO fuel_ups (log_record_id, fuel_amount) VALUES ($1, 10)")
+            .bind(log_record.id)
+            .execute(&pool)
+            .await;
+
+        // Assert
+        let err = res.expect_err("details of another type should be rejected");
+        assert!(err
+            .as_database_error()
+            .is_some_and(|e| e.is_foreign_key_violation()));
+    }
+
+    #[sqlx::test]
+    async fn record_without_details_fails_on_commit(pool: PgPool) {
+        // Arrange
+        let vehicle_id = seed_user_and_vehicle(&pool).await;
+        let log_record = DbLogRecord {
+            vehicle_id,
+            log_type: LogType::FuelUp { fuel_amount: 10.0 },
+            ..Faker.fake()
+        };
+        let mut tx = pool.begin().await.expect("could not begin transaction");
+
+        // Act
+        query("INSERT INTO log_records (id, vehicle_id, log_date, odometer, log_type) VALUES ($1, $2, $3, $4, $5)")
+            .bind(log_record.id)
+            .bind(log_record.vehicle_id)
+            .bind(log_record.date)
+            .bind(log_record.odometer)
+            .bind(log_record.log_type())
+            .execute(&mut *tx)
+            .await
+            .expect("the check should be deferred until commit");
+        let res = tx.commit().await;
+
+        // Assert
+        let err = res.expect_err("a record without its details should be rejected");
+        assert!(err
+            .as_database_error()
+            .is_some_and(|e| e.constraint() == Some("log_records_details")));
+        assert!(matches!(
+            find(&pool, &log_record.id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn details_cannot_be_removed_from_record(pool: PgPool) {
+        // Arrange
+        let log_record = seed(&pool, LogType::FuelUp { fuel_amount: 10.0 }).await;
+
+        // Act
+        let res = query("DELETE FROM fuel_ups WHERE log_record_id = $1")
+            .bind(log_record.id)
+            .execute(&pool)
+            .await;
+
+        // Assert
+        res.expect_err("removing the details should be rejected");
+        assert_eq!(count_details(&pool, "fuel_ups", &log_record.id).await, 1);
+    }
+
+    #[sqlx::test]
+    async fn restore_requires_deleted_record(pool: PgPool) {
+        // Arrange
+        let log_record = seed(&pool, Faker.fake()).await;
+
+        // Act
+        let mut conn = pool.acquire().await.expect("could not acquire connection");
+        let res = restore(&mut conn, &log_record.id).await;
+
+        // Assert
+        assert!(matches!(res, Err(ApiError::ResourceNotFound)));
+    }
+}
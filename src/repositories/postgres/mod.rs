@@ -0,0 +1,37 @@
+pub mod audit;
+pub mod health;
+pub mod idempotency;
+pub mod log_record;
+pub mod organisation;
+pub mod sync;
+pub mod user;
+pub mod vehicle;
+
+use sqlx::{query, PgExecutor, Row};
+use uuid::Uuid;
+
+use crate::{error::ApiError, types::EntityTags};
+
+/// Checks the `If-Match` tags of a write against the current version of an
+/// active row, locking the row for the rest of the transaction. Writes
+/// without preconditions are always allowed.
+#[tracing::instrument(name = "postgres_check_version", skip(executor), err)]
+async fn check_version(
+    executor: impl PgExecutor<'_>,
+    table: &'static str,
+    id: &Uuid,
+    if_match: Option<&EntityTags>,
+) -> Result<(), ApiError> {
+    let Some(if_match) = if_match else {
+        return Ok(());
+    };
+
+    let sql =
+        format!("SELECT version FROM {table} WHERE id = $1 AND deleted_at IS NULL FOR UPDATE");
+    let version = query(&sql)
+        .bind(id)
+        .fetch_one(executor)
+        .await?
+        .try_get::<i64, _>("version")?;
+    if_match.ensure_matches(version)
+}
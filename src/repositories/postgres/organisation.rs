@@ -0,0 +1,346 @@
+use axum::async_trait;
+use sqlx::{query, query_as, query_scalar, PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::db::{
+        LogRecord as DbLogRecord, Organisation as DbOrganisation,
+        OrganisationMember as DbOrganisationMember, Vehicle as DbVehicle,
+    },
+    repositories::{
+        postgres::{log_record, user::ensure_user},
+        OrganisationRepository,
+    },
+    types::{
+        permissions::{
+            ensure_can_add, ensure_can_change, ensure_can_remove, ensure_role, MANAGERS, MEMBERS,
+        },
+        MemberRole,
+    },
+};
+
+async fn find(executor: impl PgExecutor<'_>, id: &Uuid) -> Result<DbOrganisation, ApiError> {
+    let sql = "SELECT * FROM organisations WHERE id = $1";
+    Ok(query_as::<_, DbOrganisation>(sql)
+        .bind(id)
+        .fetch_one(executor)
+        .await?)
+}
+
+/// Reads the role of the acting user, locking their membership for the rest
+/// of the transaction. Anonymous requests hold no role.
+async fn actor_role(
+    executor: impl PgExecutor<'_>,
+    organisation_id: &Uuid,
+    actor_id: Option<Uuid>,
+) -> Result<Option<MemberRole>, ApiError> {
+    let Some(actor_id) = actor_id else {
+        tracing::debug!("anonymous request holds no organisation role");
+        return Ok(None);
+    };
+    let sql = "
+        SELECT role FROM organisation_members
+        WHERE organisation_id = $1 AND user_id = $2
+        FOR SHARE";
+    Ok(query_scalar::<_, MemberRole>(sql)
+        .bind(organisation_id)
+        .bind(actor_id)
+        .fetch_optional(executor)
+        .await?)
+}
+
+/// Reads the role of a member, locking their membership for the rest of the
+/// transaction
+async fn member_role(
+    executor: impl PgExecutor<'_>,
+    organisation_id: &Uuid,
+    user_id: &Uuid,
+) -> Result<Option<MemberRole>, ApiError> {
+    let sql = "
+        SELECT role FROM organisation_members
+        WHERE organisation_id = $1 AND user_id = $2
+        FOR UPDATE";
+    Ok(query_scalar::<_, MemberRole>(sql)
+        .bind(organisation_id)
+        .bind(user_id)
+        .fetch_optional(executor)
+        .await?)
+}
+
+/// Whether the organisation has an owner besides `user_id`. Locks the other
+/// owners, so concurrent removals of the last two can't both pass.
+async fn has_other_owner(
+    executor: impl PgExecutor<'_>,
+    organisation_id: &Uuid,
+    user_id: &Uuid,
+) -> Result<bool, ApiError> {
+    let sql = "
+        SELECT user_id FROM organisation_members
+        WHERE organisation_id = $1 AND user_id <> $2 AND role = $3
+        FOR UPDATE";
+    let other_owners = query_scalar::<_, Uuid>(sql)
+        .bind(organisation_id)
+        .bind(user_id)
+        .bind(MemberRole::Owner)
+        .fetch_all(executor)
+        .await?;
+    Ok(!other_owners.is_empty())
+}
+
+/// Ensure that the given user belongs to the organisation, as required of any
+/// user owning a vehicle within it. The membership can't be removed until
+/// the transaction ends.
+#[tracing::instrument(name = "organisation_repository_ensure_member", skip(executor), err)]
+pub async fn ensure_member(
+    executor: impl PgExecutor<'_>,
+    organisation_id: &Uuid,
+    user_id: &Uuid,
+) -> Result<(), ApiError> {
+    let sql = "
+        SELECT 1 FROM organisation_members
+        WHERE organisation_id = $1 AND user_id = $2
+        FOR SHARE";
+    let is_member = query(sql)
+        .bind(organisation_id)
+        .bind(user_id)
+        .fetch_optional(executor)
+        .await?
+        .is_some();
+
+    if is_member {
+        Ok(())
+    } else {
+        tracing::debug!("user is not a member of the organisation");
+        Err(ApiError::NotOrganisationMember)
+    }
+}
+
+/// Ensure that the acting user may move a vehicle from the organisation
+/// `from` to `to`, either of which may be none. They must be an owner or
+/// admin of each organisation the vehicle leaves or joins.
+#[tracing::instrument(name = "organisation_repository_ensure_can_move", skip(conn), err)]
+pub async fn ensure_can_move(
+    conn: &mut PgConnection,
+    from: Option<Uuid>,
+    to: Option<Uuid>,
+    actor_id: Option<Uuid>,
+) -> Result<(), ApiError> {
+    if from == to {
+        return Ok(());
+    }
+    for organisation_id in [from, to].into_iter().flatten() {
+        ensure_role(
+            actor_role(&mut *conn, &organisation_id, actor_id).await?,
+            MANAGERS,
+        )?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl OrganisationRepository for PgPool {
+    async fn find(&self, id: &Uuid) -> Result<DbOrganisation, ApiError> {
+        find(self, id).await
+    }
+
+    async fn list(&self) -> Result<Vec<DbOrganisation>, ApiError> {
+        let sql = "SELECT * FROM organisations";
+        Ok(query_as::<_, DbOrganisation>(sql).fetch_all(self).await?)
+    }
+
+    async fn insert(
+        &self,
+        organisation: &DbOrganisation,
+        owner_id: &Uuid,
+    ) -> Result<Uuid, ApiError> {
+        let mut tx = self.begin().await?;
+        ensure_user(&mut *tx, owner_id).await?;
+
+        let sql = "INSERT INTO organisations (name) VALUES ($1) RETURNING id";
+        let id = query_scalar::<_, Uuid>(sql)
+            .bind(&organisation.name)
+            .fetch_one(&mut *tx)
+            .await?;
+        let sql =
+            "INSERT INTO organisation_members (organisation_id, user_id, role) VALUES ($1, $2, $3)";
+        query(sql)
+            .bind(id)
+            .bind(owner_id)
+            .bind(MemberRole::Owner)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    async fn update(
+        &self,
+        organisation: &DbOrganisation,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisation, ApiError> {
+        let mut tx = self.begin().await?;
+        find(&mut *tx, &organisation.id).await?;
+        ensure_role(
+            actor_role(&mut *tx, &organisation.id, actor_id).await?,
+            MANAGERS,
+        )?;
+
+        let sql = "UPDATE organisations SET name = $1 WHERE id = $2 RETURNING *";
+        let updated_organisation = query_as::<_, DbOrganisation>(sql)
+            .bind(&organisation.name)
+            .bind(organisation.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(updated_organisation)
+    }
+
+    async fn delete(&self, id: &Uuid, actor_id: Option<Uuid>) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+        find(&mut *tx, id).await?;
+        ensure_role(
+            actor_role(&mut *tx, id, actor_id).await?,
+            &[MemberRole::Owner],
+        )?;
+
+        let sql = "DELETE FROM organisations WHERE id = $1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_members(
+        &self,
+        organisation_id: &Uuid,
+    ) -> Result<Vec<DbOrganisationMember>, ApiError> {
+        find(self, organisation_id).await?;
+        let sql = "SELECT * FROM organisation_members WHERE organisation_id = $1";
+        Ok(query_as::<_, DbOrganisationMember>(sql)
+            .bind(organisation_id)
+            .fetch_all(self)
+            .await?)
+    }
+
+    async fn add_member(
+        &self,
+        member: &DbOrganisationMember,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisationMember, ApiError> {
+        let mut tx = self.begin().await?;
+        find(&mut *tx, &member.organisation_id).await?;
+        ensure_can_add(
+            actor_role(&mut *tx, &member.organisation_id, actor_id).await?,
+            member.role,
+        )?;
+        ensure_user(&mut *tx, &member.user_id).await?;
+
+        let sql = "
+            INSERT INTO organisation_members (
+                organisation_id,
+                user_id,
+                role
+            ) VALUES (
+                $1,
+                $2,
+                $3
+            ) RETURNING *";
+        let member = query_as::<_, DbOrganisationMember>(sql)
+            .bind(member.organisation_id)
+            .bind(member.user_id)
+            .bind(member.role)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(member)
+    }
+
+    async fn update_member(
+        &self,
+        member: &DbOrganisationMember,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbOrganisationMember, ApiError> {
+        let organisation_id = &member.organisation_id;
+        let mut tx = self.begin().await?;
+        find(&mut *tx, organisation_id).await?;
+        let actor_role = actor_role(&mut *tx, organisation_id, actor_id).await?;
+        let role = member_role(&mut *tx, organisation_id, &member.user_id).await?;
+        let other_owner = has_other_owner(&mut *tx, organisation_id, &member.user_id).await?;
+        ensure_can_change(actor_role, role, member.role, other_owner)?;
+
+        let sql = "
+            UPDATE organisation_members
+            SET role = $1
+            WHERE organisation_id = $2 AND user_id = $3
+            RETURNING *";
+        let member = query_as::<_, DbOrganisationMember>(sql)
+            .bind(member.role)
+            .bind(organisation_id)
+            .bind(member.user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(member)
+    }
+
+    async fn remove_member(
+        &self,
+        organisation_id: &Uuid,
+        user_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+        find(&mut *tx, organisation_id).await?;
+        let actor_role = actor_role(&mut *tx, organisation_id, actor_id).await?;
+        let role = member_role(&mut *tx, organisation_id, user_id).await?;
+        let other_owner = has_other_owner(&mut *tx, organisation_id, user_id).await?;
+        let sql = "
+            SELECT id FROM vehicles
+            WHERE organisation_id = $1 AND owner_id = $2 AND deleted_at IS NULL";
+        let vehicle_ids = query_scalar::<_, Uuid>(sql)
+            .bind(organisation_id)
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        ensure_can_remove(
+            actor_id == Some(*user_id),
+            actor_role,
+            role,
+            other_owner,
+            vehicle_ids,
+        )?;
+
+        let sql = "DELETE FROM organisation_members WHERE organisation_id = $1 AND user_id = $2";
+        query(sql)
+            .bind(organisation_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_vehicles(
+        &self,
+        organisation_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<DbVehicle>, ApiError> {
+        find(self, organisation_id).await?;
+        ensure_role(actor_role(self, organisation_id, actor_id).await?, MEMBERS)?;
+        let sql = "SELECT * FROM vehicles WHERE organisation_id = $1 AND deleted_at IS NULL";
+        Ok(query_as::<_, DbVehicle>(sql)
+            .bind(organisation_id)
+            .fetch_all(self)
+            .await?)
+    }
+
+    async fn list_log_records(
+        &self,
+        organisation_id: &Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecord>, ApiError> {
+        find(self, organisation_id).await?;
+        ensure_role(actor_role(self, organisation_id, actor_id).await?, MEMBERS)?;
+        Ok(log_record::list_by_organisation(self, organisation_id).await?)
+    }
+}
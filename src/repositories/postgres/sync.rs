@@ -0,0 +1,110 @@
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, query, FromRow, PgConnection, PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::db::{LogRecord as DbLogRecord, User as DbUser, Vehicle as DbVehicle},
+    repositories::{postgres::log_record, ChangeSet, Changes, SyncRepository},
+    types::{AuditResource, SyncToken},
+};
+
+/// Matches rows written by transactions that were not yet visible in the
+/// snapshot bound to `$1`, or every row when no snapshot is bound
+const CHANGED_SINCE: &str = "($1::text IS NULL OR (
+    change_xid >= pg_snapshot_xmin($1::pg_snapshot)
+    AND NOT pg_visible_in_snapshot(change_xid, $1::pg_snapshot)
+))";
+
+#[async_trait]
+impl SyncRepository for PgPool {
+    async fn changes(&self, since: Option<&str>) -> Result<ChangeSet, ApiError> {
+        let since = since.map(SyncToken::parse).transpose()?;
+
+        // Every read, and the token handed out, must share one snapshot so that
+        // nothing committed in between is skipped by the next sync
+        let mut tx = self.begin().await?;
+        query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+        let token = query("SELECT pg_current_snapshot()::text AS token")
+            .fetch_one(&mut *tx)
+            .await?
+            .try_get::<String, _>("token")?;
+
+        let users = Changes {
+            changed: changed::<DbUser>(&mut tx, "SELECT * FROM users", since.as_ref()).await?,
+            deleted: deleted(&mut tx, "users", AuditResource::User, since.as_ref()).await?,
+        };
+        let vehicles = Changes {
+            changed: changed::<DbVehicle>(&mut tx, "SELECT * FROM vehicles", since.as_ref())
+                .await?,
+            deleted: deleted(&mut tx, "vehicles", AuditResource::Vehicle, since.as_ref()).await?,
+        };
+        let log_records = Changes {
+            changed: changed::<DbLogRecord>(&mut tx, log_record::SELECT, since.as_ref()).await?,
+            deleted: deleted(
+                &mut tx,
+                "log_records",
+                AuditResource::LogRecord,
+                since.as_ref(),
+            )
+            .await?,
+        };
+        tx.commit().await?;
+
+        Ok(ChangeSet {
+            token,
+            users,
+            vehicles,
+            log_records,
+        })
+    }
+}
+
+/// Reads the active rows selected by `select` written since the token
+async fn changed<T>(
+    conn: &mut PgConnection,
+    select: &'static str,
+    since: Option<&SyncToken>,
+) -> Result<Vec<(T, DateTime<Utc>)>, ApiError>
+where
+    T: for<'r> FromRow<'r, PgRow>,
+{
+    let sql = format!("{select} WHERE deleted_at IS NULL AND {CHANGED_SINCE}");
+    query(&sql)
+        .bind(since.map(SyncToken::as_str))
+        .fetch_all(conn)
+        .await?
+        .iter()
+        .map(|row| Ok((T::from_row(row)?, row.try_get("updated_at")?)))
+        .collect()
+}
+
+/// Reads the ids of rows of `table` deleted or purged since the token. A full
+/// sync has nothing to delete.
+async fn deleted(
+    conn: &mut PgConnection,
+    table: &'static str,
+    resource_type: AuditResource,
+    since: Option<&SyncToken>,
+) -> Result<Vec<Uuid>, ApiError> {
+    if since.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT id FROM {table} WHERE deleted_at IS NOT NULL AND {CHANGED_SINCE}
+        UNION
+        SELECT resource_id FROM purged_rows WHERE resource_type = $2 AND {CHANGED_SINCE}"
+    );
+    query(&sql)
+        .bind(since.map(SyncToken::as_str))
+        .bind(resource_type)
+        .fetch_all(conn)
+        .await?
+        .iter()
+        .map(|row| row.try_get::<Uuid, _>("id").map_err(Into::into))
+        .collect()
+}
@@ -0,0 +1,264 @@
+use axum::async_trait;
+use sqlx::{query, query_as, PgExecutor, PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{api::DeleteUserParams, db::User as DbUser},
+    repositories::{
+        postgres::{check_version, organisation::ensure_member},
+        UserRepository,
+    },
+    types::{DeletionMode, EntityTags},
+};
+
+/// Ensure that a user is active, keeping them so until the transaction ends
+pub async fn ensure_user(executor: impl PgExecutor<'_>, user_id: &Uuid) -> Result<(), ApiError> {
+    let sql = "SELECT id FROM users WHERE id = $1 AND deleted_at IS NULL FOR SHARE";
+    query(sql).bind(user_id).fetch_one(executor).await?;
+    Ok(())
+}
+
+#[async_trait]
+impl UserRepository for PgPool {
+    async fn find(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+        let sql = "SELECT * FROM users WHERE id = $1 AND deleted_at IS NULL";
+        Ok(query_as::<_, DbUser>(sql).bind(id).fetch_one(self).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbUser>, ApiError> {
+        let sql = "SELECT * FROM users WHERE deleted_at IS NULL";
+        Ok(query_as::<_, DbUser>(sql).fetch_all(self).await?)
+    }
+
+    async fn insert(&self, user: &DbUser) -> Result<Uuid, ApiError> {
+        let sql = "
+            INSERT INTO users (
+                first_name,
+                last_name,
+                username,
+                email
+            ) VALUES (
+                $1,
+                $2,
+                $3,
+                $4
+            ) RETURNING id";
+        let res = query(sql)
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(&user.username)
+            .bind(&user.email)
+            .fetch_one(self)
+            .await?;
+        Ok(res.try_get::<Uuid, _>("id")?)
+    }
+
+    async fn update(
+        &self,
+        user: &DbUser,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbUser, ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut *tx, "users", &user.id, if_match).await?;
+
+        let sql = "
+            UPDATE users
+            SET
+                first_name = $1,
+                last_name = $2,
+                username = $3,
+                email = $4
+            WHERE id = $5 AND deleted_at IS NULL
+            RETURNING *";
+        let updated_user = query_as::<_, DbUser>(sql)
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(&user.username)
+            .bind(&user.email)
+            .bind(user.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(updated_user)
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        params: &DeleteUserParams,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut *tx, "users", id, if_match).await?;
+
+        let sql = "SELECT id FROM vehicles WHERE owner_id = $1 AND deleted_at IS NULL";
+        let vehicle_ids = query(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<Uuid, _>("id"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !vehicle_ids.is_empty() {
+            match params.mode {
+                DeletionMode::Restrict => {
+                    tracing::error!(?vehicle_ids, "user still owns vehicles");
+                    return Err(ApiError::DependentResources {
+                        resource: "vehicles".to_owned(),
+                        ids: vehicle_ids,
+                    });
+                }
+                DeletionMode::Cascade => {
+                    // now() is fixed for the transaction, so cascaded rows share the
+                    // user's deletion timestamp and can be restored along with it
+                    tracing::debug!(?vehicle_ids, "deleting vehicles owned by user");
+                    let sql = "
+                        UPDATE log_records
+                        SET deleted_at = now()
+                        WHERE vehicle_id = ANY($1) AND deleted_at IS NULL";
+                    query(sql).bind(&vehicle_ids).execute(&mut *tx).await?;
+                    let sql = "UPDATE vehicles SET deleted_at = now() WHERE id = ANY($1)";
+                    query(sql).bind(&vehicle_ids).execute(&mut *tx).await?;
+                }
+                DeletionMode::Reassign => {
+                    let new_owner_id = params.reassign_to.ok_or_else(|| {
+                        ApiError::InvalidRequest(
+                            "reassign_to is required when reassigning vehicles".to_owned(),
+                        )
+                    })?;
+                    if new_owner_id == *id {
+                        return Err(ApiError::InvalidRequest(
+                            "vehicles can't be reassigned to the user being deleted".to_owned(),
+                        ));
+                    }
+                    // Keeps the new owner from being deleted until the vehicles are theirs
+                    let sql = "SELECT id FROM users WHERE id = $1 AND deleted_at IS NULL FOR SHARE";
+                    query(sql).bind(new_owner_id).fetch_one(&mut *tx).await?;
+
+                    // Fleet vehicles may only be handed to members of their organisation
+                    let sql = "
+                        SELECT DISTINCT organisation_id
+                        FROM vehicles
+                        WHERE id = ANY($1) AND organisation_id IS NOT NULL";
+                    let organisation_ids = query(sql)
+                        .bind(&vehicle_ids)
+                        .fetch_all(&mut *tx)
+                        .await?
+                        .iter()
+                        .map(|row| row.try_get::<Uuid, _>("organisation_id"))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    for organisation_id in organisation_ids {
+                        ensure_member(&mut *tx, &organisation_id, &new_owner_id).await?;
+                    }
+
+                    tracing::debug!(?vehicle_ids, %new_owner_id, "reassigning vehicles owned by user");
+                    let sql = "UPDATE vehicles SET owner_id = $1 WHERE id = ANY($2)";
+                    query(sql)
+                        .bind(new_owner_id)
+                        .bind(&vehicle_ids)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        let sql = "UPDATE users SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL";
+        let res = query(sql).bind(id).execute(&mut *tx).await?;
+        if res.rows_affected() < 1 {
+            tracing::error!("no user was deleted");
+            return Err(ApiError::ResourceNotFound);
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+        let mut tx = self.begin().await?;
+
+        // Restore vehicles and log records which were deleted along with the user
+        let sql = "
+            UPDATE log_records
+            SET deleted_at = NULL
+            WHERE vehicle_id IN (SELECT id FROM vehicles WHERE owner_id = $1)
+                AND deleted_at = (SELECT deleted_at FROM users WHERE id = $1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "
+            UPDATE vehicles
+            SET deleted_at = NULL
+            WHERE owner_id = $1 AND deleted_at = (SELECT deleted_at FROM users WHERE id = $1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+
+        let sql = "
+            UPDATE users
+            SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING *";
+        let restored_user = query_as::<_, DbUser>(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(restored_user)
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+
+        let sql = "SELECT deleted_at IS NOT NULL AS deleted FROM users WHERE id = $1 FOR UPDATE";
+        let deleted = query(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?
+            .try_get::<bool, _>("deleted")?;
+        if !deleted {
+            return Err(ApiError::Conflict(
+                "user must be deleted before it can be purged".to_owned(),
+            ));
+        }
+
+        let sql = "SELECT id FROM vehicles WHERE owner_id = $1 AND deleted_at IS NULL";
+        let vehicle_ids = query(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<Uuid, _>("id"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !vehicle_ids.is_empty() {
+            tracing::error!(?vehicle_ids, "user still owns vehicles");
+            return Err(ApiError::DependentResources {
+                resource: "vehicles".to_owned(),
+                ids: vehicle_ids,
+            });
+        }
+
+        let sql = "
+            DELETE FROM log_records
+            WHERE vehicle_id IN (SELECT id FROM vehicles WHERE owner_id = $1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "DELETE FROM vehicles WHERE owner_id = $1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "DELETE FROM users WHERE id = $1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn is_admin(&self, id: &Uuid) -> Result<bool, ApiError> {
+        let sql = "
+            SELECT EXISTS (
+                SELECT 1 FROM users WHERE id = $1 AND is_admin AND deleted_at IS NULL
+            )";
+        Ok(query(sql)
+            .bind(id)
+            .fetch_one(self)
+            .await?
+            .try_get::<bool, _>(0)?)
+    }
+}
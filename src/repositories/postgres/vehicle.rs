@@ -0,0 +1,258 @@
+use axum::async_trait;
+use sqlx::{query, query_as, query_scalar, PgExecutor, PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::db::Vehicle as DbVehicle,
+    repositories::{
+        postgres::{
+            check_version,
+            organisation::{ensure_can_move, ensure_member},
+            user::ensure_user,
+        },
+        VehicleRepository,
+    },
+    types::EntityTags,
+};
+
+/// Ensure that a vehicle is active, keeping it so until the transaction ends
+pub async fn ensure_vehicle(
+    executor: impl PgExecutor<'_>,
+    vehicle_id: &Uuid,
+) -> Result<(), ApiError> {
+    let sql = "SELECT id FROM vehicles WHERE id = $1 AND deleted_at IS NULL FOR SHARE";
+    query(sql).bind(vehicle_id).fetch_one(executor).await?;
+    Ok(())
+}
+
+#[async_trait]
+impl VehicleRepository for PgPool {
+    async fn find(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+        let sql = "SELECT * FROM vehicles WHERE id = $1 AND deleted_at IS NULL";
+        Ok(query_as::<_, DbVehicle>(sql)
+            .bind(id)
+            .fetch_one(self)
+            .await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbVehicle>, ApiError> {
+        let sql = "SELECT * FROM vehicles WHERE deleted_at IS NULL";
+        Ok(query_as::<_, DbVehicle>(sql).fetch_all(self).await?)
+    }
+
+    async fn insert(&self, vehicle: &DbVehicle, actor_id: Option<Uuid>) -> Result<Uuid, ApiError> {
+        let mut tx = self.begin().await?;
+        ensure_can_move(&mut tx, None, vehicle.organisation_id, actor_id).await?;
+        ensure_user(&mut *tx, &vehicle.owner_id).await?;
+        if let Some(organisation_id) = vehicle.organisation_id {
+            ensure_member(&mut *tx, &organisation_id, &vehicle.owner_id).await?;
+        }
+        let sql = "
+            INSERT INTO vehicles (
+                owner_id,
+                make,
+                model,
+                year,
+                odometer_unit,
+                organisation_id
+            ) VALUES (
+                $1,
+                $2,
+                $3,
+                $4,
+                $5,
+                $6
+            ) RETURNING id";
+        let res = query(sql)
+            .bind(vehicle.owner_id)
+            .bind(&vehicle.make)
+            .bind(&vehicle.model)
+            .bind(vehicle.year)
+            .bind(&vehicle.odometer_unit)
+            .bind(vehicle.organisation_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(res.try_get::<Uuid, _>("id")?)
+    }
+
+    async fn update(
+        &self,
+        vehicle: &DbVehicle,
+        if_match: Option<&EntityTags>,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbVehicle, ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut *tx, "vehicles", &vehicle.id, if_match).await?;
+
+        // Ownership can't be changed by an update, so check the stored owner
+        let sql = "
+            SELECT owner_id, organisation_id FROM vehicles
+            WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE";
+        let (owner_id, stored_organisation_id) = query_as::<_, (Uuid, Option<Uuid>)>(sql)
+            .bind(vehicle.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        ensure_can_move(
+            &mut tx,
+            stored_organisation_id,
+            vehicle.organisation_id,
+            actor_id,
+        )
+        .await?;
+        if let Some(organisation_id) = vehicle.organisation_id {
+            ensure_member(&mut *tx, &organisation_id, &owner_id).await?;
+        }
+        let sql = "
+            UPDATE vehicles
+            SET
+                make = $1,
+                model = $2,
+                year = $3,
+                odometer_unit = $4,
+                organisation_id = $5
+            WHERE id = $6 AND deleted_at IS NULL
+            RETURNING *";
+        let updated_vehicle = query_as::<_, DbVehicle>(sql)
+            .bind(&vehicle.make)
+            .bind(&vehicle.model)
+            .bind(vehicle.year)
+            .bind(&vehicle.odometer_unit)
+            .bind(vehicle.organisation_id)
+            .bind(vehicle.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(updated_vehicle)
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        cascade: bool,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut *tx, "vehicles", id, if_match).await?;
+
+        let sql = "SELECT id FROM log_records WHERE vehicle_id = $1 AND deleted_at IS NULL";
+        let log_record_ids = query(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<Uuid, _>("id"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !log_record_ids.is_empty() {
+            if cascade {
+                // now() is fixed for the transaction, so cascaded rows share the
+                // vehicle's deletion timestamp and can be restored along with it
+                tracing::debug!("deleting {} log records of vehicle", log_record_ids.len());
+                let sql = "UPDATE log_records SET deleted_at = now() WHERE id = ANY($1)";
+                query(sql).bind(&log_record_ids).execute(&mut *tx).await?;
+            } else {
+                tracing::error!("vehicle still has log records");
+                return Err(ApiError::DependentResources {
+                    resource: "log_records".to_owned(),
+                    ids: log_record_ids,
+                });
+            }
+        }
+
+        let sql = "
+            UPDATE vehicles
+            SET deleted_at = now()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id";
+        query(sql).bind(id).fetch_one(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+        let mut tx = self.begin().await?;
+
+        let sql = "
+            SELECT users.deleted_at IS NULL AS owner_active
+            FROM vehicles
+            JOIN users ON users.id = vehicles.owner_id
+            WHERE vehicles.id = $1 AND vehicles.deleted_at IS NOT NULL";
+        let owner_active = query(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?
+            .try_get::<bool, _>("owner_active")?;
+        if !owner_active {
+            return Err(ApiError::Conflict(
+                "the vehicle's owner is deleted and must be restored first".to_owned(),
+            ));
+        }
+
+        // Restore log records which were deleted along with the vehicle
+        let sql = "
+            UPDATE log_records
+            SET deleted_at = NULL
+            WHERE vehicle_id = $1
+                AND deleted_at = (SELECT deleted_at FROM vehicles WHERE id = $1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+
+        let sql = "UPDATE vehicles SET deleted_at = NULL WHERE id = $1 RETURNING *";
+        let restored_vehicle = query_as::<_, DbVehicle>(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(restored_vehicle)
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+
+        let sql = "SELECT deleted_at IS NOT NULL AS deleted FROM vehicles WHERE id = $1 FOR UPDATE";
+        let deleted = query(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?
+            .try_get::<bool, _>("deleted")?;
+        if !deleted {
+            return Err(ApiError::Conflict(
+                "vehicle must be deleted before it can be purged".to_owned(),
+            ));
+        }
+
+        let sql = "SELECT id FROM log_records WHERE vehicle_id = $1 AND deleted_at IS NULL";
+        let log_record_ids = query(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<Uuid, _>("id"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !log_record_ids.is_empty() {
+            tracing::error!("vehicle still has log records");
+            return Err(ApiError::DependentResources {
+                resource: "log_records".to_owned(),
+                ids: log_record_ids,
+            });
+        }
+
+        let sql = "DELETE FROM log_records WHERE vehicle_id = $1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "DELETE FROM vehicles WHERE id = $1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<i64, ApiError> {
+        let sql = "SELECT count(*) FROM vehicles WHERE deleted_at IS NULL";
+        Ok(query_scalar::<_, i64>(sql).fetch_one(self).await?)
+    }
+}
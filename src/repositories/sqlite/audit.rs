@@ -0,0 +1,64 @@
+use axum::async_trait;
+use sqlx::{query, QueryBuilder, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{api::ListAuditEntriesParams, db::AuditEntry as DbAuditEntry},
+    repositories::{sqlite::now, AuditRepository},
+    types::{AuditAction, AuditResource},
+};
+
+#[async_trait]
+impl AuditRepository for SqlitePool {
+    async fn record(
+        &self,
+        actor_id: Option<Uuid>,
+        resource_type: AuditResource,
+        resource_id: Option<Uuid>,
+        action: AuditAction,
+        request_id: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let sql = "
+            INSERT INTO audit_log(id, actor_id, resource_type, resource_id, action, request_id, occurred_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)";
+        query(sql)
+            .bind(Uuid::new_v4())
+            .bind(actor_id)
+            .bind(resource_type)
+            .bind(resource_id)
+            .bind(action)
+            .bind(request_id)
+            .bind(now())
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn list(&self, params: &ListAuditEntriesParams) -> Result<Vec<DbAuditEntry>, ApiError> {
+        let mut qb = QueryBuilder::<Sqlite>::new("SELECT * FROM audit_log WHERE true");
+        if let Some(actor_id) = params.actor_id {
+            qb.push(" AND actor_id = ").push_bind(actor_id);
+        }
+        if let Some(resource_type) = params.resource_type {
+            qb.push(" AND resource_type = ").push_bind(resource_type);
+        }
+        if let Some(resource_id) = params.resource_id {
+            qb.push(" AND resource_id = ").push_bind(resource_id);
+        }
+        if let Some(action) = params.action {
+            qb.push(" AND action = ").push_bind(action);
+        }
+        if let Some(since) = params.since {
+            qb.push(" AND occurred_at >= ").push_bind(since);
+        }
+        if let Some(until) = params.until {
+            qb.push(" AND occurred_at < ").push_bind(until);
+        }
+        // Entries recorded within the same instant keep their order
+        qb.push(" ORDER BY occurred_at DESC, rowid DESC LIMIT ")
+            .push_bind(params.limit.unwrap_or(-1));
+
+        Ok(qb.build_query_as::<DbAuditEntry>().fetch_all(self).await?)
+    }
+}
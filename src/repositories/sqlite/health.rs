@@ -0,0 +1,32 @@
+use axum::async_trait;
+use sqlx::{query, query_scalar, SqlitePool};
+
+use crate::{
+    error::ApiError,
+    migrations,
+    repositories::{ensure_migrated, unreachable, HealthRepository},
+};
+
+#[async_trait]
+impl HealthRepository for SqlitePool {
+    async fn ready(&self) -> Result<(), ApiError> {
+        let mut conn = self.acquire().await.map_err(unreachable)?;
+        query("SELECT 1")
+            .execute(&mut *conn)
+            .await
+            .map_err(unreachable)?;
+
+        let sql = "SELECT version FROM _sqlx_migrations WHERE success ORDER BY version";
+        let applied = query_scalar::<_, i64>(sql)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::warn!(?e, "could not read applied migrations");
+                ApiError::Unavailable("migrations have not been applied".to_owned())
+            })?;
+        ensure_migrated(&migrations::pending_of(
+            &migrations::SQLITE_MIGRATOR,
+            &applied,
+        ))
+    }
+}
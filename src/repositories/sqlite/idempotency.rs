@@ -0,0 +1,117 @@
+use axum::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{query, query_as, query_scalar, SqlitePool};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    repositories::{
+        sqlite::now, Claim, IdempotencyRepository, CLAIM_LEASE_SECONDS, IDEMPOTENCY_KEY_TTL_HOURS,
+    },
+};
+
+/// Timestamps are bound rather than read from SQLite's clock, so that they
+/// compare as text in the format of every other timestamp
+#[async_trait]
+impl IdempotencyRepository for SqlitePool {
+    async fn claim(
+        &self,
+        scope: &'static str,
+        key: &str,
+        request: &serde_json::Value,
+    ) -> Result<Claim, ApiError> {
+        query("DELETE FROM idempotency_keys WHERE scope = ?1 AND key = ?2 AND created_at < ?3")
+            .bind(scope)
+            .bind(key)
+            .bind(now() - Duration::hours(IDEMPOTENCY_KEY_TTL_HOURS.into()))
+            .execute(self)
+            .await?;
+
+        let lease = Duration::milliseconds((CLAIM_LEASE_SECONDS * 1000.0) as i64);
+        // The key may be released between the upsert and the read, in which
+        // case it is free to claim again
+        loop {
+            let claimed_at = now();
+            let claimed = query_scalar::<_, DateTime<Utc>>(
+                "INSERT INTO idempotency_keys (scope, key, request, created_at, claimed_at)
+                VALUES (?1, ?2, ?3, ?4, ?4)
+                ON CONFLICT (scope, key) DO UPDATE
+                SET request = excluded.request, created_at = ?4, claimed_at = ?4
+                WHERE idempotency_keys.resource_id IS NULL
+                    AND idempotency_keys.claimed_at < ?5
+                RETURNING claimed_at",
+            )
+            .bind(scope)
+            .bind(key)
+            .bind(request)
+            .bind(claimed_at)
+            .bind(claimed_at - lease)
+            .fetch_optional(self)
+            .await?;
+            if let Some(claimed_at) = claimed {
+                return Ok(Claim::New(claimed_at));
+            }
+
+            let existing = query_as::<_, (Option<Uuid>, serde_json::Value)>(
+                "SELECT resource_id, request FROM idempotency_keys WHERE scope = ?1 AND key = ?2",
+            )
+            .bind(scope)
+            .bind(key)
+            .fetch_optional(self)
+            .await?;
+            let Some((resource_id, existing_request)) = existing else {
+                tracing::debug!("idempotency key was released while claiming it");
+                continue;
+            };
+
+            if existing_request != *request {
+                return Err(ApiError::Conflict(
+                    "the idempotency key was already used for a different request".to_owned(),
+                ));
+            }
+            return resource_id.map(Claim::Completed).ok_or_else(|| {
+                ApiError::Conflict(
+                    "a request with the same idempotency key is still in progress".to_owned(),
+                )
+            });
+        }
+    }
+
+    async fn complete(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+        resource_id: &Uuid,
+    ) -> Result<(), ApiError> {
+        let res = query(
+            "UPDATE idempotency_keys SET resource_id = ?4
+            WHERE scope = ?1 AND key = ?2 AND claimed_at = ?3",
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(claimed_at)
+        .bind(resource_id)
+        .execute(self)
+        .await?;
+        if res.rows_affected() < 1 {
+            tracing::warn!("idempotency key was taken over before the request completed");
+        }
+        Ok(())
+    }
+
+    async fn release(
+        &self,
+        scope: &'static str,
+        key: &str,
+        claimed_at: &DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        query("DELETE FROM idempotency_keys WHERE scope = ?1 AND key = ?2 AND claimed_at = ?3")
+            .bind(scope)
+            .bind(key)
+            .bind(claimed_at)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+use axum::async_trait;
+use chrono::Duration;
+use sqlx::{
+    query, query_as, query_scalar, Acquire, Sqlite, SqliteConnection, SqliteExecutor, SqlitePool,
+    Transaction,
+};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{
+        api::ReadLogRecordResponse,
+        db::{
+            LogRecord as DbLogRecord, LogRecordDuplicate as DbLogRecordDuplicate,
+            LogRecordRevision as DbLogRecordRevision,
+        },
+    },
+    repositories::{
+        sqlite::{check_version, now, vehicle::ensure_vehicle},
+        LogRecordBatch, LogRecordRepository, DUPLICATE_WINDOW_HOURS,
+    },
+    types::{EntityTags, LogTypeName, RevisionAction},
+};
+
+/// Reads an active log record
+async fn find(executor: impl SqliteExecutor<'_>, id: &Uuid) -> Result<DbLogRecord, sqlx::Error> {
+    let sql = "SELECT * FROM log_records WHERE id = ?1 AND deleted_at IS NULL";
+    query_as::<_, DbLogRecord>(sql)
+        .bind(id)
+        .fetch_one(executor)
+        .await
+}
+
+/// Reads every active log record
+async fn list(executor: impl SqliteExecutor<'_>) -> Result<Vec<DbLogRecord>, sqlx::Error> {
+    let sql = "SELECT * FROM log_records WHERE deleted_at IS NULL ORDER BY rowid";
+    query_as::<_, DbLogRecord>(sql).fetch_all(executor).await
+}
+
+/// Finds the earliest active log record for the same vehicle with the same
+/// type and odometer reading, dated within the duplicate window. Dates are
+/// stored as RFC 3339 text, which compares in chronological order, and
+/// records sharing a date are ordered by insertion.
+async fn find_duplicate(
+    conn: &mut SqliteConnection,
+    log_record: &DbLogRecord,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let window = Duration::hours(DUPLICATE_WINDOW_HOURS.into());
+    let sql = "
+        SELECT id FROM log_records
+        WHERE vehicle_id = ?1
+            AND log_type = ?2
+            AND odometer = ?3
+            AND log_date BETWEEN ?4 AND ?5
+            AND deleted_at IS NULL
+        ORDER BY log_date, rowid
+        LIMIT 1";
+    query_scalar::<_, Uuid>(sql)
+        .bind(log_record.vehicle_id)
+        .bind(log_record.log_type())
+        .bind(log_record.odometer)
+        .bind(log_record.date - window)
+        .bind(log_record.date + window)
+        .fetch_optional(conn)
+        .await
+}
+
+/// Writes a new log record of an active vehicle, unless it looks like a
+/// duplicate of an existing one and duplicates are not allowed
+async fn create(
+    conn: &mut SqliteConnection,
+    log_record: &DbLogRecord,
+    allow_duplicate: bool,
+) -> Result<Uuid, ApiError> {
+    let mut tx = conn.begin().await?;
+    ensure_vehicle(&mut *tx, &log_record.vehicle_id).await?;
+    if !allow_duplicate {
+        if let Some(existing_id) = find_duplicate(&mut tx, log_record).await? {
+            tracing::info!(%existing_id, "log record looks like a duplicate");
+            return Err(ApiError::DuplicateLogRecord { existing_id });
+        }
+    }
+
+    let sql = "
+        INSERT INTO log_records (
+            id,
+            vehicle_id,
+            log_date,
+            odometer,
+            log_type,
+            notes,
+            fuel_amount,
+            tire_rotation_type,
+            tire_type,
+            new_tires,
+            brake_location,
+            brake_part,
+            fluid_type
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)";
+    query(sql)
+        .bind(log_record.id)
+        .bind(log_record.vehicle_id)
+        .bind(log_record.date)
+        .bind(log_record.odometer)
+        .bind(log_record.log_type())
+        .bind(&log_record.notes)
+        .bind(log_record.fuel_amount())
+        .bind(log_record.tire_rotation_type())
+        .bind(log_record.tire_type())
+        .bind(log_record.new_tires())
+        .bind(log_record.brake_location())
+        .bind(log_record.brake_part())
+        .bind(log_record.fluid_type())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(log_record.id)
+}
+
+/// Appends the log record's current state to its revision history
+async fn record_revision(
+    executor: impl SqliteExecutor<'_>,
+    previous: DbLogRecord,
+    action: RevisionAction,
+    changed_by: Option<Uuid>,
+) -> Result<(), ApiError> {
+    let previous = ReadLogRecordResponse::try_from(previous)?;
+    let snapshot =
+        serde_json::to_value(&previous).map_err(|e| ApiError::Conversion(e.to_string()))?;
+    let sql = "
+        INSERT INTO log_record_revisions(id, log_record_id, action, changed_by, changed_at, previous)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+    query(sql)
+        .bind(Uuid::new_v4())
+        .bind(previous.id)
+        .bind(action)
+        .bind(changed_by)
+        .bind(now())
+        .bind(snapshot)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Overwrites an active log record, keeping its previous state in the
+/// revision history. The details of every log type share the log record's
+/// row, so columns of other types are written as `NULL`.
+async fn update(
+    conn: &mut SqliteConnection,
+    log_record: &DbLogRecord,
+    changed_by: Option<Uuid>,
+    if_match: Option<&EntityTags>,
+) -> Result<DbLogRecord, ApiError> {
+    let mut tx = conn.begin().await?;
+    check_version(&mut tx, "log_records", &log_record.id, if_match).await?;
+    let existing = find(&mut *tx, &log_record.id).await?;
+    record_revision(&mut *tx, existing, RevisionAction::Update, changed_by).await?;
+
+    let sql = "
+        UPDATE log_records
+        SET
+            log_date = ?1,
+            odometer = ?2,
+            log_type = ?3,
+            notes = ?4,
+            fuel_amount = ?5,
+            tire_rotation_type = ?6,
+            tire_type = ?7,
+            new_tires = ?8,
+            brake_location = ?9,
+            brake_part = ?10,
+            fluid_type = ?11,
+            version = version + 1
+        WHERE id = ?12 AND deleted_at IS NULL
+        RETURNING *";
+    let updated = query_as::<_, DbLogRecord>(sql)
+        .bind(log_record.date)
+        .bind(log_record.odometer)
+        .bind(log_record.log_type())
+        .bind(&log_record.notes)
+        .bind(log_record.fuel_amount())
+        .bind(log_record.tire_rotation_type())
+        .bind(log_record.tire_type())
+        .bind(log_record.new_tires())
+        .bind(log_record.brake_location())
+        .bind(log_record.brake_part())
+        .bind(log_record.fluid_type())
+        .bind(log_record.id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(updated)
+}
+
+/// Soft deletes an active log record, keeping its previous state in the
+/// revision history
+async fn delete(
+    conn: &mut SqliteConnection,
+    id: &Uuid,
+    changed_by: Option<Uuid>,
+    if_match: Option<&EntityTags>,
+) -> Result<(), ApiError> {
+    let mut tx = conn.begin().await?;
+    check_version(&mut tx, "log_records", id, if_match).await?;
+    let existing = find(&mut *tx, id).await?;
+    record_revision(&mut *tx, existing, RevisionAction::Delete, changed_by).await?;
+
+    let sql = "
+        UPDATE log_records
+        SET deleted_at = ?1, version = version + 1
+        WHERE id = ?2 AND deleted_at IS NULL";
+    query(sql).bind(now()).bind(id).execute(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Undoes the soft deletion of a log record whose vehicle is active
+async fn restore(conn: &mut SqliteConnection, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+    let mut tx = conn.begin().await?;
+
+    let sql = "
+        SELECT vehicles.deleted_at IS NULL
+        FROM log_records
+        JOIN vehicles ON vehicles.id = log_records.vehicle_id
+        WHERE log_records.id = ?1 AND log_records.deleted_at IS NOT NULL";
+    let vehicle_active = query_scalar::<_, bool>(sql)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !vehicle_active {
+        return Err(ApiError::Conflict(
+            "the log record's vehicle is deleted and must be restored first".to_owned(),
+        ));
+    }
+
+    let sql = "
+        UPDATE log_records
+        SET deleted_at = NULL, version = version + 1
+        WHERE id = ?1
+        RETURNING *";
+    let restored = query_as::<_, DbLogRecord>(sql)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(restored)
+}
+
+/// Permanently deletes a soft deleted log record along with its history
+async fn purge(conn: &mut SqliteConnection, id: &Uuid) -> Result<(), ApiError> {
+    let sql = "SELECT deleted_at IS NOT NULL FROM log_records WHERE id = ?1";
+    let deleted = query_scalar::<_, bool>(sql)
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !deleted {
+        return Err(ApiError::Conflict(
+            "log record must be deleted before it can be purged".to_owned(),
+        ));
+    }
+
+    let sql = "DELETE FROM log_records WHERE id = ?1 AND deleted_at IS NOT NULL";
+    query(sql).bind(id).execute(conn).await?;
+
+    Ok(())
+}
+
+/// Counts the active log records of each type
+async fn count_by_type(
+    executor: impl SqliteExecutor<'_>,
+) -> Result<Vec<(LogTypeName, i64)>, sqlx::Error> {
+    let sql = "
+        SELECT log_type, count(*) FROM log_records
+        WHERE deleted_at IS NULL
+        GROUP BY log_type";
+    query_as::<_, (LogTypeName, i64)>(sql)
+        .fetch_all(executor)
+        .await
+}
+
+/// Reads the active log records suspected to duplicate an earlier one. As
+/// SQLite can't subtract from RFC 3339 text, dates are compared as Julian
+/// days within the window, which is precise to the millisecond.
+async fn duplicates(
+    executor: impl SqliteExecutor<'_>,
+    vehicle_id: Option<Uuid>,
+) -> Result<Vec<DbLogRecordDuplicate>, sqlx::Error> {
+    // Each record is paired with the earliest record it duplicates, so the
+    // original of a group of duplicates is never reported itself. Records
+    // sharing a date are ordered by insertion.
+    let sql = "
+        SELECT * FROM (
+            SELECT
+                d.id AS log_record_id,
+                (
+                    SELECT o.id FROM log_records o
+                    WHERE o.vehicle_id = d.vehicle_id
+                        AND o.log_type = d.log_type
+                        AND o.odometer = d.odometer
+                        AND julianday(o.log_date) >= julianday(d.log_date) - ?2 / 24.0
+                        AND (o.log_date, o.rowid) < (d.log_date, d.rowid)
+                        AND o.deleted_at IS NULL
+                    ORDER BY o.log_date, o.rowid
+                    LIMIT 1
+                ) AS duplicate_of,
+                d.vehicle_id,
+                d.log_type,
+                d.odometer,
+                d.log_date,
+                d.rowid AS seq
+            FROM log_records d
+            WHERE d.deleted_at IS NULL
+                AND (?1 IS NULL OR d.vehicle_id = ?1)
+        )
+        WHERE duplicate_of IS NOT NULL
+        ORDER BY vehicle_id, log_date, seq";
+    query_as::<_, DbLogRecordDuplicate>(sql)
+        .bind(vehicle_id)
+        .bind(DUPLICATE_WINDOW_HOURS)
+        .fetch_all(executor)
+        .await
+}
+
+/// Reads the revisions of a log record, which may be soft deleted
+async fn history(
+    conn: &mut SqliteConnection,
+    id: &Uuid,
+) -> Result<Vec<DbLogRecordRevision>, sqlx::Error> {
+    let sql = "SELECT id FROM log_records WHERE id = ?1";
+    query(sql).bind(id).fetch_one(&mut *conn).await?;
+
+    let sql = "
+        SELECT * FROM log_record_revisions
+        WHERE log_record_id = ?1
+        ORDER BY changed_at, rowid";
+    query_as::<_, DbLogRecordRevision>(sql)
+        .bind(id)
+        .fetch_all(conn)
+        .await
+}
+
+#[async_trait]
+impl LogRecordRepository for SqlitePool {
+    async fn find(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        Ok(find(self, id).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbLogRecord>, ApiError> {
+        Ok(list(self).await?)
+    }
+
+    async fn insert(
+        &self,
+        log_record: &DbLogRecord,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, ApiError> {
+        create(&mut *self.acquire().await?, log_record, allow_duplicate).await
+    }
+
+    async fn update(
+        &self,
+        log_record: &DbLogRecord,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbLogRecord, ApiError> {
+        update(
+            &mut *self.acquire().await?,
+            log_record,
+            changed_by,
+            if_match,
+        )
+        .await
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        delete(&mut *self.acquire().await?, id, changed_by, if_match).await
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        restore(&mut *self.acquire().await?, id).await
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        purge(&mut *self.acquire().await?, id).await
+    }
+
+    async fn count_by_type(&self) -> Result<Vec<(LogTypeName, i64)>, ApiError> {
+        Ok(count_by_type(self).await?)
+    }
+
+    async fn duplicates(
+        &self,
+        vehicle_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecordDuplicate>, ApiError> {
+        Ok(duplicates(self, vehicle_id).await?)
+    }
+
+    async fn history(&self, id: &Uuid) -> Result<Vec<DbLogRecordRevision>, ApiError> {
+        Ok(history(&mut *self.acquire().await?, id).await?)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn LogRecordBatch>, ApiError> {
+        Ok(Box::new(SqliteBatch(Mutex::new(
+            Acquire::begin(self).await?,
+        ))))
+    }
+}
+
+/// A batch of log record writes made in one transaction. Each write opens a
+/// savepoint of its own, so a failed one is rolled back by itself.
+#[derive(Debug)]
+pub struct SqliteBatch(Mutex<Transaction<'static, Sqlite>>);
+
+#[async_trait]
+impl LogRecordRepository for SqliteBatch {
+    async fn find(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        Ok(find(&mut **self.0.lock().await, id).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbLogRecord>, ApiError> {
+        Ok(list(&mut **self.0.lock().await).await?)
+    }
+
+    async fn insert(
+        &self,
+        log_record: &DbLogRecord,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, ApiError> {
+        create(&mut **self.0.lock().await, log_record, allow_duplicate).await
+    }
+
+    async fn update(
+        &self,
+        log_record: &DbLogRecord,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbLogRecord, ApiError> {
+        update(&mut **self.0.lock().await, log_record, changed_by, if_match).await
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        changed_by: Option<Uuid>,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        delete(&mut **self.0.lock().await, id, changed_by, if_match).await
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbLogRecord, ApiError> {
+        restore(&mut **self.0.lock().await, id).await
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        purge(&mut **self.0.lock().await, id).await
+    }
+
+    async fn count_by_type(&self) -> Result<Vec<(LogTypeName, i64)>, ApiError> {
+        Ok(count_by_type(&mut **self.0.lock().await).await?)
+    }
+
+    async fn duplicates(
+        &self,
+        vehicle_id: Option<Uuid>,
+    ) -> Result<Vec<DbLogRecordDuplicate>, ApiError> {
+        Ok(duplicates(&mut **self.0.lock().await, vehicle_id).await?)
+    }
+
+    async fn history(&self, id: &Uuid) -> Result<Vec<DbLogRecordRevision>, ApiError> {
+        Ok(history(&mut **self.0.lock().await, id).await?)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn LogRecordBatch>, ApiError> {
+        Err(ApiError::Unsupported(
+            "log record batches can't be nested".to_owned(),
+        ))
+    }
+}
+
+#[async_trait]
+impl LogRecordBatch for SqliteBatch {
+    async fn commit(self: Box<Self>) -> Result<(), ApiError> {
+        Ok(self.0.into_inner().commit().await?)
+    }
+
+    fn log_records(&self) -> &dyn LogRecordRepository {
+        self
+    }
+}
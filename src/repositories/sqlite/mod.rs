@@ -0,0 +1,469 @@
+pub mod audit;
+pub mod health;
+pub mod idempotency;
+pub mod log_record;
+pub mod organisation;
+pub mod sync;
+pub mod user;
+pub mod vehicle;
+
+use chrono::{DateTime, Utc};
+use sqlx::{query_scalar, SqliteConnection};
+use uuid::Uuid;
+
+use crate::{error::ApiError, types::EntityTags};
+
+/// SQLite has no `now()` fixed for the transaction, so deletions bind this
+/// timestamp, which cascaded rows share with the row deleting them
+fn now() -> DateTime<Utc> {
+    Utc::now()
+}
+
+/// Checks the `If-Match` tags of a write against the current version of an
+/// active row. SQLite locks the whole database for writes, so the row needs
+/// no lock of its own.
+async fn check_version(
+    conn: &mut SqliteConnection,
+    table: &'static str,
+    id: &Uuid,
+    if_match: Option<&EntityTags>,
+) -> Result<(), ApiError> {
+    let Some(if_match) = if_match else {
+        return Ok(());
+    };
+
+    let sql = format!("SELECT version FROM {table} WHERE id = ?1 AND deleted_at IS NULL");
+    let version = query_scalar::<_, i64>(&sql)
+        .bind(id)
+        .fetch_one(conn)
+        .await?;
+    if_match.ensure_matches(version)
+}
+
+#[cfg(test)]
+mod sqlite_tests {
+    use super::*;
+    use crate::{
+        migrations::SQLITE_MIGRATOR,
+        models::{
+            api::DeleteUserParams,
+            db::{
+                LogRecord as DbLogRecord, OrganisationMember as DbOrganisationMember,
+                User as DbUser, Vehicle as DbVehicle,
+            },
+        },
+        repositories::{
+            LogRecordRepository, OrganisationRepository, SyncRepository, UserRepository,
+            VehicleRepository,
+        },
+        types::{DeletionMode, MemberRole},
+    };
+    use chrono::Duration;
+    use fake::{Fake, Faker};
+    use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+
+    async fn pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("could not open database");
+        SQLITE_MIGRATOR
+            .run(&pool)
+            .await
+            .expect("could not migrate database");
+        pool
+    }
+
+    async fn seed_user_and_vehicle(pool: &SqlitePool) -> (DbUser, DbVehicle) {
+        let user = Faker.fake::<DbUser>();
+        UserRepository::insert(pool, &user)
+            .await
+            .expect("could not insert user");
+        let vehicle = DbVehicle {
+            owner_id: user.id,
+            organisation_id: None,
+            ..Faker.fake()
+        };
+        VehicleRepository::insert(pool, &vehicle, None)
+            .await
+            .expect("could not insert vehicle");
+        (user, vehicle)
+    }
+
+    async fn seed_log_record(pool: &SqlitePool, vehicle_id: Uuid) -> DbLogRecord {
+        let log_record = DbLogRecord {
+            vehicle_id,
+            ..Faker.fake()
+        };
+        LogRecordRepository::insert(pool, &log_record, true)
+            .await
+            .expect("could not insert log record");
+        log_record
+    }
+
+    #[tokio::test]
+    async fn insert_then_find_every_log_type() {
+        // Arrange
+        let pool = pool().await;
+        let (user, vehicle) = seed_user_and_vehicle(&pool).await;
+
+        for _ in 0..20 {
+            // Act
+            let log_record = seed_log_record(&pool, vehicle.id).await;
+            let found = LogRecordRepository::find(&pool, &log_record.id)
+                .await
+                .expect("could not find log record");
+
+            // Assert
+            assert_eq!(found, log_record);
+        }
+        assert_eq!(
+            UserRepository::find(&pool, &user.id)
+                .await
+                .expect("could not find user"),
+            user
+        );
+        assert_eq!(
+            VehicleRepository::find(&pool, &vehicle.id)
+                .await
+                .expect("could not find vehicle"),
+            vehicle
+        );
+    }
+
+    #[tokio::test]
+    async fn update_changes_log_type() {
+        // Arrange
+        let pool = pool().await;
+        let (_, vehicle) = seed_user_and_vehicle(&pool).await;
+        let log_record = seed_log_record(&pool, vehicle.id).await;
+        let changed = DbLogRecord {
+            log_type: Faker.fake(),
+            ..log_record.clone()
+        };
+
+        // Act
+        let updated = LogRecordRepository::update(&pool, &changed, None, None)
+            .await
+            .expect("could not update log record");
+
+        // Assert
+        assert_eq!(updated.log_type, changed.log_type);
+        assert_eq!(updated.version, 2);
+    }
+
+    #[tokio::test]
+    async fn usernames_are_unique() {
+        // Arrange
+        let pool = pool().await;
+        let user = Faker.fake::<DbUser>();
+        UserRepository::insert(&pool, &user)
+            .await
+            .expect("could not insert user");
+        let other = DbUser {
+            username: user.username.clone(),
+            ..Faker.fake()
+        };
+
+        // Act
+        let err = UserRepository::insert(&pool, &other)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(
+            err,
+            ApiError::UniqueConstraintViolation { constraint: Some(constraint), .. }
+                if constraint == "users_username_key"
+        ));
+    }
+
+    #[tokio::test]
+    async fn vehicles_need_an_existing_owner() {
+        // Arrange
+        let pool = pool().await;
+        let vehicle = DbVehicle {
+            organisation_id: None,
+            ..Faker.fake()
+        };
+
+        // Act
+        let err = VehicleRepository::insert(&pool, &vehicle, None)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(matches!(err, ApiError::ResourceNotFound));
+    }
+
+    #[tokio::test]
+    async fn writes_bump_the_version() {
+        // Arrange
+        let pool = pool().await;
+        let (user, _) = seed_user_and_vehicle(&pool).await;
+        let stale = EntityTags::Versions(vec![1]);
+
+        // Act
+        let updated = UserRepository::update(&pool, &user, Some(&stale))
+            .await
+            .expect("could not update user");
+        let err = UserRepository::update(&pool, &user, Some(&stale))
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert_eq!(updated.version, 2);
+        assert!(matches!(err, ApiError::PreconditionFailed));
+    }
+
+    #[tokio::test]
+    async fn cascaded_deletes_are_restored_together() {
+        // Arrange
+        let pool = pool().await;
+        let (user, vehicle) = seed_user_and_vehicle(&pool).await;
+        let log_record = seed_log_record(&pool, vehicle.id).await;
+        let params = DeleteUserParams {
+            mode: DeletionMode::Cascade,
+            reassign_to: None,
+        };
+        UserRepository::delete(&pool, &user.id, &params, None)
+            .await
+            .expect("could not delete user");
+        let deleted = LogRecordRepository::find(&pool, &log_record.id).await;
+
+        // Act
+        UserRepository::restore(&pool, &user.id)
+            .await
+            .expect("could not restore user");
+
+        // Assert
+        assert!(matches!(deleted, Err(ApiError::ResourceNotFound)));
+        VehicleRepository::find(&pool, &vehicle.id)
+            .await
+            .expect("vehicle should have been restored");
+        LogRecordRepository::find(&pool, &log_record.id)
+            .await
+            .expect("log record should have been restored");
+    }
+
+    #[tokio::test]
+    async fn refuses_duplicate_log_records() {
+        // Arrange
+        let pool = pool().await;
+        let (_, vehicle) = seed_user_and_vehicle(&pool).await;
+        let existing = seed_log_record(&pool, vehicle.id).await;
+        let double_tap = DbLogRecord {
+            id: Uuid::new_v4(),
+            date: existing.date + Duration::hours(1),
+            ..existing.clone()
+        };
+        let next_week = DbLogRecord {
+            id: Uuid::new_v4(),
+            date: existing.date + Duration::weeks(1),
+            ..existing.clone()
+        };
+
+        // Act
+        let err = LogRecordRepository::insert(&pool, &double_tap, false)
+            .await
+            .expect_err("expected failure did not occur");
+        let res = LogRecordRepository::insert(&pool, &next_week, false).await;
+
+        // Assert
+        assert!(
+            matches!(err, ApiError::DuplicateLogRecord { existing_id } if existing_id == existing.id)
+        );
+        assert!(res.is_ok(), "a week later is not a duplicate");
+    }
+
+    #[tokio::test]
+    async fn duplicates_sharing_a_date_follow_insertion_order() {
+        // Arrange
+        let pool = pool().await;
+        let (_, vehicle) = seed_user_and_vehicle(&pool).await;
+        let existing = seed_log_record(&pool, vehicle.id).await;
+        for _ in 0..4 {
+            let same_date = DbLogRecord {
+                id: Uuid::new_v4(),
+                ..existing.clone()
+            };
+            LogRecordRepository::insert(&pool, &same_date, true)
+                .await
+                .expect("could not insert log record");
+        }
+        let double_tap = DbLogRecord {
+            id: Uuid::new_v4(),
+            ..existing.clone()
+        };
+
+        // Act
+        let err = LogRecordRepository::insert(&pool, &double_tap, false)
+            .await
+            .expect_err("expected failure did not occur");
+
+        // Assert
+        assert!(
+            matches!(err, ApiError::DuplicateLogRecord { existing_id } if existing_id == existing.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn fleet_vehicles_need_a_member_as_owner() {
+        // Arrange
+        let pool = pool().await;
+        let (user, _) = seed_user_and_vehicle(&pool).await;
+        let owner = Faker.fake::<DbUser>();
+        UserRepository::insert(&pool, &owner)
+            .await
+            .expect("could not insert user");
+        let organisation_id = OrganisationRepository::insert(&pool, &Faker.fake(), &owner.id)
+            .await
+            .expect("could not insert organisation");
+        let vehicle = DbVehicle {
+            owner_id: user.id,
+            organisation_id: Some(organisation_id),
+            ..Faker.fake()
+        };
+
+        // Act
+        let err = VehicleRepository::insert(&pool, &vehicle, Some(owner.id))
+            .await
+            .expect_err("expected failure did not occur");
+        let member = DbOrganisationMember {
+            organisation_id,
+            user_id: user.id,
+            role: MemberRole::Member,
+        };
+        pool.add_member(&member, Some(owner.id))
+            .await
+            .expect("could not add member");
+        VehicleRepository::insert(&pool, &vehicle, Some(owner.id))
+            .await
+            .expect("could not insert vehicle");
+
+        // Assert
+        assert!(matches!(err, ApiError::NotOrganisationMember));
+        let vehicles = pool
+            .list_vehicles(&organisation_id, Some(owner.id))
+            .await
+            .expect("could not list vehicles");
+        assert_eq!(vehicles.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deleted_organisations_release_their_vehicles() {
+        // Arrange
+        let pool = pool().await;
+        let (user, _) = seed_user_and_vehicle(&pool).await;
+        let organisation_id = OrganisationRepository::insert(&pool, &Faker.fake(), &user.id)
+            .await
+            .expect("could not insert organisation");
+        let vehicle = DbVehicle {
+            owner_id: user.id,
+            organisation_id: Some(organisation_id),
+            ..Faker.fake()
+        };
+        VehicleRepository::insert(&pool, &vehicle, Some(user.id))
+            .await
+            .expect("could not insert vehicle");
+
+        // Act
+        OrganisationRepository::delete(&pool, &organisation_id, Some(user.id))
+            .await
+            .expect("could not delete organisation");
+
+        // Assert
+        let found = VehicleRepository::find(&pool, &vehicle.id)
+            .await
+            .expect("could not find vehicle");
+        assert_eq!(found.organisation_id, None);
+        assert_eq!(found.version, 2);
+    }
+
+    #[tokio::test]
+    async fn batches_are_kept_only_once_committed() {
+        // Arrange
+        let pool = pool().await;
+        let (_, vehicle) = seed_user_and_vehicle(&pool).await;
+        let rolled_back = DbLogRecord {
+            vehicle_id: vehicle.id,
+            ..Faker.fake()
+        };
+        let committed = DbLogRecord {
+            vehicle_id: vehicle.id,
+            ..Faker.fake()
+        };
+
+        // Act
+        let batch = LogRecordRepository::begin(&pool)
+            .await
+            .expect("could not begin batch");
+        LogRecordRepository::insert(batch.log_records(), &rolled_back, true)
+            .await
+            .expect("could not insert log record");
+        drop(batch);
+        let batch = LogRecordRepository::begin(&pool)
+            .await
+            .expect("could not begin batch");
+        LogRecordRepository::insert(batch.log_records(), &committed, true)
+            .await
+            .expect("could not insert log record");
+        batch.commit().await.expect("could not commit batch");
+
+        // Assert
+        let ids = LogRecordRepository::list(&pool)
+            .await
+            .expect("could not list log records")
+            .into_iter()
+            .map(|log_record| log_record.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![committed.id]);
+    }
+
+    #[tokio::test]
+    async fn sync_reports_writes_after_the_token() {
+        // Arrange
+        let pool = pool().await;
+        let (_, vehicle) = seed_user_and_vehicle(&pool).await;
+        let deleted = seed_log_record(&pool, vehicle.id).await;
+        let purged = seed_log_record(&pool, vehicle.id).await;
+        LogRecordRepository::delete(&pool, &purged.id, None, None)
+            .await
+            .expect("could not delete log record");
+        let token = pool
+            .changes(None)
+            .await
+            .expect("could not read changes")
+            .token;
+
+        // Act
+        LogRecordRepository::delete(&pool, &deleted.id, None, None)
+            .await
+            .expect("could not delete log record");
+        LogRecordRepository::purge(&pool, &purged.id)
+            .await
+            .expect("could not purge log record");
+        let created = seed_log_record(&pool, vehicle.id).await;
+        let changes = pool
+            .changes(Some(&token))
+            .await
+            .expect("could not read changes");
+
+        // Assert
+        assert!(changes.users.changed.is_empty());
+        assert!(changes.vehicles.changed.is_empty());
+        let changed = changes
+            .log_records
+            .changed
+            .iter()
+            .map(|(log_record, _)| log_record.id)
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![created.id]);
+        let mut deleted_ids = changes.log_records.deleted;
+        deleted_ids.sort();
+        let mut expected = vec![deleted.id, purged.id];
+        expected.sort();
+        assert_eq!(deleted_ids, expected);
+    }
+}
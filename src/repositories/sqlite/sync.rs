@@ -0,0 +1,97 @@
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query, query_scalar, sqlite::SqliteRow, FromRow, Row, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::db::{LogRecord as DbLogRecord, User as DbUser, Vehicle as DbVehicle},
+    repositories::{ChangeSet, Changes, SyncRepository},
+    types::{AuditResource, SequenceToken},
+};
+
+#[async_trait]
+impl SyncRepository for SqlitePool {
+    async fn changes(&self, since: Option<&str>) -> Result<ChangeSet, ApiError> {
+        let since = since.map(SequenceToken::parse).transpose()?;
+
+        // Every read, and the token handed out, must share one transaction so
+        // that nothing committed in between is skipped by the next sync
+        let mut tx = self.begin().await?;
+        let token = query_scalar::<_, i64>("SELECT value FROM change_sequence")
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let users = Changes {
+            changed: changed::<DbUser>(&mut tx, "users", since).await?,
+            deleted: deleted(&mut tx, "users", AuditResource::User, since).await?,
+        };
+        let vehicles = Changes {
+            changed: changed::<DbVehicle>(&mut tx, "vehicles", since).await?,
+            deleted: deleted(&mut tx, "vehicles", AuditResource::Vehicle, since).await?,
+        };
+        let log_records = Changes {
+            changed: changed::<DbLogRecord>(&mut tx, "log_records", since).await?,
+            deleted: deleted(&mut tx, "log_records", AuditResource::LogRecord, since).await?,
+        };
+        tx.commit().await?;
+
+        Ok(ChangeSet {
+            token: token.to_string(),
+            users,
+            vehicles,
+            log_records,
+        })
+    }
+}
+
+/// The position of a token in the change sequence, or before every write
+/// without one
+fn position(since: Option<SequenceToken>) -> i64 {
+    since.map_or(0, |since| i64::try_from(since.0).unwrap_or(i64::MAX))
+}
+
+/// Reads the active rows of `table` written since the token
+async fn changed<T>(
+    conn: &mut SqliteConnection,
+    table: &'static str,
+    since: Option<SequenceToken>,
+) -> Result<Vec<(T, DateTime<Utc>)>, ApiError>
+where
+    T: for<'r> FromRow<'r, SqliteRow>,
+{
+    let sql = format!(
+        "SELECT * FROM {table} WHERE deleted_at IS NULL AND change_seq > ?1 ORDER BY change_seq"
+    );
+    query(&sql)
+        .bind(position(since))
+        .fetch_all(conn)
+        .await?
+        .iter()
+        .map(|row| Ok((T::from_row(row)?, row.try_get("updated_at")?)))
+        .collect()
+}
+
+/// Reads the ids of rows of `table` deleted or purged since the token. A full
+/// sync has nothing to delete.
+async fn deleted(
+    conn: &mut SqliteConnection,
+    table: &'static str,
+    resource_type: AuditResource,
+    since: Option<SequenceToken>,
+) -> Result<Vec<Uuid>, ApiError> {
+    if since.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT id FROM {table} WHERE deleted_at IS NOT NULL AND change_seq > ?1
+        UNION
+        SELECT resource_id FROM purged_rows WHERE resource_type = ?2 AND change_seq > ?1"
+    );
+    Ok(query_scalar::<_, Uuid>(&sql)
+        .bind(position(since))
+        .bind(resource_type)
+        .fetch_all(conn)
+        .await?)
+}
@@ -0,0 +1,274 @@
+use axum::async_trait;
+use sqlx::{query, query_as, query_scalar, SqliteExecutor, SqlitePool};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::{api::DeleteUserParams, db::User as DbUser},
+    repositories::{
+        sqlite::{check_version, now, organisation::ensure_member},
+        UserRepository,
+    },
+    types::{DeletionMode, EntityTags},
+};
+
+/// Ensure that a user is active. SQLite locks the whole database for writes,
+/// so they stay active until the transaction ends.
+pub async fn ensure_user(
+    executor: impl SqliteExecutor<'_>,
+    user_id: &Uuid,
+) -> Result<(), ApiError> {
+    let sql = "SELECT id FROM users WHERE id = ?1 AND deleted_at IS NULL";
+    query(sql).bind(user_id).fetch_one(executor).await?;
+    Ok(())
+}
+
+#[async_trait]
+impl UserRepository for SqlitePool {
+    async fn find(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+        let sql = "SELECT * FROM users WHERE id = ?1 AND deleted_at IS NULL";
+        Ok(query_as::<_, DbUser>(sql).bind(id).fetch_one(self).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbUser>, ApiError> {
+        let sql = "SELECT * FROM users WHERE deleted_at IS NULL ORDER BY rowid";
+        Ok(query_as::<_, DbUser>(sql).fetch_all(self).await?)
+    }
+
+    async fn insert(&self, user: &DbUser) -> Result<Uuid, ApiError> {
+        let sql = "
+            INSERT INTO users (id, first_name, last_name, username, email)
+            VALUES (?1, ?2, ?3, ?4, ?5)";
+        query(sql)
+            .bind(user.id)
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(&user.username)
+            .bind(&user.email)
+            .execute(self)
+            .await?;
+        Ok(user.id)
+    }
+
+    async fn update(
+        &self,
+        user: &DbUser,
+        if_match: Option<&EntityTags>,
+    ) -> Result<DbUser, ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut tx, "users", &user.id, if_match).await?;
+
+        let sql = "
+            UPDATE users
+            SET
+                first_name = ?1,
+                last_name = ?2,
+                username = ?3,
+                email = ?4,
+                version = version + 1
+            WHERE id = ?5 AND deleted_at IS NULL
+            RETURNING *";
+        let updated_user = query_as::<_, DbUser>(sql)
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(&user.username)
+            .bind(&user.email)
+            .bind(user.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(updated_user)
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        params: &DeleteUserParams,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut tx, "users", id, if_match).await?;
+        let deleted_at = now();
+
+        let sql = "SELECT id FROM vehicles WHERE owner_id = ?1 AND deleted_at IS NULL";
+        let vehicle_ids = query_scalar::<_, Uuid>(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        if !vehicle_ids.is_empty() {
+            match params.mode {
+                DeletionMode::Restrict => {
+                    tracing::error!(?vehicle_ids, "user still owns vehicles");
+                    return Err(ApiError::DependentResources {
+                        resource: "vehicles".to_owned(),
+                        ids: vehicle_ids,
+                    });
+                }
+                DeletionMode::Cascade => {
+                    tracing::debug!(?vehicle_ids, "deleting vehicles owned by user");
+                    let sql = "
+                        UPDATE log_records
+                        SET deleted_at = ?1, version = version + 1
+                        WHERE vehicle_id IN (
+                            SELECT id FROM vehicles WHERE owner_id = ?2 AND deleted_at IS NULL
+                        ) AND deleted_at IS NULL";
+                    query(sql)
+                        .bind(deleted_at)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                    let sql = "
+                        UPDATE vehicles
+                        SET deleted_at = ?1, version = version + 1
+                        WHERE owner_id = ?2 AND deleted_at IS NULL";
+                    query(sql)
+                        .bind(deleted_at)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                DeletionMode::Reassign => {
+                    let new_owner_id = params.reassign_to.ok_or_else(|| {
+                        ApiError::InvalidRequest(
+                            "reassign_to is required when reassigning vehicles".to_owned(),
+                        )
+                    })?;
+                    if new_owner_id == *id {
+                        return Err(ApiError::InvalidRequest(
+                            "vehicles can't be reassigned to the user being deleted".to_owned(),
+                        ));
+                    }
+                    let sql = "SELECT id FROM users WHERE id = ?1 AND deleted_at IS NULL";
+                    query_scalar::<_, Uuid>(sql)
+                        .bind(new_owner_id)
+                        .fetch_one(&mut *tx)
+                        .await?;
+
+                    // Fleet vehicles may only be handed to members of their organisation
+                    let sql = "
+                        SELECT DISTINCT organisation_id
+                        FROM vehicles
+                        WHERE owner_id = ?1 AND deleted_at IS NULL AND organisation_id IS NOT NULL";
+                    let organisation_ids = query_scalar::<_, Uuid>(sql)
+                        .bind(id)
+                        .fetch_all(&mut *tx)
+                        .await?;
+                    for organisation_id in organisation_ids {
+                        ensure_member(&mut tx, &organisation_id, &new_owner_id).await?;
+                    }
+
+                    tracing::debug!(?vehicle_ids, %new_owner_id, "reassigning vehicles owned by user");
+                    let sql = "
+                        UPDATE vehicles
+                        SET owner_id = ?1, version = version + 1
+                        WHERE owner_id = ?2 AND deleted_at IS NULL";
+                    query(sql)
+                        .bind(new_owner_id)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        let sql = "
+            UPDATE users
+            SET deleted_at = ?1, version = version + 1
+            WHERE id = ?2 AND deleted_at IS NULL";
+        let res = query(sql)
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if res.rows_affected() < 1 {
+            tracing::error!("no user was deleted");
+            return Err(ApiError::ResourceNotFound);
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbUser, ApiError> {
+        let mut tx = self.begin().await?;
+
+        // Restore vehicles and log records which were deleted along with the user
+        let sql = "
+            UPDATE log_records
+            SET deleted_at = NULL, version = version + 1
+            WHERE vehicle_id IN (SELECT id FROM vehicles WHERE owner_id = ?1)
+                AND deleted_at = (SELECT deleted_at FROM users WHERE id = ?1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "
+            UPDATE vehicles
+            SET deleted_at = NULL, version = version + 1
+            WHERE owner_id = ?1 AND deleted_at = (SELECT deleted_at FROM users WHERE id = ?1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+
+        let sql = "
+            UPDATE users
+            SET deleted_at = NULL, version = version + 1
+            WHERE id = ?1 AND deleted_at IS NOT NULL
+            RETURNING *";
+        let restored_user = query_as::<_, DbUser>(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(restored_user)
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+
+        let sql = "SELECT deleted_at IS NOT NULL FROM users WHERE id = ?1";
+        let deleted = query_scalar::<_, bool>(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !deleted {
+            return Err(ApiError::Conflict(
+                "user must be deleted before it can be purged".to_owned(),
+            ));
+        }
+
+        let sql = "SELECT id FROM vehicles WHERE owner_id = ?1 AND deleted_at IS NULL";
+        let vehicle_ids = query_scalar::<_, Uuid>(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+        if !vehicle_ids.is_empty() {
+            tracing::error!(?vehicle_ids, "user still owns vehicles");
+            return Err(ApiError::DependentResources {
+                resource: "vehicles".to_owned(),
+                ids: vehicle_ids,
+            });
+        }
+
+        let sql = "
+            DELETE FROM log_records
+            WHERE vehicle_id IN (SELECT id FROM vehicles WHERE owner_id = ?1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "DELETE FROM vehicles WHERE owner_id = ?1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "DELETE FROM users WHERE id = ?1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn is_admin(&self, id: &Uuid) -> Result<bool, ApiError> {
+        let sql = "
+            SELECT EXISTS (
+                SELECT 1 FROM users WHERE id = ?1 AND is_admin AND deleted_at IS NULL
+            )";
+        Ok(query_scalar::<_, bool>(sql)
+            .bind(id)
+            .fetch_one(self)
+            .await?)
+    }
+}
@@ -0,0 +1,254 @@
+use axum::async_trait;
+use sqlx::{query, query_as, query_scalar, SqliteExecutor, SqlitePool};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    models::db::Vehicle as DbVehicle,
+    repositories::{
+        sqlite::{
+            check_version, now,
+            organisation::{ensure_can_move, ensure_member},
+            user::ensure_user,
+        },
+        VehicleRepository,
+    },
+    types::EntityTags,
+};
+
+/// Ensure that a vehicle is active. SQLite locks the whole database for
+/// writes, so it stays active until the transaction ends.
+pub async fn ensure_vehicle(
+    executor: impl SqliteExecutor<'_>,
+    vehicle_id: &Uuid,
+) -> Result<(), ApiError> {
+    let sql = "SELECT id FROM vehicles WHERE id = ?1 AND deleted_at IS NULL";
+    query(sql).bind(vehicle_id).fetch_one(executor).await?;
+    Ok(())
+}
+
+#[async_trait]
+impl VehicleRepository for SqlitePool {
+    async fn find(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+        let sql = "SELECT * FROM vehicles WHERE id = ?1 AND deleted_at IS NULL";
+        Ok(query_as::<_, DbVehicle>(sql)
+            .bind(id)
+            .fetch_one(self)
+            .await?)
+    }
+
+    async fn list(&self) -> Result<Vec<DbVehicle>, ApiError> {
+        let sql = "SELECT * FROM vehicles WHERE deleted_at IS NULL ORDER BY rowid";
+        Ok(query_as::<_, DbVehicle>(sql).fetch_all(self).await?)
+    }
+
+    async fn insert(&self, vehicle: &DbVehicle, actor_id: Option<Uuid>) -> Result<Uuid, ApiError> {
+        let mut tx = self.begin().await?;
+        ensure_can_move(&mut tx, None, vehicle.organisation_id, actor_id).await?;
+        ensure_user(&mut *tx, &vehicle.owner_id).await?;
+        if let Some(organisation_id) = vehicle.organisation_id {
+            ensure_member(&mut tx, &organisation_id, &vehicle.owner_id).await?;
+        }
+        let sql = "
+            INSERT INTO vehicles (id, owner_id, make, model, year, odometer_unit, organisation_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)";
+        query(sql)
+            .bind(vehicle.id)
+            .bind(vehicle.owner_id)
+            .bind(&vehicle.make)
+            .bind(&vehicle.model)
+            .bind(vehicle.year)
+            .bind(&vehicle.odometer_unit)
+            .bind(vehicle.organisation_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(vehicle.id)
+    }
+
+    async fn update(
+        &self,
+        vehicle: &DbVehicle,
+        if_match: Option<&EntityTags>,
+        actor_id: Option<Uuid>,
+    ) -> Result<DbVehicle, ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut tx, "vehicles", &vehicle.id, if_match).await?;
+
+        // Ownership can't be changed by an update, so check the stored owner
+        let sql = "
+            SELECT owner_id, organisation_id FROM vehicles
+            WHERE id = ?1 AND deleted_at IS NULL";
+        let (owner_id, stored_organisation_id) = query_as::<_, (Uuid, Option<Uuid>)>(sql)
+            .bind(vehicle.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        ensure_can_move(
+            &mut tx,
+            stored_organisation_id,
+            vehicle.organisation_id,
+            actor_id,
+        )
+        .await?;
+        if let Some(organisation_id) = vehicle.organisation_id {
+            ensure_member(&mut tx, &organisation_id, &owner_id).await?;
+        }
+
+        let sql = "
+            UPDATE vehicles
+            SET
+                make = ?1,
+                model = ?2,
+                year = ?3,
+                odometer_unit = ?4,
+                organisation_id = ?5,
+                version = version + 1
+            WHERE id = ?6 AND deleted_at IS NULL
+            RETURNING *";
+        let updated_vehicle = query_as::<_, DbVehicle>(sql)
+            .bind(&vehicle.make)
+            .bind(&vehicle.model)
+            .bind(vehicle.year)
+            .bind(&vehicle.odometer_unit)
+            .bind(vehicle.organisation_id)
+            .bind(vehicle.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(updated_vehicle)
+    }
+
+    async fn delete(
+        &self,
+        id: &Uuid,
+        cascade: bool,
+        if_match: Option<&EntityTags>,
+    ) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+        check_version(&mut tx, "vehicles", id, if_match).await?;
+        let deleted_at = now();
+
+        let sql = "SELECT id FROM log_records WHERE vehicle_id = ?1 AND deleted_at IS NULL";
+        let log_record_ids = query_scalar::<_, Uuid>(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        if !log_record_ids.is_empty() {
+            if cascade {
+                tracing::debug!("deleting {} log records of vehicle", log_record_ids.len());
+                let sql = "
+                    UPDATE log_records
+                    SET deleted_at = ?1, version = version + 1
+                    WHERE vehicle_id = ?2 AND deleted_at IS NULL";
+                query(sql)
+                    .bind(deleted_at)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            } else {
+                tracing::error!("vehicle still has log records");
+                return Err(ApiError::DependentResources {
+                    resource: "log_records".to_owned(),
+                    ids: log_record_ids,
+                });
+            }
+        }
+
+        let sql = "
+            UPDATE vehicles
+            SET deleted_at = ?1, version = version + 1
+            WHERE id = ?2 AND deleted_at IS NULL
+            RETURNING id";
+        query(sql)
+            .bind(deleted_at)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<DbVehicle, ApiError> {
+        let mut tx = self.begin().await?;
+
+        let sql = "
+            SELECT users.deleted_at IS NULL
+            FROM vehicles
+            JOIN users ON users.id = vehicles.owner_id
+            WHERE vehicles.id = ?1 AND vehicles.deleted_at IS NOT NULL";
+        let owner_active = query_scalar::<_, bool>(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !owner_active {
+            return Err(ApiError::Conflict(
+                "the vehicle's owner is deleted and must be restored first".to_owned(),
+            ));
+        }
+
+        // Restore log records which were deleted along with the vehicle
+        let sql = "
+            UPDATE log_records
+            SET deleted_at = NULL, version = version + 1
+            WHERE vehicle_id = ?1
+                AND deleted_at = (SELECT deleted_at FROM vehicles WHERE id = ?1)";
+        query(sql).bind(id).execute(&mut *tx).await?;
+
+        let sql = "
+            UPDATE vehicles
+            SET deleted_at = NULL, version = version + 1
+            WHERE id = ?1
+            RETURNING *";
+        let restored_vehicle = query_as::<_, DbVehicle>(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(restored_vehicle)
+    }
+
+    async fn purge(&self, id: &Uuid) -> Result<(), ApiError> {
+        let mut tx = self.begin().await?;
+
+        let sql = "SELECT deleted_at IS NOT NULL FROM vehicles WHERE id = ?1";
+        let deleted = query_scalar::<_, bool>(sql)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !deleted {
+            return Err(ApiError::Conflict(
+                "vehicle must be deleted before it can be purged".to_owned(),
+            ));
+        }
+
+        let sql = "SELECT id FROM log_records WHERE vehicle_id = ?1 AND deleted_at IS NULL";
+        let log_record_ids = query_scalar::<_, Uuid>(sql)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+        if !log_record_ids.is_empty() {
+            tracing::error!("vehicle still has log records");
+            return Err(ApiError::DependentResources {
+                resource: "log_records".to_owned(),
+                ids: log_record_ids,
+            });
+        }
+
+        let sql = "DELETE FROM log_records WHERE vehicle_id = ?1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        let sql = "DELETE FROM vehicles WHERE id = ?1";
+        query(sql).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<i64, ApiError> {
+        let sql = "SELECT count(*) FROM vehicles WHERE deleted_at IS NULL";
+        Ok(query_scalar::<_, i64>(sql).fetch_one(self).await?)
+    }
+}
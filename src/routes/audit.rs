@@ -0,0 +1,50 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Router,
+};
+use utoipa::OpenApi;
+
+use crate::{
+    controllers::audit as controller,
+    error::ApiError,
+    extractors::actor::Actor,
+    models::api::{ListAuditEntriesParams, ListAuditEntriesResponse, Problem},
+    AppState,
+};
+
+/// List audit log entries
+#[utoipa::path(
+    get,
+    path = "",
+    operation_id = "list_audit_entries",
+    params(ListAuditEntriesParams, Actor),
+    responses(
+        (
+            status = OK,
+            description = "Matching entries, newest first",
+            body = ListAuditEntriesResponse,
+        ),
+        (status = BAD_REQUEST, response = Problem),
+        (status = FORBIDDEN, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "audit_list_route", skip(appstate), err)]
+async fn list(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Query(params): Query<ListAuditEntriesParams>,
+) -> Result<ListAuditEntriesResponse, ApiError> {
+    controller::list(appstate.users(), appstate.audit(), actor, params).await
+}
+
+/// Audit log of mutating requests, for admins
+#[derive(OpenApi)]
+#[openapi(paths(list))]
+pub struct AuditApi;
+
+#[tracing::instrument(name = "build_audit_router", skip_all)]
+pub fn build_router() -> Router<AppState> {
+    tracing::debug!("building audit router");
+    Router::new().route("/", get(list))
+}
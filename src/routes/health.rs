@@ -0,0 +1,63 @@
+use axum::{extract::State, routing::get, Router};
+
+use crate::{
+    controllers::health as controller,
+    error::ApiError,
+    models::api::{HealthResponse, Problem, VersionResponse},
+    AppState,
+};
+
+/// Check that the process is alive
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    operation_id = "healthz",
+    tag = "health",
+    responses((status = OK, description = "The process is alive", body = HealthResponse))
+)]
+#[tracing::instrument(name = "health_healthz_route")]
+async fn healthz() -> HealthResponse {
+    HealthResponse::ok()
+}
+
+/// Check that the database is reachable and fully migrated
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    operation_id = "readyz",
+    tag = "health",
+    responses(
+        (status = OK, description = "Ready to serve requests", body = HealthResponse),
+        (status = SERVICE_UNAVAILABLE, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "health_readyz_route", skip(appstate), err)]
+async fn readyz(State(appstate): State<AppState>) -> Result<HealthResponse, ApiError> {
+    controller::ready(
+        &*appstate.repositories.health,
+        appstate.replica.as_ref().map(|replica| &*replica.health),
+    )
+    .await
+}
+
+/// Read the version of the running build
+#[utoipa::path(
+    get,
+    path = "/version",
+    operation_id = "version",
+    tag = "health",
+    responses((status = OK, description = "The running build", body = VersionResponse))
+)]
+#[tracing::instrument(name = "health_version_route")]
+async fn version() -> VersionResponse {
+    VersionResponse::current()
+}
+
+#[tracing::instrument(name = "build_health_router", skip_all)]
+pub fn build_router() -> Router<AppState> {
+    tracing::debug!("building health router");
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+}
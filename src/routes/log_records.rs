@@ -1,67 +1,397 @@
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get, post, put},
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Response,
+    routing::{delete, get, patch, post, put},
     Router,
 };
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    controllers::log_record as controller,
+    controllers::{audit, idempotency, log_record as controller},
     error::ApiError,
-    extractors::custom_json::Json,
+    extractors::{
+        actor::Actor,
+        custom_json::Json,
+        idempotency_key::IdempotencyKey,
+        precondition::{IfMatch, IfNoneMatch},
+    },
+    middleware::audit::REQUEST_ID_HEADER,
     models::api::{
-        CreateLogRecordBody, CreateLogRecordResponse, DeleteLogRecordResponse,
-        ListLogRecordsResponse, ReadLogRecordResponse, UpdateLogRecordBody,
+        BatchLogRecordsBody, BatchLogRecordsResponse, CreateLogRecordBody, CreateLogRecordParams,
+        CreateLogRecordResponse, DeleteLogRecordResponse, Idempotent,
+        ListLogRecordDuplicatesParams, ListLogRecordDuplicatesResponse,
+        ListLogRecordRevisionsResponse, ListLogRecordsResponse, LogRecordOperation,
+        PatchLogRecordBody, PatchLogRecordResponse, Problem, PurgeLogRecordResponse,
+        ReadLogRecordResponse, RestoreLogRecordResponse, UpdateLogRecordBody,
         UpdateLogRecordResponse,
     },
+    types::AuditResource,
     AppState,
 };
 
+/// Read a log record
+#[utoipa::path(
+    get,
+    path = "/{log_record_id}",
+    operation_id = "read_log_record",
+    params(IfNoneMatch),
+    responses(
+        (
+            status = OK,
+            description = "The log record",
+            body = ReadLogRecordResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_MODIFIED, description = "The log record still has a matching entity tag"),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "log_records_read_route", skip(appstate), err)]
 async fn read(
     State(appstate): State<AppState>,
     Path(log_record_id): Path<Uuid>,
-) -> Result<ReadLogRecordResponse, ApiError> {
-    controller::read(&appstate.db, &log_record_id).await
+    if_none_match: IfNoneMatch,
+) -> Result<Response, ApiError> {
+    let log_record = controller::read(&*appstate.readers().log_records, &log_record_id).await?;
+    Ok(if_none_match.respond(log_record.version, log_record))
 }
 
+/// List active log records
+#[utoipa::path(
+    get,
+    path = "",
+    operation_id = "list_log_records",
+    responses(
+        (status = OK, description = "Active log records", body = ListLogRecordsResponse),
+    )
+)]
 #[tracing::instrument(name = "log_records_list_route", skip(appstate), err)]
 async fn list(State(appstate): State<AppState>) -> Result<ListLogRecordsResponse, ApiError> {
-    controller::list(&appstate.db).await
+    controller::list(&*appstate.readers().log_records).await
 }
 
+/// Create a log record
+#[utoipa::path(
+    post,
+    path = "",
+    operation_id = "create_log_record",
+    params(CreateLogRecordParams, IdempotencyKey),
+    request_body = CreateLogRecordBody,
+    responses(
+        (
+            status = CREATED,
+            description = "The log record was created",
+            body = CreateLogRecordResponse,
+            headers(
+                ("location" = String, description = "Path of the created resource"),
+                ("idempotent-replayed" = String, description = "Set on replayed requests"),
+            ),
+        ),
+        (status = BAD_REQUEST, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "log_records_create_route", skip(appstate), err)]
 async fn create(
     State(appstate): State<AppState>,
+    Query(params): Query<CreateLogRecordParams>,
+    IdempotencyKey(key): IdempotencyKey,
     Json(log_record_input): Json<CreateLogRecordBody>,
-) -> Result<CreateLogRecordResponse, ApiError> {
-    controller::create(&appstate.db, log_record_input).await
+) -> Result<Idempotent<CreateLogRecordResponse>, ApiError> {
+    let created = idempotency::create_once(
+        appstate.idempotency(),
+        "log_records",
+        key,
+        params,
+        log_record_input,
+        |params, body| async {
+            Ok(controller::create(appstate.log_records(), body, params)
+                .await?
+                .id)
+        },
+    )
+    .await?;
+    Ok(created.map(|id| CreateLogRecordResponse { id }))
 }
 
+/// Replace a log record
+#[utoipa::path(
+    put,
+    path = "/{log_record_id}",
+    operation_id = "update_log_record",
+    params(IfMatch, Actor),
+    request_body = UpdateLogRecordBody,
+    responses(
+        (
+            status = OK,
+            description = "The updated log record",
+            body = ReadLogRecordResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "log_records_update_route", skip(appstate), err)]
 async fn update(
     State(appstate): State<AppState>,
     Path(log_record_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
+    Actor(actor): Actor,
     Json(log_record_input): Json<UpdateLogRecordBody>,
 ) -> Result<UpdateLogRecordResponse, ApiError> {
-    controller::update(&appstate.db, &log_record_id, log_record_input).await
+    controller::update(
+        appstate.log_records(),
+        &log_record_id,
+        log_record_input,
+        actor,
+        if_match,
+    )
+    .await
 }
 
+/// Update some fields of a log record with a JSON merge patch
+#[utoipa::path(
+    patch,
+    path = "/{log_record_id}",
+    operation_id = "patch_log_record",
+    params(IfMatch, Actor),
+    request_body = PatchLogRecordBody,
+    responses(
+        (
+            status = OK,
+            description = "The updated log record",
+            body = ReadLogRecordResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "log_records_patch_route", skip(appstate), err)]
+async fn patch_route(
+    State(appstate): State<AppState>,
+    Path(log_record_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
+    Actor(actor): Actor,
+    Json(body): Json<PatchLogRecordBody>,
+) -> Result<PatchLogRecordResponse, ApiError> {
+    controller::patch(
+        appstate.log_records(),
+        &log_record_id,
+        body,
+        actor,
+        if_match,
+    )
+    .await
+}
+
+/// Soft delete a log record
+#[utoipa::path(
+    delete,
+    path = "/{log_record_id}",
+    operation_id = "delete_log_record",
+    params(IfMatch, Actor),
+    responses(
+        (status = NO_CONTENT, description = "The log record was deleted"),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "log_records_delete_route", skip(appstate), err)]
 async fn delete_route(
     State(appstate): State<AppState>,
     Path(log_record_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
+    Actor(actor): Actor,
 ) -> Result<DeleteLogRecordResponse, ApiError> {
-    controller::delete(&appstate.db, &log_record_id).await
+    controller::delete(appstate.log_records(), &log_record_id, actor, if_match).await
+}
+
+/// Run several log record writes in one transaction
+#[utoipa::path(
+    post,
+    path = "/batch",
+    operation_id = "batch_log_records",
+    params(Actor),
+    request_body = BatchLogRecordsBody,
+    responses(
+        (
+            status = OK,
+            description = "The outcome of each operation",
+            body = BatchLogRecordsResponse,
+        ),
+        (status = BAD_REQUEST, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "log_records_batch_route", skip(appstate), err)]
+async fn batch(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    headers: HeaderMap,
+    Json(body): Json<BatchLogRecordsBody>,
+) -> Result<BatchLogRecordsResponse, ApiError> {
+    let actions = body
+        .operations
+        .iter()
+        .map(LogRecordOperation::audit_action)
+        .collect::<Vec<_>>();
+    let response = controller::batch(appstate.log_records(), body, actor).await?;
+    if !response.committed {
+        return Ok(response);
+    }
+
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok());
+    for (action, result) in actions.into_iter().zip(&response.results) {
+        if !result.is_success() {
+            continue;
+        }
+        if let Err(e) = audit::record(
+            appstate.audit(),
+            actor,
+            AuditResource::LogRecord,
+            result.id,
+            action,
+            request_id,
+        )
+        .await
+        {
+            tracing::error!(?e, "could not record audit entry");
+        }
+    }
+
+    Ok(response)
+}
+
+/// List log records that look like duplicates of earlier ones
+#[utoipa::path(
+    get,
+    path = "/duplicates",
+    operation_id = "list_log_record_duplicates",
+    params(ListLogRecordDuplicatesParams),
+    responses(
+        (
+            status = OK,
+            description = "Suspected duplicates",
+            body = ListLogRecordDuplicatesResponse,
+        ),
+    )
+)]
+#[tracing::instrument(name = "log_records_duplicates_route", skip(appstate), err)]
+async fn duplicates(
+    State(appstate): State<AppState>,
+    Query(params): Query<ListLogRecordDuplicatesParams>,
+) -> Result<ListLogRecordDuplicatesResponse, ApiError> {
+    controller::duplicates(appstate.log_records(), params).await
 }
 
+/// List earlier revisions of a log record
+#[utoipa::path(
+    get,
+    path = "/{log_record_id}/history",
+    operation_id = "list_log_record_revisions",
+    responses(
+        (
+            status = OK,
+            description = "Revisions, oldest first",
+            body = ListLogRecordRevisionsResponse,
+        ),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "log_records_history_route", skip(appstate), err)]
+async fn history(
+    State(appstate): State<AppState>,
+    Path(log_record_id): Path<Uuid>,
+) -> Result<ListLogRecordRevisionsResponse, ApiError> {
+    controller::history(appstate.log_records(), &log_record_id).await
+}
+
+/// Restore a soft deleted log_record
+#[utoipa::path(
+    post,
+    path = "/{log_record_id}/restore",
+    operation_id = "restore_log_record",
+    responses(
+        (
+            status = OK,
+            description = "The restored log record",
+            body = ReadLogRecordResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "log_records_restore_route", skip(appstate), err)]
+async fn restore(
+    State(appstate): State<AppState>,
+    Path(log_record_id): Path<Uuid>,
+) -> Result<RestoreLogRecordResponse, ApiError> {
+    controller::restore(appstate.log_records(), &log_record_id).await
+}
+
+/// Permanently delete a soft deleted log_record
+#[utoipa::path(
+    delete,
+    path = "/{log_record_id}/purge",
+    operation_id = "purge_log_record",
+    responses(
+        (status = NO_CONTENT, description = "The log record was purged"),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "log_records_purge_route", skip(appstate), err)]
+async fn purge(
+    State(appstate): State<AppState>,
+    Path(log_record_id): Path<Uuid>,
+) -> Result<PurgeLogRecordResponse, ApiError> {
+    controller::purge(appstate.log_records(), &log_record_id).await
+}
+
+/// Maintenance and fuel log records of vehicles
+#[derive(OpenApi)]
+#[openapi(paths(
+    list,
+    read,
+    create,
+    update,
+    patch_route,
+    delete_route,
+    restore,
+    purge,
+    batch,
+    duplicates,
+    history
+))]
+pub struct LogRecordsApi;
+
 #[tracing::instrument(name = "build_log_records_router", skip_all)]
 pub fn build_router() -> Router<AppState> {
     tracing::debug!("building log_records router");
     Router::new()
         .route("/", get(list))
         .route("/", post(create))
+        .route("/batch", post(batch))
+        .route("/duplicates", get(duplicates))
         .route("/:log_record_id", get(read))
         .route("/:log_record_id", put(update))
+        .route("/:log_record_id", patch(patch_route))
         .route("/:log_record_id", delete(delete_route))
+        .route("/:log_record_id/history", get(history))
+        .route("/:log_record_id/restore", post(restore))
+        .route("/:log_record_id/purge", delete(purge))
 }
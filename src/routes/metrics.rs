@@ -0,0 +1,42 @@
+use axum::{extract::State, routing::get, Router};
+
+use crate::{
+    controllers::metrics as controller,
+    error::ApiError,
+    models::api::{metrics::OPENMETRICS_CONTENT_TYPE, MetricsResponse, Problem},
+    AppState,
+};
+
+/// Scrape request, database pool and domain metrics
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    operation_id = "metrics",
+    tag = "metrics",
+    responses(
+        (
+            status = OK,
+            description = "Metrics in the OpenMetrics text format",
+            body = String,
+            content_type = OPENMETRICS_CONTENT_TYPE
+        ),
+        (status = INTERNAL_SERVER_ERROR, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "metrics_render_route", skip(appstate), err)]
+async fn metrics_render_route(
+    State(appstate): State<AppState>,
+) -> Result<MetricsResponse, ApiError> {
+    controller::render(
+        &appstate.repositories,
+        appstate.db.as_ref(),
+        &appstate.metrics,
+    )
+    .await
+}
+
+#[tracing::instrument(name = "build_metrics_router", skip_all)]
+pub fn build_router() -> Router<AppState> {
+    tracing::debug!("building metrics router");
+    Router::new().route("/metrics", get(metrics_render_route))
+}
@@ -1,3 +1,9 @@
+pub mod audit;
+pub mod health;
 pub mod log_records;
+pub mod metrics;
+pub mod openapi;
+pub mod organisations;
+pub mod sync;
 pub mod users;
 pub mod vehicles;
@@ -0,0 +1,45 @@
+use axum::{routing::get, Json, Router};
+use utoipa::OpenApi;
+
+use crate::{
+    models::api::Problem,
+    routes::{
+        audit::AuditApi, health, log_records::LogRecordsApi, metrics,
+        organisations::OrganisationsApi, sync::SyncApi, users::UsersApi, vehicles::VehiclesApi,
+    },
+    types::{DeletionMode, FieldError},
+    AppState,
+};
+
+/// The OpenAPI document describing every route, generated from the route
+/// handlers and API models
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health::healthz,
+        health::readyz,
+        health::version,
+        metrics::metrics_render_route
+    ),
+    nest(
+        (path = "/users", api = UsersApi, tags = ["users"]),
+        (path = "/vehicles", api = VehiclesApi, tags = ["vehicles"]),
+        (path = "/log_records", api = LogRecordsApi, tags = ["log_records"]),
+        (path = "/organisations", api = OrganisationsApi, tags = ["organisations"]),
+        (path = "/audit", api = AuditApi, tags = ["audit"]),
+        (path = "/sync", api = SyncApi, tags = ["sync"]),
+    ),
+    components(schemas(DeletionMode, FieldError, Problem), responses(Problem))
+)]
+pub struct ApiDoc;
+
+#[tracing::instrument(name = "openapi_document_route")]
+async fn document() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[tracing::instrument(name = "build_openapi_router", skip_all)]
+pub fn build_router() -> Router<AppState> {
+    tracing::debug!("building openapi router");
+    Router::new().route("/openapi.json", get(document))
+}
@@ -0,0 +1,315 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post, put},
+    Router,
+};
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    controllers::organisation as controller,
+    error::ApiError,
+    extractors::{
+        actor::{Actor, ACTOR_HEADER},
+        custom_json::Json,
+    },
+    models::api::{
+        AddOrganisationMemberBody, AddOrganisationMemberResponse, CreateOrganisationBody,
+        CreateOrganisationResponse, DeleteOrganisationResponse, ListLogRecordsResponse,
+        ListOrganisationMembersResponse, ListOrganisationsResponse, ListVehiclesResponse, Problem,
+        ReadOrganisationMemberResponse, ReadOrganisationResponse, RemoveOrganisationMemberResponse,
+        UpdateOrganisationBody, UpdateOrganisationMemberBody, UpdateOrganisationMemberResponse,
+        UpdateOrganisationResponse,
+    },
+    AppState,
+};
+
+/// List organisations
+#[utoipa::path(
+    get,
+    path = "",
+    operation_id = "list_organisations",
+    responses(
+        (status = OK, description = "All organisations", body = ListOrganisationsResponse),
+    )
+)]
+#[tracing::instrument(name = "organisations_list_route", skip(appstate), err)]
+async fn list(State(appstate): State<AppState>) -> Result<ListOrganisationsResponse, ApiError> {
+    controller::list(appstate.organisations()).await
+}
+
+/// Read an organisation
+#[utoipa::path(
+    get,
+    path = "/{organisation_id}",
+    operation_id = "read_organisation",
+    responses(
+        (status = OK, description = "The organisation", body = ReadOrganisationResponse),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_read_route", skip(appstate), err)]
+async fn read(
+    State(appstate): State<AppState>,
+    Path(organisation_id): Path<Uuid>,
+) -> Result<ReadOrganisationResponse, ApiError> {
+    controller::read(appstate.organisations(), &organisation_id).await
+}
+
+/// Create an organisation
+#[utoipa::path(
+    post,
+    path = "",
+    operation_id = "create_organisation",
+    request_body = CreateOrganisationBody,
+    params(Actor),
+    responses(
+        (
+            status = CREATED,
+            description = "The organisation was created",
+            body = CreateOrganisationResponse,
+            headers(("location" = String, description = "Path of the created resource")),
+        ),
+        (status = BAD_REQUEST, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_create_route", skip(appstate), err)]
+async fn create(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Json(body): Json<CreateOrganisationBody>,
+) -> Result<CreateOrganisationResponse, ApiError> {
+    let owner_id = actor.ok_or_else(|| {
+        ApiError::InvalidRequest(format!(
+            "{ACTOR_HEADER} header is required to create an organisation"
+        ))
+    })?;
+    controller::create(appstate.organisations(), &owner_id, body).await
+}
+
+/// Rename an organisation
+#[utoipa::path(
+    put,
+    path = "/{organisation_id}",
+    operation_id = "update_organisation",
+    request_body = UpdateOrganisationBody,
+    params(Actor),
+    responses(
+        (status = OK, description = "The updated organisation", body = ReadOrganisationResponse),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_update_route", skip(appstate), err)]
+async fn update(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Path(organisation_id): Path<Uuid>,
+    Json(body): Json<UpdateOrganisationBody>,
+) -> Result<UpdateOrganisationResponse, ApiError> {
+    controller::update(appstate.organisations(), actor, &organisation_id, body).await
+}
+
+/// Delete an organisation
+#[utoipa::path(
+    delete,
+    path = "/{organisation_id}",
+    operation_id = "delete_organisation",
+    params(Actor),
+    responses(
+        (status = NO_CONTENT, description = "The organisation was deleted"),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_delete_route", skip(appstate), err)]
+async fn delete_route(
+    Path(organisation_id): Path<Uuid>,
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+) -> Result<DeleteOrganisationResponse, ApiError> {
+    controller::delete(appstate.organisations(), actor, &organisation_id).await
+}
+
+/// List the members of an organisation
+#[utoipa::path(
+    get,
+    path = "/{organisation_id}/members",
+    operation_id = "list_organisation_members",
+    responses(
+        (status = OK, description = "The members", body = ListOrganisationMembersResponse),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_list_members_route", skip(appstate), err)]
+async fn list_members(
+    State(appstate): State<AppState>,
+    Path(organisation_id): Path<Uuid>,
+) -> Result<ListOrganisationMembersResponse, ApiError> {
+    controller::list_members(appstate.organisations(), &organisation_id).await
+}
+
+/// Add a user to an organisation
+#[utoipa::path(
+    post,
+    path = "/{organisation_id}/members",
+    operation_id = "add_organisation_member",
+    request_body = AddOrganisationMemberBody,
+    params(Actor),
+    responses(
+        (
+            status = CREATED,
+            description = "The user was added",
+            body = AddOrganisationMemberResponse,
+            headers(("location" = String, description = "Path of the created resource")),
+        ),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_add_member_route", skip(appstate), err)]
+async fn add_member(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Path(organisation_id): Path<Uuid>,
+    Json(body): Json<AddOrganisationMemberBody>,
+) -> Result<AddOrganisationMemberResponse, ApiError> {
+    controller::add_member(appstate.organisations(), actor, &organisation_id, body).await
+}
+
+/// Change the role of an organisation member
+#[utoipa::path(
+    put,
+    path = "/{organisation_id}/members/{user_id}",
+    operation_id = "update_organisation_member",
+    request_body = UpdateOrganisationMemberBody,
+    params(Actor),
+    responses(
+        (status = OK, description = "The updated member", body = ReadOrganisationMemberResponse),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_update_member_route", skip(appstate), err)]
+async fn update_member(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Path((organisation_id, user_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<UpdateOrganisationMemberBody>,
+) -> Result<UpdateOrganisationMemberResponse, ApiError> {
+    controller::update_member(
+        appstate.organisations(),
+        actor,
+        &organisation_id,
+        &user_id,
+        body,
+    )
+    .await
+}
+
+/// Remove a user from an organisation, or leave it
+#[utoipa::path(
+    delete,
+    path = "/{organisation_id}/members/{user_id}",
+    operation_id = "remove_organisation_member",
+    params(Actor),
+    responses(
+        (status = NO_CONTENT, description = "The user was removed"),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_remove_member_route", skip(appstate), err)]
+async fn remove_member(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Path((organisation_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<RemoveOrganisationMemberResponse, ApiError> {
+    controller::remove_member(appstate.organisations(), actor, &organisation_id, &user_id).await
+}
+
+/// List the vehicles owned by members of an organisation
+#[utoipa::path(
+    get,
+    path = "/{organisation_id}/vehicles",
+    operation_id = "list_organisation_vehicles",
+    params(Actor),
+    responses(
+        (status = OK, description = "The vehicles", body = ListVehiclesResponse),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_list_vehicles_route", skip(appstate), err)]
+async fn list_vehicles(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Path(organisation_id): Path<Uuid>,
+) -> Result<ListVehiclesResponse, ApiError> {
+    controller::list_vehicles(appstate.organisations(), actor, &organisation_id).await
+}
+
+/// List the log records of vehicles owned by members of an organisation
+#[utoipa::path(
+    get,
+    path = "/{organisation_id}/log_records",
+    operation_id = "list_organisation_log_records",
+    params(Actor),
+    responses(
+        (status = OK, description = "The log records", body = ListLogRecordsResponse),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "organisations_list_log_records_route", skip(appstate), err)]
+async fn list_log_records(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Path(organisation_id): Path<Uuid>,
+) -> Result<ListLogRecordsResponse, ApiError> {
+    controller::list_log_records(appstate.organisations(), actor, &organisation_id).await
+}
+
+/// Organisations of users sharing vehicles
+#[derive(OpenApi)]
+#[openapi(paths(
+    list,
+    read,
+    create,
+    update,
+    delete_route,
+    list_members,
+    add_member,
+    update_member,
+    remove_member,
+    list_vehicles,
+    list_log_records
+))]
+pub struct OrganisationsApi;
+
+#[tracing::instrument(name = "build_organisations_router", skip_all)]
+pub fn build_router() -> Router<AppState> {
+    tracing::debug!("building organisations router");
+    Router::new()
+        .route("/", get(list))
+        .route("/", post(create))
+        .route("/:organisation_id", get(read))
+        .route("/:organisation_id", put(update))
+        .route("/:organisation_id", delete(delete_route))
+        .route("/:organisation_id/members", get(list_members))
+        .route("/:organisation_id/members", post(add_member))
+        .route("/:organisation_id/members/:user_id", put(update_member))
+        .route("/:organisation_id/members/:user_id", delete(remove_member))
+        .route("/:organisation_id/vehicles", get(list_vehicles))
+        .route("/:organisation_id/log_records", get(list_log_records))
+}
@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Router,
+};
+use utoipa::OpenApi;
+
+use crate::{
+    controllers::sync as controller,
+    error::ApiError,
+    models::api::{Problem, SyncParams, SyncResponse},
+    AppState,
+};
+
+/// Read everything changed since an earlier sync
+#[utoipa::path(
+    get,
+    path = "",
+    operation_id = "sync_changes",
+    params(SyncParams),
+    responses(
+        (
+            status = OK,
+            description = "The changes and the token to pass next time",
+            body = SyncResponse,
+        ),
+        (status = BAD_REQUEST, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "sync_changes_route", skip(appstate), err)]
+async fn changes(
+    State(appstate): State<AppState>,
+    Query(params): Query<SyncParams>,
+) -> Result<SyncResponse, ApiError> {
+    controller::changes(appstate.sync(), params).await
+}
+
+/// Incremental sync for offline clients
+#[derive(OpenApi)]
+#[openapi(paths(changes))]
+pub struct SyncApi;
+
+#[tracing::instrument(name = "build_sync_router", skip_all)]
+pub fn build_router() -> Router<AppState> {
+    tracing::debug!("building sync router");
+    Router::new().route("/", get(changes))
+}
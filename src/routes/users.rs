@@ -1,59 +1,240 @@
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get, post, put},
+    extract::{Path, Query, State},
+    response::Response,
+    routing::{delete, get, patch, post, put},
     Router,
 };
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    controllers::user as controller,
+    controllers::{idempotency, user as controller},
     error::ApiError,
-    extractors::custom_json::Json,
+    extractors::{
+        custom_json::Json,
+        idempotency_key::IdempotencyKey,
+        precondition::{IfMatch, IfNoneMatch},
+    },
     models::api::{
-        CreateUserBody, CreateUserResponse, DeleteUserResponse, ListUsersResponse,
-        ReadUserResponse, UpdateUserBody, UpdateUserResponse,
+        CreateUserBody, CreateUserResponse, DeleteUserParams, DeleteUserResponse, Idempotent,
+        ListUsersResponse, PatchUserBody, PatchUserResponse, Problem, PurgeUserResponse,
+        ReadUserResponse, RestoreUserResponse, UpdateUserBody, UpdateUserResponse,
     },
     AppState,
 };
 
+/// List active users
+#[utoipa::path(
+    get,
+    path = "",
+    operation_id = "list_users",
+    responses(
+        (status = OK, description = "Active users", body = ListUsersResponse),
+    )
+)]
 #[tracing::instrument(name = "users_list_route", skip(appstate), err)]
 async fn list(State(appstate): State<AppState>) -> Result<ListUsersResponse, ApiError> {
-    controller::list(&appstate.db).await
+    controller::list(&*appstate.readers().users).await
 }
 
+/// Read a user
+#[utoipa::path(
+    get,
+    path = "/{user_id}",
+    operation_id = "read_user",
+    params(IfNoneMatch),
+    responses(
+        (
+            status = OK,
+            description = "The user",
+            body = ReadUserResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_MODIFIED, description = "The user still has a matching entity tag"),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "users_read_route", skip(appstate), err)]
 async fn read(
     State(appstate): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<ReadUserResponse, ApiError> {
-    controller::read(&appstate.db, &user_id).await
+    if_none_match: IfNoneMatch,
+) -> Result<Response, ApiError> {
+    let user = controller::read(&*appstate.readers().users, &user_id).await?;
+    Ok(if_none_match.respond(user.version, user))
 }
 
+/// Create a user
+#[utoipa::path(
+    post,
+    path = "",
+    operation_id = "create_user",
+    params(IdempotencyKey),
+    request_body = CreateUserBody,
+    responses(
+        (
+            status = CREATED,
+            description = "The user was created",
+            body = CreateUserResponse,
+            headers(
+                ("location" = String, description = "Path of the created resource"),
+                ("idempotent-replayed" = String, description = "Set on replayed requests"),
+            ),
+        ),
+        (status = BAD_REQUEST, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "users_create_route", skip(appstate), err)]
 async fn create(
     State(appstate): State<AppState>,
+    IdempotencyKey(key): IdempotencyKey,
     Json(body): Json<CreateUserBody>,
-) -> Result<CreateUserResponse, ApiError> {
-    controller::create(&appstate.db, body).await
+) -> Result<Idempotent<CreateUserResponse>, ApiError> {
+    let created = idempotency::create_once(
+        appstate.idempotency(),
+        "users",
+        key,
+        (),
+        body,
+        |(), body| async { Ok(controller::create(appstate.users(), body).await?.id) },
+    )
+    .await?;
+    Ok(created.map(|id| CreateUserResponse { id }))
 }
 
+/// Replace a user
+#[utoipa::path(
+    put,
+    path = "/{user_id}",
+    operation_id = "update_user",
+    params(IfMatch),
+    request_body = UpdateUserBody,
+    responses(
+        (
+            status = OK,
+            description = "The updated user",
+            body = ReadUserResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "users_update_route", skip(appstate), err)]
 async fn update(
     State(appstate): State<AppState>,
     Path(user_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
     Json(body): Json<UpdateUserBody>,
 ) -> Result<UpdateUserResponse, ApiError> {
-    controller::update(&appstate.db, &user_id, body).await
+    controller::update(appstate.users(), &user_id, body, if_match).await
 }
 
+/// Update some fields of a user with a JSON merge patch
+#[utoipa::path(
+    patch,
+    path = "/{user_id}",
+    operation_id = "patch_user",
+    params(IfMatch),
+    request_body = PatchUserBody,
+    responses(
+        (
+            status = OK,
+            description = "The updated user",
+            body = ReadUserResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "users_patch_route", skip(appstate), err)]
+async fn patch_route(
+    State(appstate): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
+    Json(body): Json<PatchUserBody>,
+) -> Result<PatchUserResponse, ApiError> {
+    controller::patch(appstate.users(), &user_id, body, if_match).await
+}
+
+/// Soft delete a user
+#[utoipa::path(
+    delete,
+    path = "/{user_id}",
+    operation_id = "delete_user",
+    params(DeleteUserParams, IfMatch),
+    responses(
+        (status = NO_CONTENT, description = "The user was deleted"),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "users_delete_route", skip(appstate), err)]
 async fn delete_route(
     Path(user_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
     State(appstate): State<AppState>,
+    Query(params): Query<DeleteUserParams>,
 ) -> Result<DeleteUserResponse, ApiError> {
-    controller::delete(&appstate.db, &user_id).await
+    controller::delete(appstate.users(), &user_id, params, if_match).await
+}
+
+/// Restore a soft deleted user
+#[utoipa::path(
+    post,
+    path = "/{user_id}/restore",
+    operation_id = "restore_user",
+    responses(
+        (
+            status = OK,
+            description = "The restored user",
+            body = ReadUserResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "users_restore_route", skip(appstate), err)]
+async fn restore(
+    State(appstate): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<RestoreUserResponse, ApiError> {
+    controller::restore(appstate.users(), &user_id).await
 }
 
+/// Permanently delete a soft deleted user
+#[utoipa::path(
+    delete,
+    path = "/{user_id}/purge",
+    operation_id = "purge_user",
+    responses(
+        (status = NO_CONTENT, description = "The user was purged"),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "users_purge_route", skip(appstate), err)]
+async fn purge(
+    State(appstate): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<PurgeUserResponse, ApiError> {
+    controller::purge(appstate.users(), &user_id).await
+}
+
+/// Users who own vehicles
+#[derive(OpenApi)]
+#[openapi(paths(list, read, create, update, patch_route, delete_route, restore, purge))]
+pub struct UsersApi;
+
 #[tracing::instrument(name = "build_users_router", skip_all)]
 pub fn build_router() -> Router<AppState> {
     tracing::debug!("building users router");
@@ -62,5 +243,8 @@ pub fn build_router() -> Router<AppState> {
         .route("/", post(create))
         .route("/:user_id", get(read))
         .route("/:user_id", put(update))
+        .route("/:user_id", patch(patch_route))
         .route("/:user_id", delete(delete_route))
+        .route("/:user_id/restore", post(restore))
+        .route("/:user_id/purge", delete(purge))
 }
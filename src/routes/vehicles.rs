@@ -1,62 +1,256 @@
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get, post, put},
+    extract::{Path, Query, State},
+    response::Response,
+    routing::{delete, get, patch, post, put},
     Router,
 };
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    controllers::vehicle::{
-        create as create_vehicle, delete as delete_vehicle, list as list_vehicles,
-        read as read_vehicle, update as update_vehicle,
+    controllers::{
+        idempotency,
+        vehicle::{
+            create as create_vehicle, delete as delete_vehicle, list as list_vehicles,
+            patch as patch_vehicle, purge as purge_vehicle, read as read_vehicle,
+            restore as restore_vehicle, update as update_vehicle,
+        },
     },
     error::ApiError,
-    extractors::custom_json::Json,
+    extractors::{
+        actor::Actor,
+        custom_json::Json,
+        idempotency_key::IdempotencyKey,
+        precondition::{IfMatch, IfNoneMatch},
+    },
     models::api::{
-        CreateVehicleBody, CreateVehicleResponse, DeleteVehicleResponse, ListVehiclesResponse,
-        ReadVehicleResponse, UpdateVehicleBody, UpdateVehicleResponse,
+        CreateVehicleBody, CreateVehicleResponse, DeleteVehicleParams, DeleteVehicleResponse,
+        Idempotent, ListVehiclesResponse, PatchVehicleBody, PatchVehicleResponse, Problem,
+        PurgeVehicleResponse, ReadVehicleResponse, RestoreVehicleResponse, UpdateVehicleBody,
+        UpdateVehicleResponse,
     },
     AppState,
 };
 
+/// List active vehicles
+#[utoipa::path(
+    get,
+    path = "",
+    operation_id = "list_vehicles",
+    responses(
+        (status = OK, description = "Active vehicles", body = ListVehiclesResponse),
+    )
+)]
 #[tracing::instrument(name = "vehicles_list_route", skip(appstate), err)]
 async fn list(State(appstate): State<AppState>) -> Result<ListVehiclesResponse, ApiError> {
-    list_vehicles(&appstate.db).await
+    list_vehicles(&*appstate.readers().vehicles).await
 }
 
+/// Read a vehicle
+#[utoipa::path(
+    get,
+    path = "/{vehicle_id}",
+    operation_id = "read_vehicle",
+    params(IfNoneMatch),
+    responses(
+        (
+            status = OK,
+            description = "The vehicle",
+            body = ReadVehicleResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_MODIFIED, description = "The vehicle still has a matching entity tag"),
+        (status = NOT_FOUND, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "vehicles_read_route", skip(appstate), err)]
 async fn read(
     State(appstate): State<AppState>,
     Path(vehicle_id): Path<Uuid>,
-) -> Result<ReadVehicleResponse, ApiError> {
-    read_vehicle(&appstate.db, &vehicle_id).await
+    if_none_match: IfNoneMatch,
+) -> Result<Response, ApiError> {
+    let vehicle = read_vehicle(&*appstate.readers().vehicles, &vehicle_id).await?;
+    Ok(if_none_match.respond(vehicle.version, vehicle))
 }
 
+/// Create a vehicle
+#[utoipa::path(
+    post,
+    path = "",
+    operation_id = "create_vehicle",
+    params(Actor, IdempotencyKey),
+    request_body = CreateVehicleBody,
+    responses(
+        (
+            status = CREATED,
+            description = "The vehicle was created",
+            body = CreateVehicleResponse,
+            headers(
+                ("location" = String, description = "Path of the created resource"),
+                ("idempotent-replayed" = String, description = "Set on replayed requests"),
+            ),
+        ),
+        (status = BAD_REQUEST, response = Problem),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "vehicles_create_route", skip(appstate), err)]
 async fn create(
     State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    IdempotencyKey(key): IdempotencyKey,
     Json(body): Json<CreateVehicleBody>,
-) -> Result<CreateVehicleResponse, ApiError> {
-    create_vehicle(&appstate.db, body).await
+) -> Result<Idempotent<CreateVehicleResponse>, ApiError> {
+    let created = idempotency::create_once(
+        appstate.idempotency(),
+        "vehicles",
+        key,
+        (),
+        body,
+        |(), body| async { Ok(create_vehicle(appstate.vehicles(), actor, body).await?.id) },
+    )
+    .await?;
+    Ok(created.map(|id| CreateVehicleResponse { id }))
 }
 
+/// Replace a vehicle
+#[utoipa::path(
+    put,
+    path = "/{vehicle_id}",
+    operation_id = "update_vehicle",
+    params(Actor, IfMatch),
+    request_body = UpdateVehicleBody,
+    responses(
+        (
+            status = OK,
+            description = "The updated vehicle",
+            body = ReadVehicleResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "vehicles_update_route", skip(appstate), err)]
 async fn update(
     State(appstate): State<AppState>,
+    Actor(actor): Actor,
     Path(vehicle_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
     Json(body): Json<UpdateVehicleBody>,
 ) -> Result<UpdateVehicleResponse, ApiError> {
-    update_vehicle(&appstate.db, &vehicle_id, body).await
+    update_vehicle(appstate.vehicles(), actor, &vehicle_id, body, if_match).await
 }
 
+/// Update some fields of a vehicle with a JSON merge patch
+#[utoipa::path(
+    patch,
+    path = "/{vehicle_id}",
+    operation_id = "patch_vehicle",
+    params(Actor, IfMatch),
+    request_body = PatchVehicleBody,
+    responses(
+        (
+            status = OK,
+            description = "The updated vehicle",
+            body = ReadVehicleResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = FORBIDDEN, response = Problem),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+        (status = UNPROCESSABLE_ENTITY, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "vehicles_patch_route", skip(appstate), err)]
+async fn patch_route(
+    State(appstate): State<AppState>,
+    Actor(actor): Actor,
+    Path(vehicle_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
+    Json(body): Json<PatchVehicleBody>,
+) -> Result<PatchVehicleResponse, ApiError> {
+    patch_vehicle(appstate.vehicles(), actor, &vehicle_id, body, if_match).await
+}
+
+/// Soft delete a vehicle
+#[utoipa::path(
+    delete,
+    path = "/{vehicle_id}",
+    operation_id = "delete_vehicle",
+    params(DeleteVehicleParams, IfMatch),
+    responses(
+        (status = NO_CONTENT, description = "The vehicle was deleted"),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+        (status = PRECONDITION_FAILED, response = Problem),
+    )
+)]
 #[tracing::instrument(name = "vehicles_delete_route", skip(appstate), err)]
 async fn delete_route(
     Path(vehicle_id): Path<Uuid>,
+    IfMatch(if_match): IfMatch,
     State(appstate): State<AppState>,
+    Query(params): Query<DeleteVehicleParams>,
 ) -> Result<DeleteVehicleResponse, ApiError> {
-    delete_vehicle(&appstate.db, &vehicle_id).await
+    delete_vehicle(appstate.vehicles(), &vehicle_id, params, if_match).await
+}
+
+/// Restore a soft deleted vehicle
+#[utoipa::path(
+    post,
+    path = "/{vehicle_id}/restore",
+    operation_id = "restore_vehicle",
+    responses(
+        (
+            status = OK,
+            description = "The restored vehicle",
+            body = ReadVehicleResponse,
+            headers(("etag" = String, description = "Version of the resource")),
+        ),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "vehicles_restore_route", skip(appstate), err)]
+async fn restore(
+    State(appstate): State<AppState>,
+    Path(vehicle_id): Path<Uuid>,
+) -> Result<RestoreVehicleResponse, ApiError> {
+    restore_vehicle(appstate.vehicles(), &vehicle_id).await
 }
 
+/// Permanently delete a soft deleted vehicle
+#[utoipa::path(
+    delete,
+    path = "/{vehicle_id}/purge",
+    operation_id = "purge_vehicle",
+    responses(
+        (status = NO_CONTENT, description = "The vehicle was purged"),
+        (status = NOT_FOUND, response = Problem),
+        (status = CONFLICT, response = Problem),
+    )
+)]
+#[tracing::instrument(name = "vehicles_purge_route", skip(appstate), err)]
+async fn purge(
+    State(appstate): State<AppState>,
+    Path(vehicle_id): Path<Uuid>,
+) -> Result<PurgeVehicleResponse, ApiError> {
+    purge_vehicle(appstate.vehicles(), &vehicle_id).await
+}
+
+/// Vehicles and their owners
+#[derive(OpenApi)]
+#[openapi(paths(list, read, create, update, patch_route, delete_route, restore, purge))]
+pub struct VehiclesApi;
+
 #[tracing::instrument(name = "build_vehicles_router", skip_all)]
 pub fn build_router() -> Router<AppState> {
     tracing::debug!("building vehicles router");
@@ -65,5 +259,8 @@ pub fn build_router() -> Router<AppState> {
         .route("/", post(create))
         .route("/:vehicle_id", get(read))
         .route("/:vehicle_id", put(update))
+        .route("/:vehicle_id", patch(patch_route))
         .route("/:vehicle_id", delete(delete_route))
+        .route("/:vehicle_id/restore", post(restore))
+        .route("/:vehicle_id/purge", delete(purge))
 }
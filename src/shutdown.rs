@@ -0,0 +1,286 @@
+use std::{
+    future::{Future, IntoFuture},
+    time::Duration,
+};
+
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use tokio::{net::TcpListener, sync::watch, time::Instant};
+
+use crate::metrics::Metrics;
+
+/// How the server stopped after being signalled
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    /// Time from the signal until the server stopped
+    pub drain_time: Duration,
+    /// Requests still being handled when the deadline passed, which were cut
+    /// off
+    pub abandoned: i64,
+}
+
+/// Waits for SIGINT, or SIGTERM on Unix
+pub async fn signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "could not listen for SIGINT");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "could not listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = interrupt => tracing::info!("received SIGINT"),
+        () = terminate => tracing::info!("received SIGTERM"),
+    }
+}
+
+/// Records when `signal` completes, so that the deadline can start
+fn observe(
+    signal: impl Future<Output = ()> + Send + 'static,
+    deadline: Duration,
+) -> (
+    impl Future<Output = ()> + Send + 'static,
+    watch::Receiver<Option<Instant>>,
+) {
+    let (signalled_tx, signalled_rx) = watch::channel(None);
+    let signal = async move {
+        signal.await;
+        tracing::info!(?deadline, "shutting down, waiting for in-flight requests");
+        signalled_tx.send_replace(Some(Instant::now()));
+    };
+    (signal, signalled_rx)
+}
+
+/// Runs `server` until it has stopped after the signal, or until `deadline`
+/// has passed since, abandoning the requests in flight
+async fn drain(
+    server: impl Future<Output = std::io::Result<()>>,
+    mut signalled_rx: watch::Receiver<Option<Instant>>,
+    deadline: Duration,
+    metrics: &Metrics,
+) -> std::io::Result<Summary> {
+    let deadline_passed = async {
+        let signalled = signalled_rx.wait_for(Option::is_some).await.is_ok();
+        if signalled {
+            tokio::time::sleep(deadline).await;
+        } else {
+            // The server stopped without being signalled
+            std::future::pending::<()>().await;
+        }
+    };
+
+    let abandoned = tokio::select! {
+        res = server => {
+            res?;
+            0
+        }
+        () = deadline_passed => {
+            let abandoned = metrics.requests_in_flight.get();
+            tracing::warn!(abandoned, "deadline passed with requests in flight");
+            abandoned
+        }
+    };
+    let signalled_at = (*signalled_rx.borrow())
+        .ok_or_else(|| std::io::Error::other("server stopped without being signalled"))?;
+
+    Ok(Summary {
+        drain_time: signalled_at.elapsed(),
+        abandoned,
+    })
+}
+
+/// Serves `app` until `signal` completes, then stops accepting connections
+/// and lets in-flight requests finish for up to `deadline`. Requests still
+/// running after that are abandoned, counted with the in-flight gauge of
+/// `metrics`.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    signal: impl Future<Output = ()> + Send + 'static,
+    deadline: Duration,
+    metrics: &Metrics,
+) -> std::io::Result<Summary> {
+    let (signal, signalled_rx) = observe(signal, deadline);
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(signal)
+        .into_future();
+    drain(server, signalled_rx, deadline, metrics).await
+}
+
+/// Like [`serve`], but over HTTPS
+pub async fn serve_tls(
+    listener: TcpListener,
+    app: Router,
+    tls: RustlsConfig,
+    signal: impl Future<Output = ()> + Send + 'static,
+    deadline: Duration,
+    metrics: &Metrics,
+) -> std::io::Result<Summary> {
+    let (signal, signalled_rx) = observe(signal, deadline);
+    let handle = Handle::new();
+    let stop = tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            signal.await;
+            // The deadline is enforced by drain, which counts abandoned requests
+            handle.graceful_shutdown(None);
+        }
+    });
+    let server = axum_server::from_tcp_rustls(listener.into_std()?, tls)
+        .handle(handle)
+        .serve(app.into_make_service());
+
+    let summary = drain(server, signalled_rx, deadline, metrics).await;
+    stop.abort();
+    summary
+}
+
+#[cfg(test)]
+mod shutdown_tests {
+    use super::*;
+    use crate::middleware::metrics::track_requests;
+    use axum::{middleware::from_fn_with_state, routing::get};
+    use std::net::SocketAddr;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::oneshot,
+        task::JoinHandle,
+    };
+
+    /// Serves a route taking `delay` to respond, until the returned sender
+    /// signals shutdown
+    async fn start(
+        delay: Duration,
+        deadline: Duration,
+        metrics: &Metrics,
+    ) -> (
+        SocketAddr,
+        oneshot::Sender<()>,
+        JoinHandle<std::io::Result<Summary>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("could not bind listener");
+        let addr = listener.local_addr().expect("listener has no address");
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(move || async move {
+                    tokio::time::sleep(delay).await;
+                    "done"
+                }),
+            )
+            .layer(from_fn_with_state(metrics.clone(), track_requests));
+        let (signal_tx, signal_rx) = oneshot::channel::<()>();
+        let metrics = metrics.clone();
+        let server = tokio::spawn(async move {
+            let signal = async {
+                signal_rx.await.ok();
+            };
+            serve(listener, app, signal, deadline, &metrics).await
+        });
+        (addr, signal_tx, server)
+    }
+
+    async fn get_slow(addr: SocketAddr) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    async fn wait_for_request(metrics: &Metrics) {
+        while metrics.requests_in_flight.get() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn lets_in_flight_requests_finish() {
+        // Arrange
+        let metrics = Metrics::new();
+        let (addr, signal, server) =
+            start(Duration::from_millis(200), Duration::from_secs(5), &metrics).await;
+        let request = tokio::spawn(get_slow(addr));
+        wait_for_request(&metrics).await;
+
+        // Act
+        signal.send(()).expect("server is not listening");
+        let summary = server
+            .await
+            .expect("server panicked")
+            .expect("server failed");
+
+        // Assert
+        let response = request
+            .await
+            .expect("request panicked")
+            .expect("request failed");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("done"), "{response}");
+        assert_eq!(summary.abandoned, 0);
+        assert!(summary.drain_time < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn abandons_requests_after_deadline() {
+        // Arrange
+        let metrics = Metrics::new();
+        let (addr, signal, server) = start(
+            Duration::from_secs(60),
+            Duration::from_millis(100),
+            &metrics,
+        )
+        .await;
+        tokio::spawn(get_slow(addr));
+        wait_for_request(&metrics).await;
+
+        // Act
+        signal.send(()).expect("server is not listening");
+        let summary = server
+            .await
+            .expect("server panicked")
+            .expect("server failed");
+
+        // Assert
+        assert_eq!(summary.abandoned, 1);
+        assert!(summary.drain_time < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn stops_accepting_connections() {
+        // Arrange
+        let metrics = Metrics::new();
+        let (addr, signal, server) = start(Duration::ZERO, Duration::from_secs(5), &metrics).await;
+
+        // Act
+        signal.send(()).expect("server is not listening");
+        server
+            .await
+            .expect("server panicked")
+            .expect("server failed");
+        let res = get_slow(addr).await;
+
+        // Assert
+        assert!(res.is_err(), "connected after shutdown: {res:?}");
+    }
+}
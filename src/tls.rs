@@ -0,0 +1,334 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::CertificateDer,
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use tokio::task::JoinHandle;
+
+use crate::configuration::TlsConfig;
+
+/// How often the certificate files are checked for changes
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn read_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("can't open {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("can't read certificates from {}", path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("no certificates found in {}", path.display());
+    }
+    Ok(certs)
+}
+
+/// Builds the rustls configuration from the PEM files, requiring client
+/// certificates when a certificate authority is configured
+pub fn server_config(config: &TlsConfig) -> anyhow::Result<ServerConfig> {
+    let certs = read_certs(&config.cert)?;
+    let file =
+        File::open(&config.key).with_context(|| format!("can't open {}", config.key.display()))?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(file))
+        .with_context(|| format!("can't read private key from {}", config.key.display()))?
+        .with_context(|| format!("no private key found in {}", config.key.display()))?;
+
+    let builder =
+        ServerConfig::builder_with_provider(provider()).with_safe_default_protocol_versions()?;
+    let builder = match &config.ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(ca)? {
+                roots.add(cert)?;
+            }
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider()).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config = builder
+        .with_single_cert(certs, key)
+        .context("invalid certificate or private key")?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(server_config)
+}
+
+/// Loads the configured certificates for serving HTTPS
+pub fn load(config: &TlsConfig) -> anyhow::Result<RustlsConfig> {
+    Ok(RustlsConfig::from_config(Arc::new(server_config(config)?)))
+}
+
+/// Modification times of the configured files, missing for files which
+/// can't be read
+fn modified(config: &TlsConfig) -> Vec<Option<SystemTime>> {
+    let paths: [Option<&PathBuf>; 3] = [Some(&config.cert), Some(&config.key), config.ca.as_ref()];
+    paths
+        .into_iter()
+        .flatten()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+/// Checks the configured files every `interval` and reloads `rustls` when
+/// any of them changes. New connections use the reloaded certificates; if
+/// they are invalid, the previous ones are kept.
+pub fn watch(config: TlsConfig, rustls: RustlsConfig, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last_modified = modified(&config);
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            let current = modified(&config);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+
+            match server_config(&config) {
+                Ok(server_config) => {
+                    rustls.reload_from_config(Arc::new(server_config));
+                    tracing::info!("reloaded TLS certificates");
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "keeping previous TLS certificates");
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tls_tests {
+    use super::*;
+    use crate::{metrics::Metrics, shutdown::serve_tls};
+    use axum::{routing::get, Router};
+    use rcgen::{
+        BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    };
+    use rustls::{pki_types::PrivateKeyDer, ClientConfig};
+    use std::net::SocketAddr;
+    use tempfile::TempDir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        sync::oneshot,
+    };
+    use tokio_rustls::TlsConnector;
+
+    struct Authority {
+        cert: Certificate,
+        key: KeyPair,
+    }
+
+    impl Authority {
+        fn new() -> Self {
+            let key = KeyPair::generate().expect("could not generate key");
+            let mut params = CertificateParams::new(Vec::<String>::new())
+                .expect("could not build certificate parameters");
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let cert = params
+                .self_signed(&key)
+                .expect("could not sign certificate");
+            Self { cert, key }
+        }
+
+        /// Issues a certificate for `localhost`, returning it and its key
+        fn issue(&self, purpose: ExtendedKeyUsagePurpose) -> (Certificate, KeyPair) {
+            let key = KeyPair::generate().expect("could not generate key");
+            let mut params = CertificateParams::new(vec!["localhost".to_owned()])
+                .expect("could not build certificate parameters");
+            params.extended_key_usages = vec![purpose];
+            let cert = params
+                .signed_by(&key, &self.cert, &self.key)
+                .expect("could not sign certificate");
+            (cert, key)
+        }
+    }
+
+    /// Writes a server certificate issued by `authority` to `dir`
+    fn write_server_files(dir: &TempDir, authority: &Authority) -> TlsConfig {
+        let (cert, key) = authority.issue(ExtendedKeyUsagePurpose::ServerAuth);
+        let config = TlsConfig {
+            cert: dir.path().join("server.crt"),
+            key: dir.path().join("server.key"),
+            ca: None,
+        };
+        std::fs::write(&config.cert, cert.pem()).expect("could not write certificate");
+        std::fs::write(&config.key, key.serialize_pem()).expect("could not write key");
+        config
+    }
+
+    fn client_config(
+        authority: &Authority,
+        identity: Option<(Certificate, KeyPair)>,
+    ) -> ClientConfig {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(authority.cert.der().clone())
+            .expect("could not trust authority");
+        let builder = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .expect("could not choose protocol versions")
+            .with_root_certificates(roots);
+        match identity {
+            Some((cert, key)) => builder
+                .with_client_auth_cert(
+                    vec![cert.der().clone()],
+                    PrivateKeyDer::try_from(key.serialize_der()).expect("could not convert key"),
+                )
+                .expect("could not use client certificate"),
+            None => builder.with_no_client_auth(),
+        }
+    }
+
+    async fn start(tls: RustlsConfig) -> (SocketAddr, oneshot::Sender<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("could not bind listener");
+        let addr = listener.local_addr().expect("listener has no address");
+        let app = Router::new().route("/", get(|| async { "secure" }));
+        let (signal_tx, signal_rx) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let signal = async {
+                signal_rx.await.ok();
+            };
+            serve_tls(
+                listener,
+                app,
+                tls,
+                signal,
+                Duration::from_secs(1),
+                &Metrics::new(),
+            )
+            .await
+        });
+        (addr, signal_tx)
+    }
+
+    /// Requests `/` over HTTPS, returning the raw response
+    async fn get_root(addr: SocketAddr, client: ClientConfig) -> std::io::Result<String> {
+        let stream = TcpStream::connect(addr).await?;
+        let name = "localhost".try_into().expect("invalid server name");
+        let mut stream = TlsConnector::from(Arc::new(client))
+            .connect(name, stream)
+            .await?;
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[test]
+    fn rejects_missing_key() {
+        // Arrange
+        let dir = TempDir::new().expect("could not create directory");
+        let config = TlsConfig {
+            key: dir.path().join("missing.key"),
+            ..write_server_files(&dir, &Authority::new())
+        };
+
+        // Act
+        let res = server_config(&config);
+
+        // Assert
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn serves_https() {
+        // Arrange
+        let dir = TempDir::new().expect("could not create directory");
+        let authority = Authority::new();
+        let config = write_server_files(&dir, &authority);
+        let (addr, _stop) = start(load(&config).expect("could not load certificates")).await;
+
+        // Act
+        let response = get_root(addr, client_config(&authority, None))
+            .await
+            .expect("request failed");
+
+        // Assert
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("secure"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn requires_client_certificate_with_ca() {
+        // Arrange
+        let dir = TempDir::new().expect("could not create directory");
+        let authority = Authority::new();
+        let ca = dir.path().join("ca.crt");
+        std::fs::write(&ca, authority.cert.pem()).expect("could not write authority");
+        let config = TlsConfig {
+            ca: Some(ca),
+            ..write_server_files(&dir, &authority)
+        };
+        let (addr, _stop) = start(load(&config).expect("could not load certificates")).await;
+
+        // Act
+        let anonymous = get_root(addr, client_config(&authority, None)).await;
+        let identified = get_root(
+            addr,
+            client_config(
+                &authority,
+                Some(authority.issue(ExtendedKeyUsagePurpose::ClientAuth)),
+            ),
+        )
+        .await;
+
+        // Assert
+        assert!(
+            anonymous.as_ref().map_or(true, String::is_empty),
+            "served a client without a certificate: {anonymous:?}"
+        );
+        let response = identified.expect("request with client certificate failed");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn reloads_changed_certificates() {
+        // Arrange
+        let dir = TempDir::new().expect("could not create directory");
+        let config = write_server_files(&dir, &Authority::new());
+        let tls = load(&config).expect("could not load certificates");
+        let original = tls.get_inner();
+        let reloader = watch(config.clone(), tls.clone(), Duration::from_millis(10));
+        // Modification times may be as coarse as the filesystem's clock
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Act
+        let renewed = Authority::new();
+        write_server_files(&dir, &renewed);
+        let reloaded = tokio::time::timeout(Duration::from_secs(5), async {
+            while Arc::ptr_eq(&tls.get_inner(), &original) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        reloader.abort();
+
+        // Assert
+        assert!(reloaded.is_ok(), "certificates were not reloaded");
+    }
+}
@@ -0,0 +1,101 @@
+use crate::error::ApiError;
+
+/// Formats a row version as a strong entity tag
+pub fn entity_tag(version: i64) -> String {
+    format!("\"{version}\"")
+}
+
+/// The entity tags listed in an `If-Match` or `If-None-Match` header. Tags
+/// that aren't row versions can never match and are dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityTags {
+    Any,
+    Versions(Vec<i64>),
+}
+
+impl EntityTags {
+    /// Parses the value of an `If-Match` header, which uses the strong
+    /// comparison: weak tags never match
+    pub fn parse(header: &str) -> Self {
+        Self::parse_tags(header, false)
+    }
+
+    /// Parses the value of an `If-None-Match` header, which uses the weak
+    /// comparison: `W/"3"` matches version 3 like `"3"` does
+    pub fn parse_weak(header: &str) -> Self {
+        Self::parse_tags(header, true)
+    }
+
+    fn parse_tags(header: &str, weak: bool) -> Self {
+        if header.trim() == "*" {
+            return Self::Any;
+        }
+
+        let versions = header
+            .split(',')
+            .map(str::trim)
+            .map(|tag| match tag.strip_prefix("W/") {
+                Some(weak_tag) if weak => weak_tag,
+                _ => tag,
+            })
+            .filter_map(|tag| tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok())
+            .collect();
+        Self::Versions(versions)
+    }
+
+    pub fn matches(&self, version: i64) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Versions(versions) => versions.contains(&version),
+        }
+    }
+
+    /// Fails with `PreconditionFailed` unless the tags match the version
+    pub fn ensure_matches(&self, version: i64) -> Result<(), ApiError> {
+        if self.matches(version) {
+            Ok(())
+        } else {
+            tracing::debug!(?self, version, "entity tag does not match");
+            Err(ApiError::PreconditionFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test_case::test_case("*" => EntityTags::Any)]
+    #[test_case::test_case("\"3\"" => EntityTags::Versions(vec![3]))]
+    #[test_case::test_case("W/\"3\"" => EntityTags::Versions(vec![]))]
+    #[test_case::test_case("W/\"3\", \"4\"" => EntityTags::Versions(vec![4]))]
+    #[test_case::test_case("\"1\", \"2\"" => EntityTags::Versions(vec![1, 2]))]
+    #[test_case::test_case("\"abc\", 4" => EntityTags::Versions(vec![]))]
+    fn parses_correctly(header: &str) -> EntityTags {
+        EntityTags::parse(header)
+    }
+
+    #[test_case::test_case("*" => EntityTags::Any)]
+    #[test_case::test_case("W/\"3\"" => EntityTags::Versions(vec![3]))]
+    #[test_case::test_case("W/\"3\", \"4\"" => EntityTags::Versions(vec![3, 4]))]
+    fn parses_weak_correctly(header: &str) -> EntityTags {
+        EntityTags::parse_weak(header)
+    }
+
+    #[test]
+    fn weak_tags_fail_strong_comparison() {
+        // Arrange
+        let tags = EntityTags::parse("W/\"3\"");
+
+        // Act
+        let res = tags.ensure_matches(3);
+
+        // Assert
+        assert!(matches!(res, Err(ApiError::PreconditionFailed)));
+    }
+
+    #[test]
+    fn formats_as_strong_tag() {
+        assert_eq!(entity_tag(7), "\"7\"");
+    }
+}
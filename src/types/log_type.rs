@@ -1,6 +1,10 @@
+use utoipa::openapi::{schema::Type, Object, ObjectBuilder};
+
 use crate::types::{BrakeComponent, BrakeLocation, FluidType, TireRotationType, TireType};
 
-#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize, fake::Dummy)]
+#[derive(
+    Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize, fake::Dummy, utoipa::ToSchema,
+)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "log_type")]
 pub enum LogType {
@@ -10,6 +14,7 @@ pub enum LogType {
     },
     TireChange {
         #[serde(flatten)]
+        #[schema(schema_with = optional_tire_rotation)]
         rotation: Option<TireRotationType>,
         tire_type: TireType,
         new: bool,
@@ -28,14 +33,60 @@ pub enum LogType {
     BatteryReplacement,
 }
 
-impl std::fmt::Display for LogType {
+/// A tire change may also rotate the tires, in which case the flattened
+/// `tire_rotation_type` is present. Derived schemas would make it required.
+fn optional_tire_rotation() -> Object {
+    ObjectBuilder::new()
+        .property(
+            "tire_rotation_type",
+            ObjectBuilder::new()
+                .schema_type(Type::String)
+                .enum_values(Some(["front_rear", "side", "diagonal"])),
+        )
+        .build()
+}
+
+/// The `log_type` database enum, naming a [`LogType`] without its details
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, fake::Dummy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+#[sqlx(type_name = "log_type")]
+pub enum LogTypeName {
+    FuelUp,
+    TireChange,
+    BrakeReplacement,
+    TireRotation,
+    Fluids,
+    OilChange,
+    Repair,
+    WiperBladeReplacement,
+    BatteryReplacement,
+}
+
+impl From<&LogType> for LogTypeName {
+    fn from(value: &LogType) -> Self {
+        match value {
+            LogType::FuelUp { .. } => Self::FuelUp,
+            LogType::TireChange { .. } => Self::TireChange,
+            LogType::BrakeReplacement { .. } => Self::BrakeReplacement,
+            LogType::TireRotation(_) => Self::TireRotation,
+            LogType::Fluids(_) => Self::Fluids,
+            LogType::OilChange => Self::OilChange,
+            LogType::Repair => Self::Repair,
+            LogType::WiperBladeReplacement => Self::WiperBladeReplacement,
+            LogType::BatteryReplacement => Self::BatteryReplacement,
+        }
+    }
+}
+
+impl std::fmt::Display for LogTypeName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::FuelUp { .. } => write!(f, "fuel_up"),
-            Self::BrakeReplacement { .. } => write!(f, "brake_replacement"),
-            Self::TireChange { .. } => write!(f, "tire_change"),
-            Self::TireRotation(_) => write!(f, "tire_rotation"),
-            Self::Fluids(_) => write!(f, "fluids"),
+            Self::FuelUp => write!(f, "fuel_up"),
+            Self::BrakeReplacement => write!(f, "brake_replacement"),
+            Self::TireChange => write!(f, "tire_change"),
+            Self::TireRotation => write!(f, "tire_rotation"),
+            Self::Fluids => write!(f, "fluids"),
             Self::OilChange => write!(f, "oil_change"),
             Self::Repair => write!(f, "repair"),
             Self::WiperBladeReplacement => write!(f, "wiper_blade_replacement"),
@@ -44,6 +95,12 @@ impl std::fmt::Display for LogType {
     }
 }
 
+impl std::fmt::Display for LogType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        LogTypeName::from(self).fmt(f)
+    }
+}
+
 #[cfg(test)]
 mod serde_tests {
     use super::*;
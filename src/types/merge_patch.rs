@@ -0,0 +1,96 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{error::ApiError, types::FieldError};
+
+/// A JSON Merge Patch (RFC 7396) document. Members of the patch replace the
+/// matching members of the target, `null` members remove them, and members
+/// absent from the patch are left untouched.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+#[serde(transparent)]
+pub struct MergePatch(pub Map<String, Value>);
+
+impl MergePatch {
+    /// Applies the patch to the serialized form of `target`, then reads the
+    /// patched document back as `T`
+    pub fn apply_to<T: DeserializeOwned>(self, target: &impl Serialize) -> Result<T, ApiError> {
+        let mut document =
+            serde_json::to_value(target).map_err(|e| ApiError::Conversion(e.to_string()))?;
+        merge(&mut document, Value::Object(self.0));
+        serde_path_to_error::deserialize(document)
+            .map_err(|e| ApiError::Validation(vec![FieldError::from_path_error(&e)]))
+    }
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(&key);
+        } else {
+            merge(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+    use serde_json::json;
+
+    // Examples from RFC 7396 appendix A
+    #[test_case::test_case(json!({"a": "b"}), json!({"a": "c"}) => json!({"a": "c"}); "replaces member")]
+    #[test_case::test_case(json!({"a": "b"}), json!({"b": "c"}) => json!({"a": "b", "b": "c"}); "adds member")]
+    #[test_case::test_case(json!({"a": "b"}), json!({"a": null}) => json!({}); "removes member")]
+    #[test_case::test_case(json!({"a": "b", "b": "c"}), json!({"a": null}) => json!({"b": "c"}); "removes only nulled member")]
+    #[test_case::test_case(json!({"a": ["b"]}), json!({"a": "c"}) => json!({"a": "c"}); "replaces array")]
+    #[test_case::test_case(json!({"a": "c"}), json!({"a": ["b"]}) => json!({"a": ["b"]}); "replaces with array")]
+    #[test_case::test_case(json!({"a": {"b": "c"}}), json!({"a": {"b": "d", "c": null}}) => json!({"a": {"b": "d"}}); "merges nested object")]
+    #[test_case::test_case(json!({"a": [{"b": "c"}]}), json!({"a": [1]}) => json!({"a": [1]}); "replaces array of objects")]
+    #[test_case::test_case(json!({"e": null}), json!({"a": 1}) => json!({"e": null, "a": 1}); "keeps existing null")]
+    #[test_case::test_case(json!([1, 2]), json!({"a": "b", "c": null}) => json!({"a": "b"}); "replaces non object target")]
+    #[test_case::test_case(json!({}), json!({"a": {"bb": {"ccc": null}}}) => json!({"a": {"bb": {}}}); "drops nested null")]
+    fn merges_correctly(mut target: Value, patch: Value) -> Value {
+        merge(&mut target, patch);
+        target
+    }
+
+    #[test]
+    fn apply_to_reads_back_patched_document() {
+        // Arrange
+        let patch = serde_json::from_value::<MergePatch>(json!({"a": "c", "b": null}))
+            .expect("could not deserialize");
+
+        // Act
+        let patched = patch
+            .apply_to::<Map<String, Value>>(&json!({"a": "b", "b": "c"}))
+            .expect("could not apply patch");
+
+        // Assert
+        assert_eq!(Value::Object(patched), json!({"a": "c"}));
+    }
+
+    #[test]
+    fn apply_to_rejects_invalid_result() {
+        // Arrange
+        let patch =
+            serde_json::from_value::<MergePatch>(json!({"a": 1})).expect("could not deserialize");
+
+        // Act
+        let err = patch
+            .apply_to::<std::collections::HashMap<String, String>>(&json!({"a": "b"}))
+            .expect_err("expected_failure_did_not_occur");
+
+        // Assert
+        assert!(matches!(&err, ApiError::Validation(errors) if errors[0].field == "a"));
+    }
+}
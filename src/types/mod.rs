@@ -1,9 +1,19 @@
 pub mod configuration;
+pub mod entity_tag;
 pub mod log_type;
+pub mod merge_patch;
+pub mod permissions;
 pub mod primitives;
+pub mod sync_token;
+pub mod validation;
 
 pub use configuration::ServerPort;
-pub use log_type::LogType;
+pub use entity_tag::EntityTags;
+pub use log_type::{LogType, LogTypeName};
+pub use merge_patch::MergePatch;
 pub use primitives::{
-    BrakeComponent, BrakeLocation, FluidType, OdometerUnit, TireRotationType, TireType,
+    AuditAction, AuditResource, BrakeComponent, BrakeLocation, DeletionMode, FluidType, MemberRole,
+    OdometerUnit, RevisionAction, TireRotationType, TireType,
 };
+pub use sync_token::{SequenceToken, SyncToken};
+pub use validation::{FieldError, Validate, Validator};
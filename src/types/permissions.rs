@@ -0,0 +1,114 @@
+use uuid::Uuid;
+
+use crate::{error::ApiError, types::MemberRole};
+
+/// Roles allowed to rename an organisation, manage its members and move
+/// vehicles into or out of it. Only owners may grant or revoke the owner
+/// role.
+pub const MANAGERS: &[MemberRole] = &[MemberRole::Owner, MemberRole::Admin];
+
+/// Every role, as required to read the vehicles and log records of an
+/// organisation
+pub const MEMBERS: &[MemberRole] = &[MemberRole::Owner, MemberRole::Admin, MemberRole::Member];
+
+/// Ensure that the acting user holds one of `roles` in the organisation,
+/// returning their role. Anonymous requests and non-members hold no role.
+pub fn ensure_role(
+    actor_role: Option<MemberRole>,
+    roles: &[MemberRole],
+) -> Result<MemberRole, ApiError> {
+    match actor_role {
+        Some(role) if roles.contains(&role) => Ok(role),
+        _ => {
+            tracing::debug!(?actor_role, "acting user does not hold a required role");
+            let mut required = roles.iter().map(MemberRole::to_string).collect::<Vec<_>>();
+            let last = required.pop().unwrap_or_default();
+            let required = if required.is_empty() {
+                last
+            } else {
+                format!("{} or {last}", required.join(", "))
+            };
+            Err(ApiError::OrganisationRoleRequired(required))
+        }
+    }
+}
+
+fn ensure_owner(actor_role: MemberRole) -> Result<(), ApiError> {
+    ensure_role(Some(actor_role), &[MemberRole::Owner]).map(|_| ())
+}
+
+fn ensure_other_owner(other_owner: bool) -> Result<(), ApiError> {
+    if other_owner {
+        Ok(())
+    } else {
+        tracing::error!("member is the last owner of the organisation");
+        Err(ApiError::Conflict(
+            "an organisation must keep at least one owner".to_owned(),
+        ))
+    }
+}
+
+/// Ensure that the acting user may add a member with the given role
+pub fn ensure_can_add(actor_role: Option<MemberRole>, role: MemberRole) -> Result<(), ApiError> {
+    let actor_role = ensure_role(actor_role, MANAGERS)?;
+    if role == MemberRole::Owner {
+        ensure_owner(actor_role)?;
+    }
+    Ok(())
+}
+
+/// Ensure that the acting user may change the role of a member holding
+/// `role`, if there is such a member. `other_owner` tells whether the
+/// organisation has an owner besides them, which it must keep.
+pub fn ensure_can_change(
+    actor_role: Option<MemberRole>,
+    role: Option<MemberRole>,
+    new_role: MemberRole,
+    other_owner: bool,
+) -> Result<(), ApiError> {
+    let actor_role = ensure_role(actor_role, MANAGERS)?;
+    let role = role.ok_or(ApiError::ResourceNotFound)?;
+    if role == MemberRole::Owner || new_role == MemberRole::Owner {
+        ensure_owner(actor_role)?;
+    }
+    if role == MemberRole::Owner && new_role != MemberRole::Owner {
+        ensure_other_owner(other_owner)?;
+    }
+    Ok(())
+}
+
+/// Ensure that a member holding `role`, if there is such a member, may be
+/// removed by the acting user or, when `leaving`, by themselves. Members
+/// can't leave while they own the active vehicles `vehicle_ids` of the
+/// organisation, and the last owner can't leave at all.
+pub fn ensure_can_remove(
+    leaving: bool,
+    actor_role: Option<MemberRole>,
+    role: Option<MemberRole>,
+    other_owner: bool,
+    vehicle_ids: Vec<Uuid>,
+) -> Result<(), ApiError> {
+    let actor_role = if leaving {
+        None
+    } else {
+        Some(ensure_role(actor_role, MANAGERS)?)
+    };
+    let role = role.ok_or(ApiError::ResourceNotFound)?;
+    if role == MemberRole::Owner {
+        if let Some(actor_role) = actor_role {
+            ensure_owner(actor_role)?;
+        }
+        ensure_other_owner(other_owner)?;
+    }
+    if !vehicle_ids.is_empty() {
+        tracing::error!(
+            ?vehicle_ids,
+            "member still owns vehicles of the organisation"
+        );
+        return Err(ApiError::DependentResources {
+            resource: "vehicles".to_owned(),
+            ids: vehicle_ids,
+        });
+    }
+    Ok(())
+}
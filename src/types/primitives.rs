@@ -5,12 +5,19 @@ use anyhow::anyhow;
 use crate::error::ApiError;
 
 #[derive(
-    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, sqlx::Type,
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
 )]
 #[serde(tag = "tire_rotation_type")]
 #[serde(rename_all = "snake_case")]
 #[sqlx(rename_all = "snake_case")]
-#[sqlx(type_name = "text")]
+#[sqlx(type_name = "tire_rotation_type")]
 pub enum TireRotationType {
     FrontRear,
     Side,
@@ -18,11 +25,18 @@ pub enum TireRotationType {
 }
 
 #[derive(
-    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, sqlx::Type,
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
 )]
 #[serde(rename_all = "snake_case")]
 #[sqlx(rename_all = "snake_case")]
-#[sqlx(type_name = "text")]
+#[sqlx(type_name = "tire_type")]
 pub enum TireType {
     Summer,
     Winter,
@@ -30,11 +44,18 @@ pub enum TireType {
 }
 
 #[derive(
-    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, sqlx::Type,
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
 )]
 #[serde(rename_all = "snake_case")]
 #[sqlx(rename_all = "snake_case")]
-#[sqlx(type_name = "text")]
+#[sqlx(type_name = "brake_location")]
 pub enum BrakeLocation {
     Front,
     Rear,
@@ -42,11 +63,18 @@ pub enum BrakeLocation {
 }
 
 #[derive(
-    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, sqlx::Type,
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
 )]
 #[serde(rename_all = "snake_case")]
 #[sqlx(rename_all = "snake_case")]
-#[sqlx(type_name = "text")]
+#[sqlx(type_name = "brake_component")]
 pub enum BrakeComponent {
     Rotors,
     Calipers,
@@ -54,12 +82,19 @@ pub enum BrakeComponent {
 }
 
 #[derive(
-    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy, sqlx::Type,
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
 )]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "fluid_type")]
 #[sqlx(rename_all = "snake_case")]
-#[sqlx(type_name = "text")]
+#[sqlx(type_name = "fluid_type")]
 pub enum FluidType {
     Wiper,
     Transmission,
@@ -67,7 +102,131 @@ pub enum FluidType {
     Coolant,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, fake::Dummy)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+#[sqlx(type_name = "text")]
+pub enum MemberRole {
+    Owner,
+    Admin,
+    #[default]
+    Member,
+}
+
+impl std::fmt::Display for MemberRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Owner => "owner",
+            Self::Admin => "admin",
+            Self::Member => "member",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+#[sqlx(type_name = "text")]
+pub enum RevisionAction {
+    Update,
+    Delete,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+#[sqlx(type_name = "text")]
+pub enum AuditResource {
+    User,
+    Vehicle,
+    LogRecord,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+#[sqlx(type_name = "text")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+}
+
+/// Strategy used when deleting a resource which other resources depend upon
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionMode {
+    /// Refuse to delete while dependent resources exist
+    #[default]
+    Restrict,
+    /// Delete all dependent resources along with the resource
+    Cascade,
+    /// Hand dependent resources over to another resource before deleting
+    Reassign,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    fake::Dummy,
+    utoipa::ToSchema,
+)]
 pub enum OdometerUnit {
     #[serde(rename = "km")]
     #[default]
@@ -76,25 +235,32 @@ pub enum OdometerUnit {
     Imperial,
 }
 
-impl<'q> sqlx::Encode<'q, sqlx::Postgres> for OdometerUnit {
+// Stored as text by every supported database
+impl<'q, DB: sqlx::Database> sqlx::Encode<'q, DB> for OdometerUnit
+where
+    &'q str: sqlx::Encode<'q, DB>,
+{
     fn encode_by_ref(
         &self,
-        buf: &mut <sqlx::Postgres as sqlx::Database>::ArgumentBuffer<'q>,
+        buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
     ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
         let repr = match self {
             Self::Imperial => "mi",
             Self::Metric => "km",
         };
 
-        <&str as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&repr, buf)
+        <&str as sqlx::Encode<DB>>::encode_by_ref(&repr, buf)
     }
 }
 
-impl sqlx::Decode<'_, sqlx::Postgres> for OdometerUnit {
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for OdometerUnit
+where
+    String: sqlx::Decode<'r, DB>,
+{
     fn decode(
-        value: <sqlx::Postgres as sqlx::Database>::ValueRef<'_>,
+        value: <DB as sqlx::Database>::ValueRef<'r>,
     ) -> Result<Self, sqlx::error::BoxDynError> {
-        let repr = <String as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        let repr = <String as sqlx::Decode<DB>>::decode(value)?;
 
         match repr.as_str() {
             "mi" => Ok(Self::Imperial),
@@ -104,9 +270,16 @@ impl sqlx::Decode<'_, sqlx::Postgres> for OdometerUnit {
     }
 }
 
-impl sqlx::Type<sqlx::Postgres> for OdometerUnit {
-    fn type_info() -> <sqlx::Postgres as sqlx::Database>::TypeInfo {
-        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+impl<DB: sqlx::Database> sqlx::Type<DB> for OdometerUnit
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> <DB as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &<DB as sqlx::Database>::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
     }
 }
 
@@ -252,6 +425,69 @@ mod serde_tests {
         }
     }
 
+    mod member_role {
+        use super::*;
+
+        #[test_case::test_case(MemberRole::Owner => json!("owner"))]
+        #[test_case::test_case(MemberRole::Admin => json!("admin"))]
+        #[test_case::test_case(MemberRole::Member => json!("member"))]
+        fn serializes_correctly(role: MemberRole) -> serde_json::Value {
+            serde_json::to_value(role).expect("could not serialize value")
+        }
+
+        #[test_case::test_case(json!("owner") => MemberRole::Owner)]
+        #[test_case::test_case(json!("admin") => MemberRole::Admin)]
+        #[test_case::test_case(json!("member") => MemberRole::Member)]
+        fn deserializes_correctly(value: serde_json::Value) -> MemberRole {
+            serde_json::from_value(value).expect("could not deserialize type")
+        }
+    }
+
+    mod audit_resource {
+        use super::*;
+
+        #[test_case::test_case(AuditResource::User => json!("user"))]
+        #[test_case::test_case(AuditResource::Vehicle => json!("vehicle"))]
+        #[test_case::test_case(AuditResource::LogRecord => json!("log_record"))]
+        fn serializes_correctly(resource: AuditResource) -> serde_json::Value {
+            serde_json::to_value(resource).expect("could not serialize value")
+        }
+    }
+
+    mod audit_action {
+        use super::*;
+
+        #[test_case::test_case(AuditAction::Create => json!("create"))]
+        #[test_case::test_case(AuditAction::Update => json!("update"))]
+        #[test_case::test_case(AuditAction::Delete => json!("delete"))]
+        #[test_case::test_case(AuditAction::Restore => json!("restore"))]
+        #[test_case::test_case(AuditAction::Purge => json!("purge"))]
+        fn serializes_correctly(action: AuditAction) -> serde_json::Value {
+            serde_json::to_value(action).expect("could not serialize value")
+        }
+    }
+
+    mod revision_action {
+        use super::*;
+
+        #[test_case::test_case(RevisionAction::Update => json!("update"))]
+        #[test_case::test_case(RevisionAction::Delete => json!("delete"))]
+        fn serializes_correctly(action: RevisionAction) -> serde_json::Value {
+            serde_json::to_value(action).expect("could not serialize value")
+        }
+    }
+
+    mod deletion_mode {
+        use super::*;
+
+        #[test_case::test_case(json!("restrict") => DeletionMode::Restrict)]
+        #[test_case::test_case(json!("cascade") => DeletionMode::Cascade)]
+        #[test_case::test_case(json!("reassign") => DeletionMode::Reassign)]
+        fn deserializes_correctly(value: serde_json::Value) -> DeletionMode {
+            serde_json::from_value(value).expect("could not deserialize type")
+        }
+    }
+
     mod odometer_unit {
         use super::*;
 
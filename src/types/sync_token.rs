@@ -0,0 +1,77 @@
+use crate::error::ApiError;
+
+/// An opaque position in the change feed. Tokens are Postgres snapshots in
+/// their text form, `xmin:xmax:xip,...`, and changes since a token are those
+/// written by transactions that were not yet visible in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncToken(String);
+
+impl SyncToken {
+    /// Parses a token given by a client
+    pub fn parse(token: &str) -> Result<Self, ApiError> {
+        let is_xid = |part: &str| !part.is_empty() && part.parse::<u64>().is_ok();
+        let valid = match token.split(':').collect::<Vec<_>>().as_slice() {
+            [xmin, xmax, xip] => {
+                is_xid(xmin) && is_xid(xmax) && (xip.is_empty() || xip.split(',').all(is_xid))
+            }
+            _ => false,
+        };
+        if valid {
+            Ok(Self(token.to_owned()))
+        } else {
+            Err(ApiError::InvalidRequest(format!(
+                "invalid sync token: {token}"
+            )))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A position in the change feed of a backend without snapshots, which
+/// numbers its writes instead. Changes since a token are those numbered
+/// after it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SequenceToken(pub u64);
+
+impl SequenceToken {
+    /// Parses a token given by a client
+    pub fn parse(token: &str) -> Result<Self, ApiError> {
+        token
+            .parse::<u64>()
+            .map(Self)
+            .map_err(|_| ApiError::InvalidRequest(format!("invalid sync token: {token}")))
+    }
+}
+
+impl std::fmt::Display for SequenceToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test_case::test_case("740:740:" => true ; "no transactions in progress")]
+    #[test_case::test_case("740:745:741,743" => true ; "transactions in progress")]
+    #[test_case::test_case("" => false ; "empty")]
+    #[test_case::test_case("740:740" => false ; "missing part")]
+    #[test_case::test_case("740:abc:" => false ; "not a transaction id")]
+    #[test_case::test_case("740:745:741,,743" => false ; "empty transaction id")]
+    fn accepts_snapshots_only(token: &str) -> bool {
+        SyncToken::parse(token).is_ok()
+    }
+
+    #[test_case::test_case("0" => Some(0) ; "start of the feed")]
+    #[test_case::test_case("42" => Some(42) ; "sequence number")]
+    #[test_case::test_case("" => None ; "empty")]
+    #[test_case::test_case("-1" => None ; "negative")]
+    #[test_case::test_case("740:740:" => None ; "snapshot")]
+    fn parses_sequence_numbers(token: &str) -> Option<u64> {
+        SequenceToken::parse(token).ok().map(|token| token.0)
+    }
+}
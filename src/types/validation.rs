@@ -0,0 +1,187 @@
+use std::fmt::Display;
+
+use crate::error::ApiError;
+
+/// A problem with a single field of a request body
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct FieldError {
+    /// The path to the field, e.g. `owner_id` or `operations[1].body.year`
+    pub field: String,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Describes a deserialization failure at the path where it occurred
+    pub fn from_path_error(error: &serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let path = error.path().to_string();
+        let message = error.inner().to_string();
+        let message = message
+            .split_once(" at line ")
+            .map_or(message.as_str(), |(message, _)| message);
+
+        // Missing fields are reported against the object that lacks them
+        if let Some(field) = message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.strip_suffix('`'))
+        {
+            let field = match path.as_str() {
+                "." => field.to_owned(),
+                parent => format!("{parent}.{field}"),
+            };
+            return Self::new(field, "required", message);
+        }
+
+        Self::new(path, "invalid_value", message)
+    }
+}
+
+/// Request bodies that can check themselves beyond what deserialization
+/// already enforces
+pub trait Validate {
+    /// Fails with `ApiError::Validation` listing every invalid field
+    fn validate(&self) -> Result<(), ApiError>;
+}
+
+/// Collects field errors so that all of them are reported at once
+#[derive(Debug, Default)]
+pub struct Validator(Vec<FieldError>);
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn required(&mut self, field: &str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.0.push(FieldError::new(
+                field,
+                "required",
+                format!("{field} must not be empty"),
+            ));
+        }
+        self
+    }
+
+    pub fn email(&mut self, field: &str, value: &str) -> &mut Self {
+        let valid = match value.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !value.contains(char::is_whitespace)
+            }
+            None => false,
+        };
+        if !valid {
+            self.0.push(FieldError::new(
+                field,
+                "invalid_email",
+                format!("{field} must be an email address"),
+            ));
+        }
+        self
+    }
+
+    pub fn range<T: PartialOrd + Display>(
+        &mut self,
+        field: &str,
+        value: T,
+        min: T,
+        max: T,
+    ) -> &mut Self {
+        if value < min || value > max {
+            self.0.push(FieldError::new(
+                field,
+                "out_of_range",
+                format!("{field} must be between {min} and {max}"),
+            ));
+        }
+        self
+    }
+
+    pub fn non_negative(&mut self, field: &str, value: f32) -> &mut Self {
+        if !value.is_finite() || value < 0.0 {
+            self.0.push(FieldError::new(
+                field,
+                "negative",
+                format!("{field} must be a non-negative number"),
+            ));
+        }
+        self
+    }
+
+    pub fn finish(&mut self) -> Result<(), ApiError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::Validation(std::mem::take(&mut self.0)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod validator_tests {
+    use super::*;
+
+    fn codes(validator: &mut Validator) -> Vec<&'static str> {
+        match validator.finish() {
+            Ok(()) => vec![],
+            Err(ApiError::Validation(errors)) => errors.iter().map(|e| e.code).collect(),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test_case::test_case("Jane" => Vec::<&str>::new() ; "present")]
+    #[test_case::test_case("" => vec!["required"] ; "empty")]
+    #[test_case::test_case("   " => vec!["required"] ; "blank")]
+    fn required(value: &str) -> Vec<&'static str> {
+        codes(Validator::new().required("first_name", value))
+    }
+
+    #[test_case::test_case("jane@example.com" => Vec::<&str>::new() ; "valid")]
+    #[test_case::test_case("jane.example.com" => vec!["invalid_email"] ; "missing at")]
+    #[test_case::test_case("@example.com" => vec!["invalid_email"] ; "missing local part")]
+    #[test_case::test_case("jane@localhost" => vec!["invalid_email"] ; "missing domain dot")]
+    #[test_case::test_case("jane@@example.com" => vec!["invalid_email"] ; "double at")]
+    #[test_case::test_case("jane doe@example.com" => vec!["invalid_email"] ; "whitespace")]
+    fn email(value: &str) -> Vec<&'static str> {
+        codes(Validator::new().email("email", value))
+    }
+
+    #[test_case::test_case(0.0 => Vec::<&str>::new() ; "zero")]
+    #[test_case::test_case(45.5 => Vec::<&str>::new() ; "positive")]
+    #[test_case::test_case(-1.0 => vec!["negative"] ; "negative")]
+    #[test_case::test_case(f32::NAN => vec!["negative"] ; "not a number")]
+    fn non_negative(value: f32) -> Vec<&'static str> {
+        codes(Validator::new().non_negative("fuel_amount", value))
+    }
+
+    #[test]
+    fn collects_every_error() {
+        // Arrange
+        let mut validator = Validator::new();
+
+        // Act
+        validator
+            .required("first_name", "")
+            .range("year", 1800, 1886, 2025)
+            .email("email", "nope");
+
+        // Assert
+        assert_eq!(
+            codes(&mut validator),
+            vec!["required", "out_of_range", "invalid_email"]
+        );
+    }
+}
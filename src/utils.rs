@@ -29,6 +29,7 @@ pub mod test_utils {
 
             crate::controllers::vehicle::create(
                 pool,
+                None,
                 CreateVehicleBody {
                     owner_id: user_id,
                     ..Faker.fake()
@@ -45,5 +46,12 @@ pub mod test_utils {
                 .expect("failed to seed user")
                 .id
         }
+
+        pub async fn seed_organisation(pool: &PgPool, owner_id: &Uuid) -> Uuid {
+            crate::controllers::organisation::create(pool, owner_id, Faker.fake())
+                .await
+                .expect("failed to seed organisation")
+                .id
+        }
     }
 }
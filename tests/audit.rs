@@ -0,0 +1,192 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{seed_admin, seed_log_record_and_vehicle, seed_user, TestApp};
+use fake::{Fake, Faker};
+use fuel_logger_rs::models::DbUser;
+use serde_json::json;
+use uuid::Uuid;
+
+backend_tests!(
+    records_mutating_calls,
+    filters_entries,
+    requires_admin,
+    rejects_actor_without_trusted_proxy,
+    skips_idempotent_replays;
+    sql: counts_failed_writes,
+);
+
+async fn records_mutating_calls(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let admin = seed_admin(&app).await;
+    let user = Faker.fake::<DbUser>();
+    let request_id = Uuid::new_v4().to_string();
+
+    // Act
+    let create_res = server
+        .post("/users")
+        .add_header("x-user-id", admin.id.to_string())
+        .add_header("x-request-id", request_id.clone())
+        .json(&json!({
+            "first_name": user.first_name,
+            "last_name": user.last_name,
+            "username": user.username,
+            "email": user.email,
+        }))
+        .await;
+    let created_user_id = create_res.json::<serde_json::Value>()["id"].clone();
+    server
+        .get(format!("/users/{}", admin.id).as_str())
+        .await
+        .assert_status(StatusCode::OK);
+    let res = server
+        .get("/audit")
+        .add_header("x-user-id", admin.id.to_string())
+        .await;
+
+    // Assert
+    create_res.assert_status(StatusCode::CREATED);
+    assert_eq!(create_res.header("x-request-id"), request_id.as_str());
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!([{
+        "actor_id": admin.id,
+        "resource_type": "user",
+        "resource_id": created_user_id,
+        "action": "create",
+        "request_id": request_id,
+    }]));
+    assert_eq!(res.json::<Vec<serde_json::Value>>().len(), 1);
+}
+
+async fn filters_entries(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let admin = seed_admin(&app).await;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    server
+        .delete(format!("/log_records/{}", log_record.id).as_str())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    server
+        .post(format!("/log_records/{}/restore", log_record.id).as_str())
+        .await
+        .assert_status(StatusCode::OK);
+    server
+        .delete(format!("/vehicles/{}", log_record.vehicle_id).as_str())
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    // Act
+    let res = server
+        .get("/audit")
+        .add_query_param("resource_type", "log_record")
+        .add_query_param("action", "restore")
+        .add_header("x-user-id", admin.id.to_string())
+        .await;
+    let all_res = server
+        .get("/audit")
+        .add_header("x-user-id", admin.id.to_string())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!([{
+        "resource_type": "log_record",
+        "resource_id": log_record.id,
+        "action": "restore",
+        "actor_id": null,
+    }]));
+    assert_eq!(res.json::<Vec<serde_json::Value>>().len(), 1);
+    // Failed calls are not recorded
+    assert_eq!(all_res.json::<Vec<serde_json::Value>>().len(), 2);
+}
+
+async fn requires_admin(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+
+    // Act
+    let anonymous_res = server.get("/audit").await;
+    let user_res = server
+        .get("/audit")
+        .add_header("x-user-id", user.id.to_string())
+        .await;
+
+    // Assert
+    anonymous_res.assert_status(StatusCode::FORBIDDEN);
+    user_res.assert_status(StatusCode::FORBIDDEN);
+}
+
+async fn rejects_actor_without_trusted_proxy(app: TestApp) {
+    // Arrange
+    let server = app.untrusted_server();
+    let admin = seed_admin(&app).await;
+
+    // Act
+    let res = server
+        .get("/audit")
+        .add_header("x-user-id", admin.id.to_string())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::BAD_REQUEST);
+}
+
+async fn counts_failed_writes(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = Faker.fake::<DbUser>();
+    app.execute("DROP TABLE audit_log").await;
+
+    // Act
+    let res = server
+        .post("/users")
+        .json(&json!({
+            "first_name": user.first_name,
+            "last_name": user.last_name,
+            "username": user.username,
+            "email": user.email,
+        }))
+        .await;
+    let metrics_res = server.get("/metrics").await;
+
+    // Assert
+    res.assert_status(StatusCode::CREATED);
+    assert!(metrics_res
+        .text()
+        .contains("fuel_logger_audit_write_failures_total 1"));
+}
+
+async fn skips_idempotent_replays(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let admin = seed_admin(&app).await;
+    let user = Faker.fake::<DbUser>();
+    let input = json!({
+        "first_name": user.first_name,
+        "last_name": user.last_name,
+        "username": user.username,
+        "email": user.email,
+    });
+
+    // Act
+    for _ in 0..2 {
+        server
+            .post("/users")
+            .add_header("x-user-id", admin.id.to_string())
+            .add_header("idempotency-key", "create-user")
+            .json(&input)
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+    let res = server
+        .get("/audit")
+        .add_header("x-user-id", admin.id.to_string())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    assert_eq!(res.json::<Vec<serde_json::Value>>().len(), 1);
+}
@@ -1,139 +1,137 @@
 #![allow(dead_code)]
 use fake::{Fake, Faker};
-use fuel_logger_rs::models::{DbLogRecord, DbUser, DbVehicle};
-use sqlx::{query_as, PgPool};
+use fuel_logger_rs::{
+    models::{DbLogRecord, DbOrganisation, DbOrganisationMember, DbUser, DbVehicle},
+    types::MemberRole,
+};
 use uuid::Uuid;
 
-pub async fn seed_user(pool: &PgPool) -> DbUser {
+use super::TestApp;
+
+pub async fn seed_user(app: &TestApp) -> DbUser {
     let user = Faker.fake::<DbUser>();
-    query_as::<_, DbUser>(
-        "
-        INSERT INTO users (
-            id, 
-            first_name, 
-            last_name, 
-            username, 
-            email
-        ) VALUES (
-            $1, 
-            $2, 
-            $3, 
-            $4, 
-            $5
-        ) RETURNING *",
-    )
-    .bind(user.id)
-    .bind(&user.first_name)
-    .bind(&user.last_name)
-    .bind(&user.username)
-    .bind(&user.email)
-    .fetch_one(pool)
-    .await
-    .expect("could not seed user record")
+    let users = &app.repositories.users;
+    let id = users
+        .insert(&user)
+        .await
+        .expect("could not seed user record");
+    users.find(&id).await.expect("could not read user record")
+}
+
+pub async fn seed_admin(app: &TestApp) -> DbUser {
+    let user = seed_user(app).await;
+    app.grant_admin(&user.id).await;
+    user
+}
+
+pub async fn seed_vehicle(app: &TestApp, owner_id: Uuid) -> DbVehicle {
+    let vehicle = DbVehicle {
+        owner_id,
+        ..Faker.fake()
+    };
+    let vehicles = &app.repositories.vehicles;
+    let id = vehicles
+        .insert(&vehicle, None)
+        .await
+        .expect("could not seed vehicle record");
+    vehicles
+        .find(&id)
+        .await
+        .expect("could not read vehicle record")
+}
+
+/// Seeds an organisation owned by the given user
+pub async fn seed_organisation(app: &TestApp, owner_id: Uuid) -> DbOrganisation {
+    let organisation = Faker.fake::<DbOrganisation>();
+    let organisations = &app.repositories.organisations;
+    let id = organisations
+        .insert(&organisation, &owner_id)
+        .await
+        .expect("could not seed organisation record");
+    organisations
+        .find(&id)
+        .await
+        .expect("could not read organisation record")
+}
+
+/// One of the owners of an organisation, on whose behalf it is seeded
+async fn organisation_owner(app: &TestApp, organisation_id: Uuid) -> Uuid {
+    app.repositories
+        .organisations
+        .list_members(&organisation_id)
+        .await
+        .expect("could not read organisation members")
+        .into_iter()
+        .find(|member| member.role == MemberRole::Owner)
+        .expect("organisation has no owner")
+        .user_id
+}
+
+/// Adds a member to an organisation on behalf of one of its owners
+pub async fn seed_member(app: &TestApp, organisation_id: Uuid, user_id: Uuid, role: MemberRole) {
+    let owner_id = organisation_owner(app, organisation_id).await;
+    app.repositories
+        .organisations
+        .add_member(
+            &DbOrganisationMember {
+                organisation_id,
+                user_id,
+                role,
+            },
+            Some(owner_id),
+        )
+        .await
+        .expect("could not seed organisation member");
 }
 
-pub async fn seed_vehicle(pool: &PgPool, owner_id: Uuid) -> DbVehicle {
+/// Seeds a vehicle of an organisation, owned by one of its members, on
+/// behalf of one of its owners
+pub async fn seed_fleet_vehicle(app: &TestApp, organisation_id: Uuid, owner_id: Uuid) -> DbVehicle {
     let vehicle = DbVehicle {
         owner_id,
+        organisation_id: Some(organisation_id),
         ..Faker.fake()
     };
-    query_as::<_, DbVehicle>(
-        "
-        INSERT INTO vehicles (
-            id, 
-            owner_id, 
-            make, 
-            model, 
-            year, 
-            odometer_unit
-        ) VALUES (
-            $1, 
-            $2, 
-            $3, 
-            $4, 
-            $5, 
-            $6
-        ) RETURNING *",
-    )
-    .bind(vehicle.id)
-    .bind(vehicle.owner_id)
-    .bind(vehicle.make)
-    .bind(vehicle.model)
-    .bind(vehicle.year)
-    .bind(vehicle.odometer_unit)
-    .fetch_one(pool)
-    .await
-    .expect("could not seed vehicle record")
+    let actor_id = organisation_owner(app, organisation_id).await;
+    let vehicles = &app.repositories.vehicles;
+    let id = vehicles
+        .insert(&vehicle, Some(actor_id))
+        .await
+        .expect("could not seed vehicle record");
+    vehicles
+        .find(&id)
+        .await
+        .expect("could not read vehicle record")
 }
 
-pub async fn seed_vehicle_and_user(pool: &PgPool) -> DbVehicle {
-    let user = seed_user(pool).await;
+pub async fn seed_vehicle_and_user(app: &TestApp) -> DbVehicle {
+    let user = seed_user(app).await;
 
-    seed_vehicle(pool, user.id).await
+    seed_vehicle(app, user.id).await
 }
 
-pub async fn seed_log_record_and_vehicle(pool: &PgPool) -> DbLogRecord {
-    let vehicle = seed_vehicle_and_user(pool).await;
-    seed_log_record(pool, vehicle.id).await
+pub async fn seed_log_record_and_vehicle(app: &TestApp) -> DbLogRecord {
+    let vehicle = seed_vehicle_and_user(app).await;
+    seed_log_record(app, vehicle.id).await
 }
 
-pub async fn seed_log_record(pool: &PgPool, vehicle_id: Uuid) -> DbLogRecord {
+pub async fn seed_log_record(app: &TestApp, vehicle_id: Uuid) -> DbLogRecord {
     let log_record = DbLogRecord {
         vehicle_id,
         ..Faker.fake()
     };
 
-    write_log_record(pool, log_record).await
+    write_log_record(app, log_record).await
 }
 
-pub async fn write_log_record(pool: &PgPool, log_record: DbLogRecord) -> DbLogRecord {
-    query_as::<_, DbLogRecord>(
-        "
-        INSERT INTO log_records (
-            id,
-            vehicle_id,
-            log_date,
-            odometer,
-            log_type,
-            fuel_amount,
-            notes,
-            tire_rotation_type,
-            tire_type,
-            new_tires,
-            brake_location,
-            brake_part,
-            fluid_type
-        ) VALUES (
-            $1,
-            $2,
-            $3,
-            $4,
-            $5,
-            $6,
-            $7,
-            $8,
-            $9,
-            $10,
-            $11,
-            $12,
-            $13
-        ) RETURNING *
-    ",
-    )
-    .bind(log_record.id)
-    .bind(log_record.vehicle_id)
-    .bind(log_record.date)
-    .bind(log_record.odometer)
-    .bind(log_record.log_type())
-    .bind(log_record.fuel_amount())
-    .bind(&log_record.notes)
-    .bind(log_record.tire_rotation_type())
-    .bind(log_record.tire_type())
-    .bind(log_record.new_tires())
-    .bind(log_record.brake_location())
-    .bind(log_record.brake_part())
-    .bind(log_record.fluid_type())
-    .fetch_one(pool)
-    .await
-    .expect("could not write log_record")
+pub async fn write_log_record(app: &TestApp, log_record: DbLogRecord) -> DbLogRecord {
+    let log_records = &app.repositories.log_records;
+    let id = log_records
+        .insert(&log_record, true)
+        .await
+        .expect("could not write log_record");
+    log_records
+        .find(&id)
+        .await
+        .expect("could not read log_record")
 }
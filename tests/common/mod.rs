@@ -3,6 +3,7 @@ pub mod db;
 pub mod server;
 
 pub use db::{
-    seed_log_record, seed_log_record_and_vehicle, seed_user, seed_vehicle, seed_vehicle_and_user,
+    seed_admin, seed_fleet_vehicle, seed_log_record, seed_log_record_and_vehicle, seed_member,
+    seed_organisation, seed_user, seed_vehicle, seed_vehicle_and_user, write_log_record,
 };
-pub use server::test_server;
+pub use server::{test_server, Backend, TestApp};
@@ -1,8 +1,194 @@
+#![allow(dead_code)]
 use axum_test::TestServer;
-use sqlx::PgPool;
+use fuel_logger_rs::{
+    build_router_with_state,
+    migrations::SQLITE_MIGRATOR,
+    repositories::{memory::InMemory, Repositories},
+    AppState,
+};
+use sqlx::{sqlite::SqlitePoolOptions, PgPool, SqlitePool};
+use uuid::Uuid;
 
-pub fn test_server(pool: &PgPool) -> TestServer {
-    let app = fuel_logger_rs::build_router(pool);
+/// A server behind a trusted proxy, so that tests can act as any user
+pub fn test_server(state: AppState) -> TestServer {
+    let app = build_router_with_state(state.with_trusted_proxy(true));
 
     TestServer::new(app).expect("could not create test server")
 }
+
+/// Where a test app keeps its data
+#[derive(Debug, Clone)]
+pub enum Backend {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+    Memory(InMemory),
+}
+
+/// A test server along with the repositories it keeps its data in, through
+/// which tests seed and inspect that data whatever the backend
+pub struct TestApp {
+    pub server: TestServer,
+    pub repositories: Repositories,
+    state: AppState,
+    backend: Backend,
+}
+
+impl TestApp {
+    pub fn postgres(pool: &PgPool) -> Self {
+        Self::new(
+            AppState::postgres(pool),
+            Repositories::postgres(pool),
+            Backend::Postgres(pool.clone()),
+        )
+    }
+
+    /// An app keeping its data in a fresh in-memory SQLite database
+    pub async fn sqlite() -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("could not open database");
+        SQLITE_MIGRATOR
+            .run(&pool)
+            .await
+            .expect("could not migrate database");
+
+        Self::new(
+            AppState::sqlite(&pool),
+            Repositories::sqlite(&pool),
+            Backend::Sqlite(pool),
+        )
+    }
+
+    pub fn memory() -> Self {
+        let store = InMemory::default();
+
+        Self::new(
+            AppState::in_memory(&store),
+            Repositories::in_memory(&store),
+            Backend::Memory(store),
+        )
+    }
+
+    fn new(state: AppState, repositories: Repositories, backend: Backend) -> Self {
+        Self {
+            server: test_server(state.clone()),
+            repositories,
+            state,
+            backend,
+        }
+    }
+
+    /// A server sharing the app's data without trusting the `X-User-Id`
+    /// header
+    pub fn untrusted_server(&self) -> TestServer {
+        let app = build_router_with_state(self.state.clone().with_trusted_proxy(false));
+
+        TestServer::new(app).expect("could not create test server")
+    }
+
+    /// The pool of the Postgres database, for checks of its schema
+    pub fn postgres_pool(&self) -> Option<&PgPool> {
+        match &self.backend {
+            Backend::Postgres(pool) => Some(pool),
+            _ => None,
+        }
+    }
+
+    /// Grants a user admin rights, which the API can't do
+    pub async fn grant_admin(&self, user_id: &Uuid) {
+        match &self.backend {
+            Backend::Postgres(pool) => {
+                sqlx::query("UPDATE users SET is_admin = true WHERE id = $1")
+                    .bind(user_id)
+                    .execute(pool)
+                    .await
+                    .expect("could not grant admin privileges");
+            }
+            Backend::Sqlite(pool) => {
+                sqlx::query("UPDATE users SET is_admin = 1 WHERE id = ?1")
+                    .bind(user_id)
+                    .execute(pool)
+                    .await
+                    .expect("could not grant admin privileges");
+            }
+            Backend::Memory(store) => store
+                .grant_admin(user_id)
+                .await
+                .expect("could not grant admin privileges"),
+        }
+    }
+
+    /// Runs a statement against the database, which only SQL backends have
+    pub async fn execute(&self, sql: &str) {
+        match &self.backend {
+            Backend::Postgres(pool) => {
+                sqlx::query(sql)
+                    .execute(pool)
+                    .await
+                    .expect("could not execute");
+            }
+            Backend::Sqlite(pool) => {
+                sqlx::query(sql)
+                    .execute(pool)
+                    .await
+                    .expect("could not execute");
+            }
+            Backend::Memory(_) => panic!("the in-memory backend has no database"),
+        }
+    }
+}
+
+/// Runs each listed test, an `async fn(TestApp)` of the including file,
+/// against every backend. Tests listed after `sql:` need a database, so they
+/// skip the in-memory backend.
+#[macro_export]
+macro_rules! backend_tests {
+    ($($test:ident),* $(,)? $(; sql: $($sql_test:ident),* $(,)?)?) => {
+        mod postgres {
+            use $crate::common::TestApp;
+
+            $(
+                #[sqlx::test]
+                async fn $test(pool: sqlx::PgPool) {
+                    super::$test(TestApp::postgres(&pool)).await;
+                }
+            )*
+            $($(
+                #[sqlx::test]
+                async fn $sql_test(pool: sqlx::PgPool) {
+                    super::$sql_test(TestApp::postgres(&pool)).await;
+                }
+            )*)?
+        }
+
+        mod sqlite {
+            use $crate::common::TestApp;
+
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(TestApp::sqlite().await).await;
+                }
+            )*
+            $($(
+                #[tokio::test]
+                async fn $sql_test() {
+                    super::$sql_test(TestApp::sqlite().await).await;
+                }
+            )*)?
+        }
+
+        mod memory {
+            use $crate::common::TestApp;
+
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(TestApp::memory()).await;
+                }
+            )*
+        }
+    };
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use serde_json::json;
+
+backend_tests!(
+    healthz,
+    readyz,
+    version;
+    sql: readyz_with_pending_migration,
+);
+
+async fn healthz(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+
+    // Act
+    let res = server.get("/healthz").await;
+
+    // Assert
+    res.assert_status_ok();
+    res.assert_json(&json!({"status": "ok"}));
+}
+
+async fn readyz(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+
+    // Act
+    let res = server.get("/readyz").await;
+
+    // Assert
+    res.assert_status_ok();
+    res.assert_json(&json!({"status": "ok"}));
+}
+
+async fn readyz_with_pending_migration(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    app.execute(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT max(version) FROM _sqlx_migrations)",
+    )
+    .await;
+
+    // Act
+    let res = server.get("/readyz").await;
+
+    // Assert
+    res.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    res.assert_json_contains(&json!({"type": "urn:fuel-logger:problem:unavailable"}));
+}
+
+async fn version(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+
+    // Act
+    let res = server.get("/version").await;
+
+    // Assert
+    res.assert_status_ok();
+    let body = res.json::<serde_json::Value>();
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["git_sha"].as_str().is_some_and(|sha| !sha.is_empty()));
+}
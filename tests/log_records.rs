@@ -1,21 +1,45 @@
 use axum::http::StatusCode;
 use chrono::{DateTime, Utc};
 use common::{
-    db::write_log_record, seed_log_record, seed_log_record_and_vehicle, seed_vehicle_and_user,
+    seed_admin, seed_log_record, seed_log_record_and_vehicle, seed_vehicle_and_user,
+    write_log_record, TestApp,
 };
 use fake::{Fake, Faker};
 use fuel_logger_rs::{models::DbLogRecord, types::LogType};
 use serde_json::json;
-use sqlx::{query_as, PgPool, Row};
+use sqlx::Row;
 use uuid::Uuid;
 
 mod common;
 
-#[sqlx::test]
-async fn create(pool: PgPool) {
+backend_tests!(
+    create,
+    create_for_missing_vehicle,
+    create_for_deleted_vehicle,
+    read,
+    list,
+    update,
+    patch,
+    update_changes_type,
+    delete,
+    restore,
+    purge,
+    history,
+    invalid_actor_header,
+    patch_with_if_match,
+    create_with_idempotency_key_replays,
+    create_with_idempotency_key_and_other_params_conflicts,
+    create_duplicate_conflicts_and_is_reported,
+    batch,
+    batch_for_deleted_vehicle,
+    atomic_batch_then_history,
+    create_with_invalid_fields,
+);
+
+async fn create(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let vehicle = seed_vehicle_and_user(&pool).await;
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
     let odometer = (100..100000).fake::<i32>();
     let input = json!({
         "vehicle_id": vehicle.id,
@@ -28,13 +52,16 @@ async fn create(pool: PgPool) {
 
     // Act
     let res = server.post("/log_records").json(&input).await;
-    let created_log_record_id =
-        sqlx::query("SELECT id FROM log_records WHERE odometer = $1 LIMIT 1")
-            .bind(odometer)
-            .fetch_one(&pool)
-            .await
-            .expect("could not fetch from database")
-            .get::<Uuid, _>("id");
+    let created_log_record_id = app
+        .repositories
+        .log_records
+        .list()
+        .await
+        .expect("could not read log records")
+        .into_iter()
+        .find(|log_record| log_record.odometer == odometer)
+        .expect("log record was not written")
+        .id;
 
     // Assert
     res.assert_status(StatusCode::CREATED);
@@ -45,11 +72,56 @@ async fn create(pool: PgPool) {
     res.assert_json_contains(&json!({"id": created_log_record_id}));
 }
 
-#[sqlx::test]
-async fn read(pool: PgPool) {
+async fn create_for_missing_vehicle(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let input = json!({
+        "vehicle_id": Uuid::new_v4(),
+        "odometer": (100..100000).fake::<i32>(),
+        "fuel_amount": Faker.fake::<f32>(),
+        "log_type": "fuel_up",
+    });
+
+    // Act
+    let res = server.post("/log_records").json(&input).await;
+
+    // Assert
+    res.assert_status(StatusCode::NOT_FOUND);
+}
+
+async fn create_for_deleted_vehicle(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    server
+        .delete(format!("/vehicles/{}", vehicle.id).as_str())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let input = json!({
+        "vehicle_id": vehicle.id,
+        "odometer": (100..100000).fake::<i32>(),
+        "fuel_amount": Faker.fake::<f32>(),
+        "log_type": "fuel_up",
+    });
+
+    // Act
+    let res = server.post("/log_records").json(&input).await;
+    let log_records = app
+        .repositories
+        .log_records
+        .list()
+        .await
+        .expect("could not read log records");
+
+    // Assert
+    res.assert_status(StatusCode::NOT_FOUND);
+    assert!(log_records.is_empty());
+}
+
+async fn read(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let log_record = seed_log_record_and_vehicle(&pool).await;
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
 
     // Act
     let res = server
@@ -67,13 +139,12 @@ async fn read(pool: PgPool) {
     }));
 }
 
-#[sqlx::test]
-async fn list(pool: PgPool) {
+async fn list(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let vehicle = seed_vehicle_and_user(&pool).await;
-    let log_record_1 = seed_log_record(&pool, vehicle.id).await;
-    let log_record_2 = seed_log_record(&pool, vehicle.id).await;
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let log_record_1 = seed_log_record(&app, vehicle.id).await;
+    let log_record_2 = seed_log_record(&app, vehicle.id).await;
 
     // Act
     let res = server.get("/log_records").await;
@@ -98,11 +169,10 @@ async fn list(pool: PgPool) {
     ]));
 }
 
-#[sqlx::test]
-async fn update(pool: PgPool) {
+async fn update(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let vehicle = seed_vehicle_and_user(&pool).await;
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
     let initial_log_record = DbLogRecord {
         log_type: LogType::FuelUp {
             fuel_amount: Faker.fake(),
@@ -111,12 +181,13 @@ async fn update(pool: PgPool) {
         ..Faker.fake()
     };
 
-    let log_record = write_log_record(&pool, initial_log_record).await;
+    let log_record = write_log_record(&app, initial_log_record).await;
 
     let updated_log_record = DbLogRecord {
         id: log_record.id,
         vehicle_id: log_record.vehicle_id,
         log_type: log_record.log_type,
+        version: log_record.version + 1,
         ..Faker.fake()
     };
     let update_body = json!({
@@ -133,12 +204,12 @@ async fn update(pool: PgPool) {
         .put(format!("/log_records/{}", log_record.id).as_str())
         .json(&update_body)
         .await;
-    let written_log_record =
-        query_as::<_, DbLogRecord>("SELECT * FROM log_records WHERE id = $1 LIMIT 1")
-            .bind(updated_log_record.id)
-            .fetch_one(&pool)
-            .await
-            .expect("could not read vehicle from db");
+    let written_log_record = app
+        .repositories
+        .log_records
+        .find(&updated_log_record.id)
+        .await
+        .expect("could not read log record");
 
     // Assert
     res.assert_status(StatusCode::OK);
@@ -151,11 +222,49 @@ async fn update(pool: PgPool) {
     }));
 }
 
-#[sqlx::test]
-async fn update_wrong_type(pool: PgPool) {
+async fn patch(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let log_record = write_log_record(
+        &app,
+        DbLogRecord {
+            log_type: LogType::FuelUp { fuel_amount: 30.0 },
+            vehicle_id: vehicle.id,
+            ..Faker.fake()
+        },
+    )
+    .await;
+
+    // Act
+    let res = server
+        .patch(format!("/log_records/{}", log_record.id).as_str())
+        .json(&json!({"fuel_amount": 45.5, "notes": null}))
+        .await;
+    let written_log_record = app
+        .repositories
+        .log_records
+        .find(&log_record.id)
+        .await
+        .expect("could not read log record");
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    assert_eq!(
+        written_log_record,
+        DbLogRecord {
+            log_type: LogType::FuelUp { fuel_amount: 45.5 },
+            notes: None,
+            version: log_record.version + 1,
+            ..log_record
+        }
+    );
+}
+
+async fn update_changes_type(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let vehicle = seed_vehicle_and_user(&pool).await;
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
     let initial_log_record = DbLogRecord {
         log_type: LogType::FuelUp {
             fuel_amount: Faker.fake(),
@@ -164,7 +273,7 @@ async fn update_wrong_type(pool: PgPool) {
         ..Faker.fake()
     };
 
-    let log_record = write_log_record(&pool, initial_log_record).await;
+    let log_record = write_log_record(&app, initial_log_record).await;
 
     let updated_log_record = DbLogRecord {
         id: log_record.id,
@@ -185,24 +294,33 @@ async fn update_wrong_type(pool: PgPool) {
         .put(format!("/log_records/{}", log_record.id).as_str())
         .json(&update_body)
         .await;
+    let written_log_record = app
+        .repositories
+        .log_records
+        .find(&log_record.id)
+        .await
+        .expect("could not read log record");
 
     // Assert
-    res.assert_status(StatusCode::BAD_REQUEST);
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({"log_type": "battery_replacement"}));
+    assert_eq!(written_log_record.log_type, LogType::BatteryReplacement);
+    if let Some(pool) = app.postgres_pool() {
+        // The details of the previous type are dropped along with it
+        let fuel_ups = sqlx::query("SELECT count(*) FROM fuel_ups WHERE log_record_id = $1")
+            .bind(log_record.id)
+            .fetch_one(pool)
+            .await
+            .expect("could not read fuel ups from db")
+            .get::<i64, _>(0);
+        assert_eq!(fuel_ups, 0);
+    }
 }
 
-#[sqlx::test]
-async fn delete(pool: PgPool) {
+async fn delete(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let log_record = seed_log_record_and_vehicle(&pool).await;
-    assert!(
-        query_as::<_, DbLogRecord>("SELECT * FROM log_records WHERE id = $1 LIMIT 1")
-            .bind(log_record.id)
-            .fetch_optional(&pool)
-            .await
-            .expect("could not read log_record from db")
-            .is_some()
-    );
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
 
     // Act
     let res = server
@@ -212,12 +330,414 @@ async fn delete(pool: PgPool) {
     // Assert
     res.assert_status(StatusCode::NO_CONTENT);
     assert!(res.into_bytes().is_empty());
-    assert!(
-        query_as::<_, DbLogRecord>("SELECT * FROM log_records WHERE id = $1 LIMIT 1")
-            .bind(log_record.id)
-            .fetch_optional(&pool)
-            .await
-            .expect("could not read vehicle from db")
-            .is_none()
+    assert!(app
+        .repositories
+        .log_records
+        .find(&log_record.id)
+        .await
+        .is_err());
+}
+
+async fn restore(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    server
+        .delete(format!("/log_records/{}", log_record.id).as_str())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    server
+        .get(format!("/log_records/{}", log_record.id).as_str())
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Act
+    let res = server
+        .post(format!("/log_records/{}/restore", log_record.id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({"id": log_record.id}));
+    server
+        .get(format!("/log_records/{}", log_record.id).as_str())
+        .await
+        .assert_status(StatusCode::OK);
+}
+
+async fn purge(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+
+    // Act
+    let premature_res = server
+        .delete(format!("/log_records/{}/purge", log_record.id).as_str())
+        .await;
+    server
+        .delete(format!("/log_records/{}", log_record.id).as_str())
+        .await;
+    let res = server
+        .delete(format!("/log_records/{}/purge", log_record.id).as_str())
+        .await;
+
+    // Assert
+    premature_res.assert_status(StatusCode::CONFLICT);
+    res.assert_status(StatusCode::NO_CONTENT);
+    // History outlives soft deletion, but not purging
+    assert!(app
+        .repositories
+        .log_records
+        .history(&log_record.id)
+        .await
+        .is_err());
+}
+
+async fn history(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    let actor_id = Uuid::new_v4();
+
+    // Act
+    server
+        .delete(format!("/log_records/{}", log_record.id).as_str())
+        .add_header("x-user-id", actor_id.to_string())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let res = server
+        .get(format!("/log_records/{}/history", log_record.id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!([{
+        "log_record_id": log_record.id,
+        "action": "delete",
+        "changed_by": actor_id,
+        "previous": {
+            "id": log_record.id,
+            "vehicle_id": log_record.vehicle_id,
+            "odometer": log_record.odometer,
+        },
+    }]));
+}
+
+async fn invalid_actor_header(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+
+    // Act
+    let res = server
+        .delete(format!("/log_records/{}", log_record.id).as_str())
+        .add_header("x-user-id", "not-a-uuid")
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::BAD_REQUEST);
+}
+
+async fn patch_with_if_match(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    let etag = format!("\"{}\"", log_record.version);
+
+    // Act
+    let res = server
+        .patch(format!("/log_records/{}", log_record.id).as_str())
+        .add_header("if-match", etag.clone())
+        .json(&json!({"notes": "first"}))
+        .await;
+    let stale_res = server
+        .patch(format!("/log_records/{}", log_record.id).as_str())
+        .add_header("if-match", etag)
+        .json(&json!({"notes": "second"}))
+        .await;
+    let any_res = server
+        .patch(format!("/log_records/{}", log_record.id).as_str())
+        .add_header("if-match", "*")
+        .json(&json!({"notes": "third"}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    stale_res.assert_status(StatusCode::PRECONDITION_FAILED);
+    any_res.assert_status(StatusCode::OK);
+    any_res.assert_json_contains(&json!({"notes": "third"}));
+}
+
+async fn create_with_idempotency_key_replays(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let input = json!({
+        "vehicle_id": vehicle.id,
+        "odometer": (100..100000).fake::<i32>(),
+        "fuel_amount": Faker.fake::<f32>(),
+        "log_type": "fuel_up",
+    });
+
+    // Act
+    let first_res = server
+        .post("/log_records")
+        .add_header("idempotency-key", "fill-up-1")
+        .json(&input)
+        .await;
+    let retry_res = server
+        .post("/log_records")
+        .add_header("idempotency-key", "fill-up-1")
+        .json(&input)
+        .await;
+    let log_record_count = app
+        .repositories
+        .log_records
+        .list()
+        .await
+        .expect("could not read log records")
+        .iter()
+        .filter(|log_record| log_record.vehicle_id == vehicle.id)
+        .count();
+
+    // Assert
+    first_res.assert_status(StatusCode::CREATED);
+    retry_res.assert_status(StatusCode::CREATED);
+    assert_eq!(retry_res.header("location"), first_res.header("location"));
+    assert_eq!(
+        retry_res.json::<serde_json::Value>(),
+        first_res.json::<serde_json::Value>()
     );
+    assert_eq!(retry_res.header("idempotent-replayed"), "true");
+    assert_eq!(log_record_count, 1);
+}
+
+async fn create_with_idempotency_key_and_other_params_conflicts(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let input = json!({
+        "vehicle_id": vehicle.id,
+        "odometer": (100..100000).fake::<i32>(),
+        "fuel_amount": Faker.fake::<f32>(),
+        "log_type": "fuel_up",
+    });
+
+    // Act
+    let first_res = server
+        .post("/log_records")
+        .add_header("idempotency-key", "fill-up-1")
+        .json(&input)
+        .await;
+    let retry_res = server
+        .post("/log_records")
+        .add_query_param("allow_duplicate", true)
+        .add_header("idempotency-key", "fill-up-1")
+        .json(&input)
+        .await;
+
+    // Assert
+    first_res.assert_status(StatusCode::CREATED);
+    retry_res.assert_status(StatusCode::CONFLICT);
+    retry_res.assert_json_contains(&json!({
+        "detail": "the idempotency key was already used for a different request",
+    }));
+}
+
+async fn create_duplicate_conflicts_and_is_reported(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let date = Utc::now();
+    let input = json!({
+        "vehicle_id": vehicle.id,
+        "odometer": (100..100000).fake::<i32>(),
+        "date": date,
+        "fuel_amount": Faker.fake::<f32>(),
+        "log_type": "fuel_up",
+    });
+    let mut later_input = input.clone();
+    later_input["date"] = json!(date + chrono::Duration::hours(1));
+    let first_res = server.post("/log_records").json(&input).await;
+    let existing_id = first_res.json::<serde_json::Value>()["id"].clone();
+
+    // Act
+    let duplicate_res = server.post("/log_records").json(&input).await;
+    let allowed_res = server
+        .post("/log_records")
+        .add_query_param("allow_duplicate", true)
+        .json(&later_input)
+        .await;
+    let report_res = server
+        .get("/log_records/duplicates")
+        .add_query_param("vehicle_id", vehicle.id)
+        .await;
+
+    // Assert
+    first_res.assert_status(StatusCode::CREATED);
+    duplicate_res.assert_status(StatusCode::CONFLICT);
+    duplicate_res.assert_json_contains(&json!({"existing_id": existing_id}));
+    allowed_res.assert_status(StatusCode::CREATED);
+    report_res.assert_status(StatusCode::OK);
+    report_res.assert_json_contains(&json!([{
+        "log_record_id": allowed_res.json::<serde_json::Value>()["id"],
+        "duplicate_of": existing_id,
+        "vehicle_id": vehicle.id,
+    }]));
+}
+
+async fn batch(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let admin = seed_admin(&app).await;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    let input = json!({
+        "operations": [
+            {
+                "op": "create",
+                "body": {
+                    "vehicle_id": log_record.vehicle_id,
+                    "odometer": log_record.odometer + 100,
+                    "fuel_amount": Faker.fake::<f32>(),
+                    "log_type": "fuel_up",
+                },
+            },
+            {
+                "op": "patch",
+                "id": log_record.id,
+                "body": {"notes": "batched"},
+                "version": log_record.version,
+            },
+            {"op": "delete", "id": Uuid::new_v4()},
+        ],
+    });
+
+    // Act
+    let res = server
+        .post("/log_records/batch")
+        .add_header("x-user-id", admin.id.to_string())
+        .json(&input)
+        .await;
+    let audit_res = server
+        .get("/audit")
+        .add_header("x-user-id", admin.id.to_string())
+        .await;
+    let notes = app
+        .repositories
+        .log_records
+        .find(&log_record.id)
+        .await
+        .expect("could not read log record")
+        .notes;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({
+        "committed": true,
+        "results": [
+            {"status": 201},
+            {"status": 200, "id": log_record.id},
+            {"status": 404},
+        ],
+    }));
+    assert_eq!(notes.as_deref(), Some("batched"));
+    audit_res.assert_status(StatusCode::OK);
+    assert_eq!(audit_res.json::<Vec<serde_json::Value>>().len(), 2);
+}
+
+async fn batch_for_deleted_vehicle(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    server
+        .delete(format!("/vehicles/{}", vehicle.id).as_str())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let input = json!({
+        "operations": [
+            {
+                "op": "create",
+                "body": {
+                    "vehicle_id": vehicle.id,
+                    "odometer": (100..100000).fake::<i32>(),
+                    "fuel_amount": Faker.fake::<f32>(),
+                    "log_type": "fuel_up",
+                },
+            },
+        ],
+    });
+
+    // Act
+    let res = server.post("/log_records/batch").json(&input).await;
+    let log_records = app
+        .repositories
+        .log_records
+        .list()
+        .await
+        .expect("could not read log records");
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({"results": [{"status": 404}]}));
+    assert!(log_records.is_empty());
+}
+
+async fn atomic_batch_then_history(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    let input = json!({
+        "atomic": true,
+        "operations": [
+            {
+                "op": "patch",
+                "id": log_record.id,
+                "body": {"notes": "batched"},
+                "version": log_record.version,
+            },
+            {"op": "delete", "id": log_record.id, "version": log_record.version + 1},
+        ],
+    });
+
+    // Act
+    let res = server.post("/log_records/batch").json(&input).await;
+    let history_res = server
+        .get(format!("/log_records/{}/history", log_record.id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({
+        "committed": true,
+        "results": [{"status": 200}, {"status": 204}],
+    }));
+    history_res.assert_status(StatusCode::OK);
+    history_res.assert_json_contains(&json!([
+        {"action": "update", "previous": {"notes": log_record.notes}},
+        {"action": "delete", "previous": {"notes": "batched"}},
+    ]));
+}
+
+async fn create_with_invalid_fields(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let input = json!({
+        "vehicle_id": vehicle.id,
+        "odometer": u32::MAX,
+        "fuel_amount": -10.0,
+        "log_type": "fuel_up",
+    });
+
+    // Act
+    let res = server.post("/log_records").json(&input).await;
+
+    // Assert
+    res.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    res.assert_json_contains(&json!({
+        "errors": [
+            {"field": "odometer", "code": "out_of_range"},
+            {"field": "fuel_amount", "code": "negative"},
+        ],
+    }));
 }
@@ -0,0 +1,56 @@
+mod common;
+
+use axum::http::header::CONTENT_TYPE;
+use fuel_logger_rs::AppState;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test]
+async fn serves_openmetrics(pool: PgPool) {
+    // Arrange
+    let app = common::TestApp::postgres(&pool);
+    let server = &app.server;
+    let user = common::seed_user(&app).await;
+    common::seed_vehicle(&app, user.id).await;
+
+    // Act
+    let res = server.get("/metrics").await;
+
+    // Assert
+    res.assert_status_ok();
+    assert_eq!(
+        res.header(CONTENT_TYPE),
+        "application/openmetrics-text; version=1.0.0; charset=utf-8"
+    );
+    let body = res.text();
+    assert!(body.contains("fuel_logger_vehicles 1\n"));
+    assert!(body.contains("fuel_logger_db_pool_connections "));
+    // The scrape itself is the only request being handled
+    assert!(body.contains("fuel_logger_http_requests_in_flight 1\n"));
+    assert!(body.ends_with("# EOF\n"));
+}
+
+#[sqlx::test]
+async fn counts_requests_by_route_template(pool: PgPool) {
+    // Arrange
+    let server = common::test_server(AppState::postgres(&pool));
+    server.get(&format!("/vehicles/{}", Uuid::new_v4())).await;
+    server.get(&format!("/vehicles/{}", Uuid::new_v4())).await;
+    server.get("/nowhere").await;
+
+    // Act
+    let res = server.get("/metrics").await;
+
+    // Assert
+    res.assert_status_ok();
+    let body = res.text();
+    assert!(body.contains(
+        r#"fuel_logger_http_requests_total{method="GET",route="/vehicles/:vehicle_id",status="404"} 2"#
+    ));
+    assert!(body.contains(
+        r#"fuel_logger_http_requests_total{method="GET",route="unmatched",status="404"} 1"#
+    ));
+    assert!(body.contains(
+        r#"fuel_logger_http_request_duration_seconds_count{method="GET",route="/vehicles/:vehicle_id",status="404"} 2"#
+    ));
+}
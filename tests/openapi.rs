@@ -0,0 +1,101 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header::CONTENT_TYPE, Method, Request, StatusCode},
+};
+use fuel_logger_rs::{build_router, routes::openapi::ApiDoc, AppState};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+const METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+];
+
+#[test]
+fn committed_document_is_up_to_date() {
+    // Arrange
+    let committed = include_str!("../openapi.json");
+
+    // Act
+    let generated = ApiDoc::openapi()
+        .to_pretty_json()
+        .expect("could not serialize OpenAPI document");
+
+    // Assert
+    assert!(
+        generated.trim_end() == committed.trim_end(),
+        "openapi.json is out of date, regenerate it with `cargo run -- --openapi > openapi.json`"
+    );
+}
+
+#[sqlx::test]
+async fn serves_document(pool: PgPool) {
+    // Arrange
+    let server = common::test_server(AppState::postgres(&pool));
+
+    // Act
+    let res = server.get("/openapi.json").await;
+
+    // Assert
+    res.assert_status_ok();
+    res.assert_json(&serde_json::to_value(ApiDoc::openapi()).expect("could not serialize"));
+}
+
+#[sqlx::test]
+async fn documented_operations_are_routed(pool: PgPool) {
+    // Arrange
+    let app = build_router(&pool);
+    let document = serde_json::to_value(ApiDoc::openapi()).expect("could not serialize");
+    let paths = document["paths"].as_object().expect("no paths documented");
+
+    for (path, item) in paths {
+        // Path parameters are all ids
+        let path = path
+            .split('/')
+            .map(|segment| {
+                if segment.starts_with('{') {
+                    Uuid::new_v4().to_string()
+                } else {
+                    segment.to_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        for method in METHODS {
+            if item.get(method.as_str().to_lowercase()).is_none() {
+                continue;
+            }
+
+            // Act
+            let request = Request::builder()
+                .method(method.clone())
+                .uri(&path)
+                .body(Body::empty())
+                .expect("could not build request");
+            let res = app.clone().oneshot(request).await.expect("infallible");
+
+            // Assert
+            // Unrouted requests are answered by the router without a problem
+            // document, handlers fail with one
+            assert_ne!(
+                res.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{method} {path} is not routed"
+            );
+            if res.status() == StatusCode::NOT_FOUND {
+                let content_type = res.headers().get(CONTENT_TYPE);
+                assert!(
+                    content_type.is_some_and(|value| value == "application/problem+json"),
+                    "{method} {path} is not routed"
+                );
+            }
+        }
+    }
+}
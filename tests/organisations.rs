@@ -0,0 +1,502 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{
+    seed_fleet_vehicle, seed_log_record, seed_member, seed_organisation, seed_user, seed_vehicle,
+    TestApp,
+};
+use fake::{faker::company::en::CompanyName, Fake};
+use fuel_logger_rs::types::MemberRole;
+use serde_json::json;
+use uuid::Uuid;
+
+backend_tests!(
+    create,
+    create_requires_actor,
+    read,
+    update,
+    delete_keeps_vehicles,
+    add_and_list_members,
+    add_member_for_missing_user,
+    members_cannot_add_members,
+    remove_last_owner,
+    remove_member_owning_vehicles,
+    list_fleet_vehicles_and_log_records,
+    list_fleet_as_non_member,
+    vehicle_owner_must_be_member,
+    create_fleet_vehicle_as_member,
+    assign_vehicle_as_admin,
+    assign_vehicle_as_non_member,
+    assign_vehicle_as_member,
+    unassign_vehicle_as_member,
+);
+
+async fn create(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    let name = CompanyName().fake::<String>();
+    let input = json!({
+        "name": name,
+    });
+
+    // Act
+    let res = server
+        .post("/organisations")
+        .add_header("x-user-id", user.id.to_string())
+        .json(&input)
+        .await;
+    let created_organisation_id = app
+        .repositories
+        .organisations
+        .list()
+        .await
+        .expect("could not read organisations")
+        .into_iter()
+        .find(|organisation| organisation.name == name)
+        .expect("organisation was not written")
+        .id;
+
+    // Assert
+    res.assert_status(StatusCode::CREATED);
+    assert_eq!(
+        res.header("location"),
+        format!("/organisations/{created_organisation_id}")
+    );
+    res.assert_json_contains(&json!({"id": created_organisation_id}));
+    server
+        .get(format!("/organisations/{created_organisation_id}/members").as_str())
+        .await
+        .assert_json(&json!([{
+            "organisation_id": created_organisation_id,
+            "user_id": user.id,
+            "role": "owner",
+        }]));
+}
+
+async fn create_requires_actor(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+
+    // Act
+    let res = server
+        .post("/organisations")
+        .json(&json!({"name": CompanyName().fake::<String>()}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::BAD_REQUEST);
+}
+
+async fn read(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+
+    // Act
+    let res = server
+        .get(format!("/organisations/{}", organisation.id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({
+        "id": organisation.id,
+        "name": organisation.name,
+    }));
+}
+
+async fn update(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let updated_name = CompanyName().fake::<String>();
+
+    // Act
+    let res = server
+        .put(format!("/organisations/{}", organisation.id).as_str())
+        .add_header("x-user-id", owner.id.to_string())
+        .json(&json!({"name": updated_name}))
+        .await;
+    let written_organisation = app
+        .repositories
+        .organisations
+        .find(&organisation.id)
+        .await
+        .expect("could not read organisation");
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    assert_eq!(written_organisation.name, updated_name);
+}
+
+async fn delete_keeps_vehicles(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    let organisation = seed_organisation(&app, user.id).await;
+    let vehicle = seed_fleet_vehicle(&app, organisation.id, user.id).await;
+
+    // Act
+    let res = server
+        .delete(format!("/organisations/{}", organisation.id).as_str())
+        .add_header("x-user-id", user.id.to_string())
+        .await;
+    let vehicle_organisation_id = app
+        .repositories
+        .vehicles
+        .find(&vehicle.id)
+        .await
+        .expect("could not read vehicle")
+        .organisation_id;
+
+    // Assert
+    res.assert_status(StatusCode::NO_CONTENT);
+    assert!(vehicle_organisation_id.is_none());
+}
+
+async fn add_and_list_members(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let user = seed_user(&app).await;
+
+    // Act
+    let add_res = server
+        .post(format!("/organisations/{}/members", organisation.id).as_str())
+        .add_header("x-user-id", owner.id.to_string())
+        .json(&json!({"user_id": user.id, "role": "admin"}))
+        .await;
+    let list_res = server
+        .get(format!("/organisations/{}/members", organisation.id).as_str())
+        .await;
+
+    // Assert
+    add_res.assert_status(StatusCode::CREATED);
+    assert_eq!(
+        add_res.header("location"),
+        format!("/organisations/{}/members/{}", organisation.id, user.id)
+    );
+    list_res.assert_status(StatusCode::OK);
+    list_res.assert_json(&json!([
+        {
+            "organisation_id": organisation.id,
+            "user_id": owner.id,
+            "role": "owner",
+        },
+        {
+            "organisation_id": organisation.id,
+            "user_id": user.id,
+            "role": "admin",
+        },
+    ]));
+}
+
+async fn add_member_for_missing_user(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+
+    // Act
+    let res = server
+        .post(format!("/organisations/{}/members", organisation.id).as_str())
+        .add_header("x-user-id", owner.id.to_string())
+        .json(&json!({"user_id": Uuid::new_v4()}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::NOT_FOUND);
+}
+
+async fn members_cannot_add_members(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let member = seed_user(&app).await;
+    seed_member(&app, organisation.id, member.id, MemberRole::Member).await;
+    let user = seed_user(&app).await;
+
+    // Act
+    let res = server
+        .post(format!("/organisations/{}/members", organisation.id).as_str())
+        .add_header("x-user-id", member.id.to_string())
+        .json(&json!({"user_id": user.id}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::FORBIDDEN);
+    res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:organisation-role-required",
+        "detail": "the acting user must be an organisation owner or admin",
+    }));
+}
+
+async fn remove_last_owner(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+
+    // Act
+    let res = server
+        .delete(format!("/organisations/{}/members/{}", organisation.id, owner.id).as_str())
+        .add_header("x-user-id", owner.id.to_string())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::CONFLICT);
+}
+
+async fn remove_member_owning_vehicles(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let user = seed_user(&app).await;
+    seed_member(&app, organisation.id, user.id, MemberRole::Member).await;
+    let vehicle = seed_fleet_vehicle(&app, organisation.id, user.id).await;
+
+    // Act
+    let res = server
+        .delete(format!("/organisations/{}/members/{}", organisation.id, user.id).as_str())
+        .add_header("x-user-id", owner.id.to_string())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::CONFLICT);
+    res.assert_json_contains(&json!({"dependents": [vehicle.id]}));
+}
+
+async fn list_fleet_vehicles_and_log_records(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let user = seed_user(&app).await;
+    seed_member(&app, organisation.id, user.id, MemberRole::Member).await;
+    let create_res = server
+        .post("/vehicles")
+        .add_header("x-user-id", owner.id.to_string())
+        .json(&json!({
+            "owner_id": user.id,
+            "organisation_id": organisation.id,
+            "make": CompanyName().fake::<String>(),
+            "model": CompanyName().fake::<String>(),
+            "year": 2020,
+        }))
+        .await;
+    let vehicle_id = create_res.json::<serde_json::Value>()["id"]
+        .as_str()
+        .expect("missing vehicle id")
+        .parse::<Uuid>()
+        .expect("invalid vehicle id");
+    let log_record = seed_log_record(&app, vehicle_id).await;
+
+    // Act
+    let vehicles_res = server
+        .get(format!("/organisations/{}/vehicles", organisation.id).as_str())
+        .add_header("x-user-id", user.id.to_string())
+        .await;
+    let log_records_res = server
+        .get(format!("/organisations/{}/log_records", organisation.id).as_str())
+        .add_header("x-user-id", user.id.to_string())
+        .await;
+
+    // Assert
+    create_res.assert_status(StatusCode::CREATED);
+    vehicles_res.assert_status(StatusCode::OK);
+    vehicles_res.assert_json_contains(&json!([{
+        "id": vehicle_id,
+        "organisation_id": organisation.id,
+    }]));
+    log_records_res.assert_status(StatusCode::OK);
+    log_records_res.assert_json_contains(&json!([{
+        "id": log_record.id,
+        "vehicle_id": vehicle_id,
+    }]));
+}
+
+async fn list_fleet_as_non_member(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let user = seed_user(&app).await;
+
+    // Act
+    let vehicles_res = server
+        .get(format!("/organisations/{}/vehicles", organisation.id).as_str())
+        .add_header("x-user-id", user.id.to_string())
+        .await;
+    let log_records_res = server
+        .get(format!("/organisations/{}/log_records", organisation.id).as_str())
+        .await;
+
+    // Assert
+    vehicles_res.assert_status(StatusCode::FORBIDDEN);
+    vehicles_res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:organisation-role-required",
+        "detail": "the acting user must be an organisation owner, admin or member",
+    }));
+    log_records_res.assert_status(StatusCode::FORBIDDEN);
+}
+
+async fn vehicle_owner_must_be_member(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let user = seed_user(&app).await;
+
+    // Act
+    let res = server
+        .post("/vehicles")
+        .add_header("x-user-id", owner.id.to_string())
+        .json(&json!({
+            "owner_id": user.id,
+            "organisation_id": organisation.id,
+            "make": CompanyName().fake::<String>(),
+            "model": CompanyName().fake::<String>(),
+            "year": 2020,
+        }))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::FORBIDDEN);
+    res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:not-organisation-member",
+    }));
+}
+
+async fn create_fleet_vehicle_as_member(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let member = seed_user(&app).await;
+    seed_member(&app, organisation.id, member.id, MemberRole::Member).await;
+
+    // Act
+    let res = server
+        .post("/vehicles")
+        .add_header("x-user-id", member.id.to_string())
+        .json(&json!({
+            "owner_id": member.id,
+            "organisation_id": organisation.id,
+            "make": CompanyName().fake::<String>(),
+            "model": CompanyName().fake::<String>(),
+            "year": 2020,
+        }))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::FORBIDDEN);
+    res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:organisation-role-required",
+        "detail": "the acting user must be an organisation owner or admin",
+    }));
+}
+
+async fn assign_vehicle_as_admin(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let admin = seed_user(&app).await;
+    seed_member(&app, organisation.id, admin.id, MemberRole::Admin).await;
+    let member = seed_user(&app).await;
+    seed_member(&app, organisation.id, member.id, MemberRole::Member).await;
+    let vehicle = seed_vehicle(&app, member.id).await;
+
+    // Act
+    let res = server
+        .patch(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("x-user-id", admin.id.to_string())
+        .json(&json!({"organisation_id": organisation.id}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({"organisation_id": organisation.id}));
+}
+
+async fn assign_vehicle_as_non_member(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let user = seed_user(&app).await;
+    let vehicle = seed_vehicle(&app, user.id).await;
+
+    // Act
+    let res = server
+        .patch(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("x-user-id", user.id.to_string())
+        .json(&json!({"organisation_id": organisation.id}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::FORBIDDEN);
+    res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:organisation-role-required",
+    }));
+}
+
+async fn assign_vehicle_as_member(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let member = seed_user(&app).await;
+    seed_member(&app, organisation.id, member.id, MemberRole::Member).await;
+    let vehicle = seed_vehicle(&app, member.id).await;
+
+    // Act
+    let res = server
+        .put(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("x-user-id", member.id.to_string())
+        .json(&json!({
+            "owner_id": member.id,
+            "organisation_id": organisation.id,
+            "make": vehicle.make,
+            "model": vehicle.model,
+            "year": vehicle.year,
+        }))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::FORBIDDEN);
+    res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:organisation-role-required",
+    }));
+}
+
+async fn unassign_vehicle_as_member(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let owner = seed_user(&app).await;
+    let organisation = seed_organisation(&app, owner.id).await;
+    let member = seed_user(&app).await;
+    seed_member(&app, organisation.id, member.id, MemberRole::Member).await;
+    let vehicle = seed_fleet_vehicle(&app, organisation.id, member.id).await;
+
+    // Act
+    let res = server
+        .patch(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("x-user-id", member.id.to_string())
+        .json(&json!({"organisation_id": null}))
+        .await;
+    let vehicles_res = server
+        .get(format!("/organisations/{}/vehicles", organisation.id).as_str())
+        .add_header("x-user-id", member.id.to_string())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::FORBIDDEN);
+    vehicles_res.assert_json_contains(&json!([{"id": vehicle.id}]));
+}
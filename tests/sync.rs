@@ -0,0 +1,57 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{seed_log_record_and_vehicle, seed_user, TestApp};
+use serde_json::json;
+
+backend_tests!(sync_since_token, invalid_token);
+
+async fn sync_since_token(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    let first_res = server.get("/sync").await;
+    let token = first_res.json::<serde_json::Value>()["token"].clone();
+    server
+        .patch(format!("/log_records/{}", log_record.id).as_str())
+        .json(&json!({"notes": "synced"}))
+        .await
+        .assert_status(StatusCode::OK);
+    let user = seed_user(&app).await;
+    server
+        .delete(format!("/users/{}", user.id).as_str())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Act
+    let res = server
+        .get("/sync")
+        .add_query_param("since", token.as_str().expect("token is not a string"))
+        .await;
+
+    // Assert
+    first_res.assert_status(StatusCode::OK);
+    first_res.assert_json_contains(&json!({
+        "log_records": {"changed": [{"id": log_record.id, "version": 1}], "deleted": []},
+    }));
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({
+        "users": {"changed": [], "deleted": [user.id]},
+        "vehicles": {"changed": [], "deleted": []},
+        "log_records": {
+            "changed": [{"id": log_record.id, "notes": "synced", "version": 2}],
+            "deleted": [],
+        },
+    }));
+}
+
+async fn invalid_token(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+
+    // Act
+    let res = server.get("/sync").add_query_param("since", "abc").await;
+
+    // Assert
+    res.assert_status(StatusCode::BAD_REQUEST);
+}
@@ -1,7 +1,8 @@
 mod common;
 
 use axum::http::StatusCode;
-use common::seed_user;
+use axum_test::TestServer;
+use common::{seed_log_record_and_vehicle, seed_user, seed_vehicle_and_user, TestApp};
 use fake::{
     faker::{
         internet::en::{FreeEmail, Username},
@@ -9,15 +10,36 @@ use fake::{
     },
     Fake, Faker,
 };
-use fuel_logger_rs::models::DbUser;
+use fuel_logger_rs::{build_router_with_state, models::DbUser, AppState};
 use serde_json::json;
-use sqlx::{query_as, PgPool, Row};
-use uuid::Uuid;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::time::Duration;
 
-#[sqlx::test]
-async fn create(pool: PgPool) {
+backend_tests!(
+    create,
+    read,
+    list,
+    update,
+    patch,
+    patch_rejects_null_required_field,
+    delete,
+    delete_with_vehicles_conflicts,
+    delete_with_cascade,
+    delete_with_reassignment,
+    restore,
+    restore_brings_back_cascaded_log_records,
+    delete_with_stale_if_match,
+    patch_with_weak_if_match,
+    create_retry_after_failure_with_idempotency_key,
+    create_with_idempotency_key_replays,
+    create_with_invalid_fields,
+    create_with_taken_username,
+    create_with_taken_email,
+);
+
+async fn create(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
+    let server = &app.server;
     let username = Username().fake::<String>();
     let input = json!({
         "first_name": FirstName().fake::<String>(),
@@ -28,12 +50,16 @@ async fn create(pool: PgPool) {
 
     // Act
     let res = server.post("/users").json(&input).await;
-    let created_user_id = sqlx::query("SELECT id FROM users WHERE username = $1 LIMIT 1")
-        .bind(username)
-        .fetch_one(&pool)
+    let created_user_id = app
+        .repositories
+        .users
+        .list()
         .await
-        .expect("could not fetch from database")
-        .get::<Uuid, _>("id");
+        .expect("could not read users")
+        .into_iter()
+        .find(|user| user.username == username)
+        .expect("user was not written")
+        .id;
 
     // Assert
     res.assert_status(StatusCode::CREATED);
@@ -41,11 +67,10 @@ async fn create(pool: PgPool) {
     res.assert_json_contains(&json!({"id": created_user_id}));
 }
 
-#[sqlx::test]
-async fn read(pool: PgPool) {
+async fn read(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let user = seed_user(&pool).await;
+    let server = &app.server;
+    let user = seed_user(&app).await;
 
     // Act
     let res = server.get(format!("/users/{}", user.id).as_str()).await;
@@ -61,12 +86,11 @@ async fn read(pool: PgPool) {
     }));
 }
 
-#[sqlx::test]
-async fn list(pool: PgPool) {
+async fn list(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let user_1 = seed_user(&pool).await;
-    let user_2 = seed_user(&pool).await;
+    let server = &app.server;
+    let user_1 = seed_user(&app).await;
+    let user_2 = seed_user(&app).await;
 
     // Act
     let res = server.get("/users").await;
@@ -91,13 +115,13 @@ async fn list(pool: PgPool) {
     ]));
 }
 
-#[sqlx::test]
-async fn update(pool: PgPool) {
+async fn update(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let user = seed_user(&pool).await;
+    let server = &app.server;
+    let user = seed_user(&app).await;
     let updated_user = DbUser {
         id: user.id,
+        version: user.version + 1,
         ..Faker.fake()
     };
     let update_body = json!({
@@ -112,11 +136,12 @@ async fn update(pool: PgPool) {
         .put(format!("/users/{}", user.id).as_str())
         .json(&update_body)
         .await;
-    let written_user = query_as::<_, DbUser>("SELECT * FROM users WHERE id = $1 LIMIT 1")
-        .bind(updated_user.id)
-        .fetch_one(&pool)
+    let written_user = app
+        .repositories
+        .users
+        .find(&updated_user.id)
         .await
-        .expect("could not read user from db");
+        .expect("could not read user");
 
     // Assert
     res.assert_status(StatusCode::OK);
@@ -130,19 +155,58 @@ async fn update(pool: PgPool) {
     }));
 }
 
-#[sqlx::test]
-async fn delete(pool: PgPool) {
-    // Arrange
-    let server = common::test_server(&pool);
-    let user = seed_user(&pool).await;
-    assert!(
-        query_as::<_, DbUser>("SELECT * FROM users WHERE id = $1 LIMIT 1")
-            .bind(user.id)
-            .fetch_optional(&pool)
-            .await
-            .expect("could not read user from db")
-            .is_some()
+async fn patch(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    let email = FreeEmail().fake::<String>();
+
+    // Act
+    let res = server
+        .patch(format!("/users/{}", user.id).as_str())
+        .json(&json!({"email": email}))
+        .await;
+    let written_user = app
+        .repositories
+        .users
+        .find(&user.id)
+        .await
+        .expect("could not read user");
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    assert_eq!(
+        written_user,
+        DbUser {
+            email,
+            version: user.version + 1,
+            ..user
+        }
     );
+}
+
+async fn patch_rejects_null_required_field(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+
+    // Act
+    let res = server
+        .patch(format!("/users/{}", user.id).as_str())
+        .json(&json!({"email": null}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    res.assert_json_contains(&json!({
+        "errors": [{"field": "email", "code": "required"}],
+    }));
+}
+
+async fn delete(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
 
     // Act
     let res = server.delete(format!("/users/{}", user.id).as_str()).await;
@@ -150,12 +214,337 @@ async fn delete(pool: PgPool) {
     // Assert
     res.assert_status(StatusCode::NO_CONTENT);
     assert!(res.into_bytes().is_empty());
-    assert!(
-        query_as::<_, DbUser>("SELECT * FROM users WHERE id = $1 LIMIT 1")
-            .bind(user.id)
-            .fetch_optional(&pool)
-            .await
-            .expect("could not read user from db")
-            .is_none()
+    assert!(app.repositories.users.find(&user.id).await.is_err());
+}
+
+async fn delete_with_vehicles_conflicts(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+
+    // Act
+    let res = server
+        .delete(format!("/users/{}", vehicle.owner_id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::CONFLICT);
+    res.assert_json_contains(&json!({"dependents": [vehicle.id]}));
+}
+
+async fn delete_with_cascade(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    let owner_id = app
+        .repositories
+        .vehicles
+        .find(&log_record.vehicle_id)
+        .await
+        .expect("could not read vehicle")
+        .owner_id;
+
+    // Act
+    let res = server
+        .delete(format!("/users/{owner_id}").as_str())
+        .add_query_param("mode", "cascade")
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::NO_CONTENT);
+    assert!(app
+        .repositories
+        .log_records
+        .find(&log_record.id)
+        .await
+        .is_err());
+}
+
+async fn delete_with_reassignment(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let new_owner = seed_user(&app).await;
+
+    // Act
+    let res = server
+        .delete(format!("/users/{}", vehicle.owner_id).as_str())
+        .add_query_param("mode", "reassign")
+        .add_query_param("reassign_to", new_owner.id)
+        .await;
+    let owner_id = app
+        .repositories
+        .vehicles
+        .find(&vehicle.id)
+        .await
+        .expect("could not read vehicle")
+        .owner_id;
+
+    // Assert
+    res.assert_status(StatusCode::NO_CONTENT);
+    assert_eq!(owner_id, new_owner.id);
+}
+
+async fn restore(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    server
+        .delete(format!("/users/{}", user.id).as_str())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Act
+    let res = server
+        .post(format!("/users/{}/restore", user.id).as_str())
+        .await;
+    let second_res = server
+        .post(format!("/users/{}/restore", user.id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({"id": user.id, "username": user.username}));
+    second_res.assert_status(StatusCode::NOT_FOUND);
+}
+
+async fn restore_brings_back_cascaded_log_records(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    let owner_id = app
+        .repositories
+        .vehicles
+        .find(&log_record.vehicle_id)
+        .await
+        .expect("could not read vehicle")
+        .owner_id;
+    server
+        .delete(format!("/users/{owner_id}").as_str())
+        .add_query_param("mode", "cascade")
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Act
+    let res = server
+        .post(format!("/users/{owner_id}/restore").as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({"id": owner_id}));
+    server
+        .get(format!("/log_records/{}", log_record.id).as_str())
+        .await
+        .assert_status(StatusCode::OK);
+}
+
+async fn delete_with_stale_if_match(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    let etag = format!("\"{}\"", user.version);
+    server
+        .patch(format!("/users/{}", user.id).as_str())
+        .json(&json!({"email": FreeEmail().fake::<String>()}))
+        .await
+        .assert_status(StatusCode::OK);
+
+    // Act
+    let res = server
+        .delete(format!("/users/{}", user.id).as_str())
+        .add_header("if-match", etag)
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::PRECONDITION_FAILED);
+    server
+        .get(format!("/users/{}", user.id).as_str())
+        .await
+        .assert_status(StatusCode::OK);
+}
+
+async fn patch_with_weak_if_match(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+
+    // Act
+    let res = server
+        .patch(format!("/users/{}", user.id).as_str())
+        .add_header("if-match", format!("W/\"{}\"", user.version))
+        .json(&json!({"email": FreeEmail().fake::<String>()}))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::PRECONDITION_FAILED);
+}
+
+async fn create_retry_after_failure_with_idempotency_key(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let existing_user = seed_user(&app).await;
+    let mut input = json!({
+        "first_name": FirstName().fake::<String>(),
+        "last_name": LastName().fake::<String>(),
+        "username": existing_user.username,
+        "email": FreeEmail().fake::<String>(),
+    });
+    let failed_res = server
+        .post("/users")
+        .add_header("idempotency-key", "sign-up")
+        .json(&input)
+        .await;
+    input["username"] = json!(Username().fake::<String>());
+
+    // Act
+    let res = server
+        .post("/users")
+        .add_header("idempotency-key", "sign-up")
+        .json(&input)
+        .await;
+
+    // Assert
+    failed_res.assert_status(StatusCode::CONFLICT);
+    res.assert_status(StatusCode::CREATED);
+}
+
+async fn create_with_idempotency_key_replays(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let input = json!({
+        "first_name": FirstName().fake::<String>(),
+        "last_name": LastName().fake::<String>(),
+        "username": Username().fake::<String>(),
+        "email": FreeEmail().fake::<String>(),
+    });
+
+    // Act
+    let first_res = server
+        .post("/users")
+        .add_header("idempotency-key", "retry-me")
+        .json(&input)
+        .await;
+    let retry_res = server
+        .post("/users")
+        .add_header("idempotency-key", "retry-me")
+        .json(&input)
+        .await;
+
+    // Assert
+    first_res.assert_status(StatusCode::CREATED);
+    retry_res.assert_status(StatusCode::CREATED);
+    assert_eq!(
+        retry_res.json::<serde_json::Value>()["id"],
+        first_res.json::<serde_json::Value>()["id"]
     );
+    assert_eq!(retry_res.header("idempotent-replayed"), "true");
+}
+
+async fn create_with_invalid_fields(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let input = json!({
+        "first_name": " ",
+        "last_name": LastName().fake::<String>(),
+        "username": Username().fake::<String>(),
+        "email": "not-an-email",
+    });
+
+    // Act
+    let res = server.post("/users").json(&input).await;
+
+    // Assert
+    res.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.header("content-type"), "application/problem+json");
+    res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:validation-failed",
+        "title": "Validation failed",
+        "status": 422,
+        "detail": "the request body is invalid",
+        "instance": "/users",
+        "errors": [
+            {
+                "field": "first_name",
+                "code": "required",
+                "message": "first_name must not be empty",
+            },
+            {
+                "field": "email",
+                "code": "invalid_email",
+                "message": "email must be an email address",
+            },
+        ],
+    }));
+}
+
+async fn create_with_taken_username(app: TestApp) {
+    let existing_user = seed_user(&app).await;
+    assert_taken_field(
+        &app,
+        "username",
+        existing_user.username,
+        "users_username_key",
+    )
+    .await;
+}
+
+async fn create_with_taken_email(app: TestApp) {
+    let existing_user = seed_user(&app).await;
+    assert_taken_field(&app, "email", existing_user.email, "users_email_key").await;
+}
+
+async fn assert_taken_field(app: &TestApp, field: &str, value: String, constraint: &str) {
+    // Arrange
+    let server = &app.server;
+    let mut input = json!({
+        "first_name": FirstName().fake::<String>(),
+        "last_name": LastName().fake::<String>(),
+        "username": Username().fake::<String>(),
+        "email": FreeEmail().fake::<String>(),
+    });
+    input[field] = json!(value);
+
+    // Act
+    let res = server
+        .post("/users")
+        .add_header("x-request-id", "taken-field")
+        .json(&input)
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::CONFLICT);
+    assert_eq!(res.header("content-type"), "application/problem+json");
+    res.assert_json_contains(&json!({
+        "type": "urn:fuel-logger:problem:unique-violation",
+        "status": 409,
+        "constraint": constraint,
+        "fields": [field],
+        "request_id": "taken-field",
+    }));
+}
+
+#[sqlx::test]
+async fn lists_and_reads_come_from_replica(pool: PgPool) {
+    // Arrange
+    let unreachable_replica = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://postgres@127.0.0.1:1/fuel")
+        .expect("could not build replica pool");
+    let app = build_router_with_state(AppState::postgres(&pool).with_replica(&unreachable_replica));
+    let server = TestServer::new(app).expect("could not create test server");
+    let user = seed_user(&TestApp::postgres(&pool)).await;
+
+    // Act
+    let list = server.get("/users").await;
+    let read = server.get(&format!("/users/{}", user.id)).await;
+    let update = server
+        .patch(&format!("/users/{}", user.id))
+        .json(&json!({"first_name": FirstName().fake::<String>()}))
+        .await;
+
+    // Assert
+    list.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    read.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    update.assert_status_ok();
 }
@@ -1,24 +1,43 @@
 mod common;
 
 use axum::http::StatusCode;
-use common::{seed_user, seed_vehicle, seed_vehicle_and_user};
+use common::{
+    seed_log_record_and_vehicle, seed_user, seed_vehicle, seed_vehicle_and_user, TestApp,
+};
 use fake::{
     faker::company::en::{Buzzword, CompanyName},
     Fake, Faker,
 };
 use fuel_logger_rs::models::DbVehicle;
 use serde_json::json;
-use sqlx::{query_as, PgPool, Row};
 use uuid::Uuid;
 
-#[sqlx::test]
-async fn create(pool: PgPool) {
+backend_tests!(
+    create,
+    create_for_missing_owner,
+    create_for_deleted_owner,
+    read,
+    list,
+    update,
+    patch,
+    delete,
+    delete_with_log_records_conflicts,
+    delete_with_cascade,
+    restore_brings_back_cascaded_log_records,
+    purge,
+    read_not_modified,
+    update_with_if_match,
+    create_with_reused_idempotency_key_conflicts,
+    create_with_invalid_fields,
+);
+
+async fn create(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let user = seed_user(&pool).await;
+    let server = &app.server;
+    let user = seed_user(&app).await;
     let make = CompanyName().fake::<String>();
     let model = Buzzword().fake::<String>();
-    let year = (1950..2030).fake::<i32>();
+    let year = (1950..2025).fake::<i32>();
     let input = json!({
         "owner_id": user.id,
         "make": make,
@@ -28,15 +47,16 @@ async fn create(pool: PgPool) {
 
     // Act
     let res = server.post("/vehicles").json(&input).await;
-    let created_vehicle_id =
-        sqlx::query("SELECT id FROM vehicles WHERE make = $1 AND model = $2 AND year = $3 LIMIT 1")
-            .bind(make)
-            .bind(model)
-            .bind(year)
-            .fetch_one(&pool)
-            .await
-            .expect("could not fetch from database")
-            .get::<Uuid, _>("id");
+    let created_vehicle_id = app
+        .repositories
+        .vehicles
+        .list()
+        .await
+        .expect("could not read vehicles")
+        .into_iter()
+        .find(|vehicle| vehicle.make == make && vehicle.model == model && vehicle.year == year)
+        .expect("vehicle was not written")
+        .id;
 
     // Assert
     res.assert_status(StatusCode::CREATED);
@@ -47,11 +67,56 @@ async fn create(pool: PgPool) {
     res.assert_json_contains(&json!({"id": created_vehicle_id}));
 }
 
-#[sqlx::test]
-async fn read(pool: PgPool) {
+async fn create_for_missing_owner(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let input = json!({
+        "owner_id": Uuid::new_v4(),
+        "make": CompanyName().fake::<String>(),
+        "model": Buzzword().fake::<String>(),
+        "year": (1950..2025).fake::<i32>(),
+    });
+
+    // Act
+    let res = server.post("/vehicles").json(&input).await;
+
+    // Assert
+    res.assert_status(StatusCode::NOT_FOUND);
+}
+
+async fn create_for_deleted_owner(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    server
+        .delete(format!("/users/{}", user.id).as_str())
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let input = json!({
+        "owner_id": user.id,
+        "make": CompanyName().fake::<String>(),
+        "model": Buzzword().fake::<String>(),
+        "year": (1950..2025).fake::<i32>(),
+    });
+
+    // Act
+    let res = server.post("/vehicles").json(&input).await;
+    let vehicles = app
+        .repositories
+        .vehicles
+        .list()
+        .await
+        .expect("could not read vehicles");
+
+    // Assert
+    res.assert_status(StatusCode::NOT_FOUND);
+    assert!(vehicles.is_empty());
+}
+
+async fn read(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let vehicle = seed_vehicle_and_user(&pool).await;
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
 
     // Act
     let res = server
@@ -69,13 +134,12 @@ async fn read(pool: PgPool) {
     }));
 }
 
-#[sqlx::test]
-async fn list(pool: PgPool) {
+async fn list(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let user = seed_user(&pool).await;
-    let vehicle_1 = seed_vehicle(&pool, user.id).await;
-    let vehicle_2 = seed_vehicle(&pool, user.id).await;
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    let vehicle_1 = seed_vehicle(&app, user.id).await;
+    let vehicle_2 = seed_vehicle(&app, user.id).await;
 
     // Act
     let res = server.get("/vehicles").await;
@@ -100,14 +164,14 @@ async fn list(pool: PgPool) {
     ]));
 }
 
-#[sqlx::test]
-async fn update(pool: PgPool) {
+async fn update(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let vehicle = seed_vehicle_and_user(&pool).await;
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
     let updated_vehicle = DbVehicle {
         id: vehicle.id,
         owner_id: vehicle.owner_id,
+        version: vehicle.version + 1,
         ..Faker.fake()
     };
     let update_body = json!({
@@ -124,15 +188,12 @@ async fn update(pool: PgPool) {
         .put(format!("/vehicles/{}", vehicle.id).as_str())
         .json(&update_body)
         .await;
-    let written_vehicle = query_as::<_, DbVehicle>(
-        "SELECT * FROM vehicles WHERE make = $1 AND model = $2 AND year = $3 LIMIT 1",
-    )
-    .bind(updated_vehicle.make.clone())
-    .bind(updated_vehicle.model.clone())
-    .bind(updated_vehicle.year)
-    .fetch_one(&pool)
-    .await
-    .expect("could not read vehicle from db");
+    let written_vehicle = app
+        .repositories
+        .vehicles
+        .find(&updated_vehicle.id)
+        .await
+        .expect("could not read vehicle");
 
     // Assert
     res.assert_status(StatusCode::OK);
@@ -146,19 +207,39 @@ async fn update(pool: PgPool) {
     }));
 }
 
-#[sqlx::test]
-async fn delete(pool: PgPool) {
+async fn patch(app: TestApp) {
     // Arrange
-    let server = common::test_server(&pool);
-    let vehicle = seed_vehicle_and_user(&pool).await;
-    assert!(
-        query_as::<_, DbVehicle>("SELECT * FROM vehicles WHERE id = $1 LIMIT 1")
-            .bind(vehicle.id)
-            .fetch_optional(&pool)
-            .await
-            .expect("could not read vehicle from db")
-            .is_some()
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+
+    // Act
+    let res = server
+        .patch(format!("/vehicles/{}", vehicle.id).as_str())
+        .json(&json!({"model": "Patched"}))
+        .await;
+    let written_vehicle = app
+        .repositories
+        .vehicles
+        .find(&vehicle.id)
+        .await
+        .expect("could not read vehicle");
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    assert_eq!(
+        written_vehicle,
+        DbVehicle {
+            model: "Patched".to_owned(),
+            version: vehicle.version + 1,
+            ..vehicle
+        }
     );
+}
+
+async fn delete(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
 
     // Act
     let res = server
@@ -168,12 +249,232 @@ async fn delete(pool: PgPool) {
     // Assert
     res.assert_status(StatusCode::NO_CONTENT);
     assert!(res.into_bytes().is_empty());
-    assert!(
-        query_as::<_, DbVehicle>("SELECT * FROM vehicles WHERE id = $1 LIMIT 1")
-            .bind(vehicle.id)
-            .fetch_optional(&pool)
-            .await
-            .expect("could not read vehicle from db")
-            .is_none()
-    );
+    assert!(app.repositories.vehicles.find(&vehicle.id).await.is_err());
+}
+
+async fn delete_with_log_records_conflicts(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+
+    // Act
+    let res = server
+        .delete(format!("/vehicles/{}", log_record.vehicle_id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::CONFLICT);
+    res.assert_json_contains(&json!({"dependents": [log_record.id]}));
+}
+
+async fn delete_with_cascade(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+
+    // Act
+    let res = server
+        .delete(format!("/vehicles/{}", log_record.vehicle_id).as_str())
+        .add_query_param("cascade", true)
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::NO_CONTENT);
+    assert!(app
+        .repositories
+        .vehicles
+        .find(&log_record.vehicle_id)
+        .await
+        .is_err());
+}
+
+async fn restore_brings_back_cascaded_log_records(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    server
+        .delete(format!("/vehicles/{}", log_record.vehicle_id).as_str())
+        .add_query_param("cascade", true)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Act
+    let res = server
+        .post(format!("/vehicles/{}/restore", log_record.vehicle_id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    res.assert_json_contains(&json!({"id": log_record.vehicle_id}));
+    server
+        .get(format!("/log_records/{}", log_record.id).as_str())
+        .await
+        .assert_status(StatusCode::OK);
+}
+
+async fn purge(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let log_record = seed_log_record_and_vehicle(&app).await;
+    server
+        .delete(format!("/vehicles/{}", log_record.vehicle_id).as_str())
+        .add_query_param("cascade", true)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Act
+    let res = server
+        .delete(format!("/vehicles/{}/purge", log_record.vehicle_id).as_str())
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::NO_CONTENT);
+    // Only soft deleted vehicles can be restored
+    assert!(app
+        .repositories
+        .vehicles
+        .restore(&log_record.vehicle_id)
+        .await
+        .is_err());
+}
+
+async fn read_not_modified(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let read_res = server
+        .get(format!("/vehicles/{}", vehicle.id).as_str())
+        .await;
+    let etag = read_res.header("etag");
+
+    // Act
+    let res = server
+        .get(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("if-none-match", etag.clone())
+        .await;
+    let weak_res = server
+        .get(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("if-none-match", format!("W/\"{}\"", vehicle.version))
+        .await;
+    let stale_res = server
+        .get(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("if-none-match", "\"0\"")
+        .await;
+
+    // Assert
+    assert_eq!(etag, format!("\"{}\"", vehicle.version));
+    res.assert_status(StatusCode::NOT_MODIFIED);
+    weak_res.assert_status(StatusCode::NOT_MODIFIED);
+    assert!(res.into_bytes().is_empty());
+    stale_res.assert_status(StatusCode::OK);
+}
+
+async fn update_with_if_match(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let vehicle = seed_vehicle_and_user(&app).await;
+    let etag = server
+        .get(format!("/vehicles/{}", vehicle.id).as_str())
+        .await
+        .header("etag");
+    let update_body = json!({
+        "owner_id": vehicle.owner_id,
+        "make": vehicle.make,
+        "model": vehicle.model,
+        "year": 2010,
+    });
+
+    // Act
+    let res = server
+        .put(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("if-match", etag.clone())
+        .json(&update_body)
+        .await;
+    let stale_res = server
+        .put(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("if-match", etag.clone())
+        .json(&update_body)
+        .await;
+    let stale_delete_res = server
+        .delete(format!("/vehicles/{}", vehicle.id).as_str())
+        .add_header("if-match", etag)
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::OK);
+    assert_eq!(res.header("etag"), format!("\"{}\"", vehicle.version + 1));
+    stale_res.assert_status(StatusCode::PRECONDITION_FAILED);
+    stale_delete_res.assert_status(StatusCode::PRECONDITION_FAILED);
+}
+
+async fn create_with_reused_idempotency_key_conflicts(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+    let input = json!({
+        "owner_id": user.id,
+        "make": CompanyName().fake::<String>(),
+        "model": CompanyName().fake::<String>(),
+        "year": 2020,
+    });
+    server
+        .post("/vehicles")
+        .add_header("idempotency-key", "new-car")
+        .json(&input)
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    // Act
+    let res = server
+        .post("/vehicles")
+        .add_header("idempotency-key", "new-car")
+        .json(&json!({
+            "owner_id": user.id,
+            "make": CompanyName().fake::<String>(),
+            "model": CompanyName().fake::<String>(),
+            "year": 2021,
+        }))
+        .await;
+
+    // Assert
+    res.assert_status(StatusCode::CONFLICT);
+}
+
+async fn create_with_invalid_fields(app: TestApp) {
+    // Arrange
+    let server = &app.server;
+    let user = seed_user(&app).await;
+
+    // Act
+    let wrong_type_res = server
+        .post("/vehicles")
+        .json(&json!({
+            "owner_id": user.id,
+            "make": CompanyName().fake::<String>(),
+            "model": CompanyName().fake::<String>(),
+            "year": "new",
+        }))
+        .await;
+    let out_of_range_res = server
+        .post("/vehicles")
+        .json(&json!({
+            "owner_id": user.id,
+            "make": "",
+            "model": CompanyName().fake::<String>(),
+            "year": 1850,
+        }))
+        .await;
+
+    // Assert
+    wrong_type_res.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    wrong_type_res.assert_json_contains(&json!({
+        "errors": [{"field": "year", "code": "invalid_value"}],
+    }));
+    out_of_range_res.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    out_of_range_res.assert_json_contains(&json!({
+        "errors": [
+            {"field": "make", "code": "required"},
+            {"field": "year", "code": "out_of_range"},
+        ],
+    }));
 }